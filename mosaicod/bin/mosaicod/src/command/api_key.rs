@@ -18,6 +18,11 @@ pub enum ApiKey {
         #[arg(short, long, required = true)]
         permissions: String,
 
+        /// Restrict the permissions to the resources whose locator starts with a prefix,
+        /// using the `<prefix>:<permission>` format (e.g. `fleet_a_:write`). Can be repeated.
+        #[arg(short, long = "scope")]
+        scopes: Vec<String>,
+
         /// Define a description for the key
         #[arg(short, long)]
         description: Option<String>,
@@ -69,12 +74,18 @@ pub fn auth(auth: ApiKey) -> Result<()> {
     match auth {
         ApiKey::Create {
             permissions,
+            scopes,
             description,
             expires_in,
             expires_at,
         } => {
             let permissions = permissions.parse()?;
 
+            let scopes = scopes
+                .iter()
+                .map(|scope| scope.parse())
+                .collect::<std::result::Result<Vec<types::auth::ApiKeyScope>, _>>()?;
+
             // Only one at a time between expires_at and expires_in can be set.
             let expiration_datetime: Option<types::Timestamp> = if let Some(expires_in) = expires_in
             {
@@ -106,15 +117,25 @@ pub fn auth(auth: ApiKey) -> Result<()> {
             // If no description is provided use the empty string
             let description = description.unwrap_or_default();
 
-            let policy: core::error::PublicResult<types::ApiKey> = rt.block_on(async {
-                let fauth =
-                    facade::Auth::create(permissions, description, expiration_datetime, db).await?;
-                Ok(fauth.into_api_key())
+            let token = types::auth::Token::new();
+
+            let res: core::error::PublicResult<()> = rt.block_on(async {
+                facade::Auth::create(
+                    &token,
+                    permissions,
+                    scopes,
+                    description,
+                    expiration_datetime,
+                    db,
+                )
+                .await?;
+                Ok(())
             });
 
-            let policy = policy?;
+            res?;
 
-            println!("{}", policy.key);
+            // The token is printed only once, since only its hash is stored
+            println!("{}", token);
         }
 
         ApiKey::Revoke { fingerprint } => {
@@ -171,6 +192,21 @@ fn print_authz_policy_details(policy: types::ApiKey) {
         String::from(policy.permission)
     );
 
+    println!(
+        "{:>13} {}",
+        "SCOPES:".bold(),
+        if policy.scopes.is_empty() {
+            "all resources".yellow().to_string()
+        } else {
+            policy
+                .scopes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    );
+
     println!(
         "{:>13} {}",
         "EXPIRES:".bold(),
@@ -217,7 +253,7 @@ fn print_authz_policy_list(policies: Vec<types::ApiKey>) {
 
         println!(
             "{:>12} {:>24} {:>24} {:>10} {:>14}    {}",
-            policy.token().fingerprint(),
+            policy.fingerprint(),
             datetime.to_string(),
             expired_datetime.map_or("never".yellow(), |ts| { ts.to_string().white() }),
             expired,
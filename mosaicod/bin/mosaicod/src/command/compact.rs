@@ -0,0 +1,89 @@
+use crate::common;
+use clap::Args;
+use colored::Colorize;
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_facade as facade;
+
+#[derive(Args, Debug)]
+pub struct Compact {
+    /// Locator of the sequence to compact
+    pub sequence: String,
+
+    /// Topic to compact, can be repeated. All the sequence topics are compacted if not provided
+    #[arg(short, long = "topic")]
+    pub topics: Vec<String>,
+
+    /// Use the store on the local filesystem at the specified directory path
+    #[arg(long)]
+    pub local_store: Option<std::path::PathBuf>,
+
+    /// Object storage service used when `--local-store` is not set. It defaults to the
+    /// value of `MOSAICOD_STORE_BACKEND`.
+    #[arg(long, value_enum)]
+    pub store_backend: Option<common::StoreBackend>,
+}
+
+/// Merges the chunks of the locked topics of a sequence into larger chunks.
+pub fn compact(args: Compact) -> Result<()> {
+    let store = common::init_store(args.local_store.as_deref(), args.store_backend)?;
+
+    let rt = common::init_runtime()?;
+
+    let db = common::init_db(
+        &rt,
+        &db::Config {
+            db_url: params::params().db_url.value.parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    params::params().db_url.env.clone(),
+                    "unable to parse".to_owned(),
+                )
+            })?,
+        },
+    )?;
+
+    let locator: types::SequenceLocator = args.sequence.parse()?;
+
+    let topics = args
+        .topics
+        .iter()
+        .map(|topic| topic.parse())
+        .collect::<std::result::Result<Vec<types::TopicLocator>, _>>()?;
+
+    let context = common::init_context(store, db)?;
+
+    rt.block_on(async {
+        let handle = facade::sequence::Handle::try_from_locator(&context, locator).await?;
+
+        for mut topic in facade::sequence::topic_list(&context, &handle).await? {
+            if !topics.is_empty() && !topics.contains(topic.locator()) {
+                continue;
+            }
+
+            if !facade::topic::archived(&context, &topic).await? {
+                println!(
+                    "{:>9} {} (still being uploaded)",
+                    "SKIPPED".yellow().bold(),
+                    topic.locator()
+                );
+                continue;
+            }
+
+            let report = facade::topic::compact(&context, &mut topic).await?;
+
+            println!(
+                "{:>9} {} ({} chunks, {} bytes -> {} chunks, {} bytes)",
+                "COMPACTED".green().bold(),
+                topic.locator(),
+                report.chunks_before,
+                report.bytes_before,
+                report.chunks_after,
+                report.bytes_after
+            );
+        }
+
+        Ok::<(), core::error::BoxPublicError>(())
+    })?;
+
+    Ok(())
+}
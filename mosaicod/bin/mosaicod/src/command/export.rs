@@ -0,0 +1,143 @@
+use crate::common;
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use futures::TryStreamExt;
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_facade as facade;
+use std::io::Write;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    /// Single MCAP recording, readable by Foxglove and ROS tooling
+    Mcap,
+    /// Tar archive with all the sequence data and records, to be imported in another daemon
+    Bundle,
+}
+
+#[derive(Args, Debug)]
+pub struct Export {
+    /// Locator of the sequence to export
+    pub sequence: String,
+
+    /// Path of the exported file
+    #[arg(short, long)]
+    pub output: std::path::PathBuf,
+
+    /// Format of the exported file
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Mcap)]
+    pub format: ExportFormat,
+
+    /// Topic to export, can be repeated. All the sequence topics are exported if not provided
+    #[arg(short, long = "topic")]
+    pub topics: Vec<String>,
+
+    /// Export only data with a timestamp (in nanoseconds) greater than or equal to this value
+    #[arg(long)]
+    pub start_ns: Option<i64>,
+
+    /// Export only data with a timestamp (in nanoseconds) lower than this value
+    #[arg(long)]
+    pub end_ns: Option<i64>,
+
+    /// Use the store on the local filesystem at the specified directory path
+    #[arg(long)]
+    pub local_store: Option<std::path::PathBuf>,
+
+    /// Object storage service used when `--local-store` is not set. It defaults to the
+    /// value of `MOSAICOD_STORE_BACKEND`.
+    #[arg(long, value_enum)]
+    pub store_backend: Option<common::StoreBackend>,
+}
+
+/// Exports a sequence as a single file.
+pub fn export(args: Export) -> Result<()> {
+    let store = common::init_store(args.local_store.as_deref(), args.store_backend)?;
+
+    let rt = common::init_runtime()?;
+
+    let db = common::init_db(
+        &rt,
+        &db::Config {
+            db_url: params::params().db_url.value.parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    params::params().db_url.env.clone(),
+                    "unable to parse".to_owned(),
+                )
+            })?,
+        },
+    )?;
+
+    let locator: types::SequenceLocator = args.sequence.parse()?;
+
+    let topics = if args.topics.is_empty() {
+        None
+    } else {
+        Some(
+            args.topics
+                .iter()
+                .map(|topic| topic.parse())
+                .collect::<std::result::Result<Vec<types::TopicLocator>, _>>()?,
+        )
+    };
+
+    let timestamp_range = match (args.start_ns, args.end_ns) {
+        (None, None) => None,
+        (start, end) => Some(types::TimestampRange::between(
+            start.map_or_else(types::Timestamp::unbounded_neg, Into::into),
+            end.map_or_else(types::Timestamp::unbounded_pos, Into::into),
+        )),
+    };
+
+    let context = common::init_context(store, db)?;
+
+    let write_error = |e: std::io::Error| {
+        core::Error::bad_request(format!("unable to write `{}`: {e}", args.output.display()))
+    };
+
+    let mut file = std::fs::File::create(&args.output).map_err(write_error)?;
+
+    let written = rt.block_on(async {
+        let mut stream = match args.format {
+            ExportFormat::Mcap => {
+                facade::export::mcap(&context, locator.clone(), topics, timestamp_range).await?
+            }
+            ExportFormat::Bundle => {
+                if topics.is_some() || timestamp_range.is_some() {
+                    Err(core::Error::bad_request(
+                        "bundles contain the whole sequence, topics and timestamp range can't be selected"
+                            .to_owned(),
+                    ))?;
+                }
+                facade::export::bundle(&context, locator.clone()).await?
+            }
+        };
+
+        let mut written = 0;
+        while let Some(bytes) = stream.try_next().await? {
+            file.write_all(&bytes).map_err(write_error)?;
+            written += bytes.len();
+        }
+
+        Ok::<usize, core::error::BoxPublicError>(written)
+    });
+
+    let written = match written {
+        Ok(written) => written,
+        Err(e) => {
+            // Do not leave a truncated file behind
+            let _ = std::fs::remove_file(&args.output);
+            return Err(e);
+        }
+    };
+
+    println!(
+        "{:>9} {} to {} ({} bytes)",
+        "EXPORTED".green().bold(),
+        locator,
+        args.output.display(),
+        written
+    );
+
+    Ok(())
+}
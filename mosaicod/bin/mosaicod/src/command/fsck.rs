@@ -0,0 +1,96 @@
+use crate::common;
+use clap::Args;
+use colored::Colorize;
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_facade as facade;
+
+#[derive(Args, Debug)]
+pub struct Fsck {
+    /// Fix the database records and restore the missing metadata files
+    #[arg(long, default_value_t = false)]
+    pub repair: bool,
+
+    /// Delete the folders and the data files not referenced by the database
+    #[arg(long, default_value_t = false)]
+    pub gc: bool,
+
+    /// Use the store on the local filesystem at the specified directory path
+    #[arg(long)]
+    pub local_store: Option<std::path::PathBuf>,
+
+    /// Object storage service used when `--local-store` is not set. It defaults to the
+    /// value of `MOSAICOD_STORE_BACKEND`.
+    #[arg(long, value_enum)]
+    pub store_backend: Option<common::StoreBackend>,
+}
+
+/// Checks the consistency between the database and the store.
+pub fn fsck(args: Fsck) -> Result<()> {
+    let store = common::init_store(args.local_store.as_deref(), args.store_backend)?;
+
+    let rt = common::init_runtime()?;
+
+    let db = common::init_db(
+        &rt,
+        &db::Config {
+            db_url: params::params().db_url.value.parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    params::params().db_url.env.clone(),
+                    "unable to parse".to_owned(),
+                )
+            })?,
+        },
+    )?;
+
+    let context = common::init_context(store, db)?;
+
+    let report = rt.block_on(facade::fsck::check(&context))?;
+
+    println!(
+        "{:>9} {} sequences, {} topics, {} chunks",
+        "CHECKED".green().bold(),
+        report.sequences,
+        report.topics,
+        report.chunks
+    );
+
+    let mut unresolved = 0;
+    for issue in &report.issues {
+        let fix = if issue.is_garbage() {
+            args.gc
+        } else {
+            args.repair && issue.is_repairable()
+        };
+
+        if !fix {
+            println!("{:>9} {}", "FOUND".yellow().bold(), issue);
+            unresolved += 1;
+            continue;
+        }
+
+        let fixed = rt.block_on(facade::fsck::repair(
+            &context,
+            issue,
+            types::allow_data_loss(),
+        ))?;
+
+        let label = if !fixed {
+            // Resolved since the check, e.g. by a concurrent upload
+            "SKIPPED"
+        } else if issue.is_garbage() {
+            "DELETED"
+        } else {
+            "REPAIRED"
+        };
+        println!("{:>9} {}", label.green().bold(), issue);
+    }
+
+    if unresolved > 0 {
+        Err(core::Error::bad_request(format!(
+            "{unresolved} inconsistencies found, use --repair and --gc to fix them"
+        )))?;
+    }
+
+    Ok(())
+}
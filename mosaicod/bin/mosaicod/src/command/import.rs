@@ -0,0 +1,144 @@
+use crate::common;
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use mosaicod_core::{
+    self as core,
+    error::PublicResult as Result,
+    params,
+    types::{self, MetadataBlob},
+};
+use mosaicod_db as db;
+use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ImportFormat {
+    /// MCAP recording
+    Mcap,
+    /// Bundle produced by `mosaicod export --format bundle`
+    Bundle,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum OnConflict {
+    /// Fail if the sequence already exists
+    Fail,
+    /// Leave the existing sequence untouched
+    Skip,
+    /// Delete the existing sequence and replace it
+    Replace,
+}
+
+impl From<OnConflict> for types::OnConflict {
+    fn from(value: OnConflict) -> Self {
+        match value {
+            OnConflict::Fail => Self::Fail,
+            OnConflict::Skip => Self::Skip,
+            OnConflict::Replace => Self::Replace,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct Import {
+    /// Path of the MCAP recording or bundle to import
+    pub path: std::path::PathBuf,
+
+    /// Locator of the sequence created to store the recording.
+    /// Required for MCAP recordings, bundles default to the locator of the exported sequence
+    #[arg(short, long)]
+    pub sequence: Option<String>,
+
+    /// Format of the imported file
+    #[arg(short, long, value_enum, default_value_t = ImportFormat::Mcap)]
+    pub format: ImportFormat,
+
+    /// Action taken when importing a bundle of an already existing sequence
+    #[arg(long, value_enum, default_value_t = OnConflict::Fail)]
+    pub on_conflict: OnConflict,
+
+    /// Read the recording from the store, `PATH` is relative to the root of the store
+    #[arg(long, default_value_t = false)]
+    pub from_store: bool,
+
+    /// Use the store on the local filesystem at the specified directory path
+    #[arg(long)]
+    pub local_store: Option<std::path::PathBuf>,
+
+    /// Object storage service used when `--local-store` is not set. It defaults to the
+    /// value of `MOSAICOD_STORE_BACKEND`.
+    #[arg(long, value_enum)]
+    pub store_backend: Option<common::StoreBackend>,
+
+    /// User metadata, formatted as a JSON object, associated with the sequence
+    #[arg(short, long)]
+    pub metadata: Option<String>,
+}
+
+/// Imports an MCAP recording or a sequence bundle as a new sequence.
+pub fn import(args: Import) -> Result<()> {
+    let store = common::init_store(args.local_store.as_deref(), args.store_backend)?;
+
+    let rt = common::init_runtime()?;
+
+    let db = common::init_db(
+        &rt,
+        &db::Config {
+            db_url: params::params().db_url.value.parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    params::params().db_url.env.clone(),
+                    "unable to parse".to_owned(),
+                )
+            })?,
+        },
+    )?;
+
+    let locator: Option<types::SequenceLocator> =
+        args.sequence.as_deref().map(str::parse).transpose()?;
+
+    let user_metadata = args
+        .metadata
+        .as_deref()
+        .map(marshal::JsonMetadataBlob::try_from_str)
+        .transpose()?;
+
+    let context = common::init_context(store, db)?;
+
+    let data: Box<dyn std::io::Read + Send> = if args.from_store {
+        Box::new(rt.block_on(context.store.blocking_reader(&args.path))?)
+    } else {
+        Box::new(std::io::BufReader::new(
+            std::fs::File::open(&args.path).map_err(|e| {
+                core::Error::bad_request(format!("unable to read `{}`: {e}", args.path.display()))
+            })?,
+        ))
+    };
+
+    let report = match args.format {
+        ImportFormat::Mcap => {
+            let locator = locator.ok_or_else(|| {
+                core::Error::bad_request(
+                    "a sequence locator is required to import MCAP recordings".to_owned(),
+                )
+            })?;
+            rt.block_on(facade::import::mcap(&context, locator, user_metadata, data))?
+        }
+        ImportFormat::Bundle => rt.block_on(facade::import::bundle(
+            &context,
+            locator,
+            user_metadata,
+            data,
+            args.on_conflict.into(),
+        ))?,
+    };
+
+    for topic in report.topics {
+        println!("{:>9} {}", "IMPORTED".green().bold(), topic);
+    }
+
+    for (skipped, reason) in report.skipped {
+        println!("{:>9} {} ({})", "SKIPPED".yellow().bold(), skipped, reason);
+    }
+
+    Ok(())
+}
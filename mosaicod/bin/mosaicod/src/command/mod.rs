@@ -3,3 +3,15 @@ pub use run::*;
 
 mod api_key;
 pub use api_key::*;
+
+mod import;
+pub use import::*;
+
+mod export;
+pub use export::*;
+
+mod fsck;
+pub use fsck::*;
+
+mod compact;
+pub use compact::*;
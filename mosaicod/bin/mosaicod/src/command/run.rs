@@ -3,7 +3,6 @@ use clap::Args;
 use mosaicod_core::{self as core, error::PublicResult as Result, params};
 use mosaicod_db as db;
 use mosaicod_server as server;
-use mosaicod_store as store;
 use signal_hook::{consts::SIGINT, iterator::Signals};
 use std::thread;
 use tracing::{debug, info};
@@ -22,6 +21,11 @@ pub struct Run {
     #[arg(long)]
     pub local_store: Option<std::path::PathBuf>,
 
+    /// Object storage service used when `--local-store` is not set. It defaults to the
+    /// value of `MOSAICOD_STORE_BACKEND`.
+    #[arg(long, value_enum)]
+    pub store_backend: Option<common::StoreBackend>,
+
     /// Enable TLS. When enabled, the following envirnoment variables needs to be set
     /// MOSAICOD_TLS_CERT_FILE, MOSAICOD_TLS_PRIVATE_KEY_FILE.
     #[arg(long, default_value_t = false)]
@@ -35,16 +39,16 @@ pub struct Run {
     /// perform any actions. See command `mosaicod api-key` for more info.
     #[arg(long, default_value_t = false)]
     pub api_key: bool,
-}
 
-fn get_store(cmds: &Run) -> Result<store::StoreRef> {
-    if let Some(path) = &cmds.local_store {
-        info!("initializing filesystem store");
-        Ok(common::init_local_store(path)?)
-    } else {
-        info!("initializing s3-compatible store");
-        Ok(common::init_s3_store()?)
-    }
+    /// Enable the Prometheus metrics exporter. Metrics are served over HTTP at
+    /// `/metrics` on the specified port, using the same host of the server.
+    #[arg(long)]
+    pub metrics_port: Option<u16>,
+
+    /// Export traces via OTLP over HTTP to the specified endpoint
+    /// (e.g. `http://localhost:4318/v1/traces`).
+    #[arg(long)]
+    pub otlp_endpoint: Option<String>,
 }
 
 fn tls_config() -> server::flight::TlsConfig {
@@ -60,7 +64,7 @@ fn tls_config() -> server::flight::TlsConfig {
 /// are hidden.
 pub fn run(args: Run, json_format: bool) -> Result<()> {
     info!("startup store");
-    let store = get_store(&args)?;
+    let store = common::init_store(args.local_store.as_deref(), args.store_backend)?;
     let store_display_name = print::store_display_name(&store);
 
     info!("startup multi-threaded runtime");
@@ -82,8 +86,24 @@ pub fn run(args: Run, json_format: bool) -> Result<()> {
     let host_is_specified = args.host.is_some();
     let host = args.host.unwrap_or("127.0.0.1".to_owned());
 
+    let metrics_addr = args
+        .metrics_port
+        .map(|port| {
+            format!("{host}:{port}").parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    "--metrics-port".to_owned(),
+                    "unable to build metrics address".to_owned(),
+                )
+            })
+        })
+        .transpose()?;
+
     let mut server = server::Server::new(host, args.port, store, db);
 
+    if let Some(addr) = metrics_addr {
+        server.flight_config.metrics(addr);
+    }
+
     if args.api_key {
         server.flight_config.enable_api_key_management();
     }
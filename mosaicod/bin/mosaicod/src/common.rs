@@ -1,12 +1,36 @@
 //! Common functions shared between multiple commands
 
-use mosaicod_core::{self as core, error::PublicResult as Result, params};
+use clap::ValueEnum;
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
 use mosaicod_db as db;
+use mosaicod_facade as facade;
+use mosaicod_query as query;
 use mosaicod_store as store;
 use std::sync::Arc;
 use std::sync::OnceLock;
 use tracing::{debug, info};
 
+/// Object storage service selectable from the command line
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum StoreBackend {
+    /// Amazon S3 or any S3-compatible service
+    S3,
+    /// Google Cloud Storage
+    Gcs,
+    /// Azure Blob Storage
+    Azure,
+}
+
+impl From<StoreBackend> for types::StoreBackend {
+    fn from(value: StoreBackend) -> Self {
+        match value {
+            StoreBackend::S3 => Self::S3,
+            StoreBackend::Gcs => Self::Gcs,
+            StoreBackend::Azure => Self::Azure,
+        }
+    }
+}
+
 /// Stores startup time
 static STARTUP_TIME: OnceLock<std::time::Instant> = OnceLock::new();
 
@@ -25,6 +49,13 @@ pub fn startup_time() -> &'static std::time::Instant {
 pub fn init_db(rt: &tokio::runtime::Runtime, config: &db::Config) -> Result<db::Database> {
     let database = rt.block_on(async {
         let database = db::Database::try_new(config).await?;
+
+        // API keys created by older versions have their payload stored in clear
+        let migrated = facade::Auth::hash_legacy_keys(database.clone()).await?;
+        if migrated > 0 {
+            info!("hashed {} legacy api keys", migrated);
+        }
+
         Ok::<db::Database, mosaicod_core::error::BoxPublicError>(database)
     })?;
 
@@ -58,6 +89,73 @@ pub fn init_s3_store() -> Result<store::StoreRef> {
     Ok(Arc::new(store::Store::try_from_s3_store(config)?))
 }
 
+pub fn init_gcs_store() -> Result<store::StoreRef> {
+    let params = params::params();
+
+    let config = store::GcsConfig {
+        bucket: params.store_bucket.value.clone(),
+        endpoint: params.store_endpoint.value.clone(),
+        service_account_path: params.store_service_account_path.value.clone(),
+    };
+
+    config.validate()?;
+
+    Ok(Arc::new(store::Store::try_from_gcs_store(config)?))
+}
+
+pub fn init_azure_store() -> Result<store::StoreRef> {
+    let params = params::params();
+
+    let config = store::AzureConfig {
+        container: params.store_bucket.value.clone(),
+        account: params.store_account.value.clone(),
+        access_key: params.store_secret_key.value.clone(),
+        endpoint: params.store_endpoint.value.clone(),
+    };
+
+    config.validate()?;
+
+    Ok(Arc::new(store::Store::try_from_azure_store(config)?))
+}
+
+/// Initializes the store on the local filesystem if `local_store` is defined, otherwise
+/// the remote store of the given `backend` is used (`MOSAICOD_STORE_BACKEND` if not
+/// provided).
+pub fn init_store(
+    local_store: Option<&std::path::Path>,
+    backend: Option<StoreBackend>,
+) -> Result<store::StoreRef> {
+    if let Some(path) = local_store {
+        info!("initializing filesystem store");
+        return init_local_store(path);
+    }
+
+    match backend.map_or(params::params().store_backend.value, Into::into) {
+        types::StoreBackend::S3 => {
+            info!("initializing s3-compatible store");
+            init_s3_store()
+        }
+        types::StoreBackend::Gcs => {
+            info!("initializing google cloud storage store");
+            init_gcs_store()
+        }
+        types::StoreBackend::Azure => {
+            info!("initializing azure blob storage store");
+            init_azure_store()
+        }
+    }
+}
+
+/// Builds the context required to call facade functions outside of the server.
+pub fn init_context(store: store::StoreRef, db: db::Database) -> Result<facade::Context> {
+    let ts_engine = query::TimeseriesEngine::try_new(
+        store.clone(),
+        params::params().query_engine_memory_pool_size.value,
+    )?;
+
+    Ok(facade::Context::new(store, db, Arc::new(ts_engine)))
+}
+
 /// Load the defined env variables from the system.
 pub fn load_env_variables() -> Result<()> {
     info!("loading environment variables");
@@ -1,4 +1,8 @@
 use clap::ValueEnum;
+use mosaicod_core::{self as core, error::PublicResult as Result};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
 pub enum LogLevel {
@@ -46,32 +50,81 @@ impl std::fmt::Display for LogFormat {
     }
 }
 
-pub fn init_logger(format: LogFormat, level: LogLevel) {
+/// Name used to identify the daemon in the exported traces
+const SERVICE_NAME: &str = "mosaicod";
+
+/// Spans exported via OTLP, independently from the log level
+const TRACES_FILTER: &str = "mosaico=info";
+
+/// Initializes the global logger.
+///
+/// If `otlp_endpoint` is provided spans are also exported via OTLP (HTTP) to the endpoint,
+/// the returned provider needs to be shut down before exiting to flush the pending spans.
+pub fn init_logger(
+    format: LogFormat,
+    level: LogLevel,
+    otlp_endpoint: Option<&str>,
+) -> Result<Option<SdkTracerProvider>> {
     use tracing_subscriber::prelude::*;
 
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(level.as_filter()));
 
-    match format {
-        LogFormat::Json => tracing_subscriber::FmtSubscriber::builder()
-            .with_max_level(tracing::Level::TRACE)
+    let fmt_layer = match format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
             .json()
-            .finish()
-            .with(filter)
-            .init(),
-        LogFormat::Pretty => tracing_subscriber::FmtSubscriber::builder()
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
             .with_target(false)
             .with_ansi(true)
-            .with_max_level(tracing::Level::TRACE)
-            .finish()
-            .with(filter)
-            .init(),
-        LogFormat::Plain => tracing_subscriber::FmtSubscriber::builder()
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::Plain => tracing_subscriber::fmt::layer()
             .with_target(false)
             .with_ansi(false)
-            .with_max_level(tracing::Level::TRACE)
-            .finish()
-            .with(filter)
-            .init(),
-    }
+            .with_filter(filter)
+            .boxed(),
+    };
+
+    let provider = otlp_endpoint.map(init_tracer_provider).transpose()?;
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(SERVICE_NAME))
+            .with_filter(tracing_subscriber::EnvFilter::new(TRACES_FILTER))
+    });
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(otel_layer)
+        .init();
+
+    Ok(provider)
+}
+
+/// Builds the provider exporting spans to `endpoint` and registers the W3C trace context
+/// propagator, used to attach the spans of the daemon to the traces of the clients.
+fn init_tracer_provider(endpoint: &str) -> Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| {
+            core::Error::invalid_configuration("--otlp-endpoint".to_owned(), e.to_string())
+        })?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            opentelemetry_sdk::Resource::builder()
+                .with_service_name(SERVICE_NAME)
+                .build(),
+        )
+        .build();
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry::global::set_tracer_provider(provider.clone());
+
+    Ok(provider)
 }
@@ -31,6 +31,18 @@ enum Commands {
     /// Start the mosaico server
     Run(command::Run),
 
+    /// Import an MCAP recording (e.g. a ROS 2 bag) or a sequence bundle as a new sequence
+    Import(command::Import),
+
+    /// Export a sequence as a single file (e.g. an MCAP recording or a bundle)
+    Export(command::Export),
+
+    /// Merge the chunks of the topics of a sequence into larger chunks
+    Compact(command::Compact),
+
+    /// Check the consistency between the database and the store, optionally fixing the issues found
+    Fsck(command::Fsck),
+
     /// Manage mosaico API keys
     #[command(subcommand, name = "api-key")]
     Auth(command::ApiKey),
@@ -48,17 +60,39 @@ fn start() -> Result<Option<String>> {
     };
 
     print::set_colors(args.log_format);
-    log::init_logger(args.log_format, args.log_level);
+
+    let otlp_endpoint = match &args.cmd {
+        Commands::Run(sub_args) => sub_args.otlp_endpoint.as_deref(),
+        Commands::Import(_)
+        | Commands::Export(_)
+        | Commands::Compact(_)
+        | Commands::Fsck(_)
+        | Commands::Auth(_) => None,
+    };
+    let tracer_provider = log::init_logger(args.log_format, args.log_level, otlp_endpoint)?;
 
     common::load_env_variables()?;
 
     let is_json_output = matches!(args.log_format, log::LogFormat::Json);
 
-    match args.cmd {
-        Commands::Run(sub_args) => command::run(sub_args, is_json_output)?,
-        Commands::Auth(sub_args) => command::auth(sub_args)?,
+    let res = match args.cmd {
+        Commands::Run(sub_args) => command::run(sub_args, is_json_output),
+        Commands::Import(sub_args) => command::import(sub_args),
+        Commands::Export(sub_args) => command::export(sub_args),
+        Commands::Compact(sub_args) => command::compact(sub_args),
+        Commands::Fsck(sub_args) => command::fsck(sub_args),
+        Commands::Auth(sub_args) => command::auth(sub_args),
+    };
+
+    // Flush pending spans before exiting
+    if let Some(provider) = tracer_provider
+        && let Err(e) = provider.shutdown()
+    {
+        eprintln!("unable to flush traces: {e}");
     }
 
+    res?;
+
     Ok(None)
 }
 
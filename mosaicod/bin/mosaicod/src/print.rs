@@ -121,5 +121,25 @@ pub fn store_display_name(store: &store::StoreRef) -> String {
                 "]".dimmed(),
             )
         }
+        store::StoreTarget::Gcs(bucket) => {
+            format!(
+                "{}{} {}{}{}",
+                "gs://".yellow(),
+                bucket.yellow(),
+                "[".dimmed(),
+                "remote".cyan(),
+                "]".dimmed(),
+            )
+        }
+        store::StoreTarget::Azure(container) => {
+            format!(
+                "{}{} {}{}{}",
+                "az://".yellow(),
+                container.yellow(),
+                "[".dimmed(),
+                "remote".cyan(),
+                "]".dimmed(),
+            )
+        }
     }
 }
@@ -1,6 +1,7 @@
 pub mod error;
 pub use error::Error;
 
+pub mod metrics;
 pub mod params;
 pub mod random;
 pub mod traits;
@@ -0,0 +1,39 @@
+//! Names and labels of the metrics exported by the daemon.
+//!
+//! Metrics are recorded using the [`metrics`](https://docs.rs/metrics) facade, if no
+//! exporter is installed recording a metric is a no-op.
+
+/// Counter of the Flight RPCs served, labeled by `rpc`, `action` and `code`
+pub const RPC_REQUESTS_TOTAL: &str = "mosaicod_rpc_requests_total";
+
+/// Histogram of the time (in seconds) required to serve a Flight RPC, labeled by `rpc`
+/// and `action`. For streaming RPCs it measures the time required to setup the stream.
+pub const RPC_DURATION_SECONDS: &str = "mosaicod_rpc_duration_seconds";
+
+/// Counter of the bytes of record batches received by `do_put`
+pub const INGESTED_BYTES_TOTAL: &str = "mosaicod_ingested_bytes_total";
+
+/// Counter of the rows of record batches received by `do_put`
+pub const INGESTED_ROWS_TOTAL: &str = "mosaicod_ingested_rows_total";
+
+/// Histogram of the time (in seconds) required to encode a chunk
+pub const CHUNK_ENCODING_SECONDS: &str = "mosaicod_chunk_encoding_seconds";
+
+/// Histogram of the time (in seconds) required to write an encoded chunk to the store
+pub const CHUNK_STORE_SECONDS: &str = "mosaicod_chunk_store_seconds";
+
+/// Histogram of the time (in seconds) required to search the chunks matching an ontology
+/// expression group, labeled by `ontology_tag`
+pub const QUERY_GROUP_SECONDS: &str = "mosaicod_query_group_seconds";
+
+/// Histogram of the time (in seconds) spent waiting for a concurrent write permit
+pub const WRITE_SEMAPHORE_WAIT_SECONDS: &str = "mosaicod_write_semaphore_wait_seconds";
+
+/// Gauge of the connections currently opened by the database pool
+pub const DB_POOL_CONNECTIONS: &str = "mosaicod_db_pool_connections";
+
+/// Gauge of the idle connections of the database pool
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "mosaicod_db_pool_idle_connections";
+
+/// Gauge of the maximum number of connections of the database pool
+pub const DB_POOL_MAX_CONNECTIONS: &str = "mosaicod_db_pool_max_connections";
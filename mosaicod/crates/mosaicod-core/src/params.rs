@@ -5,7 +5,7 @@
 //! For retrieving parameters that can be configured during startup (with env variables),
 //! see the [`load_configurables_from_env`] function and the [`configurables`] accessor.
 
-use super::error;
+use super::{error, types};
 use std::marker::PhantomData;
 
 /// Header name for client requests
@@ -27,6 +27,8 @@ pub mod ext {
     /// Json file extension
     pub const JSON: &str = "json";
     pub const PARQUET: &str = "parquet";
+    /// Arrow IPC file extension
+    pub const ARROW: &str = "arrow";
 }
 
 use std::{env, str::FromStr, sync::OnceLock};
@@ -186,11 +188,54 @@ pub struct Params {
     /// Defaults to 0 (no limit).
     pub query_engine_memory_pool_size: Param<usize>,
 
+    /// Maximum number of timestamps of the grid of a time-aligned read, requests spanning
+    /// more grid points are rejected.
+    ///
+    /// Defaults to 10M.
+    pub max_aligned_grid_size: Param<usize>,
+
     /// Size (in bytes) of the in-memory buffer used for encoding parquet data.
     ///
     /// Default to 50 MB
     pub parquet_in_memory_encoding_buffer_size: Param<usize>,
 
+    /// In-memory size (in bytes) of the batches received by a topic upload that are
+    /// accumulated before being encoded in a single chunk. Set to 0 to store each batch in
+    /// its own chunk.
+    ///
+    /// Defaults to 25 MB.
+    pub chunk_target_size: Param<usize>,
+
+    /// Number of rows received by a topic upload that are accumulated before being encoded
+    /// in a single chunk, regardless of [`Params::chunk_target_size`].
+    ///
+    /// Defaults to 0 (no limit).
+    pub chunk_target_rows: Param<usize>,
+
+    /// Step used to quantize the floating point values of point cloud topics before storing
+    /// them. Values are rounded to the largest power of two not greater than the step, so
+    /// the rounding error is at most half the step. Set to 0 to store values as they are.
+    ///
+    /// Defaults to 0 (no quantization).
+    pub point_cloud_quantization_step: Param<f64>,
+
+    /// Number of frames between two keyframes of video topics. Each chunk starts with a
+    /// keyframe, shorter intervals speed up seeking at the cost of a larger size.
+    ///
+    /// Defaults to 60.
+    pub video_keyframe_interval: Param<u32>,
+
+    /// Quantization parameter of video topics, from 0 (best quality) to 51 (smallest size).
+    ///
+    /// Defaults to 23.
+    pub video_quantization: Param<u8>,
+
+    /// Policy applied to uploads containing null, negative or unsorted timestamps, when not
+    /// specified by the client.
+    ///
+    /// Defaults to [`types::TimestampPolicy::Warn`].
+    pub timestamp_policy: Param<types::TimestampPolicy>,
+
     /// Path of the `cert.pem` file used as TLS certificate
     pub tls_certificate_file: Param<String>,
 
@@ -202,10 +247,18 @@ pub struct Params {
     /// Maximum number of database connections in the pool
     pub max_db_connections: Param<u32>,
 
+    /// Object storage service used when the store is not on the local filesystem.
+    ///
+    /// Defaults to [`types::StoreBackend::S3`].
+    pub store_backend: Param<types::StoreBackend>,
     pub store_endpoint: Param<String>,
     pub store_bucket: Param<String>,
     pub store_secret_key: Param<String, Hidden>,
     pub store_access_key: Param<String>,
+    /// Name of the Azure storage account
+    pub store_account: Param<String>,
+    /// Path of the JSON key of the Google Cloud service account
+    pub store_service_account_path: Param<String>,
 }
 
 /// Options for loading parameters from environment variables
@@ -250,8 +303,21 @@ pub fn load_params_from_env(config: ParamsLoadOptions) -> error::PublicResult<()
             "MOSAICOD_PARQUET_IN_MEMORY_ENCODING_BUFFER_SIZE",
             50 * 1_000_000,
         ),
+        chunk_target_size: Param::optional("MOSAICOD_CHUNK_TARGET_SIZE", 25 * 1_000_000),
+        chunk_target_rows: Param::optional("MOSAICOD_CHUNK_TARGET_ROWS", 0),
+        point_cloud_quantization_step: Param::optional(
+            "MOSAICOD_POINT_CLOUD_QUANTIZATION_STEP",
+            0.0,
+        ),
+        video_keyframe_interval: Param::optional("MOSAICOD_VIDEO_KEYFRAME_INTERVAL", 60),
+        video_quantization: Param::optional("MOSAICOD_VIDEO_QUANTIZATION", 23),
+        timestamp_policy: Param::optional(
+            "MOSAICOD_TIMESTAMP_POLICY",
+            types::TimestampPolicy::Warn,
+        ),
         max_batch_size: Param::optional("MOSAICOD_MAX_BATCH_SIZE", 8192),
         query_engine_memory_pool_size: Param::optional("MOSAICOD_QUERY_ENGINE_MEMORY_POOL_SIZE", 0),
+        max_aligned_grid_size: Param::optional("MOSAICOD_MAX_ALIGNED_GRID_SIZE", 10_000_000),
 
         // tls
         tls_certificate_file: Param::optional("MOSAICOD_TLS_CERT_FILE", "".to_owned()),
@@ -265,10 +331,16 @@ pub fn load_params_from_env(config: ParamsLoadOptions) -> error::PublicResult<()
         },
 
         // store
+        store_backend: Param::optional("MOSAICOD_STORE_BACKEND", types::StoreBackend::S3),
         store_endpoint: Param::optional("MOSAICOD_STORE_ENDPOINT", "".to_owned()),
         store_bucket: Param::optional("MOSAICOD_STORE_BUCKET", "".to_owned()),
         store_secret_key: Param::optional("MOSAICOD_STORE_SECRET_KEY", "".to_owned()),
         store_access_key: Param::optional("MOSAICOD_STORE_ACCESS_KEY", "".to_owned()),
+        store_account: Param::optional("MOSAICOD_STORE_ACCOUNT", "".to_owned()),
+        store_service_account_path: Param::optional(
+            "MOSAICOD_STORE_SERVICE_ACCOUNT_PATH",
+            "".to_owned(),
+        ),
     };
 
     let _ = ENV.set(ev);
@@ -1,5 +1,7 @@
 use super::*;
 use crate::{Error, error::PublicError, types};
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
 use crc32fast::Hasher;
 use std::str::FromStr;
 
@@ -23,6 +25,12 @@ pub enum ApiKeyError {
     #[error("fingerprint mismatch")]
     TokenFingerprintMismatch,
 
+    #[error("bad token hash")]
+    BadTokenHash,
+
+    #[error("missing token hash")]
+    MissingTokenHash,
+
     #[error("invalid string to permission cast")]
     InvalidStringToPermissionCast,
 
@@ -31,6 +39,12 @@ pub enum ApiKeyError {
 
     #[error("missing permissions")]
     MissingPermissions,
+
+    #[error("scope permission exceeds the permission of the key")]
+    ScopePermissionExceeded,
+
+    #[error("invalid scope `{0}`")]
+    InvalidScope(String),
 }
 
 impl PublicError for ApiKeyError {
@@ -200,6 +214,57 @@ impl Default for Token {
     }
 }
 
+/// Salted slow hash (argon2id) of a token payload, encoded as a PHC string.
+///
+/// Only the hash of the payload is persisted, so that the stored API keys can't be used
+/// to authenticate.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TokenHash(String);
+
+impl TokenHash {
+    /// Number of random bytes used to salt the hash
+    const SALT_LENGTH: usize = 16;
+
+    /// Computes the salted hash of the token payload
+    pub fn new(token: &Token) -> Self {
+        // Use of `.expect()` since salt length and hashing parameters are known to be valid
+        let salt = SaltString::encode_b64(&rand::random::<[u8; Self::SALT_LENGTH]>())
+            .expect("invalid salt length");
+
+        let hash = Argon2::default()
+            .hash_password(&token.payload, &salt)
+            .expect("invalid hashing parameters");
+
+        Self(hash.to_string())
+    }
+
+    /// Checks if the payload of `token` matches this hash
+    pub fn verify(&self, token: &Token) -> bool {
+        PasswordHash::new(&self.0)
+            .and_then(|hash| Argon2::default().verify_password(&token.payload, &hash))
+            .is_ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for TokenHash {
+    type Err = ApiKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PasswordHash::new(s).map_err(|_| ApiKeyError::BadTokenHash)?;
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl std::fmt::Display for TokenHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // PERMISSIONS
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -211,7 +276,7 @@ impl Default for Token {
 /// - **Delete**: grants read, write and delete access to data
 /// - **Manage**: grants read, write and delete access to data. Plus the authorization to manage other API keys.
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Permission {
     Read = 0b0000_0001,
     Write = 0b0000_0011,
@@ -282,20 +347,111 @@ impl TryFrom<u8> for Permission {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// SCOPES
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Grants a permission on the resources whose locator starts with `prefix`.
+///
+/// A scope can be built from a string like `fleet_a_:write`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKeyScope {
+    pub prefix: String,
+    pub permission: Permission,
+}
+
+impl ApiKeyScope {
+    /// Character used to separate prefix and permission in the scope string representation
+    const SEPARATOR: char = ':';
+
+    pub fn new(prefix: &str, permission: Permission) -> Self {
+        Self {
+            // Locators are stored without the leading slash
+            prefix: prefix.trim().trim_start_matches('/').to_owned(),
+            permission,
+        }
+    }
+
+    /// Returns `true` if the resource identified by `locator` is in this scope
+    pub fn contains(&self, locator: &str) -> bool {
+        locator.trim_start_matches('/').starts_with(&self.prefix)
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = ApiKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, permission) = s
+            .rsplit_once(Self::SEPARATOR)
+            .ok_or_else(|| ApiKeyError::InvalidScope(s.to_owned()))?;
+
+        Ok(Self::new(prefix, permission.parse()?))
+    }
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.prefix,
+            Self::SEPARATOR,
+            String::from(self.permission)
+        )
+    }
+}
+
+/// Returns the permission granted on the resource identified by `locator` by a key having
+/// `permission` and restricted to `scopes`.
+///
+/// If no scope is defined the key permission applies to every resource, otherwise the highest
+/// permission among the scopes containing the resource is returned (if any).
+pub fn scoped_permission(
+    permission: Permission,
+    scopes: &[ApiKeyScope],
+    locator: &str,
+) -> Option<Permission> {
+    if scopes.is_empty() {
+        return Some(permission);
+    }
+
+    scopes
+        .iter()
+        .filter(|scope| scope.contains(locator))
+        .map(|scope| scope.permission.min(permission))
+        .max()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// API KEY
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 /// Represent an authorization policy.
 ///
 /// The policy is composed of:
-/// * an API Key like `msco:0938n8b37r378brf`
+/// * the fingerprint of an API Key like `msco_vrfeceju4lqivysxgaseefa3tsxs0vrl_1b676530`,
+///   used as key identifier
+/// * the salted hash of the API Key payload, used to verify the key
 /// * the associated permissions (like: read, write, ..)
+/// * optional scopes restricting the permissions to some resources
 /// * a description to keep track of the purpose of the key
 /// * an optional expire date
 #[derive(Clone)]
 pub struct ApiKey {
-    pub key: Token,
+    /// Fingerprint of the token, used as key identifier
+    pub fingerprint: TokenFingerprint,
+
+    /// Hash of the token payload
+    pub payload_hash: TokenHash,
 
     /// Permissions associated with the scope
     pub permission: Permission,
 
+    /// Locator scopes restricting the key permissions, if empty the key permission
+    /// applies to every resource
+    pub scopes: Vec<ApiKeyScope>,
+
     /// Description to keep track of the purpose of the key
     pub description: String,
 
@@ -307,38 +463,73 @@ pub struct ApiKey {
 }
 
 impl ApiKey {
-    /// Create a new API key
+    /// Create a new API key for the given token
     ///
     /// # Example
     /// ```
-    /// use mosaicod_core::types::{ApiKey, auth::Permission};
+    /// use mosaicod_core::types::{ApiKey, auth::{Permission, Token}};
     ///
     /// // Read permission
-    /// let policy = ApiKey::new(Permission::Read, "dummy key".to_owned(), None);
+    /// let token = Token::new();
+    /// let policy = ApiKey::new(&token, Permission::Read, "dummy key".to_owned(), None);
     ///
     /// // Write permissions (read is implicitly inherited)
+    /// let token = Token::new();
     /// let policy = ApiKey::new(
+    ///     &token,
     ///     Permission::Write,
     ///     "dummy key".to_owned(),
     ///     None
     /// );
+    /// ```
     pub fn new(
+        token: &Token,
         permission: Permission,
         description: String,
         expires_at: Option<types::Timestamp>,
     ) -> Self {
         Self {
-            key: Token::new(),
+            fingerprint: token.fingerprint,
+            payload_hash: TokenHash::new(token),
             permission,
+            scopes: Vec::new(),
             created_at: Timestamp::now(),
             expires_at,
             description,
         }
     }
 
-    /// Get the token associated with this API key
-    pub fn token(&self) -> &Token {
-        &self.key
+    /// Restricts the key permissions to the given locator scopes.
+    ///
+    /// Returns an error if a scope grants a permission higher than the key permission.
+    pub fn with_scopes(mut self, scopes: Vec<ApiKeyScope>) -> Result<Self, ApiKeyError> {
+        if scopes
+            .iter()
+            .any(|scope| scope.permission > self.permission)
+        {
+            return Err(ApiKeyError::ScopePermissionExceeded);
+        }
+
+        self.scopes = scopes;
+        Ok(self)
+    }
+
+    /// Returns the permission granted by this key on the resource identified by `locator`.
+    /// See [`scoped_permission`] for more details.
+    pub fn permission_on(&self, locator: &str) -> Option<Permission> {
+        scoped_permission(self.permission, &self.scopes, locator)
+    }
+
+    /// Get the fingerprint of the token associated with this API key
+    pub fn fingerprint(&self) -> &str {
+        std::str::from_utf8(&self.fingerprint).unwrap()
+    }
+
+    /// Check if `token` is the token associated with this API key.
+    ///
+    /// Note: this is an intentionally slow operation since requires to compute the token hash.
+    pub fn verify(&self, token: &Token) -> bool {
+        self.fingerprint == token.fingerprint && self.payload_hash.verify(token)
     }
 
     /// Check if the API key is expired
@@ -413,6 +604,83 @@ mod tests {
             .expect("Unable to parse APi key token");
     }
 
+    #[test]
+    fn api_key_verify() {
+        let token = Token::new();
+        let api_key = ApiKey::new(&token, Permission::Read, "test".to_owned(), None);
+
+        assert_eq!(api_key.fingerprint(), token.fingerprint());
+        assert!(!api_key.payload_hash.as_str().contains(token.payload()));
+        assert!(api_key.verify(&token));
+
+        // Same fingerprint, different payload
+        let forged = Token::from_bytes([b'a'; 32], token.fingerprint);
+        assert!(!api_key.verify(&forged));
+        assert!(!api_key.verify(&Token::new()));
+
+        // Hash string roundtrip
+        let hash: TokenHash = api_key.payload_hash.to_string().parse().unwrap();
+        assert!(hash.verify(&token));
+        assert_eq!(
+            "not a hash".parse::<TokenHash>().unwrap_err(),
+            ApiKeyError::BadTokenHash
+        );
+    }
+
+    #[test]
+    fn api_key_scopes() {
+        let scope: ApiKeyScope = "/fleet_a_:write".parse().unwrap();
+        assert_eq!(scope, ApiKeyScope::new("fleet_a_", Permission::Write));
+        assert_eq!(scope.to_string(), "fleet_a_:write");
+        assert!(scope.contains("fleet_a_run1/camera"));
+        assert!(scope.contains("/fleet_a_run1"));
+        assert!(!scope.contains("fleet_b_run1"));
+
+        assert!(matches!(
+            "fleet_a_".parse::<ApiKeyScope>(),
+            Err(ApiKeyError::InvalidScope(_))
+        ));
+        assert!("fleet_a_:admin".parse::<ApiKeyScope>().is_err());
+
+        // Write only under `fleet_a_` and read everything else
+        let api_key = ApiKey::new(&Token::new(), Permission::Write, String::new(), None)
+            .with_scopes(vec![
+                ApiKeyScope::new("fleet_a_", Permission::Write),
+                ApiKeyScope::new("", Permission::Read),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            api_key.permission_on("fleet_a_run1/gps"),
+            Some(Permission::Write)
+        );
+        assert_eq!(
+            api_key.permission_on("fleet_b_run1"),
+            Some(Permission::Read)
+        );
+
+        // Only resources under `fleet_a_` are visible
+        let api_key = ApiKey::new(&Token::new(), Permission::Delete, String::new(), None)
+            .with_scopes(vec![ApiKeyScope::new("fleet_a_", Permission::Read)])
+            .unwrap();
+        assert_eq!(
+            api_key.permission_on("fleet_a_run1"),
+            Some(Permission::Read)
+        );
+        assert_eq!(api_key.permission_on("fleet_b_run1"), None);
+
+        // Unscoped keys grant the same permission everywhere
+        let api_key = ApiKey::new(&Token::new(), Permission::Read, String::new(), None);
+        assert_eq!(api_key.permission_on("anything"), Some(Permission::Read));
+
+        // Scopes can't exceed key permission
+        assert!(matches!(
+            ApiKey::new(&Token::new(), Permission::Read, String::new(), None)
+                .with_scopes(vec![ApiKeyScope::new("fleet_a_", Permission::Write)]),
+            Err(ApiKeyError::ScopePermissionExceeded)
+        ));
+    }
+
     #[test]
     fn api_key_bad() {
         // Change header with a longer string
@@ -17,6 +17,57 @@ impl OntologyModelStats {
     }
 }
 
+/// Statistics of the timestamp column of a chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampStats {
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+
+    pub has_null: bool,
+
+    /// `true` if the evaluated timestamps are all non-null and sorted in non-decreasing order
+    pub sorted: bool,
+
+    /// Last evaluated timestamp, used to check the ordering
+    last: Option<i64>,
+}
+
+impl Default for TimestampStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimestampStats {
+    pub fn new() -> Self {
+        Self {
+            min: None,
+            max: None,
+            has_null: false,
+            sorted: true,
+            last: None,
+        }
+    }
+
+    /// Evaluates the next timestamp (in row order) and updates the statistics.
+    /// If the provided value is [`None`], it is condered a null value.
+    pub fn eval(&mut self, val: Option<i64>) {
+        let Some(val) = val else {
+            self.has_null = true;
+            self.sorted = false;
+            return;
+        };
+
+        if self.last.is_some_and(|last| last > val) {
+            self.sorted = false;
+        }
+        self.last = Some(val);
+
+        self.min = Some(self.min.map_or(val, |min| min.min(val)));
+        self.max = Some(self.max.map_or(val, |max| max.max(val)));
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Stats {
     Numeric(NumericStats),
@@ -172,6 +223,29 @@ impl TextualStats {
 mod tests {
     use super::*;
 
+    #[test]
+    fn timestamp_stats() {
+        let mut stats = TimestampStats::new();
+        assert!(stats.sorted);
+        assert_eq!(stats.min, None);
+
+        for ts in [10, 20, 20, 30] {
+            stats.eval(Some(ts));
+        }
+        assert_eq!((stats.min, stats.max), (Some(10), Some(30)));
+        assert!(stats.sorted);
+
+        stats.eval(Some(5));
+        assert_eq!((stats.min, stats.max), (Some(5), Some(30)));
+        assert!(!stats.sorted);
+
+        let mut stats = TimestampStats::new();
+        stats.eval(Some(10));
+        stats.eval(None);
+        assert!(stats.has_null);
+        assert!(!stats.sorted);
+    }
+
     #[test]
     fn text_stats_empty_string_is_valid_min() {
         let mut stats = TextualStats::new();
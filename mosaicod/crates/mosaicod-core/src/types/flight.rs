@@ -1,16 +1,61 @@
 use crate::types;
-use crate::types::TimestampRange;
+use crate::types::{FillPolicy, TimestampRange};
 
 /// Message used to initiate the flight communication to upload a new datastream
 pub struct DoPutCmd {
     pub resource_locator: String, //(cabba) TODO: replace this with a resource locator
     pub key: String,
+    /// Token used to continue an interrupted upload, required if the topic already has data
+    pub resume_token: Option<types::ResumeToken>,
+    /// If `false` the topic is left unlocked at the end of the stream, so that more data can be
+    /// appended by a following upload
+    pub finalize: bool,
+    /// Policy applied to invalid timestamps, the server default if not provided
+    pub timestamp_policy: Option<types::TimestampPolicy>,
 }
 
 /// Request info on a mosaico resource (topic or sequence)
 pub struct GetFlightInfoCmd {
     pub resource_locator: String, //(cabba) TODO: replace this with a resource locator
     pub timestamp_range: Option<TimestampRange>,
+    /// Optional list of (dotted) field paths to project, e.g. `pose.position.x`
+    pub fields: Option<Vec<String>>,
+    /// Optional ontology filter (as raw JSON) used to filter the streamed rows
+    pub ontology_filter: Option<String>,
+    /// Optional time alignment of several topics of the requested sequence
+    pub align: Option<AlignCmd>,
+    /// Optional export of the requested sequence as a single file
+    pub export: Option<ExportCmd>,
+}
+
+/// Request of a query whose results are streamed by `do_get`
+pub struct QueryCmd {
+    /// Query filter (as raw JSON)
+    pub query: String,
+}
+
+/// Request of a time-aligned read of several topics of the same sequence
+pub struct AlignCmd {
+    /// Topics to align
+    pub topics: Vec<types::TopicLocator>,
+    /// Distance in nanoseconds between two consecutive points of the alignment grid
+    pub period_ns: i64,
+    /// Policy used to fill topic values on the grid points
+    pub fill: FillPolicy,
+}
+
+/// File formats used to export sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Mcap,
+}
+
+/// Request of an export of a sequence as a single file
+pub struct ExportCmd {
+    /// Format of the exported file
+    pub format: ExportFormat,
+    /// Topics to export, all the sequence topics if not provided
+    pub topics: Option<Vec<types::TopicLocator>>,
 }
 
 pub struct TicketTopic {
@@ -18,4 +63,46 @@ pub struct TicketTopic {
     pub locator: types::TopicLocator,
     /// Optional timestamp range used to limit the data stream
     pub timestamp_range: Option<TimestampRange>,
+    /// Optional list of (dotted) field paths used to project the data stream
+    pub fields: Option<Vec<String>>,
+    /// Optional ontology filter (as raw JSON) used to filter the data stream rows
+    pub ontology_filter: Option<String>,
+}
+
+/// Ticket used to stream several topics aligned on a common timestamp grid
+pub struct TicketAligned {
+    /// Topics to align
+    pub topics: Vec<types::TopicLocator>,
+    /// Distance in nanoseconds between two consecutive points of the alignment grid
+    pub period_ns: i64,
+    /// Policy used to fill topic values on the grid points
+    pub fill: FillPolicy,
+    /// Optional timestamp range used to limit the alignment grid
+    pub timestamp_range: Option<TimestampRange>,
+}
+
+/// Ticket used to stream a sequence exported as a single file
+pub struct TicketExport {
+    /// Exported sequence
+    pub sequence: types::SequenceLocator,
+    /// Format of the exported file
+    pub format: ExportFormat,
+    /// Exported topics
+    pub topics: Vec<types::TopicLocator>,
+    /// Optional timestamp range used to limit the exported data
+    pub timestamp_range: Option<TimestampRange>,
+}
+
+/// Ticket used to stream the results of a query
+pub struct TicketQuery {
+    /// Query filter (as raw JSON)
+    pub query: String,
+}
+
+/// Tickets handled by the `do_get` endpoint
+pub enum Ticket {
+    Topic(TicketTopic),
+    Aligned(TicketAligned),
+    Export(TicketExport),
+    Query(TicketQuery),
 }
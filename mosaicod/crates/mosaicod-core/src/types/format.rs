@@ -28,6 +28,19 @@ pub enum Format {
     /// Resource format for images and dense multi-dimensional arrays.
     /// This format is optimized for storing high-dimensional data efficiently.
     Image,
+
+    /// Resource format for point clouds, where each record holds a scan as lists of
+    /// floating point coordinates (e.g. `x`, `y`, `z`, `intensity`).
+    /// This format is optimized for the compression of floating point columns.
+    PointCloud,
+
+    /// Resource format for camera streams, where each record holds a raw frame.
+    /// Consecutive frames are encoded as a compressed video stream.
+    Video,
+
+    /// Resource format for small, high-rate records (e.g. joint states or IMU samples).
+    /// Data is stored as Arrow IPC files, trading file size for encoding and decoding speed.
+    ArrowIpc,
 }
 
 impl Format {
@@ -37,6 +50,9 @@ impl Format {
             Format::Default => "default",
             Format::Ragged => "ragged",
             Format::Image => "image",
+            Format::PointCloud => "point_cloud",
+            Format::Video => "video",
+            Format::ArrowIpc => "arrow_ipc",
         }
     }
 }
@@ -49,6 +65,9 @@ impl std::str::FromStr for Format {
             "default" => Ok(Self::Default),
             "ragged" => Ok(Self::Ragged),
             "image" => Ok(Self::Image),
+            "point_cloud" => Ok(Self::PointCloud),
+            "video" => Ok(Self::Video),
+            "arrow_ipc" => Ok(Self::ArrowIpc),
             _ => Err(FormatError::unknown_format(value)),
         }
     }
@@ -78,6 +97,18 @@ mod tests {
         let image = Format::from_str("image");
         assert!(image.is_ok());
         assert_eq!(image.as_ref().unwrap(), &Format::Image);
+
+        let point_cloud = Format::from_str("point_cloud");
+        assert!(point_cloud.is_ok());
+        assert_eq!(point_cloud.as_ref().unwrap(), &Format::PointCloud);
+
+        let video = Format::from_str("video");
+        assert!(video.is_ok());
+        assert_eq!(video.as_ref().unwrap(), &Format::Video);
+
+        let arrow_ipc = Format::from_str("arrow_ipc");
+        assert!(arrow_ipc.is_ok());
+        assert_eq!(arrow_ipc.as_ref().unwrap(), &Format::ArrowIpc);
     }
 
     #[test]
@@ -85,5 +116,8 @@ mod tests {
         assert_eq!("ragged", Format::Ragged.to_string());
         assert_eq!("default", Format::Default.to_string());
         assert_eq!("image", Format::Image.to_string());
+        assert_eq!("point_cloud", Format::PointCloud.to_string());
+        assert_eq!("video", Format::Video.to_string());
+        assert_eq!("arrow_ipc", Format::ArrowIpc.to_string());
     }
 }
@@ -0,0 +1,169 @@
+use super::{SequenceLocator, Timestamp};
+use crate::{Error, error::PublicError};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Policy applied when an imported sequence has the same locator of an existing one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// The import fails, the existing sequence is left untouched
+    #[default]
+    Fail,
+    /// The import is skipped, the existing sequence is left untouched
+    Skip,
+    /// The existing sequence is deleted and replaced by the imported one
+    Replace,
+}
+
+/// Folder of the store where `sequence_export` writes the sequence bundles
+pub const EXPORTS_FOLDER: &str = "exports";
+
+/// Folder of the store where clients place the files imported with `sequence_import`
+pub const IMPORTS_FOLDER: &str = "imports";
+
+#[derive(Error, Debug)]
+pub enum TransferPathError {
+    #[error("`{0}` is not in the `exports` or `imports` folder of a sequence")]
+    OutsideTransferFolders(String),
+    #[error("`{0}` is not a valid relative path")]
+    InvalidPath(String),
+}
+
+impl PublicError for TransferPathError {
+    fn error(&self) -> Error {
+        Error::bad_request(self.to_string())
+    }
+}
+
+/// Path in the store of a file exchanged with the clients, i.e. a bundle written by
+/// `sequence_export` or a file read by `sequence_import`.
+///
+/// These files are confined in a folder named after the sequence they belong to, inside
+/// [`EXPORTS_FOLDER`] or [`IMPORTS_FOLDER`] (e.g. `exports/drive_25032026/bundle.tar`),
+/// so that clients can't read or overwrite the data files of the platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferPath {
+    path: String,
+    sequence: SequenceLocator,
+}
+
+impl TransferPath {
+    /// Returns the path of a new bundle of `sequence`, named after the current time
+    pub fn new_export(sequence: &SequenceLocator) -> Self {
+        Self {
+            path: format!(
+                "{}/{}/{}.tar",
+                EXPORTS_FOLDER,
+                sequence,
+                Timestamp::now().as_i64()
+            ),
+            sequence: sequence.clone(),
+        }
+    }
+
+    /// Sequence owning the folder of the file
+    pub fn sequence(&self) -> &SequenceLocator {
+        &self.sequence
+    }
+}
+
+impl FromStr for TransferPath {
+    type Err = TransferPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments: Vec<&str> = s.split('/').collect();
+
+        // Absolute paths produce an empty first segment
+        if segments.iter().any(|segment| {
+            segment.is_empty() || *segment == "." || *segment == ".." || segment.contains('\\')
+        }) {
+            return Err(TransferPathError::InvalidPath(s.to_owned()));
+        }
+
+        let [folder, sequence, _, ..] = segments.as_slice() else {
+            return Err(TransferPathError::OutsideTransferFolders(s.to_owned()));
+        };
+
+        if *folder != EXPORTS_FOLDER && *folder != IMPORTS_FOLDER {
+            return Err(TransferPathError::OutsideTransferFolders(s.to_owned()));
+        }
+
+        let sequence = sequence
+            .parse::<SequenceLocator>()
+            .map_err(|_| TransferPathError::OutsideTransferFolders(s.to_owned()))?;
+
+        Ok(Self {
+            path: s.to_owned(),
+            sequence,
+        })
+    }
+}
+
+impl AsRef<std::path::Path> for TransferPath {
+    fn as_ref(&self) -> &std::path::Path {
+        std::path::Path::new(&self.path)
+    }
+}
+
+impl std::fmt::Display for TransferPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+impl From<TransferPath> for String {
+    fn from(value: TransferPath) -> Self {
+        value.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_path() {
+        let path: TransferPath = "exports/drive/bundle.tar".parse().unwrap();
+        assert_eq!(path.sequence(), &"drive");
+        assert_eq!(path.to_string(), "exports/drive/bundle.tar");
+
+        let path: TransferPath = "imports/drive/bags/drive.mcap".parse().unwrap();
+        assert_eq!(path.sequence(), &"drive");
+
+        let export = TransferPath::new_export(&"drive".parse().unwrap());
+        assert!(export.to_string().starts_with("exports/drive/"));
+        assert_eq!(
+            export
+                .to_string()
+                .parse::<TransferPath>()
+                .unwrap()
+                .sequence(),
+            &"drive"
+        );
+
+        // Data files of the platform
+        assert!(
+            "sq_drive/tp_camera/data/0.parquet"
+                .parse::<TransferPath>()
+                .is_err()
+        );
+        assert!("exports/drive".parse::<TransferPath>().is_err());
+        assert!(
+            "exports/drive/../../sq_drive/tp_camera/data/0.parquet"
+                .parse::<TransferPath>()
+                .is_err()
+        );
+        assert!("/exports/drive/bundle.tar".parse::<TransferPath>().is_err());
+        assert!("exports/drive//bundle.tar".parse::<TransferPath>().is_err());
+        assert!(
+            "exports/./drive/bundle.tar"
+                .parse::<TransferPath>()
+                .is_err()
+        );
+        assert!(
+            "exports/drive:session/bundle.tar"
+                .parse::<TransferPath>()
+                .is_err()
+        );
+    }
+}
@@ -9,6 +9,9 @@ pub use time::*;
 mod format;
 pub use format::*;
 
+mod writer_options;
+pub use writer_options::*;
+
 mod notification;
 pub use notification::*;
 
@@ -27,6 +30,12 @@ pub use uuid::*;
 mod session;
 pub use session::*;
 
+mod import;
+pub use import::*;
+
+mod store;
+pub use store::*;
+
 pub mod auth;
 pub use auth::ApiKey;
 pub use auth::ApiKeyError;
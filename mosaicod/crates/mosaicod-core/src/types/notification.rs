@@ -2,12 +2,14 @@ use super::*;
 
 pub enum NotificationType {
     Error,
+    Warning,
 }
 
 impl std::fmt::Display for NotificationType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
         }
     }
 }
@@ -18,6 +20,7 @@ impl std::str::FromStr for NotificationType {
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value {
             "error" => Ok(Self::Error),
+            "warning" => Ok(Self::Warning),
             _ => Err(std::io::Error::other(format!(
                 "unknown notification type `{}`",
                 value
@@ -1,4 +1,4 @@
-use super::{Format, SessionMetadata, TimestampRange, Uuid};
+use super::{Format, SessionMetadata, TimestampRange, Uuid, WriterOptions};
 use crate::{Error, error::PublicError, params, traits, types};
 use std::cmp::PartialEq;
 use std::ops::Deref;
@@ -170,6 +170,9 @@ pub struct TopicLocator {
     inner: Locator,
     /// An optional time range to filter data within the topic.
     pub timestamp_range: Option<TimestampRange>,
+    /// Optional time intervals of interest within the topic (e.g. the intervals matching a
+    /// query), sorted by timestamp.
+    pub intervals: Option<Vec<TimestampRange>>,
 }
 
 impl TopicLocator {
@@ -177,6 +180,11 @@ impl TopicLocator {
         self.timestamp_range = Some(ts);
         self
     }
+
+    pub fn with_intervals(mut self, intervals: Vec<TimestampRange>) -> Self {
+        self.intervals = Some(intervals);
+        self
+    }
 }
 
 impl FromStr for TopicLocator {
@@ -194,6 +202,7 @@ impl FromStr for TopicLocator {
         Ok(Self {
             inner: locator,
             timestamp_range: None,
+            intervals: None,
         })
     }
 }
@@ -215,6 +224,7 @@ impl From<Locator> for TopicLocator {
         Self {
             inner: locator,
             timestamp_range: None,
+            intervals: None,
         }
     }
 }
@@ -339,6 +349,7 @@ impl std::fmt::Display for TopicPathInStore {
 pub struct TopicOntologyProperties {
     pub serialization_format: Format,
     pub ontology_tag: String,
+    pub writer_options: WriterOptions,
 }
 
 /// Properties defining the data semantic and encoding for a topic.
@@ -386,6 +397,7 @@ impl<M> TopicMetadata<M> {
 pub struct TopicChunksStats {
     pub total_size_bytes: i64,
     pub total_row_count: i64,
+    pub chunks_count: i64,
 }
 
 /// Metadata properties associated to a topic.
@@ -650,11 +662,22 @@ impl std::fmt::Display for SessionLocator {
 pub struct SequenceTopicGroup {
     pub sequence: SequenceLocator,
     pub topics: Vec<TopicLocator>,
+    /// Creation time of the sequence, if known
+    pub created_at: Option<types::Timestamp>,
 }
 
 impl SequenceTopicGroup {
     pub fn new(sequence: SequenceLocator, topics: Vec<TopicLocator>) -> Self {
-        Self { sequence, topics }
+        Self {
+            sequence,
+            topics,
+            created_at: None,
+        }
+    }
+
+    pub fn with_created_at(mut self, created_at: types::Timestamp) -> Self {
+        self.created_at = Some(created_at);
+        self
     }
 
     pub fn into_parts(self) -> (SequenceLocator, Vec<TopicLocator>) {
@@ -720,6 +743,23 @@ impl SequenceTopicGroupSet {
 
         Self(result)
     }
+
+    /// Keeps only the sequences and topics whose locator satisfies `visible`.
+    ///
+    /// A group is retained if its sequence or at least one of its topics is visible.
+    pub fn retain_visible(self, visible: impl Fn(&str) -> bool) -> Self {
+        let groups = self
+            .0
+            .into_iter()
+            .filter_map(|mut grp| {
+                grp.topics.retain(|topic| visible(topic));
+
+                (visible(&grp.sequence) || !grp.topics.is_empty()).then_some(grp)
+            })
+            .collect();
+
+        Self(groups)
+    }
 }
 
 impl Default for SequenceTopicGroupSet {
@@ -808,6 +848,37 @@ mod tests {
         assert_eq!(merged[0].topics.len(), 3);
     }
 
+    #[test]
+    fn test_retain_visible_sequence_topic_groups() {
+        let groups = SequenceTopicGroupSet::new(vec![
+            SequenceTopicGroup::new(
+                SequenceLocator::from_str("fleet_a_run").unwrap(),
+                vec![TopicLocator::from_str("fleet_a_run/gps").unwrap()],
+            ),
+            SequenceTopicGroup::new(
+                SequenceLocator::from_str("fleet_b_run").unwrap(),
+                vec![
+                    TopicLocator::from_str("fleet_b_run/gps").unwrap(),
+                    TopicLocator::from_str("fleet_b_run/camera").unwrap(),
+                ],
+            ),
+            SequenceTopicGroup::new(SequenceLocator::from_str("other").unwrap(), vec![]),
+        ]);
+
+        let groups: Vec<SequenceTopicGroup> = groups
+            .retain_visible(|loc| loc.starts_with("fleet_a_") || loc.starts_with("fleet_b_run/c"))
+            .into();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].sequence, "fleet_a_run");
+        assert_eq!(groups[0].topics.len(), 1);
+        assert_eq!(groups[1].sequence, "fleet_b_run");
+        assert_eq!(
+            groups[1].topics,
+            vec![TopicLocator::from_str("fleet_b_run/camera").unwrap()]
+        );
+    }
+
     #[test]
     fn test_str_to_locator_conversion() {
         let t1 = TopicLocator::from_str("my_sequence/topic_1").unwrap();
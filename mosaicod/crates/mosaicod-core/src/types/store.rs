@@ -0,0 +1,37 @@
+/// Object storage service used to store the data of the platform, when not running on
+/// the local filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreBackend {
+    /// Amazon S3 or any S3-compatible service (e.g. MinIO)
+    S3,
+    /// Google Cloud Storage
+    Gcs,
+    /// Azure Blob Storage
+    Azure,
+}
+
+impl std::fmt::Display for StoreBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::S3 => write!(f, "s3"),
+            Self::Gcs => write!(f, "gcs"),
+            Self::Azure => write!(f, "azure"),
+        }
+    }
+}
+
+impl std::str::FromStr for StoreBackend {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "s3" => Ok(Self::S3),
+            "gcs" => Ok(Self::Gcs),
+            "azure" => Ok(Self::Azure),
+            _ => Err(std::io::Error::other(format!(
+                "unknown store backend `{}`",
+                value
+            ))),
+        }
+    }
+}
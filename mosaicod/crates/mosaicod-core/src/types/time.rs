@@ -178,6 +178,58 @@ impl From<Timestamp> for DateTime {
     }
 }
 
+/// Policy used to fill the value of a timeseries on a timestamp that has no sample,
+/// e.g. when aligning several timeseries on a common timestamp grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillPolicy {
+    /// Use the sample closest in time, either before or after the timestamp
+    Nearest,
+    /// Use the last sample before (or at) the timestamp
+    Previous,
+    /// Linearly interpolate numeric values between the samples around the timestamp,
+    /// non-numeric values are taken from the previous sample
+    Linear,
+}
+
+/// Policy applied when the timestamps of the data being ingested are not valid, i.e. they are
+/// null, negative or not sorted in non-decreasing order (within a chunk or with respect to the
+/// data already stored).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampPolicy {
+    /// Reject the upload
+    Reject,
+    /// Sort the rows of each chunk by timestamp, null and negative timestamps are rejected
+    Sort,
+    /// Store the data as received, reporting the problem with a topic notification
+    Warn,
+}
+
+impl std::fmt::Display for TimestampPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reject => write!(f, "reject"),
+            Self::Sort => write!(f, "sort"),
+            Self::Warn => write!(f, "warn"),
+        }
+    }
+}
+
+impl std::str::FromStr for TimestampPolicy {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(Self::Reject),
+            "sort" => Ok(Self::Sort),
+            "warn" => Ok(Self::Warn),
+            _ => Err(std::io::Error::other(format!(
+                "unknown timestamp policy `{}`",
+                value
+            ))),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
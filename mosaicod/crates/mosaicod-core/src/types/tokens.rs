@@ -1,3 +1,5 @@
+use super::Uuid;
+
 /// A marker type representing a scope where data loss is explicitly acknowledged.
 ///
 /// Use [`allow_data_loss`] to build this token.
@@ -26,3 +28,71 @@ pub struct DataLossToken {
 pub fn allow_data_loss() -> DataLossToken {
     DataLossToken { _private: () }
 }
+
+/// Token required to resume the upload of a topic whose data stream was interrupted.
+///
+/// The token pins the topic and the number of data chunks persisted so far, so that an
+/// upload can only continue from the exact point where it was interrupted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumeToken {
+    topic: Uuid,
+    chunks: usize,
+}
+
+impl ResumeToken {
+    pub fn new(topic: Uuid, chunks: usize) -> Self {
+        Self { topic, chunks }
+    }
+
+    /// Uuid of the topic this token refers to
+    pub fn topic(&self) -> &Uuid {
+        &self.topic
+    }
+
+    /// Number of data chunks already persisted, i.e. the number of the next chunk to write
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+}
+
+impl std::fmt::Display for ResumeToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.topic, self.chunks)
+    }
+}
+
+impl std::str::FromStr for ResumeToken {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::Error::bad_request(format!("invalid resume token `{s}`"));
+
+        let (topic, chunks) = s.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self {
+            topic: topic.parse().map_err(|_| invalid())?,
+            chunks: chunks.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_token_roundtrip() {
+        let token = ResumeToken::new(Uuid::new(), 42);
+        let parsed: ResumeToken = token.to_string().parse().unwrap();
+        assert_eq!(parsed, token);
+        assert_eq!(parsed.chunks(), 42);
+
+        assert!("not-a-token".parse::<ResumeToken>().is_err());
+        assert!(
+            format!("{}:abc", token.topic())
+                .parse::<ResumeToken>()
+                .is_err()
+        );
+        assert!("abc:10".parse::<ResumeToken>().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+/// Compression codec of the data files of a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+/// Statistics written in the data files of a topic, used by readers to skip data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatisticsLevel {
+    /// No statistics
+    None,
+    /// Statistics for each column chunk
+    Chunk,
+    /// Statistics for each column chunk and page
+    Page,
+}
+
+/// Encoding settings of a column, each unset field keeps the default of the
+/// serialization format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnWriterOptions {
+    pub codec: Option<Codec>,
+    /// Compression level, only supported by `gzip`, `zstd` and `brotli` codecs
+    pub level: Option<i32>,
+    pub dictionary: Option<bool>,
+    pub statistics: Option<StatisticsLevel>,
+}
+
+impl ColumnWriterOptions {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Options of a topic overriding the defaults of its serialization format when
+/// writing data files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriterOptions {
+    /// Settings applied to all the columns
+    pub global: ColumnWriterOptions,
+    /// Settings applied to single columns, by field name. Nested fields are separated
+    /// by dots (e.g. `pose.x`), the settings of a field apply to all its sub-fields.
+    pub columns: BTreeMap<String, ColumnWriterOptions>,
+    /// Maximum number of rows in a row group
+    pub row_group_size: Option<usize>,
+    /// Fields with a bloom filter
+    pub bloom_filter: Vec<String>,
+}
+
+impl WriterOptions {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
@@ -0,0 +1,4 @@
+-- API key payloads are no longer stored in clear, only a salted hash is kept.
+-- Payloads of existing keys are hashed (and removed) by the daemon at startup.
+ALTER TABLE api_key_t ADD COLUMN payload_hash TEXT;
+ALTER TABLE api_key_t ALTER COLUMN payload DROP NOT NULL;
@@ -0,0 +1,15 @@
+CREATE TABLE api_key_scope_t
+(
+    fingerprint     BYTEA    NOT NULL, -- Constraint on api keys defined below
+    locator_prefix  TEXT     NOT NULL,
+    permissions     SMALLINT NOT NULL CHECK (permissions >= 0 AND permissions <= 255),
+
+    PRIMARY KEY (fingerprint, locator_prefix),
+
+    -- This constraint will cause the deletion of all
+    -- scopes of an api key if the related key is deleted.
+    CONSTRAINT fk_api_key
+        FOREIGN KEY (fingerprint)
+            REFERENCES api_key_t (fingerprint)
+            ON DELETE CASCADE
+);
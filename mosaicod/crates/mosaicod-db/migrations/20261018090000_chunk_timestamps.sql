@@ -0,0 +1,7 @@
+-- Add the timestamp range and ordering of chunks, used to skip the global sort of the
+-- topic data when chunks are sorted and non-overlapping.
+-- Timestamps are unknown (NULL) for chunks written before this migration.
+
+ALTER TABLE chunk_t ADD COLUMN min_timestamp_ns BIGINT;
+ALTER TABLE chunk_t ADD COLUMN max_timestamp_ns BIGINT;
+ALTER TABLE chunk_t ADD COLUMN sorted BOOL NOT NULL DEFAULT FALSE;
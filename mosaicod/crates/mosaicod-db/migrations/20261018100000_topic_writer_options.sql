@@ -0,0 +1,4 @@
+-- Add the writer options of topics, overriding the defaults of their serialization format
+-- when writing data files. Topics created before this migration have no options (NULL).
+
+ALTER TABLE topic_t ADD COLUMN writer_options JSONB;
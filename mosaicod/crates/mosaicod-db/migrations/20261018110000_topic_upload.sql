@@ -0,0 +1,5 @@
+-- Add the upload currently allowed to write the data of a topic. Uploads claim the topic when
+-- they start and check the claim before writing each chunk, so that concurrent uploads of the
+-- same topic can't write the same chunks. NULL if no upload ever claimed the topic.
+
+ALTER TABLE topic_t ADD COLUMN upload_uuid UUID;
@@ -7,6 +7,7 @@
 
 use log::debug;
 use sqlx::Pool;
+use tracing::Instrument;
 use url::Url;
 
 use super::Error;
@@ -34,16 +35,19 @@ pub trait AsExec {
 /// or discarded [`rollback`]
 pub struct Tx<'a> {
     inner: sqlx::Transaction<'a, DatabaseType>,
+
+    /// Span covering the transaction lifetime, closed when the transaction is dropped
+    span: tracing::Span,
 }
 
 impl<'a> Tx<'a> {
     pub async fn commit(self) -> Result<(), Error> {
-        self.inner.commit().await?;
+        self.inner.commit().instrument(self.span).await?;
         Ok(())
     }
 
     pub async fn rollback(self) -> Result<(), Error> {
-        self.inner.rollback().await?;
+        self.inner.rollback().instrument(self.span).await?;
         Ok(())
     }
 }
@@ -99,8 +103,11 @@ impl Database {
     /// This call should be used when performing **write** operations on the
     /// database.
     pub async fn transaction(&self) -> Result<Tx<'_>, Error> {
+        let span = tracing::info_span!("db_transaction");
+
         Ok(Tx {
-            inner: self.pool.begin().await?,
+            inner: self.pool.begin().instrument(span.clone()).await?,
+            span,
         })
     }
 
@@ -110,6 +117,25 @@ impl Database {
     pub fn connection(&self) -> Cx<'_> {
         Cx { inner: &self.pool }
     }
+
+    /// Returns a snapshot of the connection pool utilisation.
+    pub fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            connections: self.pool.size(),
+            idle_connections: self.pool.num_idle(),
+            max_connections: self.pool.options().get_max_connections(),
+        }
+    }
+}
+
+/// Snapshot of the utilisation of the database connection pool.
+pub struct PoolStatus {
+    /// Number of connections currently opened, both idle and in use
+    pub connections: u32,
+    /// Number of idle connections
+    pub idle_connections: usize,
+    /// Maximum number of connections the pool can open
+    pub max_connections: u32,
 }
 
 /// Testing utilities for the database module.
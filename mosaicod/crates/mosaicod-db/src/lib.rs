@@ -1,5 +1,5 @@
 mod core;
-pub use core::{AsExec, Config, Cx, Database, DatabaseType, Tx, UNREGISTERED};
+pub use core::{AsExec, Config, Cx, Database, DatabaseType, PoolStatus, Tx, UNREGISTERED};
 
 mod error;
 pub use error::Error;
@@ -1,16 +1,41 @@
 use crate::{Error, core::AsExec, sql::schema};
 use mosaicod_core::types;
 
-fn convert(record: schema::ApiKeyRecord) -> Result<types::ApiKey, Error> {
-    record
-        .try_into()
-        .map_err(|e: types::auth::ApiKeyError| Error::BadData(e.to_string()))
+fn convert(
+    record: schema::ApiKeyRecord,
+    scopes: Vec<schema::ApiKeyScopeRecord>,
+) -> Result<types::ApiKey, Error> {
+    let bad_data = |e: types::auth::ApiKeyError| Error::BadData(e.to_string());
+
+    let mut api_key: types::ApiKey = record.try_into().map_err(bad_data)?;
+
+    api_key.scopes = scopes
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<_, _>>()
+        .map_err(bad_data)?;
+
+    Ok(api_key)
+}
+
+async fn api_key_scopes_find_by_fingerprint(
+    exe: &mut impl AsExec,
+    fingerprint: &[u8],
+) -> Result<Vec<schema::ApiKeyScopeRecord>, Error> {
+    Ok(sqlx::query_as!(
+        schema::ApiKeyScopeRecord,
+        "SELECT * FROM api_key_scope_t WHERE fingerprint = $1",
+        fingerprint
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
 }
 
 pub async fn api_key_create(
     exe: &mut impl AsExec,
     policy: types::ApiKey,
 ) -> Result<types::ApiKey, Error> {
+    let scopes = policy.scopes.clone();
     let key: schema::ApiKeyRecord = policy.into();
 
     let res = sqlx::query_as!(
@@ -19,7 +44,7 @@ pub async fn api_key_create(
         INSERT INTO api_key_t
             (
                 fingerprint, 
-                payload, 
+                payload_hash, 
                 permissions,
                 description,
                 creation_unix_timestamp,
@@ -31,7 +56,7 @@ pub async fn api_key_create(
             *
         "#,
         key.fingerprint,
-        key.payload,
+        key.payload_hash,
         key.permissions,
         key.description,
         key.creation_unix_timestamp,
@@ -40,7 +65,29 @@ pub async fn api_key_create(
     .fetch_one(exe.as_exec())
     .await?;
 
-    convert(res)
+    let mut scope_records = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let scope_record = sqlx::query_as!(
+            schema::ApiKeyScopeRecord,
+            r#"
+            INSERT INTO api_key_scope_t
+                (fingerprint, locator_prefix, permissions)
+            VALUES
+                ($1, $2, $3)
+            RETURNING
+                *
+            "#,
+            res.fingerprint,
+            scope.prefix,
+            scope.permission as i16,
+        )
+        .fetch_one(exe.as_exec())
+        .await?;
+
+        scope_records.push(scope_record);
+    }
+
+    convert(res, scope_records)
 }
 
 pub async fn api_key_find_by_fingerprint(
@@ -59,7 +106,9 @@ pub async fn api_key_find_by_fingerprint(
     .fetch_one(exe.as_exec())
     .await?;
 
-    convert(res)
+    let scopes = api_key_scopes_find_by_fingerprint(exe, &res.fingerprint).await?;
+
+    convert(res, scopes)
 }
 
 pub async fn api_key_delete(exe: &mut impl AsExec, fingerprint: &str) -> Result<(), Error> {
@@ -78,10 +127,60 @@ pub async fn api_key_find_all(exe: &mut impl AsExec) -> Result<Vec<types::ApiKey
         .fetch_all(exe.as_exec())
         .await?;
 
+    let mut scopes = sqlx::query_as!(schema::ApiKeyScopeRecord, "SELECT * FROM api_key_scope_t")
+        .fetch_all(exe.as_exec())
+        .await?;
+
     let keys = keys
         .into_iter()
-        .map(convert)
+        .map(|key| {
+            let (key_scopes, others) = scopes
+                .drain(..)
+                .partition(|scope| scope.fingerprint == key.fingerprint);
+            scopes = others;
+            convert(key, key_scopes)
+        })
         .collect::<Result<Vec<types::ApiKey>, Error>>()?;
 
     Ok(keys)
 }
+
+/// Replaces the payloads stored in clear by older versions with their salted hash.
+///
+/// Returns the number of updated keys.
+pub async fn api_key_hash_legacy_payloads(exe: &mut impl AsExec) -> Result<usize, Error> {
+    let records = sqlx::query!(
+        "SELECT fingerprint, payload FROM api_key_t WHERE payload IS NOT NULL AND payload_hash IS NULL"
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+
+    for record in &records {
+        let bad_data = || Error::BadData("malformed legacy api key".to_owned());
+
+        let fingerprint: types::auth::TokenFingerprint = record
+            .fingerprint
+            .clone()
+            .try_into()
+            .map_err(|_| bad_data())?;
+        let payload: types::auth::TokenPayload = record
+            .payload
+            .clone()
+            .ok_or_else(bad_data)?
+            .try_into()
+            .map_err(|_| bad_data())?;
+
+        let hash =
+            types::auth::TokenHash::new(&types::auth::Token::from_bytes(payload, fingerprint));
+
+        sqlx::query!(
+            "UPDATE api_key_t SET payload_hash=$1, payload=NULL WHERE fingerprint=$2",
+            hash.as_str(),
+            &record.fingerprint,
+        )
+        .execute(exe.as_exec())
+        .await?;
+    }
+
+    Ok(records.len())
+}
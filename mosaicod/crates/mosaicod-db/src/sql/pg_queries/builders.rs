@@ -31,7 +31,35 @@ impl ChunkQueryBuilder {
             placeholder_counter: pidx,
         };
 
-        qb = qb.ontology_expr_group(filter, &mut qb_chunk);
+        // Chunk statistics can only tell if a chunk *may* contain matching values, so
+        // negations can't be used to discard chunks. Logical nodes containing a negation are
+        // skipped here and they will be evaluated on chunk data.
+        let ontology_tag = filter.ontology_tag().map(ToOwned::to_owned);
+        let (group, logical) = filter.into_parts();
+        let (logical, negated): (Vec<_>, Vec<_>) =
+            logical.into_iter().partition(|l| !l.has_negation());
+
+        let has_prunable_exprs = !group.is_empty() || !logical.is_empty();
+
+        qb = qb.ontology_expr_group(
+            query::OntologyExprGroup::new(group).with_logical(logical),
+            &mut qb_chunk,
+        );
+
+        // If there is nothing usable to discard chunks, restrict the search to all the chunks
+        // of the ontology tag
+        if !has_prunable_exprs
+            && !negated.is_empty()
+            && let Some(ontology_tag) = ontology_tag
+        {
+            let p = qb_chunk.consume_placeholder();
+            qb = qb.clause(query::CompiledClause::new(
+                format!(
+                    "SELECT chunk_id FROM chunk_t JOIN topic_t USING(topic_id) WHERE topic_t.ontology_tag = {p}"
+                ),
+                vec![query::Value::Text(ontology_tag)],
+            ));
+        }
 
         let qr = qb.compile()?;
         let joined_clauses = qr.clauses.join(" INTERSECT ");
@@ -160,6 +188,33 @@ impl query::CompileClause for ChunkQueryBuilder {
     }
 }
 
+impl query::CompileLogical for ChunkQueryBuilder {
+    fn compile_and(
+        &mut self,
+        clauses: Vec<query::CompiledClause>,
+    ) -> Result<query::CompiledClause, query::Error> {
+        Ok(query::join_clauses(clauses, " INTERSECT "))
+    }
+
+    fn compile_or(
+        &mut self,
+        clauses: Vec<query::CompiledClause>,
+    ) -> Result<query::CompiledClause, query::Error> {
+        if clauses.iter().any(query::CompiledClause::is_empty) {
+            return Ok(query::CompiledClause::empty());
+        }
+        Ok(query::join_clauses(clauses, " UNION "))
+    }
+
+    fn compile_not(
+        &mut self,
+        _clause: query::CompiledClause,
+    ) -> Result<query::CompiledClause, query::Error> {
+        // Negations can't be evaluated on chunk statistics
+        Err(query::Error::unsupported_op("$not".into()))
+    }
+}
+
 impl query::OntologyFieldFmt for ChunkQueryBuilder {
     fn ontology_column_fmt(&self, subfield: &query::OntologyField) -> String {
         format!("'{}'", subfield.value())
@@ -37,6 +37,8 @@ impl SqlQueryCompiler {
     }
 }
 
+impl query::CompileLogical for SqlQueryCompiler {}
+
 impl query::CompileClause for SqlQueryCompiler {
     fn compile_clause<V>(
         &mut self,
@@ -182,6 +184,8 @@ mod internal {
         }
     }
 
+    impl query::CompileLogical for JsonQueryCompiler {}
+
     impl query::CompileClause for JsonQueryCompiler {
         fn compile_clause<V>(
             &mut self,
@@ -412,4 +416,45 @@ mod tests {
             panic!("match not found");
         }
     }
+
+    #[test]
+    fn logical_operators() {
+        let placeholder = query::Placeholder::new();
+        let mut fmt = SqlQueryCompiler::new(placeholder);
+
+        let qr = ClausesCompiler::new()
+            .expr("topic.ontology_tag", Op::Eq("imu".to_owned()), &mut fmt)
+            .logical(
+                query::Logical::Or(vec!["front".to_owned(), "rear".to_owned()]),
+                &mut fmt,
+                |qb, v, fmt| qb.expr("topic.locator_name", Op::Match(v), fmt),
+            )
+            .logical(
+                query::Logical::Not(Box::new("debug".to_owned())),
+                &mut fmt,
+                |qb, v, fmt| qb.expr("topic.locator_name", Op::Match(v), fmt),
+            )
+            .compile()
+            .expect("problem building query");
+
+        dbg!(&qr);
+
+        assert_eq!(
+            qr.clauses,
+            vec![
+                "topic.ontology_tag = $1",
+                "((topic.locator_name LIKE $2) OR (topic.locator_name LIKE $3))",
+                "NOT (topic.locator_name LIKE $4)",
+            ]
+        );
+        assert_eq!(
+            qr.values,
+            vec![
+                query::Value::Text("imu".to_owned()),
+                query::Value::Text("%front%".to_owned()),
+                query::Value::Text("%rear%".to_owned()),
+                query::Value::Text("%debug%".to_owned()),
+            ]
+        );
+    }
 }
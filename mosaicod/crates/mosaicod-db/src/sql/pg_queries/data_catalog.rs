@@ -1,5 +1,5 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::trace;
+use log::{trace, warn};
 use mosaicod_core::types::{self};
 use mosaicod_query as query;
 use sqlx::{Row, postgres::PgRow};
@@ -27,26 +27,135 @@ pub async fn column_get_or_create(
     Ok(res)
 }
 
+/// Find a column given its id.
+pub async fn column_find_by_id(
+    exec: &mut impl AsExec,
+    column_id: i32,
+) -> Result<schema::ColumnRecord, Error> {
+    let res = sqlx::query_as!(
+        schema::ColumnRecord,
+        "SELECT * FROM column_t WHERE column_id=$1",
+        column_id,
+    )
+    .fetch_one(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
 pub async fn chunk_create(
     exec: &mut impl AsExec,
     chunk: &schema::ChunkRecord,
 ) -> Result<schema::ChunkRecord, Error> {
     let res = sqlx::query_as!(
         schema::ChunkRecord,
-        r#"INSERT INTO chunk_t(chunk_uuid, topic_id, data_file, size_bytes, row_count)
-        VALUES ($1, $2, $3, $4, $5)
+        r#"INSERT INTO chunk_t(
+            chunk_uuid, topic_id, data_file, size_bytes, row_count,
+            min_timestamp_ns, max_timestamp_ns, sorted
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING *"#,
         chunk.chunk_uuid,
         chunk.topic_id,
         chunk.data_file,
         chunk.size_bytes,
         chunk.row_count,
+        chunk.min_timestamp_ns,
+        chunk.max_timestamp_ns,
+        chunk.sorted,
     )
     .fetch_one(exec.as_exec())
     .await?;
     Ok(res)
 }
 
+/// Returns all the chunks of a topic, in creation order.
+///
+/// Chunks are created sequentially by the topic writer, so the order of their ids is the
+/// order of the data. File names are not used since they don't sort numerically once the
+/// chunk number outgrows its zero padding.
+pub async fn chunk_find_by_topic_id(
+    exec: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::ChunkRecord>, Error> {
+    let res = sqlx::query_as!(
+        schema::ChunkRecord,
+        "SELECT * FROM chunk_t WHERE topic_id=$1 ORDER BY chunk_id",
+        topic_id,
+    )
+    .fetch_all(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Returns `true` if some chunk references the data file `path`.
+pub async fn chunk_data_file_exists(exec: &mut impl AsExec, path: &str) -> Result<bool, Error> {
+    let row = sqlx::query("SELECT EXISTS(SELECT 1 FROM chunk_t WHERE data_file = $1) AS found")
+        .bind(path)
+        .fetch_one(exec.as_exec())
+        .await?;
+    Ok(row.try_get("found")?)
+}
+
+/// Returns `true` if some sequence or topic is stored in the store folder `path`.
+pub async fn store_folder_referenced(exec: &mut impl AsExec, path: &str) -> Result<bool, Error> {
+    let row = sqlx::query(
+        r#"SELECT
+            EXISTS(SELECT 1 FROM sequence_t WHERE path_in_store = $1)
+            OR EXISTS(SELECT 1 FROM topic_t WHERE path_in_store = $1) AS found"#,
+    )
+    .bind(path)
+    .fetch_one(exec.as_exec())
+    .await?;
+    Ok(row.try_get("found")?)
+}
+
+/// Returns all the chunks stored in the database.
+pub async fn chunk_find_all(exec: &mut impl AsExec) -> Result<Vec<schema::ChunkRecord>, Error> {
+    trace!("retrieving all chunks");
+    let res = sqlx::query_as!(schema::ChunkRecord, "SELECT * FROM chunk_t")
+        .fetch_all(exec.as_exec())
+        .await?;
+    Ok(res)
+}
+
+/// Updates the size and the number of rows of the data file of a chunk.
+pub async fn chunk_update_file_info(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+    size_bytes: i64,
+    row_count: i64,
+) -> Result<(), Error> {
+    trace!(
+        "updating chunk `{}` file info (size: {}, rows: {})",
+        chunk_id, size_bytes, row_count
+    );
+    sqlx::query!(
+        "UPDATE chunk_t SET size_bytes=$1, row_count=$2 WHERE chunk_id=$3",
+        size_bytes,
+        row_count,
+        chunk_id,
+    )
+    .execute(exec.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Deletes a chunk record, and its column statistics, from the database.
+///
+/// This function requires a [`types::DataLossToken`] because the data file of the chunk
+/// is no longer reachable by queries once the record is removed.
+pub async fn chunk_delete(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+    _: types::DataLossToken,
+) -> Result<(), Error> {
+    warn!("(data loss) deleting chunk with id `{}`", chunk_id);
+    sqlx::query!("DELETE FROM chunk_t WHERE chunk_id=$1", chunk_id)
+        .execute(exec.as_exec())
+        .await?;
+    Ok(())
+}
+
 pub async fn column_chunk_textual_create(
     exec: &mut impl AsExec,
     val: &schema::ColumnChunkTextualRecord,
@@ -96,6 +205,36 @@ pub async fn column_chunk_numeric_create(
     Ok(res)
 }
 
+/// Returns the numeric column stats of a chunk.
+pub async fn column_chunk_numeric_find_by_chunk_id(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+) -> Result<Vec<schema::ColumnChunkNumericRecord>, Error> {
+    let res = sqlx::query_as!(
+        schema::ColumnChunkNumericRecord,
+        "SELECT * FROM column_chunk_numeric_t WHERE chunk_id=$1 ORDER BY column_id",
+        chunk_id,
+    )
+    .fetch_all(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Returns the textual column stats of a chunk.
+pub async fn column_chunk_textual_find_by_chunk_id(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+) -> Result<Vec<schema::ColumnChunkTextualRecord>, Error> {
+    let res = sqlx::query_as!(
+        schema::ColumnChunkTextualRecord,
+        "SELECT * FROM column_chunk_textual_t WHERE chunk_id=$1 ORDER BY column_id",
+        chunk_id,
+    )
+    .fetch_all(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
 /// Batch insert multiple numeric column chunk stats in a single query.
 /// More efficient than individual inserts when inserting many stats.
 pub async fn column_chunk_numeric_create_batch(
@@ -192,6 +331,9 @@ fn cast_chunk_data(row: PgRow) -> Result<schema::ChunkRecord, Error> {
         data_file: row.try_get("data_file")?,
         size_bytes: row.try_get("size_bytes")?,
         row_count: row.try_get("row_count")?,
+        min_timestamp_ns: row.try_get("min_timestamp_ns")?,
+        max_timestamp_ns: row.try_get("max_timestamp_ns")?,
+        sorted: row.try_get("sorted")?,
     })
 }
 
@@ -203,7 +345,8 @@ pub async fn topic_get_stats(
     let res = sqlx::query!(
         r#"SELECT
             COALESCE(SUM(size_bytes), 0)::BIGINT as "total_size_bytes!",
-            COALESCE(SUM(row_count), 0)::BIGINT as "total_row_count!"
+            COALESCE(SUM(row_count), 0)::BIGINT as "total_row_count!",
+            COUNT(*)::BIGINT as "chunks_count!"
         FROM chunk_t
         WHERE topic_id = (SELECT topic_id FROM topic_t WHERE locator_name = $1)"#,
         loc as &str,
@@ -214,5 +357,6 @@ pub async fn topic_get_stats(
     Ok(types::TopicChunksStats {
         total_size_bytes: res.total_size_bytes,
         total_row_count: res.total_row_count,
+        chunks_count: res.chunks_count,
     })
 }
@@ -25,14 +25,15 @@ pub async fn sequences_group_from_topics(
                 types::SequenceTopicGroup::new(
                     seq.locator_name
                         .parse::<types::SequenceLocator>()
-                        .map_err(|_| Error::BadData(seq.locator_name))?,
+                        .map_err(|_| Error::BadData(seq.locator_name.clone()))?,
                     vec![
                         topic
                             .locator_name
                             .parse()
                             .map_err(|_| Error::BadData(topic.locator_name.clone()))?,
                     ],
-                ),
+                )
+                .with_created_at(seq.creation_timestamp()),
             );
         }
     }
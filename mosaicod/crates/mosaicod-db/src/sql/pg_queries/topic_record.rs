@@ -15,6 +15,8 @@ fn cast_topic_data(row: PgRow) -> Result<schema::TopicRecord, Error> {
         ontology_tag: row.try_get("ontology_tag")?,
         serialization_format: row.try_get("serialization_format")?,
         user_metadata: row.try_get("user_metadata")?,
+        writer_options: row.try_get("writer_options")?,
+        upload_uuid: row.try_get("upload_uuid")?,
         path_in_store: row.try_get("path_in_store")?,
         creation_unix_tstamp: row.try_get("creation_unix_tstamp")?,
         completion_unix_tstamp: row.try_get("completion_unix_tstamp")?,
@@ -128,10 +130,11 @@ pub async fn topic_create(
                 (
                     topic_uuid, sequence_id, session_id, locator_name, creation_unix_tstamp,
                     serialization_format, ontology_tag, user_metadata, chunks_number,
-                    total_bytes, start_index_timestamp, end_index_timestamp, path_in_store
+                    total_bytes, start_index_timestamp, end_index_timestamp, path_in_store,
+                    writer_options
                 ) 
             VALUES 
-                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             RETURNING 
                 *
     "#,
@@ -148,6 +151,7 @@ pub async fn topic_create(
         record.start_index_timestamp,
         record.end_index_timestamp,
         record.path_in_store,
+        record.writer_options,
     )
     .fetch_one(exe.as_exec())
     .await?;
@@ -276,18 +280,89 @@ pub async fn topic_update_completion_tstamp(
     Ok(())
 }
 
+/// Returns the upload currently allowed to write the topic data, if any.
+///
+/// The topic row stays locked until the end of the transaction, serializing the uploads
+/// claiming the topic and the chunks they write. The lock doesn't block the creation of
+/// chunk records referencing the topic.
+pub async fn topic_lock_upload(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Option<types::Uuid>, Error> {
+    trace!("locking upload of topic `{}`", topic_id);
+    let row = sqlx::query("SELECT upload_uuid FROM topic_t WHERE topic_id = $1 FOR NO KEY UPDATE")
+        .bind(topic_id)
+        .fetch_one(exe.as_exec())
+        .await?;
+
+    let upload: Option<uuid::Uuid> = row.try_get("upload_uuid")?;
+    Ok(upload.map(Into::into))
+}
+
+/// Sets the upload allowed to write the topic data, `None` releases the topic.
+pub async fn topic_update_upload(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    upload: Option<&types::Uuid>,
+) -> Result<(), Error> {
+    trace!("updating upload of topic `{}` to `{:?}`", topic_id, upload);
+    sqlx::query("UPDATE topic_t SET upload_uuid = $1 WHERE topic_id = $2")
+        .bind(upload.map(|uuid| *uuid.as_ref()))
+        .bind(topic_id)
+        .execute(exe.as_exec())
+        .await?;
+    Ok(())
+}
+
+/// Moves the topic to a new root folder in the store, returns `false` if the topic is no longer
+/// stored in `current`, e.g. because it was moved by a concurrent operation.
+///
+/// The topic row stays locked until the end of the transaction, serializing concurrent moves.
+pub async fn topic_update_path_in_store(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    current: &types::TopicPathInStore,
+    new: &types::TopicPathInStore,
+) -> Result<bool, Error> {
+    trace!(
+        "moving topic `{}` from `{}` to `{}`",
+        topic_id, current, new
+    );
+    let res = sqlx::query!(
+        r#"
+            UPDATE topic_t
+            SET path_in_store = $1
+            WHERE topic_id = $2 AND path_in_store = $3
+    "#,
+        new.to_string(),
+        topic_id,
+        current.to_string(),
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+/// Returns the topics matching the sequence and topic filters.
+///
+/// If `scopes` is not empty only the topics contained in some scope are returned. Only the
+/// topics of the sequences after the cursor of `page` are returned. If the page has a limit,
+/// the topics of at most `limit + 1` sequences are returned, the extra sequence tells the
+/// caller whether a next page exists.
 pub async fn topic_from_query_filter(
     exe: &mut impl AsExec,
     filter_seq: Option<query::SequenceFilter>,
     filter_top: Option<query::TopicFilter>,
+    scopes: &[types::auth::ApiKeyScope],
+    page: &query::Page,
 ) -> Result<Vec<schema::TopicRecord>, Error> {
     // Return empty vector if there is nothing to filter
     if filter_seq.is_none() && filter_top.is_none() {
         return Ok(Vec::new());
     }
 
-    let select = r#"
-        SELECT topic.*
+    let from = r#"
         FROM topic_t topic
         INNER JOIN sequence_t sequence
             ON topic.sequence_id = sequence.sequence_id
@@ -296,56 +371,17 @@ pub async fn topic_from_query_filter(
     let mut qb = query::ClausesCompiler::new();
 
     let placeholder = query::Placeholder::new();
+    let mut page_placeholder = placeholder.clone();
 
     let mut sql_fmt = super::SqlQueryCompiler::new(placeholder.clone());
     let mut json_fmt = super::JsonQueryCompiler::new(placeholder);
 
     if let Some(seq) = filter_seq {
-        if let Some(op) = seq.name {
-            qb = qb.expr("sequence.locator_name", op, &mut sql_fmt);
-        }
-
-        if let Some(op) = seq.created_at {
-            qb = qb.expr("sequence.creation_unix_tstamp", op, &mut sql_fmt);
-        }
-
-        let fmt = json_fmt.with_field("sequence.user_metadata".into());
-
-        for (field, op) in seq.user_metadata {
-            qb = qb.expr(&field, op, fmt);
-        }
+        qb = sequence_filter_clauses(qb, seq, &mut sql_fmt, &mut json_fmt);
     }
 
     if let Some(top) = filter_top {
-        if let Some(op) = top.name {
-            // Substring has a +1 for zero based indexing
-            //
-            // So using sequence locator '/a/b' and topic locator 'a/b/c/d'
-            // the query produces '/c/d', slash included
-            qb = qb.expr(
-                "SUBSTRING(topic.locator_name, LENGTH(sequence.locator_name) + 1)",
-                op,
-                &mut sql_fmt,
-            );
-        }
-
-        if let Some(op) = top.created_at {
-            qb = qb.expr("topic.creation_unix_tstamp", op, &mut sql_fmt);
-        }
-
-        if let Some(op) = top.ontology_tag {
-            qb = qb.expr("topic.ontology_tag", op, &mut sql_fmt);
-        }
-
-        if let Some(op) = top.serialization_format {
-            qb = qb.expr("topic.serialization_format", op, &mut sql_fmt);
-        }
-
-        let fmt = json_fmt.with_field("topic.user_metadata".into());
-
-        for (field, op) in top.user_metadata {
-            qb = qb.expr(&field, op, fmt);
-        }
+        qb = topic_filter_clauses(qb, top, &mut sql_fmt, &mut json_fmt);
     }
 
     let qr = qb.compile()?;
@@ -355,15 +391,66 @@ pub async fn topic_from_query_filter(
         return Ok(Vec::new());
     }
 
-    // Since we have do an early-return is the query is unfiltered there is always a WHERE clause
-    let query = format!("{select} WHERE {}", qr.clauses.join(" AND "));
+    let (mut clauses, mut values) = (qr.clauses, qr.values);
+
+    // Scopes are applied before the page, so that pages only hold visible sequences
+    if !scopes.is_empty() {
+        let scope_clauses: Vec<String> = scopes
+            .iter()
+            .map(|scope| {
+                values.push(query::Value::Text(scope.prefix.clone()));
+                format!(
+                    "starts_with(topic.locator_name, ${})",
+                    page_placeholder.consume()
+                )
+            })
+            .collect();
+        clauses.push(format!("({})", scope_clauses.join(" OR ")));
+    }
+
+    match &page.after {
+        Some(query::Cursor::Name(name)) => {
+            clauses.push(format!(
+                "sequence.locator_name > ${}",
+                page_placeholder.consume()
+            ));
+            values.push(query::Value::Text(name.clone()));
+        }
+        Some(query::Cursor::CreatedAt(ts, name)) => {
+            clauses.push(format!(
+                "(sequence.creation_unix_tstamp, sequence.locator_name) > (${}, ${})",
+                page_placeholder.consume(),
+                page_placeholder.consume()
+            ));
+            values.push(query::Value::Integer(ts.as_i64()));
+            values.push(query::Value::Text(name.clone()));
+        }
+        None => {}
+    }
 
-    trace!("query values: {:?}", qr.values);
+    // Since we have do an early-return is the query is unfiltered there is always a WHERE clause
+    let filtered = format!("{from} WHERE {}", clauses.join(" AND "));
+
+    let query = if let Some(limit) = page.limit {
+        let order = match page.order_by {
+            query::OrderBy::Name => "sequence.locator_name",
+            query::OrderBy::CreatedAt => "sequence.creation_unix_tstamp, sequence.locator_name",
+        };
+        values.push(query::Value::Integer(limit as i64 + 1));
+        format!(
+            "SELECT * FROM (SELECT topic.*, DENSE_RANK() OVER (ORDER BY {order}) AS sequence_rank {filtered}) ranked WHERE sequence_rank <= ${}",
+            page_placeholder.consume()
+        )
+    } else {
+        format!("SELECT topic.* {filtered}")
+    };
+
+    trace!("query values: {:?}", values);
     trace!("generated SQL query: {}", query);
 
     let mut r = sqlx::query(&query);
 
-    for v in qr.values.into_iter() {
+    for v in values.into_iter() {
         match v {
             query::Value::Integer(v) => r = r.bind(v),
             query::Value::Float(v) => r = r.bind(v),
@@ -377,6 +464,82 @@ pub async fn topic_from_query_filter(
     r.into_iter().collect()
 }
 
+/// Appends to `qb` the clauses required by the sequence filter
+fn sequence_filter_clauses(
+    mut qb: query::ClausesCompiler,
+    seq: query::SequenceFilter,
+    sql_fmt: &mut super::SqlQueryCompiler,
+    json_fmt: &mut super::JsonQueryCompiler,
+) -> query::ClausesCompiler {
+    if let Some(op) = seq.name {
+        qb = qb.expr("sequence.locator_name", op, sql_fmt);
+    }
+
+    if let Some(op) = seq.created_at {
+        qb = qb.expr("sequence.creation_unix_tstamp", op, sql_fmt);
+    }
+
+    let fmt = json_fmt.with_field("sequence.user_metadata".into());
+
+    for (field, op) in seq.user_metadata {
+        qb = qb.expr(&field, op, fmt);
+    }
+
+    for logical in seq.logical {
+        qb = qb.logical(logical, sql_fmt, |qb, seq, sql_fmt| {
+            sequence_filter_clauses(qb, seq, sql_fmt, json_fmt)
+        });
+    }
+
+    qb
+}
+
+/// Appends to `qb` the clauses required by the topic filter
+fn topic_filter_clauses(
+    mut qb: query::ClausesCompiler,
+    top: query::TopicFilter,
+    sql_fmt: &mut super::SqlQueryCompiler,
+    json_fmt: &mut super::JsonQueryCompiler,
+) -> query::ClausesCompiler {
+    if let Some(op) = top.name {
+        // Substring has a +1 for zero based indexing
+        //
+        // So using sequence locator '/a/b' and topic locator 'a/b/c/d'
+        // the query produces '/c/d', slash included
+        qb = qb.expr(
+            "SUBSTRING(topic.locator_name, LENGTH(sequence.locator_name) + 1)",
+            op,
+            sql_fmt,
+        );
+    }
+
+    if let Some(op) = top.created_at {
+        qb = qb.expr("topic.creation_unix_tstamp", op, sql_fmt);
+    }
+
+    if let Some(op) = top.ontology_tag {
+        qb = qb.expr("topic.ontology_tag", op, sql_fmt);
+    }
+
+    if let Some(op) = top.serialization_format {
+        qb = qb.expr("topic.serialization_format", op, sql_fmt);
+    }
+
+    let fmt = json_fmt.with_field("topic.user_metadata".into());
+
+    for (field, op) in top.user_metadata {
+        qb = qb.expr(&field, op, fmt);
+    }
+
+    for logical in top.logical {
+        qb = qb.logical(logical, sql_fmt, |qb, top, sql_fmt| {
+            topic_filter_clauses(qb, top, sql_fmt, json_fmt)
+        });
+    }
+
+    qb
+}
+
 /// Returns true if the topic is archived (upload completed).
 pub async fn topic_archived(exe: &mut impl AsExec, topic_id: i32) -> Result<bool, Error> {
     trace!("topic (id=`{}`) locked? ", topic_id);
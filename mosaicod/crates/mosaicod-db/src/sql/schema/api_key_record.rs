@@ -7,8 +7,10 @@ pub struct ApiKeyRecord {
     /// This identifier is the fingerprint part of the API key.
     pub(crate) fingerprint: Vec<u8>,
 
-    /// Payload part of the API key
-    pub(crate) payload: Vec<u8>,
+    /// Payload part of the API key, stored in clear by older versions.
+    ///
+    /// It's always `None` after [`crate::api_key_hash_legacy_payloads`] was called.
+    pub(crate) payload: Option<Vec<u8>>,
 
     /// Permissions are stored as 2 bytes (for future usages, and since some modern database
     /// system have no support for 1 byte words)
@@ -22,16 +24,42 @@ pub struct ApiKeyRecord {
 
     /// UNIX timestamp in milliseconds of the expiration date
     pub(crate) expiration_unix_timestamp: Option<i64>,
+
+    /// Salted hash of the payload part of the API key
+    pub(crate) payload_hash: Option<String>,
+}
+
+/// Locator scope associated to an API key
+pub struct ApiKeyScopeRecord {
+    /// Fingerprint of the API key owning the scope
+    pub(crate) fingerprint: Vec<u8>,
+
+    /// Prefix of the locators included in the scope
+    pub locator_prefix: String,
+
+    /// Permissions granted in the scope, stored like [`ApiKeyRecord::permissions`]
+    pub(crate) permissions: i16,
+}
+
+impl TryFrom<ApiKeyScopeRecord> for types::auth::ApiKeyScope {
+    type Error = types::auth::ApiKeyError;
+
+    fn try_from(value: ApiKeyScopeRecord) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            &value.locator_prefix,
+            (value.permissions as u8).try_into()?,
+        ))
+    }
 }
 
 impl TryFrom<ApiKeyRecord> for types::ApiKey {
     type Error = types::auth::ApiKeyError;
 
     fn try_from(value: ApiKeyRecord) -> Result<Self, Self::Error> {
-        let payload: types::auth::TokenPayload = value
-            .payload
-            .try_into()
-            .map_err(|_| types::auth::ApiKeyError::BadTokenPayload)?;
+        let payload_hash: types::auth::TokenHash = value
+            .payload_hash
+            .ok_or(types::auth::ApiKeyError::MissingTokenHash)?
+            .parse()?;
 
         let fingerprint: types::auth::TokenFingerprint = value
             .fingerprint
@@ -39,8 +67,11 @@ impl TryFrom<ApiKeyRecord> for types::ApiKey {
             .map_err(|_| types::auth::ApiKeyError::BadTokenFingerprint)?;
 
         Ok(Self {
-            key: types::auth::Token::from_bytes(payload, fingerprint),
+            fingerprint,
+            payload_hash,
             permission: (value.permissions as u8).try_into()?,
+            // Scopes are stored in a different table
+            scopes: Vec::new(),
             description: value.description,
             created_at: value.creation_unix_timestamp.into(),
             expires_at: value.expiration_unix_timestamp.map(Into::into),
@@ -51,8 +82,9 @@ impl TryFrom<ApiKeyRecord> for types::ApiKey {
 impl From<types::ApiKey> for ApiKeyRecord {
     fn from(value: types::ApiKey) -> Self {
         Self {
-            fingerprint: value.token().fingerprint().as_bytes().into(),
-            payload: value.token().payload().as_bytes().into(),
+            fingerprint: value.fingerprint().as_bytes().into(),
+            payload: None,
+            payload_hash: Some(value.payload_hash.to_string()),
             permissions: value.permission as i16,
             description: value.description,
             creation_unix_timestamp: value.created_at.into(),
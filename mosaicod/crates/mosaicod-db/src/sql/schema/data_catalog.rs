@@ -1,4 +1,5 @@
 use crate as db;
+use mosaicod_core::types;
 
 #[derive(Debug)]
 pub struct ColumnRecord {
@@ -29,6 +30,12 @@ pub struct ChunkRecord {
     pub(crate) data_file: String,
     pub size_bytes: i64,
     pub row_count: i64,
+    /// Smallest timestamp of the chunk, `None` if unknown
+    pub min_timestamp_ns: Option<i64>,
+    /// Largest timestamp of the chunk, `None` if unknown
+    pub max_timestamp_ns: Option<i64>,
+    /// `true` if the chunk rows are known to be sorted by timestamp
+    pub sorted: bool,
 }
 
 impl ChunkRecord {
@@ -45,9 +52,20 @@ impl ChunkRecord {
             data_file: data_file.as_ref().to_string_lossy().to_string(),
             size_bytes,
             row_count,
+            min_timestamp_ns: None,
+            max_timestamp_ns: None,
+            sorted: false,
         }
     }
 
+    /// Sets the timestamp range and ordering of the chunk from its timestamp statistics.
+    pub fn with_timestamps(mut self, timestamps: &types::TimestampStats) -> Self {
+        self.min_timestamp_ns = timestamps.min;
+        self.max_timestamp_ns = timestamps.max;
+        self.sorted = timestamps.sorted && timestamps.min.is_some();
+        self
+    }
+
     pub fn data_file(&self) -> &std::path::Path {
         std::path::Path::new(&self.data_file)
     }
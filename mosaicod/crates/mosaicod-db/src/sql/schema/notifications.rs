@@ -37,6 +37,12 @@ impl SequenceNotificationRecord {
         }
     }
 
+    /// Overrides the creation timestamp, e.g. when the notification is restored from a bundle.
+    pub fn with_creation_timestamp(mut self, timestamp: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = timestamp.into();
+        self
+    }
+
     pub fn into_notification(self, loc: types::SequenceLocator) -> types::Notification {
         types::Notification {
             uuid: self.sequence_notification_uuid.into(),
@@ -107,6 +113,12 @@ impl TopicNotificationRecord {
         }
     }
 
+    /// Overrides the creation timestamp, e.g. when the notification is restored from a bundle.
+    pub fn with_creation_timestamp(mut self, timestamp: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = timestamp.into();
+        self
+    }
+
     pub fn into_notification(self, loc: types::TopicLocator) -> types::Notification {
         types::Notification {
             uuid: self.topic_notification_uuid.into(),
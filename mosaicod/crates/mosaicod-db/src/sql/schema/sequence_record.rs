@@ -56,6 +56,12 @@ impl SequenceRecord {
         self
     }
 
+    /// Overrides the creation timestamp, e.g. when the sequence is restored from a bundle.
+    pub fn with_creation_timestamp(mut self, timestamp: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = timestamp.into();
+        self
+    }
+
     pub fn creation_timestamp(&self) -> types::Timestamp {
         types::Timestamp::from(self.creation_unix_tstamp)
     }
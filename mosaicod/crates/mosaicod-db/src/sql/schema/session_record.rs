@@ -36,6 +36,18 @@ impl SessionRecord {
         }
     }
 
+    /// Overrides the creation and completion timestamps, e.g. when the session is restored
+    /// from a bundle.
+    pub fn with_timestamps(
+        mut self,
+        created_at: types::Timestamp,
+        completed_at: Option<types::Timestamp>,
+    ) -> Self {
+        self.creation_unix_tstamp = created_at.into();
+        self.completion_unix_tstamp = completed_at.map(Into::into);
+        self
+    }
+
     /// Returns the creation timestamp of the session.
     pub fn creation_timestamp(&self) -> types::Timestamp {
         types::Timestamp::from(self.creation_unix_tstamp)
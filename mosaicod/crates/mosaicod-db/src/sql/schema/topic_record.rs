@@ -17,6 +17,12 @@ pub struct TopicRecord {
     // Do not expose directly this field
     pub(crate) user_metadata: Option<serde_json::Value>,
 
+    /// Options overriding the defaults of the serialization format
+    pub(crate) writer_options: Option<serde_json::Value>,
+
+    /// Upload currently allowed to write the topic data
+    pub(crate) upload_uuid: Option<uuid::Uuid>,
+
     /// Path inside Object store where to find data and backup files.
     pub(crate) path_in_store: Option<String>,
 
@@ -53,6 +59,8 @@ impl TopicRecord {
             ontology_tag: ontology_tag.to_owned(),
             serialization_format: serialization_format.to_owned(),
             user_metadata: None,
+            writer_options: None,
+            upload_uuid: None,
             path_in_store: path_in_store.map(Into::into),
             creation_unix_tstamp: types::Timestamp::now().into(),
             completion_unix_tstamp: None,
@@ -68,6 +76,18 @@ impl TopicRecord {
         self
     }
 
+    pub fn with_writer_options(mut self, writer_options: types::WriterOptions) -> Self {
+        self.writer_options = (!writer_options.is_empty())
+            .then(|| marshal::JsonWriterOptions::from(writer_options).into());
+        self
+    }
+
+    /// Overrides the creation timestamp, e.g. when the topic is restored from a bundle.
+    pub fn with_creation_timestamp(mut self, timestamp: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = timestamp.into();
+        self
+    }
+
     pub fn uuid(&self) -> types::Uuid {
         self.topic_uuid.into()
     }
@@ -98,6 +118,18 @@ impl TopicRecord {
         self.user_metadata.clone().map(Into::into)
     }
 
+    pub fn writer_options(&self) -> types::WriterOptions {
+        self.writer_options
+            .clone()
+            .and_then(|value| {
+                marshal::JsonWriterOptions::try_from(value)
+                    .inspect_err(|e| error!("BUG: invalid writer options in database: {}", e))
+                    .ok()
+            })
+            .map(Into::into)
+            .unwrap_or_default()
+    }
+
     pub fn creation_timestamp(&self) -> types::Timestamp {
         types::Timestamp::from(self.creation_unix_tstamp)
     }
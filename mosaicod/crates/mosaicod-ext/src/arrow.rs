@@ -94,6 +94,13 @@ pub async fn schema_from_parquet_reader(
     Ok(Arc::new(schema))
 }
 
+/// Returns the number of rows stored in a parquet file, reading only its footer
+pub async fn row_count_from_parquet_reader(reader: &mut ParquetObjectReader) -> Result<i64, Error> {
+    let metadata = reader.get_metadata(None).await?;
+
+    Ok(metadata.file_metadata().num_rows())
+}
+
 /// Checks if the given Arrow [`DataType`] is considered numeric
 #[must_use]
 pub fn is_numeric(data_type: &DataType) -> bool {
@@ -302,12 +309,46 @@ pub fn stats_from_arrow_field(field: &Field) -> types::Stats {
     }
 }
 
+/// Same as [`stats_from_arrow_field`], but lists of numeric or textual values get the
+/// statistics of their items (e.g. the bounding box of the point coordinates of a scan).
+pub fn stats_from_arrow_field_with_lists(field: &Field) -> types::Stats {
+    match field.data_type() {
+        DataType::List(item) | DataType::LargeList(item) => stats_from_arrow_field(item),
+        _ => stats_from_arrow_field(field),
+    }
+}
+
+/// Returns the items of a list array, [`None`] if the array is not a list.
+fn list_values(array: &ArrayRef) -> Option<ArrayRef> {
+    fn values<O: arrow::array::OffsetSizeTrait>(
+        list: &arrow::array::GenericListArray<O>,
+    ) -> ArrayRef {
+        // Only the items referenced by the (possibly sliced) list are returned
+        let offsets = list.value_offsets();
+        let start = offsets.first().map_or(0, |o| o.as_usize());
+        let end = offsets.last().map_or(0, |o| o.as_usize());
+        list.values().slice(start, end - start)
+    }
+
+    match array.data_type() {
+        DataType::List(_) => Some(values(array.as_list::<i32>())),
+        DataType::LargeList(_) => Some(values(array.as_list::<i64>())),
+        _ => None,
+    }
+}
+
 /// Inspects an array and updates the provided statistics using SIMD-optimized Arrow compute kernels.
+///
+/// List arrays are inspected item by item.
 pub fn stats_inspect_array(stats: &mut types::Stats, array: &ArrayRef) -> Result<(), Error> {
     use arrow::array::Array;
     use arrow::compute;
     use types::Stats;
 
+    if let Some(values) = list_values(array) {
+        return stats_inspect_array(stats, &values);
+    }
+
     match stats {
         Stats::Numeric(stats) => {
             let narray = cast_array_to_numeric(array)?;
@@ -357,6 +398,147 @@ pub fn ontology_model_stats_inspect_record_batch(
     Ok(())
 }
 
+/// Inspects the timestamp column of a [`RecordBatch`] and updates the timestamp statistics
+/// accordingly. Batches without an `Int64` timestamp column are ignored.
+pub fn timestamp_stats_inspect_record_batch(
+    stats: &mut types::TimestampStats,
+    batch: &RecordBatch,
+) {
+    let Some(timestamps) = batch
+        .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+        .and_then(|column| column.as_primitive_opt::<arrow::datatypes::Int64Type>())
+    else {
+        return;
+    };
+
+    for ts in timestamps {
+        stats.eval(ts);
+    }
+}
+
+/// Sorts the rows of a [`RecordBatch`] by timestamp, null timestamps are placed last.
+///
+/// The sort is stable, so rows with the same timestamp keep their relative order.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the batch has no `Int64` timestamp column.
+pub fn sort_by_timestamp(batch: &RecordBatch) -> Result<RecordBatch, Error> {
+    use arrow::array::Array;
+
+    let timestamps = batch
+        .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+        .and_then(|column| column.as_primitive_opt::<arrow::datatypes::Int64Type>())
+        .ok_or_else(|| Error(Box::new(SchemaError::MissingTimestampInSchema)))?;
+
+    let mut indices: Vec<u32> = (0..batch.num_rows() as u32).collect();
+    indices.sort_by_key(|&i| {
+        let ts = timestamps
+            .is_valid(i as usize)
+            .then(|| timestamps.value(i as usize));
+        (ts.is_none(), ts)
+    });
+
+    Ok(arrow::compute::take_record_batch(
+        batch,
+        &arrow::array::UInt32Array::from(indices),
+    )?)
+}
+
+/// Same as [`ontology_model_stats_from_schema`], but lists of numeric or textual values
+/// get the statistics of their items, see [`stats_from_arrow_field_with_lists`].
+pub fn ontology_model_stats_from_schema_with_lists(
+    schema: &SchemaRef,
+) -> types::OntologyModelStats {
+    let mut cs = types::OntologyModelStats::empty();
+    for (squashed_name, field) in schema.squashed_iter() {
+        cs.cols.insert(
+            squashed_name.clone(),
+            stats_from_arrow_field_with_lists(field.as_ref()),
+        );
+    }
+    cs
+}
+
+/// Rounds the floating point values of a [`RecordBatch`], including the ones nested in
+/// lists and structs, to a grid of `step` spacing.
+///
+/// The grid spacing is the largest power of two not greater than `step`, so the rounding
+/// error is at most `step / 2` and the low mantissa bits of the rounded values are zeroed,
+/// which makes them highly compressible.
+///
+/// # Panics
+///
+/// Panics if `step` is not a positive number.
+pub fn quantize_record_batch(batch: &RecordBatch, step: f64) -> Result<RecordBatch, Error> {
+    assert!(step > 0.0, "quantization step must be positive");
+
+    let step = 2_f64.powi(step.log2().floor() as i32);
+
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| quantize_array(column, step))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
+
+fn quantize_array(array: &ArrayRef, step: f64) -> Result<ArrayRef, Error> {
+    use arrow::array::{Array, GenericListArray};
+    use arrow::datatypes::{Float32Type, Float64Type};
+
+    let quantized: ArrayRef = match array.data_type() {
+        DataType::Float32 => {
+            let step = step as f32;
+            Arc::new(
+                array
+                    .as_primitive::<Float32Type>()
+                    .unary::<_, Float32Type>(|v| (v / step).round() * step),
+            )
+        }
+        DataType::Float64 => Arc::new(
+            array
+                .as_primitive::<Float64Type>()
+                .unary::<_, Float64Type>(|v| (v / step).round() * step),
+        ),
+        DataType::List(item) => {
+            let list = array.as_list::<i32>();
+            Arc::new(GenericListArray::try_new(
+                item.clone(),
+                list.offsets().clone(),
+                quantize_array(list.values(), step)?,
+                list.nulls().cloned(),
+            )?)
+        }
+        DataType::LargeList(item) => {
+            let list = array.as_list::<i64>();
+            Arc::new(GenericListArray::try_new(
+                item.clone(),
+                list.offsets().clone(),
+                quantize_array(list.values(), step)?,
+                list.nulls().cloned(),
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let columns = array
+                .as_struct()
+                .columns()
+                .iter()
+                .map(|column| quantize_array(column, step))
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                columns,
+                array.nulls().cloned(),
+            )?)
+        }
+        _ => array.clone(),
+    };
+
+    Ok(quantized)
+}
+
 /// Creates an empty chunk that holds al schema fields.
 ///
 /// The schema fields are flattened inside the chunk.
@@ -400,6 +582,96 @@ pub mod testing {
         .unwrap()
     }
 
+    /// Returns a batch of two point cloud scans with `x`, `y` and `z` list columns
+    pub fn dummy_point_cloud_batch() -> RecordBatch {
+        use arrow::array::{Array, Float32Builder, ListBuilder};
+
+        let coords = [
+            [
+                vec![0.1_f32, 1.1, -0.1],
+                vec![0.2, 1.2, -0.2],
+                vec![0.3, 1.3, -0.3],
+            ],
+            [vec![-1.5_f32, 3.0], vec![0.0, 0.5], vec![2.0, 2.5]],
+        ];
+
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![10000, 10010]))];
+        let mut fields = vec![Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        )];
+
+        for (axis, name) in ["x", "y", "z"].iter().enumerate() {
+            let mut builder = ListBuilder::new(Float32Builder::new());
+            for scan in &coords {
+                builder.values().append_slice(&scan[axis]);
+                builder.append(true);
+            }
+            let array = builder.finish();
+            fields.push(Field::new(*name, array.data_type().clone(), true));
+            columns.push(Arc::new(array));
+        }
+
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+
+    /// Returns a batch of raw `rgb8` camera frames of `width`x`height` pixels, with a
+    /// pattern moving between consecutive frames and timestamps starting from 10000 with a
+    /// step of 10.
+    pub fn dummy_video_batch(frames: usize, width: usize, height: usize) -> RecordBatch {
+        use arrow::array::{BinaryArray, Int32Array, StringArray};
+
+        let data: Vec<Vec<u8>> = (0..frames)
+            .map(|frame| {
+                let mut pixels = Vec::with_capacity(width * height * 3);
+                for y in 0..height {
+                    for x in 0..width {
+                        let shifted = x + 2 * frame;
+                        pixels.push((shifted * 255 / (width + 2 * frames)) as u8);
+                        pixels.push((y * 255 / height) as u8);
+                        pixels.push(if (shifted / 8 + y / 8) % 2 == 0 {
+                            64
+                        } else {
+                            192
+                        });
+                    }
+                }
+                pixels
+            })
+            .collect();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("data", DataType::Binary, false),
+            Field::new("format", DataType::Utf8, false),
+            Field::new("width", DataType::Int32, false),
+            Field::new("height", DataType::Int32, false),
+            Field::new("stride", DataType::Int32, false),
+            Field::new("encoding", DataType::Utf8, false),
+        ]));
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from_iter_values(
+                    (0..frames as i64).map(|i| 10000 + 10 * i),
+                )),
+                Arc::new(BinaryArray::from_iter_values(data)),
+                Arc::new(StringArray::from(vec!["raw"; frames])),
+                Arc::new(Int32Array::from(vec![width as i32; frames])),
+                Arc::new(Int32Array::from(vec![height as i32; frames])),
+                Arc::new(Int32Array::from(vec![(width * 3) as i32; frames])),
+                Arc::new(StringArray::from(vec!["rgb8"; frames])),
+            ],
+        )
+        .unwrap()
+    }
+
     pub fn dummy_batch() -> RecordBatch {
         let schema = Arc::new(Schema::new(vec![
             Field::new(
@@ -611,4 +883,76 @@ mod tests {
             vec!["list_of_ints".to_owned(), "map_data".to_owned(),]
         );
     }
+
+    #[test]
+    fn sort_batch_by_timestamp() {
+        use arrow::array::Int64Array;
+
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, true),
+            Field::new("value", DataType::Int64, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![
+                    Some(30),
+                    None,
+                    Some(10),
+                    Some(30),
+                    Some(20),
+                ])),
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5])),
+            ],
+        )
+        .unwrap();
+
+        let mut stats = types::TimestampStats::new();
+        timestamp_stats_inspect_record_batch(&mut stats, &batch);
+        assert_eq!((stats.min, stats.max), (Some(10), Some(30)));
+        assert!(stats.has_null);
+        assert!(!stats.sorted);
+
+        let sorted = sort_by_timestamp(&batch).unwrap();
+        let values = sorted
+            .column(1)
+            .as_primitive::<arrow::datatypes::Int64Type>();
+        assert_eq!(values.values().to_vec(), vec![3, 5, 1, 4, 2]);
+    }
+
+    #[test]
+    fn point_cloud_stats_and_quantization() {
+        use arrow::datatypes::Float32Type;
+
+        let batch = testing::dummy_point_cloud_batch();
+        let schema = batch.schema();
+
+        // Lists are unsupported by default, while their items are inspected on request
+        let stats = ontology_model_stats_from_schema(&schema);
+        assert_eq!(stats.cols.get("x"), Some(&types::Stats::Unsupported));
+
+        let mut stats = ontology_model_stats_from_schema_with_lists(&schema);
+        // Only the last scan is inspected
+        ontology_model_stats_inspect_record_batch(&mut stats, &batch.slice(1, 1)).unwrap();
+        match stats.cols.get("x") {
+            Some(types::Stats::Numeric(s)) => {
+                assert!((s.min - -1.5).abs() < params::EPSILON);
+                assert!((s.max - 3.0).abs() < params::EPSILON);
+            }
+            s => panic!("unexpected stats for `x`: {s:?}"),
+        }
+
+        let quantized = quantize_record_batch(&batch, 0.3).unwrap();
+        let x = quantized.column_by_name("x").unwrap().as_list::<i32>();
+        let first = x.value(0);
+        // Quantized on a 0.25 grid
+        assert_eq!(
+            first.as_primitive::<Float32Type>().values().to_vec(),
+            vec![0.0, 1.0, 0.0]
+        );
+        assert_eq!(
+            x.value_offsets(),
+            batch.column(1).as_list::<i32>().value_offsets()
+        );
+    }
 }
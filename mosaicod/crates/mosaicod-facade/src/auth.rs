@@ -1,5 +1,232 @@
-use mosaicod_core::{error::PublicResult as Result, types};
+use hmac::{Hmac, Mac};
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
 use mosaicod_db as db;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Time for which a successful verification of a token is reused
+const VERIFICATION_TTL: Duration = Duration::from_secs(60);
+
+/// Maximum number of cached verifications
+const VERIFICATION_CACHE_CAPACITY: usize = 10_000;
+
+/// Maximum number of hash verifications running at the same time, each verification
+/// allocates about 19 MiB
+const MAX_CONCURRENT_VERIFICATIONS: usize = 4;
+
+/// Number of failed verifications after which the tokens sent by a client are rejected
+/// without computing the hash
+const MAX_FAILED_VERIFICATIONS: u32 = 5;
+
+/// Time window in which failed verifications are counted
+const FAILED_VERIFICATIONS_WINDOW: Duration = Duration::from_secs(60);
+
+/// Verifies tokens against the hash of their API key, shared by all the requests served
+/// with the same [`crate::Context`].
+///
+/// Successful verifications are cached for a short time, while clients with too many
+/// failed verifications are blocked for a while, so that they can't keep the verification
+/// slots busy with forged tokens. Failures are counted by client rather than by API key:
+/// fingerprints are not secret, so blocking a key would let anyone lock it out.
+pub struct ApiKeyVerifier {
+    verified: VerificationCache,
+    failures: FailureLimiter,
+    semaphore: tokio::sync::Semaphore,
+}
+
+impl Default for ApiKeyVerifier {
+    fn default() -> Self {
+        Self {
+            verified: VerificationCache::new(VERIFICATION_TTL, VERIFICATION_CACHE_CAPACITY),
+            failures: FailureLimiter::new(
+                FAILED_VERIFICATIONS_WINDOW,
+                MAX_FAILED_VERIFICATIONS,
+                VERIFICATION_CACHE_CAPACITY,
+            ),
+            semaphore: tokio::sync::Semaphore::new(MAX_CONCURRENT_VERIFICATIONS),
+        }
+    }
+}
+
+impl ApiKeyVerifier {
+    /// Verifies `token`, sent by `client`, against the hash of `api_key`.
+    ///
+    /// Returns an [`core::Error::unauthenticated`] error if the token does not match or if
+    /// the client has too many failed verifications. Failures of unknown clients are not
+    /// counted.
+    pub async fn verify(
+        &self,
+        api_key: &types::ApiKey,
+        token: &types::auth::Token,
+        client: Option<IpAddr>,
+    ) -> Result<()> {
+        if self.verified.contains(api_key, token) {
+            return Ok(());
+        }
+
+        let is_blocked = || client.is_some_and(|client| self.failures.is_blocked(&client));
+
+        if is_blocked() {
+            Err(core::Error::unauthenticated())?;
+        }
+
+        // Limit the memory used by concurrent verifications
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|e| core::Error::internal(Some(e.to_string())))?;
+
+        // Failures may have been recorded while waiting for the permit
+        if is_blocked() {
+            Err(core::Error::unauthenticated())?;
+        }
+
+        // Hash verification is intentionally slow, move it out of the async runtime
+        let api_key_copy = api_key.clone();
+        let token_copy = *token;
+        let verified = tokio::task::spawn_blocking(move || api_key_copy.verify(&token_copy))
+            .await
+            .map_err(|e| core::Error::internal(Some(e.to_string())))?;
+
+        if !verified {
+            if let Some(client) = client {
+                self.failures.record(client);
+            }
+            Err(core::Error::unauthenticated())?;
+        }
+
+        self.verified.insert(api_key, token);
+
+        Ok(())
+    }
+
+    /// Drops the cached state of the API key identified by `fingerprint`, to be called when
+    /// the key is revoked.
+    pub fn forget(&self, fingerprint: &str) {
+        self.verified.remove(fingerprint);
+    }
+}
+
+/// Cache of the tokens successfully verified against the hash of their API key.
+///
+/// Verifying a token against its argon2 hash is intentionally slow, and it is required for
+/// every request. Entries are keyed by fingerprint and store a keyed digest (HMAC-SHA256,
+/// with a random key generated at startup) of the token payload and of the stored hash, so
+/// that a token is accepted without computing the hash only if it matches exactly one already
+/// verified for the same API key.
+struct VerificationCache {
+    key: Vec<u8>,
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, (Vec<u8>, Instant)>>,
+}
+
+impl VerificationCache {
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            key: core::random::alphanumeric(32).into_bytes(),
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn digest(&self, api_key: &types::ApiKey, token: &types::auth::Token) -> Vec<u8> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("hmac accepts keys of any size");
+        mac.update(token.payload().as_bytes());
+        mac.update(api_key.payload_hash.as_str().as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Returns `true` if `token` was successfully verified against `api_key` in the
+    /// last [`VerificationCache::ttl`]
+    fn contains(&self, api_key: &types::ApiKey, token: &types::auth::Token) -> bool {
+        let digest = self.digest(api_key, token);
+        let entries = self.entries.lock().expect("verification cache poisoned");
+
+        entries
+            .get(token.fingerprint())
+            .is_some_and(|(cached, verified_at)| {
+                *cached == digest && verified_at.elapsed() < self.ttl
+            })
+    }
+
+    fn insert(&self, api_key: &types::ApiKey, token: &types::auth::Token) {
+        let digest = self.digest(api_key, token);
+        let mut entries = self.entries.lock().expect("verification cache poisoned");
+
+        if entries.len() >= self.capacity {
+            entries.retain(|_, (_, verified_at)| verified_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+
+        entries.insert(token.fingerprint().to_owned(), (digest, Instant::now()));
+    }
+
+    fn remove(&self, fingerprint: &str) {
+        self.entries
+            .lock()
+            .expect("verification cache poisoned")
+            .remove(fingerprint);
+    }
+}
+
+/// Counter of the failed verifications of the tokens, keyed by client address.
+///
+/// A client is blocked once `max_failures` verifications fail in `window`, until the
+/// window expires.
+struct FailureLimiter {
+    window: Duration,
+    max_failures: u32,
+    capacity: usize,
+    entries: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+}
+
+impl FailureLimiter {
+    fn new(window: Duration, max_failures: u32, capacity: usize) -> Self {
+        Self {
+            window,
+            max_failures,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns `true` if `client` has too many failed verifications in the current window
+    fn is_blocked(&self, client: &IpAddr) -> bool {
+        let entries = self.entries.lock().expect("failure limiter poisoned");
+
+        entries.get(client).is_some_and(|(failures, since)| {
+            *failures >= self.max_failures && since.elapsed() < self.window
+        })
+    }
+
+    fn record(&self, client: IpAddr) {
+        let mut entries = self.entries.lock().expect("failure limiter poisoned");
+
+        if entries.len() >= self.capacity {
+            entries.retain(|_, (_, since)| since.elapsed() < self.window);
+        }
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+
+        let (failures, since) = entries.entry(client).or_insert((0, Instant::now()));
+
+        if since.elapsed() >= self.window {
+            *failures = 0;
+            *since = Instant::now();
+        }
+        *failures += 1;
+    }
+}
 
 pub struct Auth {
     api_key: types::ApiKey,
@@ -24,16 +251,40 @@ impl Auth {
         Ok(Self { api_key, db })
     }
 
-    /// Creates a new API key in the system
+    /// Lookup the API key associated with `token`, sent by `client`, and verify the token
+    /// against the stored hash using `verifier`.
+    ///
+    /// Returns an [`core::Error::unauthenticated`] error if the token does not match.
+    pub async fn try_from_token(
+        token: &types::auth::Token,
+        client: Option<IpAddr>,
+        verifier: &ApiKeyVerifier,
+        db: db::Database,
+    ) -> Result<Self> {
+        let auth = Self::try_from_fingerprint(token.fingerprint(), db).await?;
+
+        verifier.verify(&auth.api_key, token, client).await?;
+
+        Ok(auth)
+    }
+
+    /// Creates a new API key in the system for the given `token`.
+    ///
+    /// Only the hash of the token payload is stored, so the token can't be retrieved later.
+    /// If `scopes` is not empty the key permissions are restricted to the resources in scope.
     pub async fn create(
+        token: &types::auth::Token,
         permissions: types::auth::Permission,
+        scopes: Vec<types::auth::ApiKeyScope>,
         description: String,
         expires_at: Option<types::Timestamp>,
         db: db::Database,
     ) -> Result<Self> {
+        let api_key =
+            types::ApiKey::new(token, permissions, description, expires_at).with_scopes(scopes)?;
+
         let mut tx = db.transaction().await?;
 
-        let api_key = types::ApiKey::new(permissions, description, expires_at);
         let api_key = db::api_key_create(&mut tx, api_key).await?;
 
         tx.commit().await?;
@@ -48,11 +299,27 @@ impl Auth {
         Ok(db::api_key_find_all(&mut cx).await?)
     }
 
-    /// Deletes the current API key
+    /// Replaces the API key payloads stored in clear by older versions with their hash.
+    ///
+    /// Returns the number of migrated keys.
+    pub async fn hash_legacy_keys(db: db::Database) -> Result<usize> {
+        let mut tx = db.transaction().await?;
+
+        let migrated = db::api_key_hash_legacy_payloads(&mut tx).await?;
+
+        tx.commit().await?;
+
+        Ok(migrated)
+    }
+
+    /// Deletes the current API key.
+    ///
+    /// The verifications of the key cached by a running daemon must be dropped with
+    /// [`ApiKeyVerifier::forget`].
     pub async fn delete(self) -> Result<()> {
         let mut tx = self.db.transaction().await?;
 
-        db::api_key_delete(&mut tx, self.api_key.key.fingerprint()).await?;
+        db::api_key_delete(&mut tx, self.api_key.fingerprint()).await?;
 
         tx.commit().await?;
 
@@ -74,12 +341,107 @@ impl Auth {
 mod tests {
     use super::*;
 
+    #[test]
+    fn verification_cache() {
+        let token = types::auth::Token::new();
+        let api_key =
+            types::ApiKey::new(&token, types::auth::Permission::Read, String::new(), None);
+
+        let cache = VerificationCache::new(Duration::from_secs(60), 2);
+        assert!(!cache.contains(&api_key, &token));
+
+        cache.insert(&api_key, &token);
+        assert!(cache.contains(&api_key, &token));
+
+        // Same fingerprint, different payload
+        let forged =
+            types::auth::Token::try_from_parts(&"a".repeat(32), token.fingerprint()).unwrap();
+        assert!(!cache.contains(&api_key, &forged));
+
+        // Revoked keys are removed
+        cache.remove(token.fingerprint());
+        assert!(!cache.contains(&api_key, &token));
+
+        // The cache is bounded
+        for _ in 0..5 {
+            let token = types::auth::Token::new();
+            cache.insert(&api_key, &token);
+        }
+        assert!(cache.entries.lock().unwrap().len() <= 2);
+
+        // Verifications expire
+        let cache = VerificationCache::new(Duration::ZERO, 2);
+        cache.insert(&api_key, &token);
+        assert!(!cache.contains(&api_key, &token));
+    }
+
+    #[test]
+    fn failure_limiter() {
+        let client = IpAddr::from([10, 0, 0, 1]);
+        let other = IpAddr::from([10, 0, 0, 2]);
+
+        let limiter = FailureLimiter::new(Duration::from_secs(60), 2, 2);
+        assert!(!limiter.is_blocked(&client));
+
+        limiter.record(client);
+        assert!(!limiter.is_blocked(&client));
+        limiter.record(client);
+        assert!(limiter.is_blocked(&client));
+        assert!(!limiter.is_blocked(&other));
+
+        // The limiter is bounded
+        for i in 0..5 {
+            limiter.record(IpAddr::from([10, 0, 1, i]));
+        }
+        assert!(limiter.entries.lock().unwrap().len() <= 2);
+
+        // Failures expire
+        let limiter = FailureLimiter::new(Duration::ZERO, 1, 2);
+        limiter.record(client);
+        assert!(!limiter.is_blocked(&client));
+    }
+
+    #[tokio::test]
+    async fn verifier_blocks_failing_clients() {
+        let token = types::auth::Token::new();
+        let api_key =
+            types::ApiKey::new(&token, types::auth::Permission::Read, String::new(), None);
+
+        let attacker = Some(IpAddr::from([10, 0, 0, 1]));
+        let client = Some(IpAddr::from([10, 0, 0, 2]));
+
+        let verifier = ApiKeyVerifier::default();
+
+        let forged =
+            types::auth::Token::try_from_parts(&"a".repeat(32), token.fingerprint()).unwrap();
+        for _ in 0..MAX_FAILED_VERIFICATIONS {
+            assert!(verifier.verify(&api_key, &forged, attacker).await.is_err());
+        }
+
+        // The failing client is blocked, even for the right token
+        assert!(verifier.verify(&api_key, &token, attacker).await.is_err());
+
+        // While the key still authenticates the other clients
+        verifier.verify(&api_key, &token, client).await.unwrap();
+
+        // Also once the cached verification is dropped
+        verifier.forget(token.fingerprint());
+        verifier.verify(&api_key, &token, client).await.unwrap();
+    }
+
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
     async fn auth_policy_create_and_delete(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
         let database = db::testing::Database::new(pool);
 
+        let token = types::auth::Token::new();
+
         let fauth = Auth::create(
+            &token,
             types::auth::Permission::Read,
+            vec![types::auth::ApiKeyScope::new(
+                "fleet_a_",
+                types::auth::Permission::Read,
+            )],
             "some text".to_owned(),
             None,
             database.clone(),
@@ -92,25 +454,78 @@ mod tests {
         {
             let mut cx = database.connection();
 
-            let res_key = db::api_key_find_by_fingerprint(&mut cx, key.token().fingerprint())
+            let res_key = db::api_key_find_by_fingerprint(&mut cx, token.fingerprint())
                 .await
                 .unwrap();
 
             assert_eq!(res_key.permission, key.permission);
-            assert_eq!(res_key.token(), key.token());
+            assert_eq!(res_key.payload_hash, key.payload_hash);
+            assert_eq!(res_key.scopes, key.scopes);
+            assert!(res_key.verify(&token));
         }
 
+        let verifier = ApiKeyVerifier::default();
+
+        // The token is verified against the stored hash
+        Auth::try_from_token(&token, None, &verifier, database.clone())
+            .await
+            .unwrap();
+
+        // Same fingerprint, different payload
+        let forged =
+            types::auth::Token::try_from_parts(&"a".repeat(32), token.fingerprint()).unwrap();
+        assert!(
+            Auth::try_from_token(&forged, None, &verifier, database.clone())
+                .await
+                .is_err()
+        );
+
         fauth.delete().await.unwrap();
+        verifier.forget(token.fingerprint());
 
         {
             let mut cx = database.connection();
 
-            let res_policy =
-                db::api_key_find_by_fingerprint(&mut cx, key.token().fingerprint()).await;
+            let res_policy = db::api_key_find_by_fingerprint(&mut cx, token.fingerprint()).await;
 
             assert!(res_policy.is_err());
         }
 
         Ok(())
     }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn auth_hash_legacy_keys(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
+        let database = db::testing::Database::new(pool.clone());
+
+        // Key stored by an older version, with the payload in clear
+        let token = types::auth::Token::new();
+        sqlx::query(
+            "INSERT INTO api_key_t (fingerprint, payload, permissions, description, creation_unix_timestamp)
+            VALUES ($1, $2, $3, '', 0)",
+        )
+        .bind(token.fingerprint().as_bytes())
+        .bind(token.payload().as_bytes())
+        .bind(types::auth::Permission::Write as i16)
+        .execute(&pool)
+        .await?;
+
+        assert_eq!(Auth::hash_legacy_keys(database.clone()).await.unwrap(), 1);
+        assert_eq!(Auth::hash_legacy_keys(database.clone()).await.unwrap(), 0);
+
+        let fauth =
+            Auth::try_from_token(&token, None, &ApiKeyVerifier::default(), database.clone())
+                .await
+                .unwrap();
+        assert_eq!(fauth.api_key().permission, types::auth::Permission::Write);
+
+        let payload: Option<Vec<u8>> =
+            sqlx::query_scalar("SELECT payload FROM api_key_t WHERE fingerprint = $1")
+                .bind(token.fingerprint().as_bytes())
+                .fetch_one(&pool)
+                .await?;
+        assert!(payload.is_none());
+
+        Ok(())
+    }
 }
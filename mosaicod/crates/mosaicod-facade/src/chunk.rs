@@ -1,6 +1,7 @@
 use super::{Context, topic};
 use mosaicod_core::{error::PublicResult as Result, types};
 use mosaicod_db as db;
+use mosaicod_rw as rw;
 
 pub struct Chunk<'a> {
     tx: db::Tx<'a>,
@@ -11,8 +12,7 @@ impl<'a> Chunk<'a> {
     pub async fn create(
         topic_uuid: &types::Uuid,
         datafile: impl AsRef<std::path::Path>,
-        size_bytes: i64,
-        row_count: i64,
+        metadata: &rw::ChunkMetadata,
         context: &'a Context,
     ) -> Result<Self> {
         let topic_id = topic::Handle::try_from_uuid(context, topic_uuid)
@@ -23,7 +23,13 @@ impl<'a> Chunk<'a> {
 
         let chunk = db::chunk_create(
             &mut tx,
-            &db::ChunkRecord::new(topic_id, datafile, size_bytes, row_count),
+            &db::ChunkRecord::new(
+                topic_id,
+                datafile,
+                metadata.size_bytes as i64,
+                metadata.row_count as i64,
+            )
+            .with_timestamps(&metadata.timestamps),
         )
         .await?;
 
@@ -38,50 +44,63 @@ impl<'a> Chunk<'a> {
         ontology_tag: &str,
         cstats: types::OntologyModelStats,
     ) -> Result<()> {
-        let mut numeric_batch: Vec<db::ColumnChunkNumericRecord> = Vec::new();
-        let mut textual_batch: Vec<db::ColumnChunkTextualRecord> = Vec::new();
+        stats_write_to_db(&mut self.tx, self.chunk.chunk_id, ontology_tag, cstats).await
+    }
 
-        // First pass: resolve column IDs and collect stats for batch insert
-        for (field, stats) in cstats.cols {
-            if stats.is_unsupported() {
-                continue;
-            }
+    pub async fn finalize(self) -> Result<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
 
-            let column = db::column_get_or_create(&mut self.tx, &field, ontology_tag).await?;
+/// Writes the column statistics of a chunk using batch inserts.
+///
+/// Column IDs are resolved first, then two batch INSERT operations are performed
+/// (one for numeric, one for textual stats).
+pub(super) async fn stats_write_to_db(
+    exe: &mut impl db::AsExec,
+    chunk_id: i32,
+    ontology_tag: &str,
+    cstats: types::OntologyModelStats,
+) -> Result<()> {
+    let mut numeric_batch: Vec<db::ColumnChunkNumericRecord> = Vec::new();
+    let mut textual_batch: Vec<db::ColumnChunkTextualRecord> = Vec::new();
 
-            match stats {
-                types::Stats::Textual(stats) => {
-                    let (min, max, has_null) = stats.into_owned();
-                    textual_batch.push(db::ColumnChunkTextualRecord::try_new(
-                        column.column_id,
-                        self.chunk.chunk_id,
-                        min,
-                        max,
-                        has_null,
-                    )?);
-                }
-                types::Stats::Numeric(stats) => {
-                    numeric_batch.push(db::ColumnChunkNumericRecord::new(
-                        column.column_id,
-                        self.chunk.chunk_id,
-                        stats.min,
-                        stats.max,
-                        stats.has_null,
-                        stats.has_nan,
-                    ));
-                }
-                types::Stats::Unsupported => {}
-            }
+    // First pass: resolve column IDs and collect stats for batch insert
+    for (field, stats) in cstats.cols {
+        if stats.is_unsupported() {
+            continue;
         }
 
-        db::column_chunk_numeric_create_batch(&mut self.tx, &numeric_batch).await?;
-        db::column_chunk_textual_create_batch(&mut self.tx, &textual_batch).await?;
+        let column = db::column_get_or_create(exe, &field, ontology_tag).await?;
 
-        Ok(())
+        match stats {
+            types::Stats::Textual(stats) => {
+                let (min, max, has_null) = stats.into_owned();
+                textual_batch.push(db::ColumnChunkTextualRecord::try_new(
+                    column.column_id,
+                    chunk_id,
+                    min,
+                    max,
+                    has_null,
+                )?);
+            }
+            types::Stats::Numeric(stats) => {
+                numeric_batch.push(db::ColumnChunkNumericRecord::new(
+                    column.column_id,
+                    chunk_id,
+                    stats.min,
+                    stats.max,
+                    stats.has_null,
+                    stats.has_nan,
+                ));
+            }
+            types::Stats::Unsupported => {}
+        }
     }
 
-    pub async fn finalize(self) -> Result<()> {
-        self.tx.commit().await?;
-        Ok(())
-    }
+    db::column_chunk_numeric_create_batch(exe, &numeric_batch).await?;
+    db::column_chunk_textual_create_batch(exe, &textual_batch).await?;
+
+    Ok(())
 }
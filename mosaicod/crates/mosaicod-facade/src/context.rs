@@ -1,16 +1,20 @@
+use crate::ApiKeyVerifier;
 use mosaicod_db as db;
 use mosaicod_query as query;
 use mosaicod_store as store;
+use std::sync::Arc;
 
 /// Shared context for all facade functions.
 ///
 /// Contains references to the store, database, and timeseries engine
-/// that facade functions require to perform their operations.
+/// that facade functions require to perform their operations, and the state
+/// used to verify API keys.
 #[derive(Clone)]
 pub struct Context {
     pub store: store::StoreRef,
     pub db: db::Database,
     pub timeseries_querier: query::TimeseriesEngineRef,
+    pub api_key_verifier: Arc<ApiKeyVerifier>,
 }
 
 impl Context {
@@ -23,6 +27,7 @@ impl Context {
             store,
             db,
             timeseries_querier: ts_gw,
+            api_key_verifier: Arc::new(ApiKeyVerifier::default()),
         }
     }
 }
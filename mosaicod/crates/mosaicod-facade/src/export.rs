@@ -0,0 +1,573 @@
+//! This module provides the export of sequences in formats readable by other tools
+//! (e.g. MCAP recordings for Foxglove and ROS tooling) and as bundles, used to move
+//! sequences between deployments.
+
+use super::{Context, sequence, topic};
+use arrow::array::RecordBatch;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+use mosaicod_mcap as mcap;
+use mosaicod_query as query;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+/// Stream of the bytes composing an exported file
+pub type ExportStream = BoxStream<'static, Result<Vec<u8>>>;
+
+/// Topic data read while exporting a sequence
+struct Source {
+    channel_id: u16,
+    sequence: u32,
+    encoder: mcap::ChannelEncoder,
+    batches: BoxStream<'static, std::result::Result<RecordBatch, query::Error>>,
+    /// Encoded messages not yet written, along with their log time
+    messages: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl Source {
+    /// Ensures that a message is available, returns `false` if the topic has no more data.
+    async fn fill(&mut self) -> Result<bool> {
+        while self.messages.is_empty() {
+            let Some(batch) = self.batches.next().await else {
+                return Ok(false);
+            };
+            self.messages = self.encoder.encode(&batch?)?.into();
+        }
+        Ok(true)
+    }
+}
+
+struct McapExporter {
+    /// Set to `None` once the recording has been completed
+    writer: Option<mcap::StreamWriter>,
+    sources: Vec<Source>,
+}
+
+/// Exports the sequence `locator` as a single MCAP recording, returned as a stream of bytes.
+///
+/// Each topic is written as a channel (named after the topic path inside the sequence)
+/// containing a JSON message for each row, the arrow schema and the ontology tag of
+/// the topic are recorded as channel metadata. Messages of all the topics are sorted by
+/// `timestamp_ns`.
+///
+/// If `topics` is provided only these topics are exported, otherwise all the topics of the
+/// sequence. The exported data can be limited to a `timestamp_range`.
+#[tracing::instrument(skip_all, fields(sequence = %locator))]
+pub async fn mcap(
+    context: &Context,
+    locator: types::SequenceLocator,
+    topics: Option<Vec<types::TopicLocator>>,
+    timestamp_range: Option<types::TimestampRange>,
+) -> Result<ExportStream> {
+    let sequence_handle = sequence::Handle::try_from_locator(context, locator).await?;
+
+    let mut handles = sequence::topic_list(context, &sequence_handle).await?;
+    handles.sort_by_key(|h| h.locator().to_string());
+
+    if let Some(topics) = topics {
+        if let Some(topic) = topics
+            .iter()
+            .find(|topic| !handles.iter().any(|h| h.locator() == *topic))
+        {
+            Err(core::Error::bad_request(format!(
+                "topic `{topic}` doesn't belong to sequence `{}`",
+                sequence_handle.locator()
+            )))?;
+        }
+        handles.retain(|h| topics.contains(h.locator()));
+    }
+
+    let mut writer = mcap::StreamWriter::try_new()?;
+    let mut sources = Vec::with_capacity(handles.len());
+
+    for handle in handles {
+        let stats = topic::chunks_stats(context, &handle).await?;
+        if stats.total_row_count == 0 {
+            trace!("skipping topic `{}` without data", handle.locator());
+            continue;
+        }
+
+        let metadata = topic::metadata(context, &handle).await?;
+        let batch_size = topic::compute_optimal_batch_size(context, &handle).await?;
+
+        let query_result = topic::read(
+            context,
+            &handle,
+            metadata.ontology_metadata.properties.serialization_format,
+            Some(batch_size),
+            timestamp_range.clone(),
+        )
+        .await?;
+
+        let encoder = mcap::ChannelEncoder::try_new(
+            &metadata.ontology_metadata.properties.ontology_tag,
+            &query_result.schema_with_metadata(Default::default()),
+        )?;
+
+        let channel_id = writer.add_channel(&channel_topic(&sequence_handle, &handle), &encoder)?;
+
+        trace!("exporting topic `{}`", handle.locator());
+
+        sources.push(Source {
+            channel_id,
+            sequence: 0,
+            encoder,
+            batches: query_result
+                .stream()
+                .await?
+                .map_err(query::Error::from)
+                .boxed(),
+            messages: VecDeque::new(),
+        });
+    }
+
+    let exporter = McapExporter {
+        writer: Some(writer),
+        sources,
+    };
+
+    Ok(stream::try_unfold(exporter, next_mcap_bytes).boxed())
+}
+
+/// Writes messages until some bytes of the recording are available, merging the topics
+/// by timestamp.
+async fn next_mcap_bytes(mut exporter: McapExporter) -> Result<Option<(Vec<u8>, McapExporter)>> {
+    if exporter.writer.is_none() {
+        return Ok(None);
+    }
+
+    loop {
+        let mut index = 0;
+        while index < exporter.sources.len() {
+            if exporter.sources[index].fill().await? {
+                index += 1;
+            } else {
+                exporter.sources.remove(index);
+            }
+        }
+
+        // On equal timestamps the first topic is written first
+        let source = exporter
+            .sources
+            .iter_mut()
+            .min_by_key(|source| source.messages.front().map(|(log_time, _)| *log_time));
+
+        let Some(source) = source else {
+            let writer = exporter.writer.take().expect("writer checked above");
+            return Ok(Some((writer.finish()?, exporter)));
+        };
+
+        let (log_time, data) = source.messages.pop_front().expect("source filled above");
+
+        let writer = exporter.writer.as_mut().expect("writer checked above");
+        writer.write(source.channel_id, source.sequence, log_time, &data)?;
+        source.sequence = source.sequence.wrapping_add(1);
+
+        let bytes = writer.take();
+        if !bytes.is_empty() {
+            return Ok(Some((bytes, exporter)));
+        }
+    }
+}
+
+/// Builds the channel name of a topic, i.e. the topic path inside the sequence
+/// (e.g. `run_01/camera/front` becomes `/camera/front`).
+fn channel_topic(sequence: &sequence::Handle, topic: &topic::Handle) -> String {
+    format!("/{}", topic_name(sequence.locator(), topic.locator()))
+}
+
+/// File of a bundle
+enum BundleEntry {
+    Inline {
+        path: String,
+        data: Vec<u8>,
+    },
+    /// The file is read from the store when the entry is written
+    Stored {
+        path: String,
+        path_in_store: PathBuf,
+    },
+}
+
+struct BundleExporter {
+    context: Context,
+    /// Set to `None` once the archive has been completed
+    builder: Option<tar::Builder<Vec<u8>>>,
+    /// Modification time of the archived files, in seconds
+    mtime: u64,
+    entries: VecDeque<BundleEntry>,
+}
+
+/// Exports the sequence `locator` as a bundle, returned as a stream of bytes.
+///
+/// The bundle is a tar archive containing the sequence and topics metadata files, all the
+/// data chunks and a manifest describing the database records (sessions, topics, chunks
+/// with their column statistics and notifications), see [`marshal::JsonBundleManifest`].
+/// Bundles can be imported in another deployment with [`crate::import::bundle`].
+///
+/// Returns a [`core::Error::bad_request`] if some topic of the sequence is still being
+/// uploaded.
+#[tracing::instrument(skip_all, fields(sequence = %locator))]
+pub async fn bundle(context: &Context, locator: types::SequenceLocator) -> Result<ExportStream> {
+    let sequence_handle = sequence::Handle::try_from_locator(context, locator).await?;
+
+    let mut cx = context.db.connection();
+
+    let sequence_record = db::sequence_find_by_id(&mut cx, sequence_handle.id()).await?;
+    let session_records =
+        db::sequence_find_all_sessions(&mut cx, sequence_handle.locator()).await?;
+    let mut topic_records =
+        db::sequence_find_all_topics(&mut cx, sequence_handle.locator()).await?;
+    topic_records.sort_by_key(|record| record.locator().to_string());
+
+    let mut entries = VecDeque::new();
+
+    let notifications =
+        db::sequence_notifications_find_by_sequence_id(&mut cx, sequence_handle.id()).await?;
+
+    let sequence_metadata = marshal::JsonBundleSequence {
+        locator: sequence_handle.locator().to_string(),
+        created_at: sequence_record.creation_timestamp().as_i64(),
+        user_metadata: sequence_record.user_metadata(),
+        notifications: notifications
+            .into_iter()
+            .map(|n| bundle_notification(n.notification_type(), n.creation_timestamp(), n.msg))
+            .collect(),
+    };
+
+    if sequence_metadata.user_metadata.is_some() {
+        entries.push_back(BundleEntry::Stored {
+            path: marshal::BUNDLE_SEQUENCE_METADATA_FILE.to_owned(),
+            path_in_store: sequence_record.path_in_store().path_metadata(),
+        });
+    }
+
+    let sessions: HashMap<i32, usize> = session_records
+        .iter()
+        .enumerate()
+        .map(|(index, session)| (session.session_id, index))
+        .collect();
+
+    // Column names are shared by the chunks of a topic
+    let mut columns: HashMap<i32, String> = HashMap::new();
+
+    let mut topics = Vec::with_capacity(topic_records.len());
+
+    for record in topic_records {
+        let locator = record.locator();
+
+        let Some(completed_at) = record.completion_timestamp() else {
+            Err(core::Error::bad_request(format!(
+                "topic `{locator}` is still being uploaded"
+            )))?
+        };
+
+        let path_in_store = record.path_in_store().ok_or_else(|| {
+            core::Error::internal(Some(format!("Path in store not set for topic {locator}")))
+        })?;
+
+        let serialization_format = record
+            .serialization_format()
+            .ok_or_else(|| super::Error::MissingDbData("serialization_format".to_owned()))?;
+
+        let name = topic_name(sequence_handle.locator(), &locator);
+        let folder = marshal::bundle_topic_folder(&name);
+
+        entries.push_back(BundleEntry::Stored {
+            path: format!("{folder}/metadata.json"),
+            path_in_store: path_in_store.path_metadata(),
+        });
+
+        let mut chunks = Vec::new();
+        for chunk in db::chunk_find_by_topic_id(&mut cx, record.topic_id).await? {
+            let file_name = chunk
+                .data_file()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| {
+                    core::Error::internal(Some(format!(
+                        "invalid data file for a chunk of topic {locator}"
+                    )))
+                })?;
+            let file = format!("{folder}/data/{file_name}");
+
+            let mut stats = Vec::new();
+            for s in db::column_chunk_numeric_find_by_chunk_id(&mut cx, chunk.chunk_id).await? {
+                stats.push(marshal::JsonBundleColumnStats::Numeric {
+                    column: column_name(&mut cx, &mut columns, s.column_id).await?,
+                    min: Some(s.min_value).filter(|v| v.is_finite()),
+                    max: Some(s.max_value).filter(|v| v.is_finite()),
+                    has_null: s.has_null,
+                    has_nan: s.has_nan,
+                });
+            }
+            for s in db::column_chunk_textual_find_by_chunk_id(&mut cx, chunk.chunk_id).await? {
+                stats.push(marshal::JsonBundleColumnStats::Textual {
+                    column: column_name(&mut cx, &mut columns, s.column_id).await?,
+                    min: s.min_value,
+                    max: s.max_value,
+                    has_null: s.has_null,
+                });
+            }
+
+            entries.push_back(BundleEntry::Stored {
+                path: file.clone(),
+                path_in_store: chunk.data_file().to_path_buf(),
+            });
+
+            chunks.push(marshal::JsonBundleChunk {
+                file,
+                size_bytes: chunk.size_bytes,
+                row_count: chunk.row_count,
+                min_timestamp_ns: chunk.min_timestamp_ns,
+                max_timestamp_ns: chunk.max_timestamp_ns,
+                sorted: chunk.sorted,
+                stats,
+            });
+        }
+
+        let notifications = db::topic_notifications_find_by_locator(&mut cx, &locator).await?;
+
+        topics.push(marshal::JsonBundleTopic {
+            name,
+            session: sessions.get(&record.session_id).copied().ok_or_else(|| {
+                core::Error::internal(Some(format!("session not found for topic {locator}")))
+            })?,
+            properties: types::TopicOntologyProperties {
+                serialization_format,
+                ontology_tag: record.ontology_tag.clone(),
+                writer_options: record.writer_options(),
+            }
+            .into(),
+            user_metadata: record.user_metadata(),
+            created_at: record.creation_timestamp().as_i64(),
+            completed_at: Some(completed_at.as_i64()),
+            info: record.info().map(|info| marshal::JsonBundleTopicInfo {
+                chunks_number: info.chunks_number,
+                total_bytes: info.total_bytes,
+                start_index_timestamp: info.timestamp_range.start.as_i64(),
+                end_index_timestamp: info.timestamp_range.end.as_i64(),
+            }),
+            notifications: notifications
+                .into_iter()
+                .map(|n| bundle_notification(n.notification_type(), n.creation_timestamp(), n.msg))
+                .collect(),
+            chunks,
+        });
+    }
+
+    let manifest = marshal::JsonBundleManifest {
+        version: marshal::BUNDLE_VERSION,
+        sequence: sequence_metadata,
+        sessions: session_records
+            .iter()
+            .map(|session| marshal::JsonBundleSession {
+                created_at: session.creation_timestamp().as_i64(),
+                completed_at: session.completion_timestamp().map(|ts| ts.as_i64()),
+            })
+            .collect(),
+        topics,
+    };
+
+    // The manifest is the first entry, so that it can be read before the data
+    entries.push_front(BundleEntry::Inline {
+        path: marshal::BUNDLE_MANIFEST_FILE.to_owned(),
+        data: (&manifest).try_into()?,
+    });
+
+    let exporter = BundleExporter {
+        context: context.clone(),
+        builder: Some(tar::Builder::new(Vec::new())),
+        mtime: (sequence_record.creation_timestamp().as_i64() / 1_000_000_000).max(0) as u64,
+        entries,
+    };
+
+    Ok(stream::try_unfold(exporter, next_bundle_bytes).boxed())
+}
+
+/// Writes the next entry of the bundle, reading its content from the store if required.
+async fn next_bundle_bytes(
+    mut exporter: BundleExporter,
+) -> Result<Option<(Vec<u8>, BundleExporter)>> {
+    let Some(mut builder) = exporter.builder.take() else {
+        return Ok(None);
+    };
+
+    let Some(entry) = exporter.entries.pop_front() else {
+        let bytes = builder.into_inner().map_err(bundle_error)?;
+        return Ok(Some((bytes, exporter)));
+    };
+
+    let (path, data) = match entry {
+        BundleEntry::Inline { path, data } => (path, data),
+        BundleEntry::Stored {
+            path,
+            path_in_store,
+        } => {
+            trace!("adding `{}` to bundle", path_in_store.display());
+            let data = exporter.context.store.read_bytes(&path_in_store).await?;
+            (path, data)
+        }
+    };
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(exporter.mtime);
+    builder
+        .append_data(&mut header, &path, data.as_slice())
+        .map_err(bundle_error)?;
+
+    let bytes = std::mem::take(builder.get_mut());
+    exporter.builder = Some(builder);
+
+    Ok(Some((bytes, exporter)))
+}
+
+fn bundle_error(e: std::io::Error) -> core::Error {
+    core::Error::internal(Some(format!("unable to write bundle: {e}")))
+}
+
+fn bundle_notification(
+    notification_type: types::NotificationType,
+    created_at: types::Timestamp,
+    msg: Option<String>,
+) -> marshal::JsonBundleNotification {
+    marshal::JsonBundleNotification {
+        notification_type: notification_type.to_string(),
+        msg,
+        created_at: created_at.as_i64(),
+    }
+}
+
+async fn column_name(
+    exe: &mut impl db::AsExec,
+    columns: &mut HashMap<i32, String>,
+    column_id: i32,
+) -> Result<String> {
+    if let Some(name) = columns.get(&column_id) {
+        return Ok(name.clone());
+    }
+
+    let column = db::column_find_by_id(exe, column_id).await?;
+    columns.insert(column_id, column.column_name.clone());
+
+    Ok(column.column_name)
+}
+
+/// Returns the path of a topic inside the sequence (e.g. `run_01/camera/front` becomes
+/// `camera/front`).
+fn topic_name(sequence: &types::SequenceLocator, topic: &types::TopicLocator) -> String {
+    let name = topic.to_string();
+    let prefix = format!("{sequence}/");
+
+    name.strip_prefix(&prefix).unwrap_or(&name).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import;
+    use mosaicod_core::params;
+    use mosaicod_db as db;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let database = db::testing::Database::new(pool);
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store.clone(), database.clone(), ts_gw)
+    }
+
+    /// Builds a recording with two JSON channels with interleaved timestamps
+    fn recording() -> Vec<u8> {
+        let mut writer = ::mcap::Writer::new(std::io::Cursor::new(Vec::new())).unwrap();
+
+        let gps = writer
+            .add_channel(0, "/gps", "json", &Default::default())
+            .unwrap();
+        let imu = writer
+            .add_channel(0, "/imu", "json", &Default::default())
+            .unwrap();
+
+        for i in 0..10u64 {
+            let (channel_id, log_time) = if i % 3 == 0 { (gps, i) } else { (imu, i) };
+            let header = ::mcap::records::MessageHeader {
+                channel_id,
+                sequence: i as u32,
+                log_time,
+                publish_time: log_time,
+            };
+            writer
+                .write_to_known_channel(&header, format!(r#"{{"value": {i}}}"#).as_bytes())
+                .unwrap();
+        }
+
+        writer.finish().unwrap();
+        writer.into_inner().into_inner()
+    }
+
+    async fn export(
+        context: &Context,
+        topics: Option<Vec<types::TopicLocator>>,
+        timestamp_range: Option<types::TimestampRange>,
+    ) -> Result<Vec<(String, u64)>> {
+        let data: Vec<Vec<u8>> = mcap(context, "run".parse().unwrap(), topics, timestamp_range)
+            .await?
+            .try_collect()
+            .await?;
+        let data = data.concat();
+
+        Ok(mcap::messages(data.as_slice())
+            .map(|message| {
+                let message = message.unwrap();
+                (message.channel.topic.clone(), message.log_time)
+            })
+            .collect())
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn export_mcap(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        import::mcap(
+            &context,
+            "run".parse().unwrap(),
+            None,
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        // Messages of all the topics are sorted by timestamp
+        let messages = export(&context, None, None).await.unwrap();
+        let timestamps: Vec<u64> = messages.iter().map(|(_, ts)| *ts).collect();
+        assert_eq!(timestamps, (0..10).collect::<Vec<_>>());
+        assert_eq!(messages[0].0, "/gps");
+        assert_eq!(messages[1].0, "/imu");
+
+        // Topics and timestamp range subset
+        let messages = export(
+            &context,
+            Some(vec!["run/imu".parse().unwrap()]),
+            Some(types::TimestampRange::between(2.into(), 5.into())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages, [("/imu".to_owned(), 2), ("/imu".to_owned(), 4)]);
+
+        // Topics must belong to the sequence
+        assert!(
+            export(&context, Some(vec!["other/imu".parse().unwrap()]), None)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,646 @@
+//! This module checks the consistency between the records stored in the database and the
+//! files stored in the object store.
+//!
+//! Records are deleted from the database before their files are removed from the store, and
+//! data and metadata files are written before the database transaction is committed, so an
+//! interrupted operation can leave files without records or records pointing to missing files.
+//! The [`check`] function detects these inconsistencies, [`repair`] fixes them.
+
+use super::{Context, sequence, topic};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_ext as ext;
+use mosaicod_rw::{self as rw, ToIpcProperties};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// Prefixes of the store folders managed by the daemon, other folders are never reported
+const MANAGED_FOLDER_PREFIXES: [&str; 2] = ["sq_", "tp_"];
+
+/// Size and number of rows of a chunk data file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkFileInfo {
+    pub size_bytes: i64,
+    pub row_count: i64,
+}
+
+/// Inconsistency between the database and the store
+#[derive(Debug)]
+pub enum Issue {
+    /// Store folder not referenced by any sequence or topic
+    OrphanFolder { path: String },
+    /// Data file inside a topic folder not referenced by any chunk
+    OrphanFile {
+        topic: types::TopicLocator,
+        path: String,
+    },
+    /// Chunk whose data file is missing from the store
+    MissingChunkFile {
+        topic: types::TopicLocator,
+        chunk_id: i32,
+        path: String,
+    },
+    /// Chunk whose data file has a size or a number of rows different from the recorded ones
+    ChunkMismatch {
+        topic: types::TopicLocator,
+        chunk_id: i32,
+        path: String,
+        recorded: ChunkFileInfo,
+        found: ChunkFileInfo,
+    },
+    /// Chunk whose data file can't be read
+    UnreadableChunkFile {
+        topic: types::TopicLocator,
+        chunk_id: i32,
+        path: String,
+        reason: String,
+    },
+    /// Sequence with user metadata but without its metadata file
+    MissingSequenceMetadata {
+        sequence: types::SequenceLocator,
+        path: String,
+    },
+    /// Topic without its metadata file
+    MissingTopicMetadata {
+        topic: types::TopicLocator,
+        path: String,
+    },
+}
+
+impl Issue {
+    /// Returns `true` if the issue is fixed by deleting files from the store
+    pub fn is_garbage(&self) -> bool {
+        matches!(self, Self::OrphanFolder { .. } | Self::OrphanFile { .. })
+    }
+
+    /// Returns `true` if the issue can be fixed by [`repair`]
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::UnreadableChunkFile { .. })
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OrphanFolder { path } => {
+                write!(
+                    f,
+                    "folder `{path}` is not referenced by any sequence or topic"
+                )
+            }
+            Self::OrphanFile { topic, path } => {
+                write!(
+                    f,
+                    "file `{path}` of topic `{topic}` is not referenced by any chunk"
+                )
+            }
+            Self::MissingChunkFile {
+                topic,
+                chunk_id,
+                path,
+            } => write!(
+                f,
+                "chunk {chunk_id} of topic `{topic}` references the missing file `{path}`"
+            ),
+            Self::ChunkMismatch {
+                topic,
+                chunk_id,
+                path,
+                recorded,
+                found,
+            } => write!(
+                f,
+                "file `{path}` of chunk {chunk_id} of topic `{topic}` has {} bytes and {} rows, \
+                 {} bytes and {} rows recorded",
+                found.size_bytes, found.row_count, recorded.size_bytes, recorded.row_count
+            ),
+            Self::UnreadableChunkFile {
+                topic,
+                chunk_id,
+                path,
+                reason,
+            } => write!(
+                f,
+                "file `{path}` of chunk {chunk_id} of topic `{topic}` can't be read: {reason}"
+            ),
+            Self::MissingSequenceMetadata { sequence, path } => {
+                write!(
+                    f,
+                    "metadata file `{path}` of sequence `{sequence}` is missing"
+                )
+            }
+            Self::MissingTopicMetadata { topic, path } => {
+                write!(f, "metadata file `{path}` of topic `{topic}` is missing")
+            }
+        }
+    }
+}
+
+/// Result of a consistency check
+#[derive(Debug, Default)]
+pub struct Report {
+    pub sequences: usize,
+    pub topics: usize,
+    pub chunks: usize,
+    pub issues: Vec<Issue>,
+}
+
+/// Checks the consistency of all the sequences, topics and chunks stored in the database
+/// against the content of the store.
+///
+/// The store is listed before reading the database, so resources created during the check
+/// are not reported as orphans. The check should still run while no data is being uploaded,
+/// since files written by in-progress uploads are reported until their records are committed.
+pub async fn check(context: &Context) -> Result<Report> {
+    trace!("listing store content");
+    let mut folders: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for file in context.store.list("", None).await? {
+        if let Some((folder, _)) = file.split_once('/') {
+            folders.entry(folder.to_owned()).or_default().insert(file);
+        }
+    }
+
+    let mut cx = context.db.connection();
+
+    let sequences = db::sequence_find_all(&mut cx).await?;
+    let topics = db::topic_find_all(&mut cx).await?;
+
+    let mut chunks: HashMap<i32, Vec<db::ChunkRecord>> = HashMap::new();
+    let mut chunks_number = 0;
+    for chunk in db::chunk_find_all(&mut cx).await? {
+        chunks_number += 1;
+        chunks.entry(chunk.topic_id).or_default().push(chunk);
+    }
+
+    let mut report = Report {
+        sequences: sequences.len(),
+        topics: topics.len(),
+        chunks: chunks_number,
+        issues: Vec::new(),
+    };
+
+    for record in &sequences {
+        let path_in_store = record.path_in_store();
+        let files = folders.remove(&path_in_store.to_string());
+
+        let path = path_str(&path_in_store.path_metadata());
+        let has_metadata = files.is_some_and(|files| files.contains(&path));
+
+        if record.user_metadata().is_some() && !has_metadata {
+            report.issues.push(Issue::MissingSequenceMetadata {
+                sequence: record.locator(),
+                path,
+            });
+        }
+    }
+
+    for record in &topics {
+        let Some(path_in_store) = record.path_in_store() else {
+            continue;
+        };
+        let locator = record.locator();
+        let mut files = folders
+            .remove(&path_in_store.to_string())
+            .unwrap_or_default();
+
+        let path = path_str(&path_in_store.path_metadata());
+        if !files.remove(&path) {
+            report.issues.push(Issue::MissingTopicMetadata {
+                topic: locator.clone(),
+                path,
+            });
+        }
+
+        for chunk in chunks.remove(&record.topic_id).unwrap_or_default() {
+            let path = path_str(chunk.data_file());
+            if !files.remove(&path) {
+                report.issues.push(Issue::MissingChunkFile {
+                    topic: locator.clone(),
+                    chunk_id: chunk.chunk_id,
+                    path,
+                });
+                continue;
+            }
+
+            if let Some(issue) = check_chunk(
+                context,
+                &locator,
+                record.serialization_format(),
+                &chunk,
+                path,
+            )
+            .await?
+            {
+                report.issues.push(issue);
+            }
+        }
+
+        // Files outside the data folder are not managed by the daemon
+        let data_folder = path_str(&path_in_store.path_data_folder(&record.uuid()));
+        for path in files {
+            if Path::new(&path).starts_with(&data_folder) {
+                report.issues.push(Issue::OrphanFile {
+                    topic: locator.clone(),
+                    path,
+                });
+            }
+        }
+    }
+
+    for folder in folders.into_keys() {
+        if MANAGED_FOLDER_PREFIXES
+            .iter()
+            .any(|prefix| folder.starts_with(prefix))
+        {
+            report.issues.push(Issue::OrphanFolder { path: folder });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Fixes an issue reported by [`check`].
+///
+/// - orphan folders and files are deleted from the store,
+/// - chunks whose data file is missing are deleted from the database, along with their
+///   column statistics, and the data info of their topic is updated,
+/// - chunks whose data file has a different size or number of rows are updated to match
+///   the file,
+/// - missing metadata files are written again using the database records.
+///
+/// Unreadable chunk files can't be repaired automatically, an error is returned.
+///
+/// Orphans and missing files are checked again before deleting anything, returns `false`
+/// if the issue no longer exists (e.g. the orphan belongs to a resource created after the
+/// check) and nothing was done.
+///
+/// A [`types::DataLossToken`] is required since orphan files and chunk records are
+/// permanently deleted.
+pub async fn repair(
+    context: &Context,
+    issue: &Issue,
+    allow_data_loss: types::DataLossToken,
+) -> Result<bool> {
+    trace!("repairing: {}", issue);
+
+    match issue {
+        Issue::OrphanFolder { path } => {
+            let mut cx = context.db.connection();
+            if db::store_folder_referenced(&mut cx, path).await? {
+                return Ok(false);
+            }
+            context.store.delete_recursive(path).await?;
+        }
+        Issue::OrphanFile { path, .. } => {
+            let mut cx = context.db.connection();
+            if db::chunk_data_file_exists(&mut cx, path).await? {
+                return Ok(false);
+            }
+            context.store.delete(path).await?;
+        }
+        Issue::MissingChunkFile {
+            topic,
+            chunk_id,
+            path,
+        } => {
+            if context.store.exists(path).await? {
+                return Ok(false);
+            }
+
+            let mut tx = context.db.transaction().await?;
+            let record = db::topic_find_by_locator(&mut tx, topic).await?;
+            db::chunk_delete(&mut tx, *chunk_id, allow_data_loss).await?;
+
+            // Finalized topics cache the number and the size of their chunks
+            if let Some(info) = record.info() {
+                let stats = db::topic_get_stats(&mut tx, topic).await?;
+                let info = types::TopicDataInfo {
+                    chunks_number: stats.chunks_count as u64,
+                    total_bytes: stats.total_size_bytes as u64,
+                    timestamp_range: info.timestamp_range,
+                };
+                db::topic_update_system_info(&mut tx, topic, &info).await?;
+            }
+            tx.commit().await?;
+        }
+        Issue::ChunkMismatch {
+            chunk_id, found, ..
+        } => {
+            let mut tx = context.db.transaction().await?;
+            db::chunk_update_file_info(&mut tx, *chunk_id, found.size_bytes, found.row_count)
+                .await?;
+            tx.commit().await?;
+        }
+        Issue::UnreadableChunkFile { path, .. } => Err(core::Error::bad_request(format!(
+            "file `{path}` can't be repaired automatically"
+        )))?,
+        Issue::MissingSequenceMetadata { sequence, path } => {
+            let mut cx = context.db.connection();
+            let record = db::sequence_find_by_locator(&mut cx, sequence).await?;
+            if let Some(user_metadata) = record.user_metadata() {
+                sequence::metadata_write_to_store(context, Path::new(path), user_metadata).await?;
+            }
+        }
+        Issue::MissingTopicMetadata { topic, path } => {
+            let handle = topic::Handle::try_from_locator(context, topic.clone()).await?;
+            let metadata = topic::metadata(context, &handle).await?;
+            topic::metadata_write_to_store(context, Path::new(path), metadata).await?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Compares the data file of a chunk with the values recorded in the database.
+async fn check_chunk(
+    context: &Context,
+    topic: &types::TopicLocator,
+    format: Option<types::Format>,
+    chunk: &db::ChunkRecord,
+    path: String,
+) -> Result<Option<Issue>> {
+    let size_bytes = context.store.size(&path).await? as i64;
+
+    let row_count = match format.filter(|format| format.to_ipc_properties().is_some()) {
+        // Arrow IPC files do not record their number of rows, so all the batches are read
+        Some(format) => {
+            let buffer = context.store.read_bytes(&path).await?;
+            rw::ChunkReader::new(format, buffer.into())
+                .and_then(|mut reader| {
+                    reader.try_fold(0, |rows, batch| Ok(rows + batch?.num_rows() as i64))
+                })
+                .map_err(|e| e.to_string())
+        }
+        None => {
+            let mut reader = context.store.parquet_reader(&path);
+            ext::arrow::row_count_from_parquet_reader(&mut reader)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    };
+    let row_count = match row_count {
+        Ok(row_count) => row_count,
+        Err(reason) => {
+            return Ok(Some(Issue::UnreadableChunkFile {
+                topic: topic.clone(),
+                chunk_id: chunk.chunk_id,
+                path,
+                reason,
+            }));
+        }
+    };
+
+    let recorded = ChunkFileInfo {
+        size_bytes: chunk.size_bytes,
+        row_count: chunk.row_count,
+    };
+    let found = ChunkFileInfo {
+        size_bytes,
+        row_count,
+    };
+
+    if recorded == found {
+        return Ok(None);
+    }
+
+    Ok(Some(Issue::ChunkMismatch {
+        topic: topic.clone(),
+        chunk_id: chunk.chunk_id,
+        path,
+        recorded,
+        found,
+    }))
+}
+
+/// Returns the path in the same representation used by the store listing
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import;
+    use mosaicod_core::params;
+    use mosaicod_marshal as marshal;
+    use mosaicod_query as query;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let database = db::testing::Database::new(pool);
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store.clone(), database.clone(), ts_gw)
+    }
+
+    fn recording() -> Vec<u8> {
+        let mut writer = ::mcap::Writer::new(std::io::Cursor::new(Vec::new())).unwrap();
+
+        let channel = writer
+            .add_channel(0, "/robot/status", "json", &Default::default())
+            .unwrap();
+
+        for i in 0..10u64 {
+            let header = ::mcap::records::MessageHeader {
+                channel_id: channel,
+                sequence: i as u32,
+                log_time: 1_000 + i,
+                publish_time: 1_000 + i,
+            };
+            writer
+                .write_to_known_channel(&header, format!(r#"{{"battery": {i}}}"#).as_bytes())
+                .unwrap();
+        }
+
+        writer.finish().unwrap();
+        writer.into_inner().into_inner()
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn check_and_repair(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let metadata = marshal::JsonMetadataBlob::from(serde_json::json!({"driver": "jon"}));
+        import::mcap(
+            &context,
+            "run".parse().unwrap(),
+            Some(metadata),
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        let report = check(&context).await.unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.sequences, 1);
+        assert_eq!(report.topics, 1);
+        assert_eq!(report.chunks, 1);
+
+        let mut cx = context.db.connection();
+        let sequence = db::sequence_find_by_locator(&mut cx, &"run".parse().unwrap())
+            .await
+            .unwrap();
+        let topic = db::topic_find_by_locator(&mut cx, &"run/robot/status".parse().unwrap())
+            .await
+            .unwrap();
+        let topic_path = topic.path_in_store().unwrap();
+        let chunk = db::chunk_find_by_topic_id(&mut cx, topic.topic_id)
+            .await
+            .unwrap()
+            .remove(0);
+
+        // Break the consistency between the database and the store
+        let store = &context.store;
+        store
+            .delete(sequence.path_in_store().path_metadata())
+            .await
+            .unwrap();
+        store.delete(topic_path.path_metadata()).await.unwrap();
+        store
+            .write_bytes("tp_orphan/data.parquet", vec![0u8])
+            .await
+            .unwrap();
+        store
+            .write_bytes("exports/run.tar", vec![0u8])
+            .await
+            .unwrap();
+        let orphan_file = topic_path
+            .path_data_folder(&topic.uuid())
+            .join("data-00099.parquet");
+        store.write_bytes(&orphan_file, vec![0u8]).await.unwrap();
+        db::chunk_update_file_info(&mut cx, chunk.chunk_id, chunk.size_bytes, 99)
+            .await
+            .unwrap();
+
+        let report = check(&context).await.unwrap();
+        assert_eq!(report.issues.len(), 5, "{:?}", report.issues);
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::OrphanFolder { path } if path == "tp_orphan"
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::OrphanFile { path, .. } if Path::new(path) == orphan_file
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::ChunkMismatch { recorded, found, .. } if recorded.row_count == 99 && found.row_count == 10
+        )));
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, Issue::MissingSequenceMetadata { .. }))
+        );
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, Issue::MissingTopicMetadata { .. }))
+        );
+
+        for issue in &report.issues {
+            assert!(
+                repair(&context, issue, types::allow_data_loss())
+                    .await
+                    .unwrap()
+            );
+        }
+
+        let report = check(&context).await.unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert!(store.exists("exports/run.tar").await.unwrap());
+
+        // A missing data file leaves the chunk record unreachable
+        store.delete(chunk.data_file()).await.unwrap();
+
+        let report = check(&context).await.unwrap();
+        assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+        assert!(matches!(
+            &report.issues[0],
+            Issue::MissingChunkFile { chunk_id, .. } if *chunk_id == chunk.chunk_id
+        ));
+
+        assert!(
+            repair(&context, &report.issues[0], types::allow_data_loss())
+                .await
+                .unwrap()
+        );
+
+        let chunks = db::chunk_find_by_topic_id(&mut cx, topic.topic_id)
+            .await
+            .unwrap();
+        assert!(chunks.is_empty());
+        let info = db::topic_find_by_id(&mut cx, topic.topic_id)
+            .await
+            .unwrap()
+            .info()
+            .unwrap();
+        assert_eq!(info.chunks_number, 0);
+        assert_eq!(info.total_bytes, 0);
+        assert!(check(&context).await.unwrap().issues.is_empty());
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn repair_skips_resolved_issues(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        import::mcap(
+            &context,
+            "run".parse().unwrap(),
+            None,
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        let mut cx = context.db.connection();
+        let topic = db::topic_find_by_locator(&mut cx, &"run/robot/status".parse().unwrap())
+            .await
+            .unwrap();
+        let topic_path = topic.path_in_store().unwrap();
+        let chunk = db::chunk_find_by_topic_id(&mut cx, topic.topic_id)
+            .await
+            .unwrap()
+            .remove(0);
+
+        let store = &context.store;
+        let data = store.read_bytes(chunk.data_file()).await.unwrap();
+        store.delete(chunk.data_file()).await.unwrap();
+        store
+            .write_bytes("tp_new/metadata.json", vec![0u8])
+            .await
+            .unwrap();
+
+        let report = check(&context).await.unwrap();
+        assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+
+        // The folder is now used by the topic and the data file is back
+        let new_path = types::TopicPathInStore::from("tp_new".to_owned());
+        assert!(
+            db::topic_update_path_in_store(&mut cx, topic.topic_id, &topic_path, &new_path)
+                .await
+                .unwrap()
+        );
+        store.write_bytes(chunk.data_file(), data).await.unwrap();
+
+        for issue in &report.issues {
+            assert!(
+                !repair(&context, issue, types::allow_data_loss())
+                    .await
+                    .unwrap()
+            );
+        }
+
+        assert!(store.exists("tp_new/metadata.json").await.unwrap());
+        let chunks = db::chunk_find_by_topic_id(&mut cx, topic.topic_id)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+    }
+}
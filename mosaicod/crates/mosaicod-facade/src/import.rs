@@ -0,0 +1,1237 @@
+//! This module provides the import of recordings produced by other tools (e.g. ROS 2 bags)
+//! and of bundles produced by [`crate::export::bundle`] as new sequences.
+
+use super::{Context, chunk::stats_write_to_db, sequence, session, topic};
+use arrow::array::RecordBatch;
+use log::{trace, warn};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+use mosaicod_mcap as mcap;
+use mosaicod_rw::{self as rw, ToProperties};
+use std::collections::{BTreeMap, HashMap, HashSet, btree_map::Entry};
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// Summary of an import operation
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Topics created in the imported sequence
+    pub topics: Vec<types::TopicLocator>,
+
+    /// Channels of the recording that were not imported, along with the reason.
+    /// For bundles it contains the sequence, if its import was skipped.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Events produced while decoding a recording
+enum DecodeEvent {
+    Batch {
+        channel: String,
+        ontology_tag: String,
+        format: types::Format,
+        batch: RecordBatch,
+    },
+    Skipped {
+        channel: String,
+        reason: String,
+    },
+}
+
+/// Imports the MCAP recording read from `data` as a new sequence.
+///
+/// The recording is decoded while it is read, so it is never loaded entirely in memory.
+/// Each channel of the recording is stored in a topic named after the channel,
+/// using the message log time as timestamp. Channels using an encoding that can't be
+/// converted are skipped and reported in the returned [`ImportReport`].
+///
+/// If the import fails the sequence is deleted.
+#[tracing::instrument(skip_all, fields(sequence = %locator))]
+pub async fn mcap(
+    context: &Context,
+    locator: types::SequenceLocator,
+    user_metadata: Option<marshal::JsonMetadataBlob>,
+    data: impl std::io::Read + Send + 'static,
+) -> Result<ImportReport> {
+    let sequence_handle = sequence::try_create(context, locator.clone(), user_metadata).await?;
+
+    let res = import_mcap(context, locator, data).await;
+
+    if res.is_err() {
+        warn!(
+            "import of sequence `{}` failed, deleting it",
+            sequence_handle.locator()
+        );
+        if let Err(e) = sequence::delete(context, sequence_handle, types::allow_data_loss()).await {
+            warn!("unable to delete sequence after a failed import: {}", e);
+        }
+    }
+
+    res
+}
+
+async fn import_mcap(
+    context: &Context,
+    locator: types::SequenceLocator,
+    data: impl std::io::Read + Send + 'static,
+) -> Result<ImportReport> {
+    let session_handle = session::try_create(context, locator.clone()).await?;
+
+    // Decoding is CPU-bound, it runs in a blocking thread sending the batches back.
+    // A small channel bounds the amount of decoded data kept in memory.
+    let (tx, mut rx) = mpsc::channel(2);
+    let decoding = tokio::task::spawn_blocking(move || decode_mcap(data, tx));
+
+    let mut report = ImportReport::default();
+    let mut writers: HashMap<String, topic::HandleWriter> = HashMap::new();
+
+    while let Some(event) = rx.recv().await {
+        let (channel, ontology_tag, format, batch) = match event {
+            DecodeEvent::Batch {
+                channel,
+                ontology_tag,
+                format,
+                batch,
+            } => (channel, ontology_tag, format, batch),
+            DecodeEvent::Skipped { channel, reason } => {
+                warn!("skipping channel `{}`: {}", channel, reason);
+                report.skipped.push((channel, reason));
+                continue;
+            }
+        };
+
+        if !writers.contains_key(&channel) {
+            let topic_locator = topic_locator(&locator, &channel)?;
+
+            let ontology_metadata = types::TopicOntologyMetadata::new(
+                types::TopicOntologyProperties {
+                    serialization_format: format,
+                    ontology_tag: ontology_tag.clone(),
+                    writer_options: types::WriterOptions::default(),
+                },
+                None,
+            );
+
+            let handle = topic::try_create(
+                context,
+                topic_locator.clone(),
+                &session_handle,
+                ontology_metadata,
+            )
+            .await?;
+
+            trace!("importing channel `{}` as `{}`", channel, topic_locator);
+
+            let writer =
+                topic::writer(context.clone(), handle, format, batch.schema(), None).await?;
+
+            report.topics.push(topic_locator);
+            writers.insert(channel.clone(), writer);
+        }
+
+        let writer = writers.get_mut(&channel).expect("writer created above");
+
+        writer.write(batch).await?;
+    }
+
+    decoding
+        .await
+        .map_err(|e| core::Error::internal(Some(e.to_string())))??;
+
+    for (_, writer) in writers {
+        writer.finalize().await?;
+    }
+
+    session::finalize(context, &session_handle).await?;
+
+    Ok(report)
+}
+
+/// Decodes the recording, sending a batch each time the messages buffered for a channel
+/// reach the target message size.
+fn decode_mcap(
+    data: impl std::io::Read,
+    tx: mpsc::Sender<DecodeEvent>,
+) -> std::result::Result<(), mcap::Error> {
+    let target_size = params::params().target_message_size.value;
+
+    // Channels are identified by topic name, skipped channels have no decoder
+    let mut decoders: BTreeMap<String, Option<mcap::ChannelDecoder>> = BTreeMap::new();
+
+    let send_batch = |channel: &str, decoder: &mut mcap::ChannelDecoder| {
+        let Some(batch) = decoder.flush()? else {
+            return Ok(true);
+        };
+        let event = DecodeEvent::Batch {
+            channel: channel.to_owned(),
+            ontology_tag: decoder.ontology_tag(),
+            format: decoder.format(),
+            batch,
+        };
+        // The receiver is dropped only if the import failed, stop decoding
+        Ok::<bool, mcap::Error>(tx.blocking_send(event).is_ok())
+    };
+
+    for message in mcap::messages(data) {
+        let message = message?;
+        let channel = &message.channel;
+
+        let decoder = match decoders.entry(channel.topic.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match mcap::ChannelDecoder::try_new(channel) {
+                Ok(decoder) => entry.insert(Some(decoder)),
+                Err(e @ mcap::Error::UnsupportedEncoding { .. }) => {
+                    let event = DecodeEvent::Skipped {
+                        channel: channel.topic.clone(),
+                        reason: e.to_string(),
+                    };
+                    if tx.blocking_send(event).is_err() {
+                        return Ok(());
+                    }
+                    entry.insert(None)
+                }
+                Err(e) => return Err(e),
+            },
+        };
+
+        let Some(decoder) = decoder else {
+            continue;
+        };
+
+        decoder.push(message.log_time, &message.data)?;
+
+        if decoder.buffered_bytes() >= target_size && !send_batch(&channel.topic, decoder)? {
+            return Ok(());
+        }
+    }
+
+    for (channel, decoder) in decoders.iter_mut() {
+        if let Some(decoder) = decoder
+            && !send_batch(channel, decoder)?
+        {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the locator of the topic storing `channel`, replacing the characters not
+/// allowed in locators.
+fn topic_locator(sequence: &types::SequenceLocator, channel: &str) -> Result<types::TopicLocator> {
+    let name: String = channel
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| match c {
+            '!' | '"' | '\'' | '*' | '$' | '%' | '&' | '.' | ' ' | ':' => '_',
+            c if !c.is_ascii() => '_',
+            c => c,
+        })
+        .collect();
+
+    if name.is_empty() {
+        Err(core::Error::bad_request(format!(
+            "channel `{channel}` can't be used as topic name"
+        )))?
+    }
+
+    Ok(format!("{sequence}/{name}").parse()?)
+}
+
+/// Imports the bundle read from `data`, produced by [`crate::export::bundle`], as a new
+/// sequence.
+///
+/// The sequence is imported as `locator` if provided, otherwise with the locator recorded
+/// in the bundle. The database records (sessions, topics, chunks, column statistics and
+/// notifications) are re-created with new identifiers, preserving their timestamps. If
+/// `user_metadata` is provided it replaces the metadata recorded in the bundle.
+///
+/// The bundle is read as a stream, each data file is written to the store as soon as it
+/// is read. The timestamp range, ordering, size and number of rows of the chunks are
+/// computed from the data files rather than taken from the manifest.
+///
+/// If the sequence already exists `on_conflict` decides whether the import fails, is
+/// skipped (reported in [`ImportReport::skipped`]) or replaces the existing sequence.
+///
+/// All the records are created in a single transaction, if the import fails the
+/// files already written in the store are deleted.
+#[tracing::instrument(skip_all)]
+pub async fn bundle(
+    context: &Context,
+    locator: Option<types::SequenceLocator>,
+    user_metadata: Option<marshal::JsonMetadataBlob>,
+    data: impl std::io::Read + Send + 'static,
+    on_conflict: types::OnConflict,
+) -> Result<ImportReport> {
+    // The archive is read in a blocking thread sending back the files. A small channel
+    // bounds the amount of data kept in memory.
+    let (tx, mut files) = mpsc::channel(2);
+    let reading = tokio::task::spawn_blocking(move || read_bundle(data, tx));
+
+    let res = import_bundle_files(context, locator, user_metadata, &mut files, on_conflict).await;
+
+    // Stops the reading if the import failed
+    drop(files);
+
+    let read = reading
+        .await
+        .map_err(|e| core::Error::internal(Some(e.to_string())))?;
+
+    match (res, read) {
+        (Err(e), _) => Err(e),
+        // The reading may have failed after all the referenced files were imported
+        (Ok(report), Ok(())) => Ok(report),
+        (Ok(_), Err(e)) => Err(e),
+    }
+}
+
+async fn import_bundle_files(
+    context: &Context,
+    locator: Option<types::SequenceLocator>,
+    user_metadata: Option<marshal::JsonMetadataBlob>,
+    files: &mut mpsc::Receiver<BundleFile>,
+    on_conflict: types::OnConflict,
+) -> Result<ImportReport> {
+    // The manifest is written as first file, so that it can be read before the data
+    let manifest = match files.recv().await {
+        Some(file) if file.path == marshal::BUNDLE_MANIFEST_FILE => file.data,
+        _ => Err(invalid_bundle("missing manifest"))?,
+    };
+    let mut manifest = marshal::JsonBundleManifest::try_from(manifest.as_slice())?;
+
+    if manifest.version != marshal::BUNDLE_VERSION {
+        Err(invalid_bundle(&format!(
+            "unsupported version {}",
+            manifest.version
+        )))?;
+    }
+
+    let locator = match locator {
+        Some(locator) => locator,
+        None => manifest.sequence.locator.parse()?,
+    };
+
+    if user_metadata.is_some() {
+        manifest.sequence.user_metadata = user_metadata;
+    }
+
+    validate_bundle(&manifest)?;
+
+    let mut tx = context.db.transaction().await?;
+
+    // Store folders of the replaced sequence, deleted once the import succeeds
+    let mut replaced = Vec::new();
+
+    if let Ok(existing) = db::sequence_find_by_locator(&mut tx, &locator).await {
+        match on_conflict {
+            types::OnConflict::Fail => Err(core::Error::already_exists())?,
+            types::OnConflict::Skip => {
+                warn!("sequence `{}` already exists, skipping import", locator);
+                return Ok(ImportReport {
+                    topics: Vec::new(),
+                    skipped: vec![(locator.to_string(), "sequence already exists".to_owned())],
+                });
+            }
+            types::OnConflict::Replace => {
+                warn!("(data loss) replacing sequence `{}`", locator);
+                replaced = delete_sequence_records(&mut tx, existing).await?;
+            }
+        }
+    }
+
+    // Store folders written by the import, deleted if the import fails
+    let mut written = Vec::new();
+
+    let res = import_bundle(context, &mut tx, &locator, manifest, files, &mut written).await;
+
+    let res = match res {
+        Ok(report) => tx.commit().await.map(|_| report).map_err(Into::into),
+        Err(e) => Err(e),
+    };
+
+    let folders = if res.is_ok() { replaced } else { written };
+    for folder in folders {
+        if let Err(e) = context.store.delete_recursive(&folder).await {
+            warn!("unable to delete `{}`: {}", folder.display(), e);
+        }
+    }
+
+    res
+}
+
+/// Topic of the bundle whose records have been created
+struct ImportedTopic {
+    record: db::TopicRecord,
+    locator: types::TopicLocator,
+    path_in_store: types::TopicPathInStore,
+    properties: types::TopicOntologyProperties,
+    session_uuid: types::Uuid,
+    created_at: i64,
+    completed_at: Option<i64>,
+    user_metadata: Option<marshal::JsonMetadataBlob>,
+    /// Whether the exported topic had its data info, see [`types::TopicDataInfo`]
+    has_info: bool,
+    chunks: Vec<marshal::JsonBundleChunk>,
+    /// Data files of the chunks, in the same order of `chunks`, set once written
+    files: Vec<Option<ChunkFile>>,
+}
+
+/// Data file of a chunk written to the store, along with the statistics computed from its
+/// content
+struct ChunkFile {
+    path: PathBuf,
+    stats: types::OntologyModelStats,
+    metadata: rw::ChunkMetadata,
+}
+
+async fn import_bundle(
+    context: &Context,
+    tx: &mut db::Tx<'_>,
+    locator: &types::SequenceLocator,
+    manifest: marshal::JsonBundleManifest,
+    files: &mut mpsc::Receiver<BundleFile>,
+    written: &mut Vec<PathBuf>,
+) -> Result<ImportReport> {
+    let sequence_path = types::SequencePathInStore::new();
+
+    let mut record = db::SequenceRecord::new(locator.clone(), sequence_path.clone())
+        .with_creation_timestamp(manifest.sequence.created_at.into());
+    if let Some(user_metadata) = &manifest.sequence.user_metadata {
+        record = record.with_user_metadata(user_metadata.clone());
+    }
+    let sequence_record = db::sequence_create(tx, &record).await?;
+
+    written.push(sequence_path.root().to_path_buf());
+
+    if let Some(user_metadata) = manifest.sequence.user_metadata {
+        sequence::metadata_write_to_store(
+            context,
+            sequence_path.path_metadata().as_path(),
+            user_metadata,
+        )
+        .await?;
+    }
+
+    for notification in manifest.sequence.notifications {
+        let record = db::SequenceNotificationRecord::new(
+            sequence_record.sequence_id,
+            notification_type(&notification.notification_type)?,
+            notification.msg,
+        )
+        .with_creation_timestamp(notification.created_at.into());
+        db::sequence_notification_create(tx, &record).await?;
+    }
+
+    let mut sessions = Vec::with_capacity(manifest.sessions.len());
+    for session in manifest.sessions {
+        let record = db::SessionRecord::new(sequence_record.sequence_id).with_timestamps(
+            session.created_at.into(),
+            session.completed_at.map(Into::into),
+        );
+        sessions.push(db::session_create(tx, &record).await?);
+    }
+
+    let mut topics = Vec::with_capacity(manifest.topics.len());
+
+    // Position of the chunk referencing each file of the bundle
+    let mut chunk_files: HashMap<String, (usize, usize)> = HashMap::new();
+
+    for topic in manifest.topics {
+        let topic_locator: types::TopicLocator = format!("{locator}/{}", topic.name).parse()?;
+        let session = &sessions[topic.session];
+
+        trace!("importing topic `{}`", topic_locator);
+
+        let path_in_store = types::TopicPathInStore::new();
+        let properties: types::TopicOntologyProperties = topic.properties.into();
+
+        let mut record = db::TopicRecord::new(
+            topic_locator.clone(),
+            sequence_record.sequence_id,
+            session.session_id,
+            &properties.ontology_tag,
+            &properties.serialization_format.to_string(),
+            Some(path_in_store.clone()),
+        )
+        .with_creation_timestamp(topic.created_at.into())
+        .with_writer_options(properties.writer_options.clone());
+        if let Some(user_metadata) = &topic.user_metadata {
+            record = record.with_user_metadata(user_metadata.clone());
+        }
+        let topic_record = db::topic_create(tx, &record).await?;
+
+        written.push(path_in_store.root().to_path_buf());
+
+        if let Some(completed_at) = topic.completed_at {
+            db::topic_update_completion_tstamp(tx, topic_record.topic_id, completed_at).await?;
+        }
+
+        for notification in topic.notifications {
+            let record = db::TopicNotificationRecord::new(
+                topic_record.topic_id,
+                notification_type(&notification.notification_type)?,
+                notification.msg,
+            )
+            .with_creation_timestamp(notification.created_at.into());
+            db::topic_notification_create(tx, &record).await?;
+        }
+
+        for (index, chunk) in topic.chunks.iter().enumerate() {
+            chunk_files.insert(chunk.file.clone(), (topics.len(), index));
+        }
+
+        topics.push(ImportedTopic {
+            record: topic_record,
+            locator: topic_locator,
+            path_in_store,
+            properties,
+            session_uuid: session.uuid(),
+            created_at: topic.created_at,
+            completed_at: topic.completed_at,
+            user_metadata: topic.user_metadata,
+            has_info: topic.info.is_some(),
+            files: topic.chunks.iter().map(|_| None).collect(),
+            chunks: topic.chunks,
+        });
+    }
+
+    // Data files are written as they are read, the other files of the bundle are
+    // re-created from the manifest
+    while let Some(file) = files.recv().await {
+        let Some(&(topic_index, chunk_index)) = chunk_files.get(&file.path) else {
+            continue;
+        };
+        let topic = &mut topics[topic_index];
+
+        if topic.files[chunk_index].is_some() {
+            Err(invalid_bundle(&format!("duplicated file `{}`", file.path)))?;
+        }
+
+        let format = topic.properties.serialization_format;
+        let data = bytes::Bytes::from(file.data);
+
+        // Statistics drive the chunk pruning of queries, so the ones of the manifest are
+        // not trusted
+        let (stats, metadata) = rw::chunk_reader::statistics(format, data.clone())
+            .map_err(|e| invalid_bundle(&format!("unreadable file `{}`: {e}", file.path)))?;
+
+        let path = topic.path_in_store.path_data(
+            &topic.record.uuid(),
+            chunk_index,
+            format.to_properties().as_ref(),
+        );
+
+        context.store.write_bytes(&path, data.clone()).await?;
+
+        topic.files[chunk_index] = Some(ChunkFile {
+            path,
+            stats,
+            metadata,
+        });
+    }
+
+    let mut report = ImportReport::default();
+
+    // Chunk records are created in the order of the manifest, which is the order of the
+    // data of the exported topic
+    for topic in topics {
+        let mut info = types::TopicDataInfo {
+            chunks_number: 0,
+            total_bytes: 0,
+            timestamp_range: types::TimestampRange::unbounded(),
+        };
+        let mut timestamps = types::TimestampStats::new();
+
+        for (chunk, file) in topic.chunks.into_iter().zip(topic.files) {
+            let file =
+                file.ok_or_else(|| invalid_bundle(&format!("missing file `{}`", chunk.file)))?;
+
+            let chunk_record = db::ChunkRecord::new(
+                topic.record.topic_id,
+                &file.path,
+                file.metadata.size_bytes as i64,
+                file.metadata.row_count as i64,
+            )
+            .with_timestamps(&file.metadata.timestamps);
+
+            let chunk_record = db::chunk_create(tx, &chunk_record).await?;
+
+            stats_write_to_db(
+                tx,
+                chunk_record.chunk_id,
+                &topic.properties.ontology_tag,
+                file.stats,
+            )
+            .await?;
+
+            info.chunks_number += 1;
+            info.total_bytes += file.metadata.size_bytes as u64;
+            timestamps.eval(file.metadata.timestamps.min);
+            timestamps.eval(file.metadata.timestamps.max);
+        }
+
+        // The data info is computed from the imported files, as done when finalizing a topic
+        if topic.has_info {
+            if let (Some(min), Some(max)) = (timestamps.min, timestamps.max) {
+                info.timestamp_range = types::TimestampRange::between(min.into(), max.into());
+            }
+            db::topic_update_system_info(tx, &topic.locator, &info).await?;
+        }
+
+        // The metadata file is re-created, since the locator and the session changed
+        let metadata = types::TopicMetadata::new(
+            types::TopicMetadataProperties {
+                created_at: topic.created_at.into(),
+                completed_at: topic.completed_at.map(Into::into),
+                session_uuid: topic.session_uuid,
+                resource_locator: topic.locator.clone(),
+            },
+            types::TopicOntologyMetadata::new(topic.properties, topic.user_metadata),
+        );
+        topic::metadata_write_to_store(
+            context,
+            topic.path_in_store.path_metadata().as_path(),
+            metadata,
+        )
+        .await?;
+
+        report.topics.push(topic.locator);
+    }
+
+    Ok(report)
+}
+
+/// Deletes the database records of a sequence, returning the store folders containing
+/// its files.
+async fn delete_sequence_records(
+    tx: &mut db::Tx<'_>,
+    sequence: db::SequenceRecord,
+) -> Result<Vec<PathBuf>> {
+    let locator = sequence.locator();
+    let mut folders = vec![sequence.path_in_store().root().to_path_buf()];
+
+    for topic in db::sequence_find_all_topics(tx, &locator).await? {
+        if let Some(path_in_store) = topic.path_in_store() {
+            folders.push(path_in_store.root().to_path_buf());
+        }
+        db::topic_delete(tx, &topic.locator(), types::allow_data_loss()).await?;
+    }
+
+    for session in db::sequence_find_all_sessions(tx, &locator).await? {
+        db::session_delete(tx, &session.uuid(), types::allow_data_loss()).await?;
+    }
+
+    db::sequence_delete_by_id(tx, sequence.sequence_id, types::allow_data_loss()).await?;
+
+    Ok(folders)
+}
+
+/// File of a bundle
+struct BundleFile {
+    path: String,
+    data: Vec<u8>,
+}
+
+/// Reads the (uncompressed) tar archive `data`, sending each file as soon as it is read.
+fn read_bundle(data: impl std::io::Read, tx: mpsc::Sender<BundleFile>) -> Result<()> {
+    let mut archive = tar::Archive::new(data);
+
+    let entries = archive
+        .entries()
+        .map_err(|e| invalid_bundle(&e.to_string()))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| invalid_bundle(&e.to_string()))?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .map_err(|e| invalid_bundle(&e.to_string()))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut data)
+            .map_err(|e| invalid_bundle(&e.to_string()))?;
+        if data.len() as u64 != entry.size() {
+            Err(invalid_bundle(&format!("truncated file `{path}`")))?;
+        }
+
+        // The receiver is dropped only if the import failed, stop reading
+        if tx.blocking_send(BundleFile { path, data }).is_err() {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the manifest references existing sessions and that each file is
+/// referenced by a single chunk.
+fn validate_bundle(manifest: &marshal::JsonBundleManifest) -> Result<()> {
+    let mut files = HashSet::new();
+
+    for topic in &manifest.topics {
+        if topic.session >= manifest.sessions.len() {
+            Err(invalid_bundle(&format!(
+                "unknown session for topic `{}`",
+                topic.name
+            )))?;
+        }
+
+        if let Some(chunk) = topic.chunks.iter().find(|c| !files.insert(c.file.as_str())) {
+            Err(invalid_bundle(&format!(
+                "file `{}` referenced more than once",
+                chunk.file
+            )))?;
+        }
+    }
+    Ok(())
+}
+
+fn notification_type(value: &str) -> Result<types::NotificationType> {
+    Ok(value
+        .parse()
+        .map_err(|e: std::io::Error| invalid_bundle(&e.to_string()))?)
+}
+
+fn invalid_bundle(reason: &str) -> core::Error {
+    core::Error::bad_request(format!("invalid bundle: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mosaicod_db as db;
+    use mosaicod_query as query;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let database = db::testing::Database::new(pool);
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store.clone(), database.clone(), ts_gw)
+    }
+
+    /// Builds a recording with a JSON channel and a channel using an unsupported encoding
+    fn recording() -> Vec<u8> {
+        let mut writer = ::mcap::Writer::new(std::io::Cursor::new(Vec::new())).unwrap();
+
+        let status = writer
+            .add_channel(0, "/robot/status", "json", &Default::default())
+            .unwrap();
+        let raw = writer
+            .add_channel(0, "/robot/raw", "flatbuffer", &Default::default())
+            .unwrap();
+
+        for i in 0..10u64 {
+            let header = |channel_id| ::mcap::records::MessageHeader {
+                channel_id,
+                sequence: i as u32,
+                log_time: 1_000 + i,
+                publish_time: 1_000 + i,
+            };
+            writer
+                .write_to_known_channel(
+                    &header(status),
+                    format!(r#"{{"battery": {}.5}}"#, 100 - i).as_bytes(),
+                )
+                .unwrap();
+            writer
+                .write_to_known_channel(&header(raw), &[1, 2, 3])
+                .unwrap();
+        }
+
+        writer.finish().unwrap();
+        writer.into_inner().into_inner()
+    }
+
+    #[test]
+    fn channel_topic_locator() {
+        let sequence: types::SequenceLocator = "run_01".parse().unwrap();
+
+        let locator = topic_locator(&sequence, "/camera/image_raw").unwrap();
+        assert_eq!(locator.to_string(), "run_01/camera/image_raw");
+
+        let locator = topic_locator(&sequence, "/tf.static: odom").unwrap();
+        assert_eq!(locator.to_string(), "run_01/tf_static__odom");
+
+        assert!(topic_locator(&sequence, "/").is_err());
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_mcap(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let locator: types::SequenceLocator = "imported".parse().unwrap();
+
+        let report = mcap(
+            &context,
+            locator.clone(),
+            None,
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .expect("unable to import recording");
+
+        assert_eq!(report.topics.len(), 1);
+        assert_eq!(report.topics[0].to_string(), "imported/robot/status");
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, "/robot/raw");
+
+        let handle = topic::Handle::try_from_locator(&context, report.topics[0].clone())
+            .await
+            .unwrap();
+        assert!(topic::archived(&context, &handle).await.unwrap());
+
+        let metadata = topic::metadata(&context, &handle).await.unwrap();
+        assert_eq!(metadata.ontology_metadata.properties.ontology_tag, "json");
+
+        let info = topic::data_info(&context, &handle).await.unwrap();
+        assert_eq!(info.chunks_number, 1);
+        assert!(info.total_bytes > 0);
+
+        // The sequence already exists
+        assert!(
+            mcap(&context, locator, None, std::io::Cursor::new(recording()))
+                .await
+                .is_err()
+        );
+    }
+
+    async fn export_bundle(context: &Context, locator: &str) -> Vec<u8> {
+        use futures::TryStreamExt;
+
+        let data: Vec<Vec<u8>> = crate::export::bundle(context, locator.parse().unwrap())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        data.concat()
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_bundle(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let metadata = marshal::JsonMetadataBlob::from(serde_json::json!({"driver": "jon"}));
+        mcap(
+            &context,
+            "run".parse().unwrap(),
+            Some(metadata),
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        let handle = topic::Handle::try_from_locator(&context, "run/robot/status".parse().unwrap())
+            .await
+            .unwrap();
+        topic::notify(
+            &context,
+            &handle,
+            types::NotificationType::Error,
+            "battery low".to_owned(),
+        )
+        .await
+        .unwrap();
+
+        let data = export_bundle(&context, "run").await;
+
+        // Entries are dated with the sequence creation time, in seconds
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for entry in tar::Archive::new(data.as_slice()).entries().unwrap() {
+            let mtime = entry.unwrap().header().mtime().unwrap();
+            assert!(mtime > 0 && mtime <= now);
+        }
+
+        let report = bundle(
+            &context,
+            Some("copy".parse().unwrap()),
+            None,
+            std::io::Cursor::new(data.clone()),
+            types::OnConflict::Fail,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.topics.len(), 1);
+        assert_eq!(report.topics[0].to_string(), "copy/robot/status");
+
+        let copy = topic::Handle::try_from_locator(&context, report.topics[0].clone())
+            .await
+            .unwrap();
+        assert!(topic::archived(&context, &copy).await.unwrap());
+
+        // Records are re-created preserving the timestamps
+        let original_metadata = topic::metadata(&context, &handle).await.unwrap();
+        let copy_metadata = topic::metadata(&context, &copy).await.unwrap();
+        assert_eq!(
+            copy_metadata.properties.created_at,
+            original_metadata.properties.created_at
+        );
+        assert_ne!(
+            copy_metadata.properties.session_uuid,
+            original_metadata.properties.session_uuid
+        );
+
+        let sequence_handle = sequence::Handle::try_from_locator(&context, "copy".parse().unwrap())
+            .await
+            .unwrap();
+        let sequence_metadata = sequence::metadata(&context, &sequence_handle)
+            .await
+            .unwrap();
+        let user_metadata: serde_json::Value = sequence_metadata.user_metadata.unwrap().into();
+        assert_eq!(user_metadata["driver"], "jon");
+
+        let notifications = topic::notification_list(&context, &copy).await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].msg.as_deref(), Some("battery low"));
+
+        // Data and chunk statistics are available
+        let stats = topic::chunks_stats(&context, &copy).await.unwrap();
+        assert_eq!(stats.total_row_count, 10);
+        assert_eq!(
+            topic::data_info(&context, &copy)
+                .await
+                .unwrap()
+                .chunks_number,
+            1
+        );
+
+        let mut cx = context.db.connection();
+        let chunks = db::chunk_find_by_topic_id(&mut cx, copy.id())
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(context.store.exists(chunks[0].data_file()).await.unwrap());
+
+        let original_chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await
+            .unwrap();
+        async fn numeric_stats(
+            cx: &mut impl db::AsExec,
+            chunk_id: i32,
+        ) -> Vec<(i32, f64, f64, bool, bool)> {
+            db::column_chunk_numeric_find_by_chunk_id(cx, chunk_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|s| (s.column_id, s.min_value, s.max_value, s.has_null, s.has_nan))
+                .collect()
+        }
+        let copy_stats = numeric_stats(&mut cx, chunks[0].chunk_id).await;
+        assert!(!copy_stats.is_empty());
+        assert_eq!(
+            copy_stats,
+            numeric_stats(&mut cx, original_chunks[0].chunk_id).await
+        );
+
+        // Conflicts with existing sequences
+        assert!(
+            bundle(
+                &context,
+                None,
+                None,
+                std::io::Cursor::new(data.clone()),
+                types::OnConflict::Fail
+            )
+            .await
+            .is_err()
+        );
+
+        let report = bundle(
+            &context,
+            None,
+            None,
+            std::io::Cursor::new(data.clone()),
+            types::OnConflict::Skip,
+        )
+        .await
+        .unwrap();
+        assert!(report.topics.is_empty());
+        assert_eq!(report.skipped[0].0, "run");
+
+        let report = bundle(
+            &context,
+            None,
+            None,
+            std::io::Cursor::new(data),
+            types::OnConflict::Replace,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.topics[0].to_string(), "run/robot/status");
+
+        let replaced = topic::Handle::try_from_locator(&context, report.topics[0].clone())
+            .await
+            .unwrap();
+        assert_ne!(replaced.uuid(), handle.uuid());
+        assert!(
+            !context
+                .store
+                .exists(handle.path_in_store().unwrap().path_metadata())
+                .await
+                .unwrap()
+        );
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_invalid_bundle(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        mcap(
+            &context,
+            "run".parse().unwrap(),
+            None,
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        let mut data = export_bundle(&context, "run").await;
+        data.truncate(data.len() / 2);
+
+        let locator: types::SequenceLocator = "broken".parse().unwrap();
+
+        assert!(
+            bundle(
+                &context,
+                Some(locator.clone()),
+                None,
+                std::io::Cursor::new(data),
+                types::OnConflict::Fail
+            )
+            .await
+            .is_err()
+        );
+
+        assert!(
+            sequence::Handle::try_from_locator(&context, locator)
+                .await
+                .is_err()
+        );
+
+        // Not a bundle
+        assert!(
+            bundle(
+                &context,
+                None,
+                None,
+                std::io::Cursor::new(recording()),
+                types::OnConflict::Fail
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    /// Replaces the manifest of the bundle `data` with the one returned by `edit`
+    fn edit_manifest(data: &[u8], edit: impl Fn(&mut marshal::JsonBundleManifest)) -> Vec<u8> {
+        let mut archive = tar::Archive::new(data);
+        let mut builder = tar::Builder::new(Vec::new());
+
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_path_buf();
+            let mut content = Vec::new();
+            std::io::Read::read_to_end(&mut entry, &mut content).unwrap();
+
+            if path.to_str() == Some(marshal::BUNDLE_MANIFEST_FILE) {
+                let mut manifest =
+                    marshal::JsonBundleManifest::try_from(content.as_slice()).unwrap();
+                edit(&mut manifest);
+                content = (&manifest).try_into().unwrap();
+            }
+
+            let mut header = entry.header().clone();
+            header.set_size(content.len() as u64);
+            builder
+                .append_data(&mut header, path, content.as_slice())
+                .unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_bundle_untrusted_manifest(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        mcap(
+            &context,
+            "run".parse().unwrap(),
+            None,
+            std::io::Cursor::new(recording()),
+        )
+        .await
+        .unwrap();
+
+        let data = export_bundle(&context, "run").await;
+
+        // Chunk values are computed from the data files
+        let tampered = edit_manifest(&data, |manifest| {
+            let chunk = &mut manifest.topics[0].chunks[0];
+            chunk.file = "../../escape.parquet".to_owned();
+            chunk.min_timestamp_ns = Some(-1);
+            chunk.max_timestamp_ns = Some(i64::MAX);
+            chunk.row_count = 1;
+            chunk.sorted = false;
+        });
+        assert!(
+            bundle(
+                &context,
+                Some("tampered".parse().unwrap()),
+                None,
+                std::io::Cursor::new(tampered),
+                types::OnConflict::Fail,
+            )
+            .await
+            .is_err(),
+            "chunks must reference an existing file"
+        );
+
+        let tampered = edit_manifest(&data, |manifest| {
+            let topic = &mut manifest.topics[0];
+            topic.info = Some(marshal::JsonBundleTopicInfo {
+                chunks_number: 7,
+                total_bytes: 1,
+                start_index_timestamp: -1,
+                end_index_timestamp: 0,
+            });
+
+            let chunk = &mut topic.chunks[0];
+            chunk.min_timestamp_ns = Some(-1);
+            chunk.max_timestamp_ns = Some(i64::MAX);
+            chunk.row_count = 1;
+            chunk.sorted = false;
+            for stats in &mut chunk.stats {
+                match stats {
+                    marshal::JsonBundleColumnStats::Numeric { min, max, .. } => {
+                        *min = Some(-1.0);
+                        *max = Some(-1.0);
+                    }
+                    marshal::JsonBundleColumnStats::Textual { min, max, .. } => {
+                        *min = String::new();
+                        *max = String::new();
+                    }
+                }
+            }
+        });
+        let report = bundle(
+            &context,
+            Some("tampered".parse().unwrap()),
+            None,
+            std::io::Cursor::new(tampered),
+            types::OnConflict::Fail,
+        )
+        .await
+        .unwrap();
+
+        let original =
+            topic::Handle::try_from_locator(&context, "run/robot/status".parse().unwrap())
+                .await
+                .unwrap();
+        let copy = topic::Handle::try_from_locator(&context, report.topics[0].clone())
+            .await
+            .unwrap();
+
+        // Topic info and column statistics are computed from the data files
+        let original_info = topic::data_info(&context, &original).await.unwrap();
+        let copy_info = topic::data_info(&context, &copy).await.unwrap();
+        assert_eq!(copy_info.chunks_number, original_info.chunks_number);
+        assert_eq!(copy_info.total_bytes, original_info.total_bytes);
+        assert!(copy_info.timestamp_range.start == original_info.timestamp_range.start);
+        assert!(copy_info.timestamp_range.end == original_info.timestamp_range.end);
+
+        let mut cx = context.db.connection();
+        let original = db::chunk_find_by_topic_id(&mut cx, original.id())
+            .await
+            .unwrap();
+        let copy = db::chunk_find_by_topic_id(&mut cx, copy.id())
+            .await
+            .unwrap();
+
+        let original_stats =
+            db::column_chunk_numeric_find_by_chunk_id(&mut cx, original[0].chunk_id)
+                .await
+                .unwrap();
+        let copy_stats = db::column_chunk_numeric_find_by_chunk_id(&mut cx, copy[0].chunk_id)
+            .await
+            .unwrap();
+        assert!(!copy_stats.is_empty());
+        for (copy, original) in copy_stats.iter().zip(&original_stats) {
+            assert_eq!(copy.column_id, original.column_id);
+            assert_eq!(copy.min_value, original.min_value);
+            assert_eq!(copy.max_value, original.max_value);
+        }
+        assert_eq!(copy_stats.len(), original_stats.len());
+
+        assert_eq!(copy[0].min_timestamp_ns, original[0].min_timestamp_ns);
+        assert_eq!(copy[0].max_timestamp_ns, original[0].max_timestamp_ns);
+        assert_eq!(copy[0].row_count, original[0].row_count);
+        assert_eq!(copy[0].sorted, original[0].sorted);
+        assert!(copy[0].sorted);
+
+        // Data files are named after the position of the chunk
+        assert_eq!(
+            copy[0].data_file().file_name().unwrap(),
+            "data-00000.parquet"
+        );
+
+        // A data file can't be shared by multiple chunks
+        let tampered = edit_manifest(&data, |manifest| {
+            let chunk = &manifest.topics[0].chunks[0];
+            let duplicate = marshal::JsonBundleChunk {
+                file: chunk.file.clone(),
+                stats: Vec::new(),
+                ..*chunk
+            };
+            manifest.topics[0].chunks.push(duplicate);
+        });
+        assert!(
+            bundle(
+                &context,
+                Some("duplicated".parse().unwrap()),
+                None,
+                std::io::Cursor::new(tampered),
+                types::OnConflict::Fail,
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_invalid_mcap(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let locator: types::SequenceLocator = "broken".parse().unwrap();
+
+        let mut data = recording();
+        data.truncate(data.len() / 2);
+
+        assert!(
+            mcap(&context, locator.clone(), None, std::io::Cursor::new(data))
+                .await
+                .is_err()
+        );
+
+        // Failed imports leave no sequence behind
+        assert!(
+            sequence::Handle::try_from_locator(&context, locator)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -21,6 +21,12 @@ pub mod session;
 
 pub mod topic;
 
+pub mod import;
+
+pub mod export;
+
+pub mod fsck;
+
 mod error;
 pub use error::*;
 
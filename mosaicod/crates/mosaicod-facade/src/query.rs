@@ -1,13 +1,15 @@
 use super::Error;
-use futures::stream::{FuturesUnordered, StreamExt};
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, FuturesUnordered, StreamExt};
 use log::{debug, trace};
-use mosaicod_core::{error::PublicResult as Result, params, types};
+use mosaicod_core::{error::PublicResult as Result, metrics as metric_names, params, types};
 use mosaicod_db as db;
 use mosaicod_query as query;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::Semaphore;
+use tracing::Instrument;
 
 /// Facade used to perform queries in the system, it will handle the dependencies
 /// between different components (mainly `query` and `db` modules).
@@ -15,211 +17,548 @@ use tokio::sync::Semaphore;
 /// All complex query logics needs to be implemented inside this facade.
 pub struct Query {}
 
+/// A page of query results
+#[derive(Debug, Default)]
+pub struct QueryResult {
+    pub groups: types::SequenceTopicGroupSet,
+    /// Cursor to request the next page, [`None`] if this is the last page
+    pub cursor: Option<query::Cursor>,
+}
+
+/// A topic matching a query
+#[derive(Debug, Clone)]
+pub struct TopicMatch {
+    pub sequence: types::SequenceLocator,
+    /// Creation time of the sequence
+    pub sequence_created_at: types::Timestamp,
+    pub topic: types::TopicLocator,
+    /// Timestamps of the first and last rows matching the ontology filter.
+    ///
+    /// [`None`] if the query has no ontology filter or the summary was not requested.
+    pub timestamp_range: Option<types::TimestampRange>,
+    /// Number of rows matching the ontology filter.
+    ///
+    /// [`None`] if the query has no ontology filter or the summary was not requested.
+    pub match_count: Option<u64>,
+    /// Time intervals in which the ontology filter matches, sorted by timestamp.
+    ///
+    /// [`None`] if the query has no ontology filter or the intervals were not requested.
+    pub intervals: Option<Vec<types::TimestampRange>>,
+}
+
+/// Stream of the topics matching a query, returned as soon as they are found
+pub type QueryStream = BoxStream<'static, Result<Vec<TopicMatch>>>;
+
 impl Query {
-    /// Perform a query in the system
+    /// Perform a query in the system.
+    ///
+    /// If `scopes` is not empty only the topics contained in some scope are searched, so that
+    /// pages (and their cursors) only hold sequences visible to the caller.
+    #[tracing::instrument(name = "query", skip_all)]
     pub async fn query(
         filter: query::Filter,
+        scopes: &[types::auth::ApiKeyScope],
+        ts_gw: query::TimeseriesEngineRef,
+        db: db::Database,
+    ) -> Result<QueryResult> {
+        let include_timestamp_range = filter
+            .ontology
+            .as_ref()
+            .is_some_and(|f| f.include_timestamp_range);
+
+        let page = filter.page.clone();
+
+        let mut search =
+            Search::try_new(filter, scopes, include_timestamp_range, ts_gw, db).await?;
+
+        // Matches are grouped by sequence, sequences are matched as a whole so the topics of
+        // a sequence are never split across pages
+        let mut groups: HashMap<String, types::SequenceTopicGroup> = HashMap::new();
+
+        while let Some(matches) = search.next_matches().await? {
+            for m in matches {
+                let mut topic = m.topic;
+                // The summary is also computed along with the intervals
+                if include_timestamp_range && let Some(ts_range) = m.timestamp_range {
+                    topic = topic.with_timestamp_range(ts_range);
+                }
+                if let Some(intervals) = m.intervals {
+                    topic = topic.with_intervals(intervals);
+                }
+
+                groups
+                    .entry(m.sequence.to_string())
+                    .or_insert_with(|| {
+                        types::SequenceTopicGroup::new(m.sequence, Vec::new())
+                            .with_created_at(m.sequence_created_at)
+                    })
+                    .topics
+                    .push(topic);
+            }
+        }
+
+        let groups: Vec<types::SequenceTopicGroup> = groups
+            .into_values()
+            .map(|mut grp| {
+                grp.topics.sort_unstable_by(|a, b| a.cmp(b.as_ref()));
+                grp
+            })
+            .collect();
+
+        let (groups, cursor) = page.apply(groups.into());
+
+        Ok(QueryResult { groups, cursor })
+    }
+
+    /// Perform a query in the system, streaming the matching topics as soon as the search
+    /// of each topic completes.
+    ///
+    /// The timestamp range and the number of matching rows (and the matching intervals, if
+    /// requested) are computed for each topic matching the ontology filter. Pagination is not
+    /// supported, the filter page is ignored. As in [`Query::query`], only the topics
+    /// contained in `scopes` (if any) are searched.
+    pub async fn stream(
+        filter: query::Filter,
+        scopes: &[types::auth::ApiKeyScope],
         ts_gw: query::TimeseriesEngineRef,
         db: db::Database,
-    ) -> Result<types::SequenceTopicGroupSet> {
-        let mut result: Option<types::SequenceTopicGroupSet> = None;
+    ) -> Result<QueryStream> {
+        let filter = query::Filter {
+            page: query::Page::default(),
+            ..filter
+        };
+
+        let search = Search::try_new(filter, scopes, true, ts_gw, db).await?;
+
+        Ok(stream::try_unfold(search, next_search_matches).boxed())
+    }
+}
+
+async fn next_search_matches(mut search: Search) -> Result<Option<(Vec<TopicMatch>, Search)>> {
+    Ok(search
+        .next_matches()
+        .await?
+        .map(|matches| (matches, search)))
+}
+
+/// A topic matching an ontology tag group
+struct TopicHit {
+    topic: db::TopicRecord,
+    summary: Option<query::RowsSummary>,
+    intervals: Option<Vec<types::TimestampRange>>,
+}
+
+/// Data computed by the search of each topic
+#[derive(Clone, Copy)]
+enum SearchOutput {
+    /// Only checks if the topic matches, the search stops at the first matching chunk
+    Match,
+    /// Computes the timestamp range and the number of matching rows
+    Summary,
+    /// Computes the matching intervals, along with the summary
+    Intervals(query::IntervalOptions),
+}
+
+/// Search of a topic, returns the index of the searched group and the topic if it matches
+type SearchJob = BoxFuture<'static, std::result::Result<(usize, Option<TopicHit>), Error>>;
+
+/// Metric label of the ontology tags not matching any topic
+const UNKNOWN_ONTOLOGY_TAG: &str = "unknown";
+
+/// Search job bookkeeping of an ontology tag group
+struct GroupProgress {
+    /// Ontology tag of the group, [`UNKNOWN_ONTOLOGY_TAG`] if it doesn't match any topic
+    ontology_tag: String,
+    start: Instant,
+    pending_jobs: usize,
+}
+
+/// Topics found for a sequence, waiting for the sequence to be matched by all the groups
+#[derive(Default)]
+struct SequenceProgress {
+    /// Groups that matched at least a topic of the sequence
+    groups: HashSet<usize>,
+    pending: Vec<TopicHit>,
+}
 
-        let (seq_filt, top_filt, on_filt) = filter.into_parts();
+/// A running query.
+///
+/// The ontology filter is split by `ontology_tag`, each group becomes a query to find the
+/// corresponding chunks, this is done since there is a mutual mapping between ontology and
+/// chunks (a chunk holds data of a single ontology model). The chunks of each topic are then
+/// searched by a concurrent job.
+///
+/// A sequence matches the query if each group matches at least one of its topics, so topics
+/// are returned as soon as their sequence is known to match.
+struct Search {
+    db: db::Database,
+    jobs: FuturesUnordered<SearchJob>,
+    groups: Vec<GroupProgress>,
+    sequences: HashMap<i32, SequenceProgress>,
+    /// Sequence records already fetched, by sequence id
+    sequence_records: HashMap<i32, db::SequenceRecord>,
+    /// Topics matched without ontology filter, returned at once
+    unfiltered: Option<Vec<db::TopicRecord>>,
+}
+
+impl Search {
+    async fn try_new(
+        filter: query::Filter,
+        scopes: &[types::auth::ApiKeyScope],
+        summary: bool,
+        ts_gw: query::TimeseriesEngineRef,
+        db: db::Database,
+    ) -> Result<Self> {
+        let (seq_filt, top_filt, on_filt, page) = filter.into_parts();
 
         let no_topic_filter = (seq_filt.is_none() || seq_filt.as_ref().unwrap().is_empty())
             && (top_filt.is_none() || top_filt.as_ref().unwrap().is_empty());
 
-        // This holds the set of topic that the user requested with topic and sequence filters
+        // This holds the set of topic that the user requested with topic and sequence filters.
+        // When an ontology filter is provided the matching sequences are only known after the
+        // chunk scan, so the page limit can't be applied on the topics.
         let on_topics = {
             let mut cx = db.connection();
-            db::topic_from_query_filter(&mut cx, seq_filt, top_filt).await?
+            let topics_page = if on_filt.is_some() {
+                query::Page {
+                    limit: None,
+                    ..page.clone()
+                }
+            } else {
+                page.clone()
+            };
+            db::topic_from_query_filter(&mut cx, seq_filt, top_filt, scopes, &topics_page).await?
+        };
+
+        let mut search = Self {
+            db: db.clone(),
+            jobs: FuturesUnordered::new(),
+            groups: Vec::new(),
+            sequences: HashMap::new(),
+            sequence_records: HashMap::new(),
+            unfiltered: None,
+        };
+
+        let Some(ontology_filter) = on_filt else {
+            // No ontology filter branch, simply retrieve
+            search.unfiltered = Some(on_topics);
+            return Ok(search);
         };
-        let on_topics = Arc::new(on_topics);
 
         if no_topic_filter {
             trace!("search unrestricted (no prior topics)");
+        } else if on_topics.is_empty() {
+            trace!("no topic matches the topic and sequence filters");
+            return Ok(search);
         } else {
             trace!("restricting search on #{} topics", on_topics.len());
         }
 
-        // Here we split the ontology filter elements and group them by `ontology_tag`.
-        // Each group becomes a query to find the corresponding chunks, this is done
-        // since there is a mutual mapping between ontology and chunks (a chunk holds data of a
-        // single ontology model).
-        // Each expression group is processed concurrently using unordered futures, and returns a
-        // `SequenceTopicGroups`.
-        // At the end sequence topic groups are merged (sequences are interseted and topic are
-        // joined) before return.
-        // TODO: move this code in a separate function to improve readability
-        if let Some(ontology_filter) = on_filt {
-            let start = Instant::now();
+        let output = match ontology_filter.intervals {
+            Some(intervals) => SearchOutput::Intervals(intervals),
+            None if summary => SearchOutput::Summary,
+            None => SearchOutput::Match,
+        };
+
+        let ontology_tag_expr_groups = ontology_filter.into_expr_group().split_by_ontology_tag()?;
 
-            let include_timestamp_range = ontology_filter.include_timestamp_range;
+        let max_concurrent = params::params().max_concurrent_chunk_queries.value;
+        let semaphore = Arc::new(Semaphore::new(max_concurrent));
 
-            let ontology_tag_expr_groups =
-                ontology_filter.into_expr_group().split_by_ontology_tag();
-            let expression_groups_count = ontology_tag_expr_groups.len();
+        for ontology_tag_exprs in ontology_tag_expr_groups {
+            if ontology_tag_exprs.is_empty() {
+                continue;
+            }
+
+            let group = search.groups.len();
+            let mut ontology_tag = ontology_tag_exprs
+                .ontology_tag()
+                .unwrap_or_default()
+                .to_owned();
+
+            trace!("starting search for ontology tag `{}`", ontology_tag);
+
+            let start = Instant::now();
 
-            for ontology_tag_exprs in ontology_tag_expr_groups {
-                if ontology_tag_exprs.group.is_empty() {
+            let mut cx = db.connection();
+            let chunks =
+                db::chunks_from_filters(&mut cx, ontology_tag_exprs.clone(), Some(&on_topics))
+                    .await?;
+
+            trace!("found {} chunks for provided filter", chunks.len());
+
+            // Extract a lookup structure holding all the topics for the current chunk set
+            let on_topics = if no_topic_filter {
+                None
+            } else {
+                Some(&on_topics)
+            };
+            let mut topics_map = pre_fetch_topics(&mut cx, &chunks, on_topics).await?;
+
+            let mut topic_chunks: HashMap<i32, Vec<db::ChunkRecord>> = HashMap::new();
+            for chunk in chunks {
+                let Some(topic) = topics_map.get(&chunk.topic_id) else {
+                    debug!(
+                        "can't find a topic associated with chunk `{}`, skipping",
+                        chunk.chunk_uuid
+                    );
+                    continue;
+                };
+                // Topics selected by the sequence and topic filters are already in scope
+                if !in_scope(scopes, &topic.locator()) {
                     continue;
                 }
-                trace!(
-                    "starting search for ontology tag `{}` (expression groups: {})",
-                    // grp is ensured to contain at least one element (previous check)
-                    ontology_tag_exprs.group[0].ontology_field().ontology_tag(),
-                    &expression_groups_count,
+                topic_chunks.entry(chunk.topic_id).or_default().push(chunk);
+            }
+
+            let group_span = tracing::info_span!("query_group", ontology_tag);
+            let pending_jobs = topic_chunks.len();
+
+            for (topic_id, chunks) in topic_chunks {
+                let topic = topics_map
+                    .remove(&topic_id)
+                    .expect("topic checked when grouping chunks");
+
+                // Chunks discarded by their statistics contain no matching row, but still
+                // split the runs of matching rows around them
+                let chunks = match output {
+                    SearchOutput::Intervals(_) => {
+                        db::chunk_find_by_topic_id(&mut cx, topic_id).await?
+                    }
+                    _ => chunks,
+                };
+
+                search.jobs.push(
+                    search_topic(
+                        group,
+                        topic,
+                        chunks,
+                        ontology_tag_exprs.clone(),
+                        output,
+                        ts_gw.clone(),
+                        semaphore.clone(),
+                    )
+                    .instrument(group_span.clone())
+                    .boxed(),
                 );
+            }
 
-                let ts_engine = ts_gw.clone();
-                let max_concurrent = params::params().max_concurrent_chunk_queries.value;
-                let semaphore = Arc::new(Semaphore::new(max_concurrent));
-                let mut search_jobs = FuturesUnordered::new();
+            // Tags are client supplied: only the ones resolved to some topic label the metrics,
+            // so that the number of series stays bounded
+            if pending_jobs == 0 {
+                ontology_tag = UNKNOWN_ONTOLOGY_TAG.to_owned();
+            }
 
-                let permit = semaphore
-                    .clone()
-                    .acquire_owned()
-                    .await
-                    .map_err(Error::from)?;
+            search.groups.push(GroupProgress {
+                ontology_tag,
+                start,
+                pending_jobs,
+            });
 
-                let db_clone = db.clone();
-                let on_topics = on_topics.clone();
+            search.record_group_if_done(group);
+        }
 
-                search_jobs.push(async move {
-                    let _permit = permit; // sentinel lock
+        debug!(
+            "searching {} topics over {} expression groups ({} concurrent)",
+            search.jobs.len(),
+            search.groups.len(),
+            max_concurrent
+        );
 
-                    let mut cx = db_clone.connection();
-                    let chunks = db::chunks_from_filters(
-                        &mut cx,
-                        ontology_tag_exprs.clone(),
-                        Some(&on_topics),
-                    )
-                    .await?;
+        Ok(search)
+    }
 
-                    trace!("found {} chunks for provided filter", chunks.len());
-
-                    // Extract a lookup structure holding all the topics for the current chunk set
-                    let on_topics = if no_topic_filter {
-                        None
-                    } else {
-                        Some(&on_topics)
-                    };
-                    let topics_map = pre_fetch_topics(&mut cx, &chunks, on_topics).await?;
-
-                    // Store which topic had a positive data file search
-                    let mut topics_with_data: HashSet<i32> = HashSet::new();
-
-                    // Stores the timestamp range (is requested) of each topic that matches
-                    let mut topics_timestamp_range: HashMap<String, types::TimestampRange> =
-                        HashMap::new();
-
-                    for chunk in chunks {
-                        let topic = topics_map.get(&chunk.topic_id);
-                        if topic.is_none() {
-                            debug!(
-                                "can't find a topic associated with chunk `{}`, skipping",
-                                chunk.chunk_uuid
-                            );
-                            return Ok::<_, Error>(types::SequenceTopicGroupSet::empty());
-                        }
-                        let topic = topic.unwrap();
-
-                        trace!(
-                            "searching data file `{}`",
-                            chunk.data_file().to_string_lossy()
-                        );
-
-                        let serialization_format =
-                            topic.serialization_format().ok_or_else(|| {
-                                Error::MissingDbData(format!(
-                                    "missing serialization_format in topic `{locator}`",
-                                    locator = topic.locator()
-                                ))
-                            })?;
-
-                        let qr = ts_engine
-                            .read(chunk.data_file(), serialization_format, None)
-                            .await?;
-
-                        let qr = qr.filter(ontology_tag_exprs.to_owned())?;
-
-                        // Set this to true to print a log message that the chunk will be discared
-                        let mut is_discarded = false;
-
-                        if include_timestamp_range {
-                            match qr.timestamp_range().await {
-                                Ok(ts_range) => {
-                                    topics_with_data.insert(topic.topic_id);
-                                    topics_timestamp_range
-                                        .insert(topic.locator().to_string(), ts_range);
-                                }
-                                Err(err) => {
-                                    if let query::Error::NotFound = err {
-                                        is_discarded = true;
-                                    } else {
-                                        return Err(err.into());
-                                    }
-                                }
-                            }
-                        } else if qr.has_rows().await? {
-                            trace!("found matching records in chunk");
-                            topics_with_data.insert(topic.topic_id);
-                        } else {
-                            is_discarded = true;
-                        }
-
-                        if is_discarded {
-                            trace!("discarding chunk `{}` for no query match", chunk.chunk_uuid);
-                        }
-                    }
+    /// Returns the next topics whose sequence matches the query, [`None`] once the search is
+    /// completed.
+    async fn next_matches(&mut self) -> Result<Option<Vec<TopicMatch>>> {
+        if let Some(topics) = self.unfiltered.take() {
+            let mut matches = Vec::with_capacity(topics.len());
+            for topic in topics {
+                matches.push(self.topic_match(topic, None, None).await?);
+            }
+            return Ok(Some(matches));
+        }
 
-                    trace!("topics with positive match: {:?}", topics_with_data);
-                    let topics = topics_map
-                        .values()
-                        .filter(|e| topics_with_data.contains(&e.topic_id));
-
-                    let mut groups = db::sequences_group_from_topics(&mut cx, topics).await?;
-
-                    if include_timestamp_range {
-                        groups
-                            .iter_mut()
-                            .flat_map(|grp| &mut grp.topics)
-                            .for_each(|topic| {
-                                topic.timestamp_range =
-                                    topics_timestamp_range.remove(&topic.to_string());
-                            });
-                    }
+        while let Some(job) = self.jobs.next().await {
+            let (group, hit) = job?;
 
-                    Ok(groups.into())
-                });
+            self.groups[group].pending_jobs -= 1;
+            self.record_group_if_done(group);
 
-                // Collect results from all concurrent routines
-                while let Some(groups) = search_jobs.next().await {
-                    if let Some(r) = result {
-                        result = Some(r.merge(groups?));
-                    } else {
-                        result = Some(groups?);
-                    }
-                }
+            let Some(hit) = hit else {
+                continue;
+            };
+
+            let sequence = self.sequences.entry(hit.topic.sequence_id).or_default();
+            sequence.groups.insert(group);
+            sequence.pending.push(hit);
 
-                let elapsed = start.elapsed();
+            if sequence.groups.len() < self.groups.len() {
+                continue;
+            }
 
-                debug!(
-                    "expression groups search required {}us ({:.2}us/group, {} concurrent)",
-                    elapsed.as_micros(),
-                    elapsed.as_micros() as f64 / expression_groups_count.max(1) as f64,
-                    max_concurrent
+            let hits = std::mem::take(&mut sequence.pending);
+            let mut matches = Vec::with_capacity(hits.len());
+            for hit in hits {
+                matches.push(
+                    self.topic_match(hit.topic, hit.summary, hit.intervals)
+                        .await?,
                 );
             }
-        } else {
-            // No ontology filter branch, simply retrieve
-            let mut cx = db.connection();
-            let group = db::sequences_group_from_topics(&mut cx, on_topics.iter()).await?;
-            result = Some(group.into());
+            return Ok(Some(matches));
         }
 
-        Ok(result.unwrap_or_default())
+        Ok(None)
+    }
+
+    async fn topic_match(
+        &mut self,
+        topic: db::TopicRecord,
+        summary: Option<query::RowsSummary>,
+        intervals: Option<Vec<types::TimestampRange>>,
+    ) -> Result<TopicMatch> {
+        if !self.sequence_records.contains_key(&topic.sequence_id) {
+            let mut cx = self.db.connection();
+            let sequence = db::sequence_find_by_id(&mut cx, topic.sequence_id).await?;
+            self.sequence_records.insert(topic.sequence_id, sequence);
+        }
+        let sequence = &self.sequence_records[&topic.sequence_id];
+
+        Ok(TopicMatch {
+            sequence: sequence.locator(),
+            sequence_created_at: sequence.creation_timestamp(),
+            topic: topic.locator(),
+            timestamp_range: summary.as_ref().map(|s| s.timestamp_range.clone()),
+            match_count: summary.map(|s| s.rows),
+            intervals,
+        })
+    }
+
+    /// Records the search time of `group` once all its jobs are completed
+    fn record_group_if_done(&self, group: usize) {
+        let group = &self.groups[group];
+        if group.pending_jobs == 0 {
+            metrics::histogram!(metric_names::QUERY_GROUP_SECONDS, "ontology_tag" => group.ontology_tag.clone())
+                .record(group.start.elapsed().as_secs_f64());
+        }
+    }
+}
+
+/// Searches the chunks of `topic` matching the expression group.
+///
+/// Unless `output` is [`SearchOutput::Match`] all the chunks are read to compute the
+/// requested data, otherwise the search stops at the first matching chunk. Intervals are
+/// computed over the rows of all the `chunks` at once, since a run of matching rows can span
+/// multiple chunks.
+async fn search_topic(
+    group: usize,
+    topic: db::TopicRecord,
+    chunks: Vec<db::ChunkRecord>,
+    exprs: query::OntologyExprGroup<query::Value>,
+    output: SearchOutput,
+    ts_engine: query::TimeseriesEngineRef,
+    semaphore: Arc<Semaphore>,
+) -> std::result::Result<(usize, Option<TopicHit>), Error> {
+    let _permit = semaphore.acquire_owned().await?;
+
+    let serialization_format = topic.serialization_format().ok_or_else(|| {
+        Error::MissingDbData(format!(
+            "missing serialization_format in topic `{locator}`",
+            locator = topic.locator()
+        ))
+    })?;
+
+    if let SearchOutput::Intervals(options) = output {
+        let files: Vec<_> = chunks
+            .iter()
+            .map(|chunk| chunk.data_file().to_path_buf())
+            .collect();
+
+        let topic_span = tracing::info_span!("topic_scan", chunks = files.len());
+
+        // The intervals are computed over the unfiltered rows, to find where the filter stops
+        // matching
+        let intervals = ts_engine
+            .read_files(&files, serialization_format, None, false)
+            .instrument(topic_span.clone())
+            .await?
+            .intervals(exprs, options.max_gap_ns)
+            .instrument(topic_span)
+            .await?;
+
+        let hit = intervals
+            .iter()
+            .cloned()
+            .reduce(|a, b| a.merge(b))
+            .map(|summary| TopicHit {
+                topic,
+                summary: Some(summary),
+                intervals: Some(intervals.into_iter().map(|i| i.timestamp_range).collect()),
+            });
+
+        return Ok((group, hit));
+    }
+
+    let mut matched = false;
+    let mut rows_summary: Option<query::RowsSummary> = None;
+
+    for chunk in chunks {
+        trace!(
+            "searching data file `{}`",
+            chunk.data_file().to_string_lossy()
+        );
+
+        // Span closed at the end of the iteration, covering the whole chunk scan
+        let chunk_span = tracing::info_span!("chunk_scan", chunk = %chunk.chunk_uuid);
+
+        let qr = ts_engine
+            .read(chunk.data_file(), serialization_format, None)
+            .instrument(chunk_span.clone())
+            .await?;
+
+        let chunk_summary = match output {
+            SearchOutput::Match => {
+                let qr = qr.filter(exprs.clone())?;
+                if qr.has_rows().instrument(chunk_span).await? {
+                    trace!("found matching records in chunk");
+                    matched = true;
+                    break;
+                }
+                None
+            }
+            SearchOutput::Summary => {
+                qr.filter(exprs.clone())?
+                    .rows_summary()
+                    .instrument(chunk_span)
+                    .await?
+            }
+            SearchOutput::Intervals(_) => unreachable!("intervals are searched over all chunks"),
+        };
+
+        if let Some(chunk_summary) = chunk_summary {
+            trace!("found matching records in chunk");
+            matched = true;
+            rows_summary = Some(match rows_summary {
+                Some(s) => s.merge(chunk_summary),
+                None => chunk_summary,
+            });
+        } else {
+            trace!("discarding chunk `{}` for no query match", chunk.chunk_uuid);
+        }
     }
+
+    let hit = matched.then_some(TopicHit {
+        topic,
+        summary: rows_summary,
+        intervals: None,
+    });
+
+    Ok((group, hit))
+}
+
+/// Returns `true` if `locator` is contained in some of `scopes`, or if there are no scopes
+fn in_scope(scopes: &[types::auth::ApiKeyScope], locator: &str) -> bool {
+    scopes.is_empty() || scopes.iter().any(|scope| scope.contains(locator))
 }
 
 /// A map holding pairs of (topic_id, topic_record) for easy lookup
@@ -229,7 +568,7 @@ type TopicMap = HashMap<i32, db::TopicRecord>;
 async fn pre_fetch_topics(
     cx: &mut db::Cx<'_>,
     chunks: &[db::ChunkRecord],
-    on_topics: Option<&Arc<Vec<db::TopicRecord>>>,
+    on_topics: Option<&Vec<db::TopicRecord>>,
 ) -> std::result::Result<TopicMap, Error> {
     let topic_map = if let Some(topics) = on_topics {
         topics.iter().map(|t| (t.topic_id, t.clone())).collect()
@@ -244,3 +583,316 @@ async fn pre_fetch_topics(
 
     Ok(topic_map)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, sequence, session, topic};
+    use futures::TryStreamExt;
+    use mosaicod_marshal as marshal;
+    use mosaicod_store as store;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        let database = db::testing::Database::new(pool);
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store.clone(), database.clone(), ts_gw)
+    }
+
+    async fn query_page(context: &Context, filter: &str) -> (Vec<String>, Option<String>) {
+        scoped_query_page(context, &[], filter).await
+    }
+
+    async fn scoped_query_page(
+        context: &Context,
+        scopes: &[types::auth::ApiKeyScope],
+        filter: &str,
+    ) -> (Vec<String>, Option<String>) {
+        let filter = marshal::query_filter_from_string(filter).unwrap();
+        let result = Query::query(
+            filter,
+            scopes,
+            context.timeseries_querier.clone(),
+            context.db.clone(),
+        )
+        .await
+        .unwrap();
+
+        let groups: Vec<types::SequenceTopicGroup> = result.groups.into();
+        (
+            groups.into_iter().map(|g| g.sequence.to_string()).collect(),
+            result.cursor.map(|c| c.to_string()),
+        )
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn query_pagination(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        // Created in a different order than their names
+        for name in ["seq_c", "seq_a", "seq_b"] {
+            let seq = sequence::try_create(&context, name.parse().unwrap(), None)
+                .await
+                .unwrap();
+            let session = session::try_create(&context, seq.locator().clone())
+                .await
+                .unwrap();
+            for t in ["gps", "imu"] {
+                topic::try_create(
+                    &context,
+                    format!("{name}/{t}").parse().unwrap(),
+                    &session,
+                    types::TopicOntologyMetadata::new(
+                        types::TopicOntologyProperties {
+                            ontology_tag: "dummy".to_owned(),
+                            serialization_format: types::Format::Default,
+                            writer_options: Default::default(),
+                        },
+                        None,
+                    ),
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        let (page, cursor) = query_page(
+            &context,
+            r#"{"sequence": {"locator": {"$match": "seq_"}}, "limit": 2}"#,
+        )
+        .await;
+        assert_eq!(page, vec!["seq_a", "seq_b"]);
+        let cursor = cursor.expect("a cursor to the next page");
+
+        let (page, cursor) = query_page(
+            &context,
+            &format!(r#"{{"sequence": {{"locator": {{"$match": "seq_"}}}}, "limit": 2, "cursor": "{cursor}"}}"#),
+        )
+        .await;
+        assert_eq!(page, vec!["seq_c"]);
+        assert!(cursor.is_none());
+
+        let (page, _) = query_page(
+            &context,
+            r#"{"topic": {"locator": {"$match": "imu"}}, "order_by": "created_at"}"#,
+        )
+        .await;
+        assert_eq!(page, vec!["seq_c", "seq_a", "seq_b"]);
+
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let cursor_field = cursor
+                .map(|c| format!(r#", "cursor": "{c}""#))
+                .unwrap_or_default();
+            let (page, next) = query_page(
+                &context,
+                &format!(
+                    r#"{{"topic": {{"locator": {{"$match": "gps"}}}}, "order_by": "created_at", "limit": 1{cursor_field}}}"#
+                ),
+            )
+            .await;
+            pages.push(page);
+            if next.is_none() {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(pages, vec![vec!["seq_c"], vec!["seq_a"], vec!["seq_b"]]);
+
+        // Pages of scoped callers only hold (and point to) sequences in scope
+        let scopes = [
+            types::auth::ApiKeyScope::new("seq_a", types::auth::Permission::Read),
+            types::auth::ApiKeyScope::new("seq_c/gps", types::auth::Permission::Read),
+        ];
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let cursor_field = cursor
+                .map(|c| format!(r#", "cursor": "{c}""#))
+                .unwrap_or_default();
+            let (page, next) = scoped_query_page(
+                &context,
+                &scopes,
+                &format!(r#"{{"sequence": {{"locator": {{"$match": "seq_"}}}}, "limit": 1{cursor_field}}}"#),
+            )
+            .await;
+            pages.push(page);
+            if let Some(next) = &next {
+                assert!(!next.contains("seq_b"));
+            } else {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(pages, vec![vec!["seq_a"], vec!["seq_c"]]);
+
+        // Cursors are bound to the ordering they were created with
+        assert!(
+            marshal::query_filter_from_string(
+                r#"{"sequence": {"locator": {"$match": "seq_"}}, "order_by": "created_at", "cursor": "name:seq_a"}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn query_stream(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        for name in ["seq_a", "seq_b"] {
+            let seq = sequence::try_create(&context, name.parse().unwrap(), None)
+                .await
+                .unwrap();
+            let session = session::try_create(&context, seq.locator().clone())
+                .await
+                .unwrap();
+            for t in ["gps", "imu"] {
+                topic::try_create(
+                    &context,
+                    format!("{name}/{t}").parse().unwrap(),
+                    &session,
+                    types::TopicOntologyMetadata::new(
+                        types::TopicOntologyProperties {
+                            ontology_tag: "dummy".to_owned(),
+                            serialization_format: types::Format::Default,
+                            writer_options: Default::default(),
+                        },
+                        None,
+                    ),
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        let filter =
+            marshal::query_filter_from_string(r#"{"topic": {"locator": {"$match": "gps"}}}"#)
+                .unwrap();
+        let stream = Query::stream(
+            filter,
+            &[],
+            context.timeseries_querier.clone(),
+            context.db.clone(),
+        )
+        .await
+        .unwrap();
+
+        let batches: Vec<Vec<TopicMatch>> = stream.try_collect().await.unwrap();
+        let mut topics: Vec<String> = batches
+            .into_iter()
+            .flatten()
+            .map(|m| {
+                // Without an ontology filter topic data is not read
+                assert!(m.timestamp_range.is_none());
+                assert!(m.match_count.is_none());
+                m.topic.to_string()
+            })
+            .collect();
+        topics.sort();
+
+        assert_eq!(topics, vec!["seq_a/gps", "seq_b/gps"]);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn query_intervals_across_chunks(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array, RecordBatch};
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq = sequence::try_create(&context, "seq".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session = session::try_create(&context, seq.locator().clone())
+            .await
+            .unwrap();
+        let handle = topic::try_create(
+            &context,
+            "seq/imu".parse().unwrap(),
+            &session,
+            types::TopicOntologyMetadata::new(
+                types::TopicOntologyProperties {
+                    ontology_tag: "dummy".to_owned(),
+                    serialization_format: types::Format::Default,
+                    writer_options: Default::default(),
+                },
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        // Each batch is written in its own chunk. The run starting in the first chunk ends in
+        // the second one, the third chunk has no matching row (so it's discarded by its
+        // statistics) but still splits the runs of its neighbours
+        let mut writer = topic::writer(
+            context.clone(),
+            handle,
+            types::Format::Default,
+            schema.clone(),
+            None,
+        )
+        .await
+        .unwrap()
+        .with_chunk_target(0, 0);
+        let values: [[f64; 5]; 4] = [
+            [0.0, 0.0, 0.0, 1.0, 1.0],
+            [1.0, 1.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0, 1.0],
+        ];
+        for (i, values) in values.into_iter().enumerate() {
+            let start = i as i64 * 5;
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(start..start + 5)),
+                    Arc::new(Float64Array::from(values.to_vec())),
+                ],
+            )
+            .unwrap();
+            writer.write(batch).await.unwrap();
+        }
+        writer.finalize().await.unwrap();
+
+        let filter = marshal::query_filter_from_string(
+            r#"{"ontology": {"dummy.value": {"$gt": 0.5}, "intervals": {"max_gap_ns": 0}}}"#,
+        )
+        .unwrap();
+        let stream = Query::stream(
+            filter,
+            &[],
+            context.timeseries_querier.clone(),
+            context.db.clone(),
+        )
+        .await
+        .unwrap();
+
+        let matches: Vec<TopicMatch> = stream
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].match_count, Some(10));
+
+        let intervals: Vec<(i64, i64)> = matches[0]
+            .intervals
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|i| (i.start.as_i64(), i.end.as_i64()))
+            .collect();
+        assert_eq!(intervals, vec![(3, 6), (9, 9), (15, 19)]);
+    }
+}
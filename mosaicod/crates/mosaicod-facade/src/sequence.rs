@@ -125,7 +125,7 @@ pub async fn all(context: &Context) -> Result<Vec<Handle>> {
         .collect())
 }
 
-async fn metadata_write_to_store(
+pub(super) async fn metadata_write_to_store(
     context: &Context,
     path: &path::Path,
     metadata: SequenceUserMetadata,
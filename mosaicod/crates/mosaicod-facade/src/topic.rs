@@ -1,12 +1,13 @@
-use super::{Context, Error, session};
-use arrow::datatypes::SchemaRef;
+use super::{Chunk, Context, Error, chunk::stats_write_to_db, session};
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
 use log::trace;
 use mosaicod_core::types::TopicMetadataProperties;
 use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
 use mosaicod_db as db;
 use mosaicod_ext as ext;
 use mosaicod_marshal as marshal;
-use mosaicod_rw::{self as rw, ToProperties};
+use mosaicod_query as query;
+use mosaicod_rw::{self as rw, ToIpcProperties, ToProperties};
 use mosaicod_store as store;
 use std::path;
 use std::sync::Arc;
@@ -100,6 +101,12 @@ pub async fn try_create(
 ) -> Result<Handle> {
     let path_in_store = types::TopicPathInStore::new();
 
+    // Field names are checked against the schema of the data when it is uploaded
+    rw::writer_options::validate(
+        &ontology_metadata.properties.writer_options,
+        ontology_metadata.properties.serialization_format,
+    )?;
+
     let mut tx = context.db.transaction().await?;
 
     // Check that there are not other topics with the same locator
@@ -136,7 +143,8 @@ pub async fn try_create(
             .serialization_format
             .to_string(),
         Some(path_in_store.clone()),
-    );
+    )
+    .with_writer_options(ontology_metadata.properties.writer_options.clone());
 
     if let Some(user_metadata) = &ontology_metadata.user_metadata {
         record = record.with_user_metadata(user_metadata.clone());
@@ -189,11 +197,21 @@ pub async fn archived(context: &Context, handle: &Handle) -> Result<bool> {
 /// Finalize the write procedure of the topic. The topic is locked and additional data are
 /// consolidated (e.g. metadata, timestamp bounds). This function is intended to be called by
 /// [`HandleWriter`] to finalize the writing process.
-async fn finalize(context: &Context, handle: &Handle, format: types::Format) -> Result<()> {
+///
+/// Returns an error if the topic was claimed by another upload than `upload`.
+#[tracing::instrument(skip_all, fields(topic = %handle.locator))]
+async fn finalize(
+    context: &Context,
+    handle: &Handle,
+    format: types::Format,
+    upload: &types::Uuid,
+) -> Result<()> {
     let mut tx = context.db.transaction().await?;
 
+    check_upload(handle, upload, &mut tx).await?;
+
     let info = compute_data_info(context, handle, &mut tx, format).await?;
-    data_info_write_to_db(context, handle, info).await?;
+    data_info_write_to_db(&mut tx, handle, info).await?;
 
     // Check if topic is already locked.
     if impl_archived(handle, &mut tx).await? {
@@ -203,6 +221,7 @@ async fn finalize(context: &Context, handle: &Handle, format: types::Format) ->
     // Update completion timestamp in DB and Store
     db::topic_update_completion_tstamp(&mut tx, handle.id(), types::Timestamp::now().as_i64())
         .await?;
+    db::topic_update_upload(&mut tx, handle.id(), None).await?;
 
     // This operation is done at the end to avoid deleting or reverting changes
     // to metadata file on store if some error causes a rollback on the database
@@ -243,6 +262,7 @@ pub async fn metadata(context: &Context, handle: &Handle) -> Result<TopicMetadat
                     .serialization_format()
                     .ok_or_else(|| Error::MissingDbData("serialization_format".to_owned()))?,
                 ontology_tag: db_topic.ontology_tag.clone(),
+                writer_options: db_topic.writer_options(),
             },
             user_metadata: db_topic.user_metadata(),
         },
@@ -271,6 +291,12 @@ pub async fn arrow_schema(
         Err(core::Error::not_found())?;
     }
 
+    // Arrow IPC files are read in memory, their schema is stored in the file footer
+    if format.to_ipc_properties().is_some() {
+        let buffer = context.store.read_bytes(path).await?;
+        return Ok(rw::ChunkReader::new(format, buffer.into())?.schema());
+    }
+
     // Build a parquet reader reading in memory a file
     let mut parquet_reader = context.store.parquet_reader(path);
     let schema = ext::arrow::schema_from_parquet_reader(&mut parquet_reader).await?;
@@ -283,7 +309,7 @@ pub async fn arrow_schema(
 /// # Errors
 ///
 /// Returns [`Error::NotFound`] or [`Error::WriteError`] if serialization or writing fails.
-async fn metadata_write_to_store(
+pub(super) async fn metadata_write_to_store(
     context: &Context,
     path: &path::Path,
     metadata: TopicMetadata,
@@ -300,17 +326,70 @@ async fn metadata_write_to_store(
 
 /// Returns a writer used to write chunked record batches using a specified serialization
 /// format `format`.
-pub fn writer(
+///
+/// If the topic already contains some data (e.g. a previous upload was interrupted) a
+/// `resume_token` matching the current [`resume_token`] of the topic is required, and the
+/// writer continues from the next chunk number.
+///
+/// The writer claims the topic, so that a previous upload still writing the topic fails
+/// before writing its next chunk, instead of interleaving its chunks with the new ones.
+///
+/// Returns a [`core::Error::locked_topic`] error if the topic was already finalized.
+#[tracing::instrument(skip_all, fields(topic = %handle.locator))]
+pub async fn writer(
     context: Context,
     handle: Handle,
     format: types::Format,
     schema: SchemaRef,
+    resume_token: Option<types::ResumeToken>,
 ) -> Result<HandleWriter> {
     let path_in_store = handle
         .path_in_store
         .clone()
         .ok_or(core::Error::not_found())?;
 
+    let expected_token = self::resume_token(&context, &handle).await?;
+
+    match resume_token {
+        Some(token) if token != expected_token => Err(core::Error::bad_request(format!(
+            "resume token `{token}` does not match the current state of topic `{}`",
+            handle.locator
+        )))?,
+        None if expected_token.chunks() > 0 => Err(core::Error::bad_request(format!(
+            "topic `{}` already contains data, a resume token is required to append data",
+            handle.locator
+        )))?,
+        _ => {}
+    }
+
+    if expected_token.chunks() > 0 {
+        // Appended data must be readable along the existing chunks
+        let current_schema = arrow_schema(&context, &handle, format).await?;
+        if !same_fields(&current_schema, &schema) {
+            Err(core::Error::bad_request(format!(
+                "schema does not match the schema of the data already uploaded in topic `{}`",
+                handle.locator
+            )))?;
+        }
+    }
+
+    let (ontology_tag, writer_options, last_timestamp) = {
+        let mut cx = context.db.connection();
+        let record = db::topic_find_by_id(&mut cx, handle.id()).await?;
+        let last_timestamp = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await?
+            .iter()
+            .filter_map(|chunk| chunk.max_timestamp_ns)
+            .max();
+        (
+            record.ontology_tag.clone(),
+            record.writer_options(),
+            last_timestamp,
+        )
+    };
+
+    let upload = claim(&context, &handle, &expected_token).await?;
+
     let data_folder = path_in_store.path_data_folder(handle.uuid());
 
     let writer = rw::ChunkWriter::new(
@@ -323,16 +402,112 @@ pub fn writer(
                 format.to_properties().as_ref(),
             ))
         },
-    );
+    )
+    .with_chunk_count(expected_token.chunks())
+    .with_writer_options(writer_options);
+
+    let params = params::params();
 
     Ok(HandleWriter {
         handle,
+        upload,
         format,
+        ontology_tag,
         writer,
+        pending: Vec::new(),
+        pending_size: 0,
+        pending_rows: 0,
+        chunk_target_size: params.chunk_target_size.value,
+        chunk_target_rows: params.chunk_target_rows.value,
+        timestamp_policy: params.timestamp_policy.value,
+        last_timestamp,
+        timestamp_warned: false,
         context,
     })
 }
 
+/// Returns the token required to resume the upload of the topic.
+///
+/// The token pins the number of chunks already persisted in the data catalog, so that
+/// the next upload starts from the following chunk number.
+///
+/// Returns a [`core::Error::locked_topic`] error if the topic was already finalized.
+pub async fn resume_token(context: &Context, handle: &Handle) -> Result<types::ResumeToken> {
+    let mut cx = context.db.connection();
+    impl_resume_token(handle, &mut cx).await
+}
+
+async fn impl_resume_token(
+    handle: &Handle,
+    exe: &mut impl db::AsExec,
+) -> Result<types::ResumeToken> {
+    if impl_archived(handle, exe).await? {
+        Err(core::Error::locked_topic(handle.locator().to_string()))?;
+    }
+
+    let stats = db::topic_get_stats(exe, &handle.locator).await?;
+
+    Ok(types::ResumeToken::new(
+        handle.uuid.clone(),
+        stats.chunks_count as usize,
+    ))
+}
+
+/// Claims the topic for a new upload, returning the upload id.
+///
+/// The claim fails if some chunk was written after `token` was computed, e.g. by a concurrent
+/// upload using the same token.
+async fn claim(
+    context: &Context,
+    handle: &Handle,
+    token: &types::ResumeToken,
+) -> Result<types::Uuid> {
+    let mut tx = context.db.transaction().await?;
+
+    // Locking the topic waits for the chunk being written by the previous upload, if any
+    db::topic_lock_upload(&mut tx, handle.id()).await?;
+
+    let current = impl_resume_token(handle, &mut tx).await?;
+    if &current != token {
+        Err(core::Error::bad_request(format!(
+            "resume token `{token}` does not match the current state of topic `{}`",
+            handle.locator
+        )))?;
+    }
+
+    let upload = types::Uuid::new();
+    db::topic_update_upload(&mut tx, handle.id(), Some(&upload)).await?;
+
+    tx.commit().await?;
+
+    Ok(upload)
+}
+
+/// Locks the topic until the end of the transaction, returning an error if the topic was
+/// claimed by another upload than `upload`.
+async fn check_upload(
+    handle: &Handle,
+    upload: &types::Uuid,
+    exe: &mut impl db::AsExec,
+) -> Result<()> {
+    if db::topic_lock_upload(exe, handle.id()).await?.as_ref() != Some(upload) {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` was claimed by another upload",
+            handle.locator
+        )))?;
+    }
+    Ok(())
+}
+
+/// Compares field names and data types of two schemas, ignoring metadata
+fn same_fields(a: &arrow::datatypes::Schema, b: &arrow::datatypes::Schema) -> bool {
+    a.fields().len() == b.fields().len()
+        && a.fields()
+            .iter()
+            .zip(b.fields())
+            .all(|(a, b)| a.name() == b.name() && a.data_type() == b.data_type())
+}
+
 /// Permanently deletes a topic and all its data, be caution
 ///
 /// A [`types::DataLossToken`] is required since this call will lead to data losses.
@@ -422,18 +597,7 @@ async fn compute_data_info(
         .clone()
         .ok_or(core::Error::not_found())?;
 
-    let timeseries_res = context
-        .timeseries_querier
-        .read(path_in_store.path_data_folder(handle.uuid()), format, None)
-        .await;
-
-    let timestamp_range = match timeseries_res {
-        Ok(res) => {
-            let ts_range = res.timestamp_range().await;
-            ts_range.unwrap_or(types::TimestampRange::unbounded())
-        }
-        Err(_) => types::TimestampRange::unbounded(),
-    };
+    let timestamp_range = timestamp_range(context, handle, format).await?;
 
     let record = db::topic_find_by_locator(exe, &handle.locator).await?;
 
@@ -461,17 +625,158 @@ async fn compute_data_info(
     })
 }
 
+/// Computes the range of timestamps (both bounds included) of the data currently stored
+/// in the topic.
+///
+/// An unbounded range is returned if the topic contains no data.
+pub async fn timestamp_range(
+    context: &Context,
+    handle: &Handle,
+    format: types::Format,
+) -> Result<types::TimestampRange> {
+    let path_in_store = handle
+        .path_in_store
+        .clone()
+        .ok_or(core::Error::not_found())?;
+
+    let timeseries_res = context
+        .timeseries_querier
+        .read(path_in_store.path_data_folder(handle.uuid()), format, None)
+        .await;
+
+    Ok(match timeseries_res {
+        Ok(res) => {
+            let ts_range = res.timestamp_range().await;
+            ts_range.unwrap_or(types::TimestampRange::unbounded())
+        }
+        Err(_) => types::TimestampRange::unbounded(),
+    })
+}
+
+/// Reads the data stored in the topic, sorted by timestamp, optionally limited to a
+/// `timestamp_range`.
+///
+/// The data files are read in chunk creation order. Only the chunks overlapping the requested
+/// range are read (chunks with unknown timestamps are always read). If the chunks are known to be sorted and non-overlapping, the data is streamed
+/// chunk by chunk without a global sort (see [`query::TimeseriesEngine::read_files`]).
+pub async fn read(
+    context: &Context,
+    handle: &Handle,
+    format: types::Format,
+    batch_size: Option<usize>,
+    timestamp_range: Option<types::TimestampRange>,
+) -> Result<query::TimeseriesResult> {
+    let path_in_store = handle.path_in_store.as_ref().ok_or_else(|| {
+        core::Error::internal(Some(format!(
+            "Path in store not set for topic {}",
+            handle.locator
+        )))
+    })?;
+
+    let chunks = {
+        let mut cx = context.db.connection();
+        db::chunk_find_by_topic_id(&mut cx, handle.id()).await?
+    };
+
+    let Some(timestamp_range) = timestamp_range else {
+        // Data files are read in chunk order, their names are not relevant
+        let (files, ordered) = if chunks.is_empty() {
+            (vec![path_in_store.path_data_folder(handle.uuid())], false)
+        } else {
+            let files = chunks
+                .iter()
+                .map(|chunk| chunk.data_file().to_path_buf())
+                .collect();
+            (files, chunks_ordered(&chunks))
+        };
+
+        let result = context
+            .timeseries_querier
+            .read_files(&files, format, batch_size, ordered)
+            .await?;
+        return Ok(result);
+    };
+
+    let selected: Vec<&db::ChunkRecord> = chunks
+        .iter()
+        .filter(|chunk| chunk_overlaps(chunk, &timestamp_range))
+        .collect();
+
+    trace!(
+        "reading {} of {} chunks of topic `{}` overlapping {}",
+        selected.len(),
+        chunks.len(),
+        handle.locator,
+        timestamp_range
+    );
+
+    let mut files: Vec<path::PathBuf> = selected
+        .iter()
+        .map(|chunk| chunk.data_file().to_path_buf())
+        .collect();
+
+    if files.is_empty() {
+        // A data file is still required to provide the schema of the (empty) result, its rows
+        // are discarded by the timestamp filter
+        files.push(chunks.first().map_or_else(
+            || path_in_store.path_data_folder(handle.uuid()),
+            |chunk| chunk.data_file().to_path_buf(),
+        ));
+    }
+
+    let result = context
+        .timeseries_querier
+        .read_files(
+            &files,
+            format,
+            batch_size,
+            chunks_ordered(selected.iter().copied()),
+        )
+        .await?
+        .filter_by_timestamp_range(timestamp_range)?;
+
+    Ok(result)
+}
+
+/// Returns `true` if the chunk may contain timestamps in `range`, i.e. if its timestamps are
+/// unknown or overlap the range (start included, end excluded).
+fn chunk_overlaps(chunk: &db::ChunkRecord, range: &types::TimestampRange) -> bool {
+    let (Some(min), Some(max)) = (chunk.min_timestamp_ns, chunk.max_timestamp_ns) else {
+        return true;
+    };
+
+    (range.start.is_unbounded() || max >= range.start.as_i64())
+        && (range.end.is_unbounded() || min < range.end.as_i64())
+}
+
+/// Returns `true` if all the chunks (in creation order) are sorted by timestamp and do not
+/// overlap.
+fn chunks_ordered<'a>(chunks: impl IntoIterator<Item = &'a db::ChunkRecord>) -> bool {
+    let mut last = None;
+    for chunk in chunks {
+        let (true, Some(min), Some(max)) =
+            (chunk.sorted, chunk.min_timestamp_ns, chunk.max_timestamp_ns)
+        else {
+            return false;
+        };
+
+        if last.is_some_and(|last| last > min) {
+            return false;
+        }
+        last = Some(max);
+    }
+    true
+}
+
 /// Caches metrics about topic's data.
 ///
 /// Since they can be recalculated at any time, it's enough to save them in the DB.
 async fn data_info_write_to_db(
-    context: &Context,
+    exe: &mut impl db::AsExec,
     handle: &Handle,
     system_info: types::TopicDataInfo,
 ) -> Result<()> {
-    let mut tx = context.db.transaction().await?;
-    db::topic_update_system_info(&mut tx, &handle.locator, &system_info).await?;
-    tx.commit().await?;
+    db::topic_update_system_info(exe, &handle.locator, &system_info).await?;
     Ok(())
 }
 
@@ -508,45 +813,459 @@ pub async fn compute_optimal_batch_size(context: &Context, handle: &Handle) -> R
     Ok((batch_size as usize).min(params.max_batch_size.value))
 }
 
+/// Outcome of a topic compaction
+#[derive(Debug)]
+pub struct CompactionReport {
+    pub chunks_before: usize,
+    pub chunks_after: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Merges the chunks of a finalized topic into chunks close to
+/// [`params::Params::parquet_in_memory_encoding_buffer_size`] bytes, recomputing their
+/// column statistics.
+///
+/// Compacted chunks are written in a new folder of the store, then the chunk records are
+/// replaced and the topic is moved to the new folder in a single transaction. The previous
+/// folder is deleted once the transaction is committed.
+///
+/// Returns a [`core::Error::bad_request`] error if the topic is still being uploaded.
+#[tracing::instrument(skip_all, fields(topic = %handle.locator))]
+pub async fn compact(context: &Context, handle: &mut Handle) -> Result<CompactionReport> {
+    let mut cx = context.db.connection();
+
+    if !impl_archived(handle, &mut cx).await? {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` is still being uploaded",
+            handle.locator
+        )))?;
+    }
+
+    let record = db::topic_find_by_id(&mut cx, handle.id()).await?;
+    let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id()).await?;
+
+    let bytes_before = chunks.iter().map(|chunk| chunk.size_bytes as u64).sum();
+    let target_size = params::params()
+        .parquet_in_memory_encoding_buffer_size
+        .value as i64;
+    let groups = compaction_groups(&chunks, target_size);
+
+    if groups.len() == chunks.len() {
+        trace!("topic chunks are already compacted");
+        return Ok(CompactionReport {
+            chunks_before: chunks.len(),
+            chunks_after: chunks.len(),
+            bytes_before,
+            bytes_after: bytes_before,
+        });
+    }
+
+    let current_path = record.path_in_store().ok_or(core::Error::not_found())?;
+    let new_path = types::TopicPathInStore::new();
+
+    let res = compact_into(context, handle, &record, &chunks, groups, &new_path).await;
+
+    match res {
+        Ok(compacted) => {
+            // Reads started before the swap may still be streaming the previous chunks, so
+            // the folder is not deleted here. Being no longer referenced, it's garbage
+            // collected by `fsck` (see [`crate::fsck::Issue::OrphanFolder`])
+            log::info!(
+                "topic `{}` compacted, folder `{}` left for garbage collection",
+                handle.locator,
+                current_path
+            );
+
+            handle.path_in_store = Some(new_path);
+
+            Ok(CompactionReport {
+                chunks_before: chunks.len(),
+                chunks_after: compacted.len(),
+                bytes_before,
+                bytes_after: compacted
+                    .iter()
+                    .map(|metadata| metadata.size_bytes as u64)
+                    .sum(),
+            })
+        }
+        Err(e) => {
+            let _ = context.store.delete_recursive(new_path.root()).await;
+            Err(e)
+        }
+    }
+}
+
+/// Writes the compacted chunks in `new_path` and swaps the chunk records, returning the
+/// metadata of the compacted chunks.
+async fn compact_into(
+    context: &Context,
+    handle: &Handle,
+    record: &db::TopicRecord,
+    chunks: &[db::ChunkRecord],
+    groups: Vec<std::ops::Range<usize>>,
+    new_path: &types::TopicPathInStore,
+) -> Result<Vec<rw::ChunkMetadata>> {
+    let current_path = record.path_in_store().ok_or(core::Error::not_found())?;
+    let format = record
+        .serialization_format()
+        .ok_or_else(|| Error::MissingDbData("serialization_format".to_owned()))?;
+    let writer_options = record.writer_options();
+
+    let mut compacted = Vec::with_capacity(groups.len());
+    for (chunk_number, group) in groups.into_iter().enumerate() {
+        let mut buffers = Vec::with_capacity(group.len());
+        for chunk in &chunks[group] {
+            buffers.push(context.store.read_bytes(chunk.data_file()).await?);
+        }
+
+        // Offload CPU-intensive decoding and encoding to blocking thread pool
+        let writer_options = writer_options.clone();
+        // Rows are copied as stored, so video frames are not encoded again
+        let (buffer, ontology_stats, metadata) = tokio::task::spawn_blocking(move || {
+            let mut encoder: Option<rw::InMemoryChunkEncoder> = None;
+            for buffer in buffers {
+                let buffer = bytes::Bytes::from(buffer);
+                let encoder = match &mut encoder {
+                    Some(encoder) => encoder,
+                    None => encoder.insert(rw::InMemoryChunkEncoder::try_new_with_options(
+                        rw::ChunkReader::new(format, buffer.clone())?.schema(),
+                        format,
+                        &writer_options,
+                    )?),
+                };
+                encoder.append_chunk(buffer)?;
+            }
+            encoder.ok_or(rw::Error::Unsupported)?.finalize()
+        })
+        .await
+        .map_err(|e| core::Error::internal(Some(e.to_string())))??;
+
+        let path = new_path.path_data(handle.uuid(), chunk_number, format.to_properties().as_ref());
+        context.store.write_bytes(&path, buffer).await?;
+
+        compacted.push(rw::SerializedChunk {
+            path,
+            ontology_stats,
+            metadata,
+        });
+    }
+
+    let metadata = metadata(context, handle).await?;
+    metadata_write_to_store(context, new_path.path_metadata().as_path(), metadata).await?;
+
+    let mut tx = context.db.transaction().await?;
+
+    // Taking the topic row first serializes concurrent compactions, only the first one succeeds
+    if !db::topic_update_path_in_store(&mut tx, handle.id(), &current_path, new_path).await? {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` was modified by a concurrent operation",
+            handle.locator
+        )))?;
+    }
+
+    for chunk in chunks {
+        db::chunk_delete(&mut tx, chunk.chunk_id, types::allow_data_loss()).await?;
+    }
+
+    let mut total_bytes = 0;
+    let mut compacted_metadata = Vec::with_capacity(compacted.len());
+    for chunk in compacted {
+        let chunk_record = db::chunk_create(
+            &mut tx,
+            &db::ChunkRecord::new(
+                handle.id(),
+                &chunk.path,
+                chunk.metadata.size_bytes as i64,
+                chunk.metadata.row_count as i64,
+            )
+            .with_timestamps(&chunk.metadata.timestamps),
+        )
+        .await?;
+
+        stats_write_to_db(
+            &mut tx,
+            chunk_record.chunk_id,
+            &record.ontology_tag,
+            chunk.ontology_stats,
+        )
+        .await?;
+
+        total_bytes += chunk.metadata.size_bytes as u64;
+        compacted_metadata.push(chunk.metadata);
+    }
+
+    if let Some(info) = record.info() {
+        let info = types::TopicDataInfo {
+            chunks_number: compacted_metadata.len() as u64,
+            total_bytes,
+            timestamp_range: info.timestamp_range,
+        };
+        db::topic_update_system_info(&mut tx, &handle.locator, &info).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(compacted_metadata)
+}
+
+/// Groups consecutive chunks whose total size does not exceed `target_size`, chunks larger
+/// than `target_size` are left alone.
+fn compaction_groups(chunks: &[db::ChunkRecord], target_size: i64) -> Vec<std::ops::Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (idx, chunk) in chunks.iter().enumerate() {
+        if idx > start && size + chunk.size_bytes > target_size {
+            groups.push(start..idx);
+            start = idx;
+            size = 0;
+        }
+        size += chunk.size_bytes;
+    }
+
+    if start < chunks.len() {
+        groups.push(start..chunks.len());
+    }
+
+    groups
+}
+
 /// A guard ensuring exclusive write access to [`Handle`].
 ///
 /// While this struct exists, the underlying topic is mutably borrowed, preventing
 /// any other operations (such as locking or concurrent reads) until [`HandleWriter::finalize`] is called.
+///
+/// Written batches are accumulated until [`params::Params::chunk_target_size`] bytes (or
+/// [`params::Params::chunk_target_rows`] rows) are reached, then they are encoded in a single
+/// chunk and registered in the data catalog, so the chunk size does not depend on how clients
+/// slice their data.
+///
+/// Before being encoded, the timestamps of each chunk are validated according to the
+/// [`types::TimestampPolicy`] of the writer.
 pub struct HandleWriter {
     /// Anchors the exclusive borrow of the handle, strictly tying the writer's lifetime
     /// to the topic's availability.
     handle: Handle,
 
+    /// Upload id claiming the topic, see [`writer`]
+    upload: types::Uuid,
+
     /// Serialization format used to write
     format: types::Format,
 
+    /// Ontology tag of the topic, used to register the chunk statistics
+    ontology_tag: String,
+
     /// The underlying writer handling the actual data operations.
     writer: rw::ChunkWriter<Arc<store::Store>>,
 
+    /// Batches received but not yet encoded
+    pending: Vec<RecordBatch>,
+    pending_size: usize,
+    pending_rows: usize,
+
+    /// Accumulation thresholds, see [`HandleWriter::with_chunk_target`]
+    chunk_target_size: usize,
+    chunk_target_rows: usize,
+
+    /// Policy applied to invalid timestamps, see [`HandleWriter::with_timestamp_policy`]
+    timestamp_policy: types::TimestampPolicy,
+
+    /// Largest timestamp written in the topic, if known
+    last_timestamp: Option<i64>,
+
+    /// Set once invalid timestamps are reported, to notify them only once per upload
+    timestamp_warned: bool,
+
     /// Context containing query engine for timeseries data used to finalize topic data at the end of write process
     context: Context,
 }
 
 impl HandleWriter {
-    /// Performs all the operations required to finalize the writing stream, consolidate topic data
-    /// and lock the topic
-    pub async fn finalize(self) -> Result<()> {
-        finalize(&self.context, &self.handle, self.format).await?;
+    /// Overrides the amount of data accumulated before encoding a chunk.
+    ///
+    /// A chunk is written as soon as the buffered batches reach `size_bytes` bytes (in memory)
+    /// or `rows` rows. A `size_bytes` of 0 writes each batch in its own chunk, a `rows` of 0
+    /// disables the rows threshold.
+    pub fn with_chunk_target(mut self, size_bytes: usize, rows: usize) -> Self {
+        self.chunk_target_size = size_bytes;
+        self.chunk_target_rows = rows;
+        self
+    }
+
+    /// Overrides the policy applied to invalid timestamps, i.e. null, negative or not sorted in
+    /// non-decreasing order (within a chunk or with respect to the data already written).
+    ///
+    /// With [`types::TimestampPolicy::Warn`] the problem is reported with a warning
+    /// notification on the topic, once per upload.
+    pub fn with_timestamp_policy(mut self, policy: types::TimestampPolicy) -> Self {
+        self.timestamp_policy = policy;
+        self
+    }
+
+    /// Writes a [`RecordBatch`], encoding a new chunk if the accumulation thresholds are
+    /// reached.
+    ///
+    /// Data buffered when the writer is dropped without calling [`HandleWriter::flush`] or
+    /// [`HandleWriter::finalize`] is lost.
+    pub async fn write(&mut self, batch: RecordBatch) -> Result<()> {
+        self.pending_size += batch.get_array_memory_size();
+        self.pending_rows += batch.num_rows();
+        self.pending.push(batch);
+
+        let size_reached = self.pending_size >= self.chunk_target_size;
+        let rows_reached =
+            self.chunk_target_rows > 0 && self.pending_rows >= self.chunk_target_rows;
+
+        if size_reached || rows_reached {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the buffered batches in a new chunk, if any, and registers it in the data
+    /// catalog.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        self.pending_size = 0;
+        self.pending_rows = 0;
+
+        let batch = if pending.len() == 1 {
+            pending.into_iter().next().expect("one batch is pending")
+        } else {
+            arrow::compute::concat_batches(&pending[0].schema(), &pending)
+                .map_err(|e| core::Error::internal(Some(e.to_string())))?
+        };
+
+        let batch = self.check_timestamps(batch).await?;
+
+        // Holds the claim until the chunk is registered, so that a new upload can't write the
+        // same chunk
+        let mut tx = self.context.db.transaction().await?;
+        check_upload(&self.handle, &self.upload, &mut tx).await?;
+
+        let chunk = self.writer.write(batch).await?;
+
+        if let Some(max) = chunk.metadata.timestamps.max {
+            self.last_timestamp = Some(self.last_timestamp.map_or(max, |last| last.max(max)));
+        }
+
+        let mut chunk_handle = Chunk::create(
+            &self.handle.uuid,
+            &chunk.path,
+            &chunk.metadata,
+            &self.context,
+        )
+        .await?;
+        chunk_handle
+            .push_ontology_model_stats(&self.ontology_tag, chunk.ontology_stats)
+            .await?;
+        chunk_handle.finalize().await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Applies the timestamp policy to a batch about to be encoded in a new chunk, returning the
+    /// batch to encode.
+    async fn check_timestamps(&mut self, batch: RecordBatch) -> Result<RecordBatch> {
+        let mut stats = types::TimestampStats::new();
+        ext::arrow::timestamp_stats_inspect_record_batch(&mut stats, &batch);
+
+        let Some(issue) = TimestampIssue::find(&stats, self.last_timestamp) else {
+            return Ok(batch);
+        };
+
+        match (self.timestamp_policy, &issue) {
+            (types::TimestampPolicy::Sort, TimestampIssue::Unsorted) => {
+                trace!(
+                    "sorting chunk of topic `{}` by timestamp",
+                    self.handle.locator
+                );
+                Ok(ext::arrow::sort_by_timestamp(&batch)?)
+            }
+            // Chunks overlapping the previous ones are read with a global sort
+            (types::TimestampPolicy::Sort, TimestampIssue::Overlapping { .. }) => Ok(batch),
+            (types::TimestampPolicy::Warn, _) => {
+                if !self.timestamp_warned {
+                    notify(
+                        &self.context,
+                        &self.handle,
+                        types::NotificationType::Warning,
+                        format!("invalid timestamps: {issue}"),
+                    )
+                    .await?;
+                    self.timestamp_warned = true;
+                }
+                Ok(batch)
+            }
+            _ => Err(core::Error::bad_request(format!(
+                "invalid timestamps in topic `{}`: {issue}",
+                self.handle.locator
+            )))?,
+        }
+    }
+
+    /// Flushes the buffered batches and performs all the operations required to finalize the
+    /// writing stream, consolidate topic data and lock the topic
+    pub async fn finalize(mut self) -> Result<()> {
+        self.flush().await?;
+        finalize(&self.context, &self.handle, self.format, &self.upload).await?;
         Ok(())
     }
 }
 
-impl std::ops::Deref for HandleWriter {
-    type Target = rw::ChunkWriter<Arc<store::Store>>;
+/// Problems found in the timestamps of a chunk, sorted by severity
+#[derive(Debug, PartialEq)]
+enum TimestampIssue {
+    Null,
+    Negative(i64),
+    Unsorted,
+    /// The chunk starts before the end of the data already written
+    Overlapping {
+        first: i64,
+        last: i64,
+    },
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.writer
+impl TimestampIssue {
+    /// Returns the most severe issue of a chunk, given the largest timestamp already written
+    fn find(stats: &types::TimestampStats, last_timestamp: Option<i64>) -> Option<Self> {
+        if stats.has_null {
+            return Some(Self::Null);
+        }
+        if let Some(min) = stats.min.filter(|min| *min < 0) {
+            return Some(Self::Negative(min));
+        }
+        if !stats.sorted {
+            return Some(Self::Unsorted);
+        }
+        match (stats.min, last_timestamp) {
+            (Some(first), Some(last)) if first < last => Some(Self::Overlapping { first, last }),
+            _ => None,
+        }
     }
 }
 
-impl std::ops::DerefMut for HandleWriter {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.writer
+impl std::fmt::Display for TimestampIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null timestamps"),
+            Self::Negative(ts) => write!(f, "negative timestamp {ts}"),
+            Self::Unsorted => write!(f, "timestamps not sorted"),
+            Self::Overlapping { first, last } => write!(
+                f,
+                "timestamp {first} precedes the last timestamp already written ({last})"
+            ),
+        }
     }
 }
 
@@ -555,7 +1274,6 @@ mod tests {
     use super::*;
     use crate::sequence;
     use mosaicod_core::types::NotificationType;
-    use mosaicod_query as query;
 
     fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
         let database = db::testing::Database::new(pool);
@@ -570,6 +1288,7 @@ mod tests {
             types::TopicOntologyProperties {
                 ontology_tag: "dummy".to_owned(),
                 serialization_format: types::Format::Default,
+                writer_options: Default::default(),
             },
             None,
         )
@@ -742,4 +1461,782 @@ mod tests {
                 .is_empty()
         );
     }
+
+    #[test]
+    fn chunk_compaction_groups() {
+        let chunks: Vec<db::ChunkRecord> = [10, 10, 10, 50, 5, 5, 100, 10]
+            .into_iter()
+            .map(|size| db::ChunkRecord::new(0, "data.parquet", size, 1))
+            .collect();
+
+        assert_eq!(
+            compaction_groups(&chunks, 30),
+            vec![0..3, 3..4, 4..6, 6..7, 7..8]
+        );
+        assert_eq!(compaction_groups(&chunks, 1000), vec![0..8]);
+        assert!(compaction_groups(&[], 30).is_empty());
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_chunks_order(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/test_topic".parse().unwrap(),
+            &session_handle,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+
+        // The chunk number outgrows the zero padding of the file names
+        let files = [
+            "data-99998.parquet",
+            "data-99999.parquet",
+            "data-100000.parquet",
+        ];
+
+        let mut tx = context.db.transaction().await.unwrap();
+        for file in files {
+            db::chunk_create(&mut tx, &db::ChunkRecord::new(handle.id(), file, 1, 1))
+                .await
+                .unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        let mut cx = context.db.connection();
+        let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await
+            .unwrap();
+        let chunk_files: Vec<&std::path::Path> = chunks.iter().map(|c| c.data_file()).collect();
+        assert_eq!(chunk_files, files.map(std::path::Path::new));
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_read_video_chunks_order(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{AsArray, Int64Array};
+        use arrow::datatypes::Int64Type;
+        use futures::TryStreamExt;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/test_topic".parse().unwrap(),
+            &session_handle,
+            types::TopicOntologyMetadata::new(
+                types::TopicOntologyProperties {
+                    ontology_tag: "image".to_owned(),
+                    serialization_format: types::Format::Video,
+                    writer_options: Default::default(),
+                },
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+
+        let data_folder = handle
+            .path_in_store()
+            .unwrap()
+            .path_data_folder(handle.uuid());
+
+        // The chunk number outgrows the zero padding of the file names, so the name
+        // order differs from the chunk order
+        let files = [
+            "data-99998.parquet",
+            "data-99999.parquet",
+            "data-100000.parquet",
+        ];
+
+        let frames = ext::arrow::testing::dummy_video_batch(2, 16, 16);
+
+        let mut tx = context.db.transaction().await.unwrap();
+        for (i, file) in files.iter().enumerate() {
+            let first = 10 * i as i64;
+            let mut columns = frames.columns().to_vec();
+            columns[0] = Arc::new(Int64Array::from(vec![first, first + 1]));
+            let batch = RecordBatch::try_new(frames.schema(), columns).unwrap();
+
+            let mut encoder =
+                rw::InMemoryChunkEncoder::try_new(batch.schema(), types::Format::Video).unwrap();
+            encoder.write(&batch).unwrap();
+            let (data, _, metadata) = encoder.finalize().unwrap();
+
+            let path = data_folder.join(file);
+            let size = data.len() as i64;
+            context.store.write_bytes(&path, data).await.unwrap();
+
+            let record = db::ChunkRecord::new(handle.id(), &path, size, 2)
+                .with_timestamps(&metadata.timestamps);
+            db::chunk_create(&mut tx, &record).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        let timestamps: Vec<i64> = read(&context, &handle, types::Format::Video, None, None)
+            .await
+            .unwrap()
+            .stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(timestamps, [0, 1, 10, 11, 20, 21]);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_compact(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use futures::TryStreamExt;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/test_topic".parse().unwrap(),
+            &session_handle,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        let topic_uuid = handle.uuid().clone();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        // Each batch is written in its own chunk
+        let mut writer = writer(
+            context.clone(),
+            handle,
+            types::Format::Default,
+            schema.clone(),
+            None,
+        )
+        .await
+        .unwrap()
+        .with_chunk_target(0, 0);
+        for start in (0..20).step_by(5) {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(start..start + 5)),
+                    Arc::new(Float64Array::from_iter_values(
+                        (start..start + 5).map(|v| v as f64),
+                    )),
+                ],
+            )
+            .unwrap();
+
+            writer.write(batch).await.unwrap();
+        }
+        writer.finalize().await.unwrap();
+
+        let mut handle = Handle::try_from_uuid(&context, &topic_uuid).await.unwrap();
+        let previous_path = handle.path_in_store.clone().unwrap();
+
+        // Read started before the compaction, streamed after it
+        let pending_read = read(&context, &handle, types::Format::Default, None, None)
+            .await
+            .unwrap();
+
+        let report = compact(&context, &mut handle).await.unwrap();
+        assert_eq!(report.chunks_before, 4);
+        assert_eq!(report.chunks_after, 1);
+
+        let batches: Vec<RecordBatch> = pending_read
+            .stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 20);
+
+        let mut cx = context.db.connection();
+        let record = db::topic_find_by_id(&mut cx, handle.id()).await.unwrap();
+        let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].row_count, 20);
+        assert_eq!(record.info().unwrap().chunks_number, 1);
+
+        let stats = db::column_chunk_numeric_find_by_chunk_id(&mut cx, chunks[0].chunk_id)
+            .await
+            .unwrap();
+        let column = db::column_get_or_create(&mut cx, "value", "dummy")
+            .await
+            .unwrap();
+        let value_stats = stats
+            .iter()
+            .find(|stats| stats.column_id == column.column_id)
+            .unwrap();
+        assert_eq!(value_stats.min_value, 0.0);
+        assert_eq!(value_stats.max_value, 19.0);
+
+        // The topic was moved to a new folder, the previous one is left for fsck
+        let path_in_store = record.path_in_store().unwrap();
+        assert_eq!(
+            handle.path_in_store.as_ref().unwrap().to_string(),
+            path_in_store.to_string()
+        );
+        assert_ne!(path_in_store.to_string(), previous_path.to_string());
+        assert!(
+            context
+                .store
+                .exists(previous_path.path_metadata())
+                .await
+                .unwrap()
+        );
+
+        let report = crate::fsck::check(&context).await.unwrap();
+        assert_eq!(report.issues.len(), 1);
+        let crate::fsck::Issue::OrphanFolder { path } = &report.issues[0] else {
+            panic!("unexpected issue: {}", report.issues[0]);
+        };
+        assert_eq!(path, &previous_path.to_string());
+
+        assert!(
+            crate::fsck::repair(&context, &report.issues[0], types::allow_data_loss())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !context
+                .store
+                .exists(previous_path.path_metadata())
+                .await
+                .unwrap()
+        );
+
+        // Already compacted topics are left untouched
+        let report = compact(&context, &mut handle).await.unwrap();
+        assert_eq!(report.chunks_before, 1);
+        assert_eq!(report.chunks_after, 1);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_compact_video(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::Int64Array;
+        use futures::TryStreamExt;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/test_topic".parse().unwrap(),
+            &session_handle,
+            types::TopicOntologyMetadata::new(
+                types::TopicOntologyProperties {
+                    ontology_tag: "image".to_owned(),
+                    serialization_format: types::Format::Video,
+                    writer_options: Default::default(),
+                },
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        let topic_uuid = handle.uuid().clone();
+
+        let frames = ext::arrow::testing::dummy_video_batch(4, 16, 16);
+
+        // Each batch is written in its own chunk
+        let mut writer = writer(
+            context.clone(),
+            handle,
+            types::Format::Video,
+            frames.schema(),
+            None,
+        )
+        .await
+        .unwrap()
+        .with_chunk_target(0, 0);
+        for i in 0..3 {
+            let mut columns = frames.columns().to_vec();
+            columns[0] = Arc::new(Int64Array::from_iter_values((0..4).map(|t| 10 * i + t)));
+            let batch = RecordBatch::try_new(frames.schema(), columns).unwrap();
+            writer.write(batch).await.unwrap();
+        }
+        writer.finalize().await.unwrap();
+
+        let read_frames = async |handle: &Handle, range| -> Vec<arrow::array::ArrayRef> {
+            let batches: Vec<_> = read(&context, handle, types::Format::Video, None, range)
+                .await
+                .unwrap()
+                .stream()
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            // Schema metadata holds the keyframe index of each chunk, only the rows are compared
+            arrow::compute::concat_batches(&batches[0].schema(), &batches)
+                .unwrap()
+                .columns()
+                .to_vec()
+        };
+
+        let mut handle = Handle::try_from_uuid(&context, &topic_uuid).await.unwrap();
+        let before = read_frames(&handle, None).await;
+        let range = types::TimestampRange::starting_at(12.into());
+        let before_range = read_frames(&handle, Some(range.clone())).await;
+
+        let report = compact(&context, &mut handle).await.unwrap();
+        assert_eq!(report.chunks_before, 3);
+        assert_eq!(report.chunks_after, 1);
+
+        // Frames are not encoded again, so they are bit-identical
+        assert_eq!(read_frames(&handle, None).await, before);
+
+        // The keyframe index of the compacted chunk points to the frames of each former chunk
+        assert_eq!(read_frames(&handle, Some(range)).await, before_range);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn writer_coalesces_batches(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        // (topic name, target size, target rows, expected chunks)
+        let cases = [
+            ("by_size", usize::MAX, 0, 1),
+            ("by_rows", usize::MAX, 10, 2),
+            ("each_batch", 0, 0, 4),
+        ];
+
+        for (name, size_bytes, rows, expected) in cases {
+            let handle = try_create(
+                &context,
+                format!("test_sequence/{name}").parse().unwrap(),
+                &session_handle,
+                dummy_ontology_metadata(),
+            )
+            .await
+            .unwrap();
+            let topic_id = handle.id();
+
+            let mut writer = writer(
+                context.clone(),
+                handle,
+                types::Format::Default,
+                schema.clone(),
+                None,
+            )
+            .await
+            .unwrap()
+            .with_chunk_target(size_bytes, rows);
+            for start in (0..20).step_by(5) {
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from_iter_values(start..start + 5)),
+                        Arc::new(Float64Array::from_iter_values(
+                            (start..start + 5).map(|v| v as f64),
+                        )),
+                    ],
+                )
+                .unwrap();
+
+                writer.write(batch).await.unwrap();
+            }
+            writer.finalize().await.unwrap();
+
+            let mut cx = context.db.connection();
+            let chunks = db::chunk_find_by_topic_id(&mut cx, topic_id).await.unwrap();
+            assert_eq!(chunks.len(), expected, "{name}");
+            assert_eq!(
+                chunks.iter().map(|chunk| chunk.row_count).sum::<i64>(),
+                20,
+                "{name}"
+            );
+        }
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn writer_concurrent_uploads(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+        let batch = |start: i64| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(start..start + 5)),
+                    Arc::new(Float64Array::from_iter_values(
+                        (start..start + 5).map(|v| v as f64),
+                    )),
+                ],
+            )
+            .unwrap()
+        };
+        let new_writer = |locator: types::TopicLocator, token: Option<types::ResumeToken>| {
+            let context = context.clone();
+            let schema = schema.clone();
+            async move {
+                let handle = Handle::try_from_locator(&context, locator).await?;
+                let writer = writer(context, handle, types::Format::Default, schema, token).await?;
+                Result::Ok(writer.with_chunk_target(0, 0))
+            }
+        };
+
+        // Two uploads starting together on an empty topic without token, and on a topic with
+        // some data using the same token
+        for (name, resumed) in [("empty", false), ("resumed", true)] {
+            let locator: types::TopicLocator = format!("test_sequence/{name}").parse().unwrap();
+            let handle = try_create(
+                &context,
+                locator.clone(),
+                &session_handle,
+                dummy_ontology_metadata(),
+            )
+            .await
+            .unwrap();
+
+            let mut token = None;
+            if resumed {
+                // Interrupted upload
+                let mut interrupted = new_writer(locator.clone(), None).await.unwrap();
+                interrupted.write(batch(0)).await.unwrap();
+                drop(interrupted);
+                token = Some(resume_token(&context, &handle).await.unwrap());
+            }
+
+            let mut first = new_writer(locator.clone(), token.clone()).await.unwrap();
+            let mut second = new_writer(locator.clone(), token.clone()).await.unwrap();
+
+            // The last upload claimed the topic
+            assert!(first.write(batch(100)).await.is_err(), "{name}");
+            for start in [100, 105, 110] {
+                second.write(batch(start)).await.unwrap();
+            }
+            assert!(first.finalize().await.is_err(), "{name}");
+            second.finalize().await.unwrap();
+
+            let mut cx = context.db.connection();
+            let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+                .await
+                .unwrap();
+            let expected = if resumed { 4 } else { 3 };
+            assert_eq!(chunks.len(), expected, "{name}");
+
+            let files: std::collections::HashSet<_> = chunks
+                .iter()
+                .map(|chunk| chunk.data_file().to_path_buf())
+                .collect();
+            assert_eq!(files.len(), expected, "{name}: chunks share data files");
+        }
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn writer_timestamp_policies(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use futures::TryStreamExt;
+        use types::TimestampPolicy;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+        let batch = |timestamps: Vec<i64>| {
+            let values = Float64Array::from_iter_values(timestamps.iter().map(|v| *v as f64));
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int64Array::from(timestamps)), Arc::new(values)],
+            )
+            .unwrap()
+        };
+
+        let new_writer = async |name: &str, policy| {
+            let handle = try_create(
+                &context,
+                format!("test_sequence/{name}").parse().unwrap(),
+                &session_handle,
+                dummy_ontology_metadata(),
+            )
+            .await
+            .unwrap();
+            let uuid = handle.uuid().clone();
+            let writer = writer(
+                context.clone(),
+                handle,
+                types::Format::Default,
+                schema.clone(),
+                None,
+            )
+            .await
+            .unwrap()
+            .with_chunk_target(0, 0)
+            .with_timestamp_policy(policy);
+            (uuid, writer)
+        };
+
+        // Unsorted and overlapping chunks are rejected
+        let (_, mut writer) = new_writer("reject", TimestampPolicy::Reject).await;
+        assert!(writer.write(batch(vec![3, 1, 2])).await.is_err());
+        let (_, mut writer) = new_writer("reject_overlap", TimestampPolicy::Reject).await;
+        writer.write(batch(vec![10, 11])).await.unwrap();
+        assert!(writer.write(batch(vec![5, 6])).await.is_err());
+
+        // Chunks are sorted, overlapping chunks are accepted
+        let (uuid, mut writer) = new_writer("sort", TimestampPolicy::Sort).await;
+        writer.write(batch(vec![3, 1, 2])).await.unwrap();
+        writer.write(batch(vec![6, 4, 5])).await.unwrap();
+        assert!(writer.write(batch(vec![-1, 7])).await.is_err());
+        writer.finalize().await.unwrap();
+
+        let handle = Handle::try_from_uuid(&context, &uuid).await.unwrap();
+        let mut cx = context.db.connection();
+        let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await
+            .unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.sorted));
+        assert_eq!(chunks[0].min_timestamp_ns, Some(1));
+        assert_eq!(chunks[0].max_timestamp_ns, Some(3));
+        assert!(chunks_ordered(&chunks));
+
+        let timestamps: Vec<i64> = read(&context, &handle, types::Format::Default, None, None)
+            .await
+            .unwrap()
+            .stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(timestamps, vec![1, 2, 3, 4, 5, 6]);
+
+        // Data is stored as received and the problem is notified once
+        let (uuid, mut writer) = new_writer("warn", TimestampPolicy::Warn).await;
+        writer.write(batch(vec![3, 1, 2])).await.unwrap();
+        writer.write(batch(vec![0, 1])).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        let handle = Handle::try_from_uuid(&context, &uuid).await.unwrap();
+        let chunks = db::chunk_find_by_topic_id(&mut cx, handle.id())
+            .await
+            .unwrap();
+        assert!(!chunks[0].sorted);
+        assert!(!chunks_ordered(&chunks));
+
+        let notifications = notification_list(&context, &handle).await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert!(matches!(
+            notifications[0].notification_type,
+            types::NotificationType::Warning
+        ));
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_read_timestamp_range(pool: sqlx::Pool<db::DatabaseType>) {
+        use arrow::array::{Float64Array, Int64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use futures::TryStreamExt;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_handle = sequence::try_create(&context, "test_sequence".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/test_topic".parse().unwrap(),
+            &session_handle,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        let topic_uuid = handle.uuid().clone();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        // Chunks [0, 5), [5, 10), [10, 15), [15, 20)
+        let mut writer = writer(
+            context.clone(),
+            handle,
+            types::Format::Default,
+            schema.clone(),
+            None,
+        )
+        .await
+        .unwrap()
+        .with_chunk_target(0, 0);
+        for start in (0..20).step_by(5) {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(start..start + 5)),
+                    Arc::new(Float64Array::from_iter_values(
+                        (start..start + 5).map(|v| v as f64),
+                    )),
+                ],
+            )
+            .unwrap();
+            writer.write(batch).await.unwrap();
+        }
+        writer.finalize().await.unwrap();
+
+        let handle = Handle::try_from_uuid(&context, &topic_uuid).await.unwrap();
+
+        let read_range = async |range| -> Vec<i64> {
+            read(&context, &handle, types::Format::Default, None, Some(range))
+                .await
+                .unwrap()
+                .stream()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|batch| {
+                    batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<Int64Array>()
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                })
+                .collect()
+        };
+
+        let timestamps = read_range(types::TimestampRange::between(7.into(), 12.into())).await;
+        assert_eq!(timestamps, vec![7, 8, 9, 10, 11]);
+
+        let timestamps = read_range(types::TimestampRange::starting_at(100.into())).await;
+        assert!(timestamps.is_empty());
+
+        // Chunks not overlapping the requested range are never opened
+        let chunks = {
+            let mut cx = context.db.connection();
+            db::chunk_find_by_topic_id(&mut cx, handle.id())
+                .await
+                .unwrap()
+        };
+        let first = chunks
+            .iter()
+            .find(|chunk| chunk.min_timestamp_ns == Some(0))
+            .unwrap();
+        context.store.delete(first.data_file()).await.unwrap();
+
+        let timestamps = read_range(types::TimestampRange::starting_at(15.into())).await;
+        assert_eq!(timestamps, vec![15, 16, 17, 18, 19]);
+
+        // Chunks with unknown timestamps are always read
+        let mut legacy = db::ChunkRecord::new(0, "data.parquet", 1, 1);
+        assert!(chunk_overlaps(
+            &legacy,
+            &types::TimestampRange::starting_at(100.into())
+        ));
+        legacy.min_timestamp_ns = Some(0);
+        legacy.max_timestamp_ns = Some(10);
+        assert!(!chunk_overlaps(
+            &legacy,
+            &types::TimestampRange::starting_at(100.into())
+        ));
+        assert!(!chunk_overlaps(
+            &legacy,
+            &types::TimestampRange::ending_at(0.into())
+        ));
+    }
 }
@@ -60,6 +60,12 @@ pub enum ActionRequest {
     /// Deletes an unlocked sequence from the system.
     SequenceDelete(requests::ResourceLocator),
 
+    /// Imports an MCAP recording or a sequence bundle available in the store as a new sequence.
+    SequenceImport(requests::SequenceImport),
+
+    /// Exports a sequence as a bundle written in the store.
+    SequenceExport(requests::SequenceExport),
+
     /// Creates a notification associated with a sequence.
     SequenceNotificationCreate(requests::NotificationCreate),
 
@@ -75,6 +81,12 @@ pub enum ActionRequest {
     /// Deletes an unlocked topic from the system.
     TopicDelete(requests::ResourceLocator),
 
+    /// Returns the token required to resume an interrupted topic upload.
+    TopicResume(requests::ResourceLocator),
+
+    /// Merges the chunks of a locked topic into larger chunks.
+    TopicCompact(requests::ResourceLocator),
+
     /// Creates a notification associated with a topic.
     TopicNotificationCreate(requests::NotificationCreate),
 
@@ -120,12 +132,16 @@ impl ActionRequest {
         match value {
             "sequence_create" => parse_action_req!(SequenceCreate, body),
             "sequence_delete" => parse_action_req!(SequenceDelete, body),
+            "sequence_import" => parse_action_req!(SequenceImport, body),
+            "sequence_export" => parse_action_req!(SequenceExport, body),
             "sequence_notification_create" => parse_action_req!(SequenceNotificationCreate, body),
             "sequence_notification_list" => parse_action_req!(SequenceNotificationList, body),
             "sequence_notification_purge" => parse_action_req!(SequenceNotificationPurge, body),
 
             "topic_create" => parse_action_req!(TopicCreate, body),
             "topic_delete" => parse_action_req!(TopicDelete, body),
+            "topic_resume" => parse_action_req!(TopicResume, body),
+            "topic_compact" => parse_action_req!(TopicCompact, body),
             "topic_notification_create" => parse_action_req!(TopicNotificationCreate, body),
             "topic_notification_list" => parse_action_req!(TopicNotificationList, body),
             "topic_notification_purge" => parse_action_req!(TopicNotificationPurge, body),
@@ -145,6 +161,39 @@ impl ActionRequest {
             _ => Err(ActionError::MissingAction(value.to_owned())),
         }
     }
+
+    /// Returns the name of the action, the same used to build it with [`ActionRequest::try_new`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SequenceCreate(_) => "sequence_create",
+            Self::SequenceDelete(_) => "sequence_delete",
+            Self::SequenceImport(_) => "sequence_import",
+            Self::SequenceExport(_) => "sequence_export",
+            Self::SequenceNotificationCreate(_) => "sequence_notification_create",
+            Self::SequenceNotificationList(_) => "sequence_notification_list",
+            Self::SequenceNotificationPurge(_) => "sequence_notification_purge",
+
+            Self::TopicCreate(_) => "topic_create",
+            Self::TopicDelete(_) => "topic_delete",
+            Self::TopicResume(_) => "topic_resume",
+            Self::TopicCompact(_) => "topic_compact",
+            Self::TopicNotificationCreate(_) => "topic_notification_create",
+            Self::TopicNotificationList(_) => "topic_notification_list",
+            Self::TopicNotificationPurge(_) => "topic_notification_purge",
+
+            Self::SessionCreate(_) => "session_create",
+            Self::SessionFinalize(_) => "session_finalize",
+            Self::SessionDelete(_) => "session_delete",
+
+            Self::Query(_) => "query",
+
+            Self::ApiKeyCreate(_) => "api_key_create",
+            Self::ApiKeyStatus(_) => "api_key_status",
+            Self::ApiKeyRevoke(_) => "api_key_revoke",
+
+            Self::Version(_) => "version",
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -152,11 +201,15 @@ impl ActionRequest {
 pub enum ActionResponse {
     SequenceCreate(()),
     SequenceDelete(()),
+    SequenceImport(responses::SequenceImport),
+    SequenceExport(responses::SequenceExport),
     SequenceNotificationCreate(()),
     SequenceNotificationPurge(()),
     SequenceNotificationList(responses::NotificationList),
 
     TopicCreate(responses::ResourceUuid),
+    TopicResume(responses::TopicResume),
+    TopicCompact(responses::TopicCompact),
     TopicNotificationList(responses::NotificationList),
 
     /// Returns the response key associated with the session just created
@@ -190,6 +243,14 @@ impl ActionResponse {
         Self::SequenceDelete(())
     }
 
+    pub fn sequence_import(response: responses::SequenceImport) -> Self {
+        Self::SequenceImport(response)
+    }
+
+    pub fn sequence_export(response: responses::SequenceExport) -> Self {
+        Self::SequenceExport(response)
+    }
+
     pub fn sequence_notification_create() -> Self {
         Self::SequenceNotificationCreate(())
     }
@@ -229,7 +290,7 @@ impl ActionResponse {
 
 #[cfg(test)]
 mod tests {
-    use super::ActionRequest;
+    use super::{ActionRequest, requests};
     use crate::Format;
     use serde::Deserialize;
 
@@ -264,6 +325,7 @@ mod tests {
             assert_eq!(action.session_uuid, "some_uuid");
             assert_eq!(action.serialization_format, Format::Default);
             assert_eq!(action.ontology_tag, "my_sensor");
+            assert!(action.writer_options.is_none());
             let raw_json = action
                 .user_metadata()
                 .expect("Unable to get `user_metadata`");
@@ -277,4 +339,58 @@ mod tests {
             panic!("Wrong action request, expecting `topic_create`")
         }
     }
+
+    #[test]
+    fn request_name() {
+        let action = ActionRequest::try_new("version", b"{}").unwrap();
+        assert_eq!(action.name(), "version");
+
+        let action =
+            ActionRequest::try_new("topic_resume", br#"{"locator": "seq/topic"}"#).unwrap();
+        assert_eq!(action.name(), "topic_resume");
+
+        let action =
+            ActionRequest::try_new("topic_compact", br#"{"locator": "seq/topic"}"#).unwrap();
+        assert_eq!(action.name(), "topic_compact");
+
+        let action = ActionRequest::try_new(
+            "sequence_import",
+            br#"{"locator": "seq", "path": "imports/seq/seq.mcap", "user_metadata": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(action.name(), "sequence_import");
+
+        let action = ActionRequest::try_new("sequence_export", br#"{"locator": "seq"}"#).unwrap();
+        assert_eq!(action.name(), "sequence_export");
+    }
+
+    #[test]
+    fn request_sequence_import_bundle() {
+        let action = ActionRequest::try_new(
+            "sequence_import",
+            br#"{"locator": "seq", "path": "exports/seq/bundle.tar", "format": "bundle", "on_conflict": "replace"}"#,
+        )
+        .unwrap();
+
+        let ActionRequest::SequenceImport(action) = action else {
+            panic!("Wrong action request, expecting `sequence_import`")
+        };
+        assert_eq!(action.format, requests::ImportFormat::Bundle);
+        assert_eq!(action.on_conflict, requests::OnConflict::Replace);
+        assert!(!action.has_user_metadata());
+
+        // Defaults to the import of an MCAP recording
+        let action = ActionRequest::try_new(
+            "sequence_import",
+            br#"{"locator": "seq", "path": "imports/seq/seq.mcap", "user_metadata": {}}"#,
+        )
+        .unwrap();
+
+        let ActionRequest::SequenceImport(action) = action else {
+            panic!("Wrong action request, expecting `sequence_import`")
+        };
+        assert_eq!(action.format, requests::ImportFormat::Mcap);
+        assert_eq!(action.on_conflict, requests::OnConflict::Fail);
+        assert!(action.has_user_metadata());
+    }
 }
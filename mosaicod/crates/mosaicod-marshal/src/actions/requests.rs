@@ -1,5 +1,6 @@
 use super::ActionError;
-use crate::Format;
+use crate::{Format, JsonWriterOptions};
+use mosaicod_core::types;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -22,6 +23,71 @@ impl SequenceCreate {
     }
 }
 
+/// Format of a file imported as a new sequence
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// MCAP recording
+    #[default]
+    Mcap,
+    /// Bundle produced by the export of a sequence
+    Bundle,
+}
+
+/// Policy applied when the imported sequence already exists
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    #[default]
+    Fail,
+    Skip,
+    Replace,
+}
+
+impl From<OnConflict> for types::OnConflict {
+    fn from(value: OnConflict) -> Self {
+        match value {
+            OnConflict::Fail => Self::Fail,
+            OnConflict::Skip => Self::Skip,
+            OnConflict::Replace => Self::Replace,
+        }
+    }
+}
+
+/// Message used to import a recording, available in the store, as a new sequence
+#[derive(Deserialize, Debug)]
+pub struct SequenceImport {
+    pub locator: String,
+    /// Path of the recording, relative to the root of the store. It must be inside the
+    /// `exports` or `imports` folder of a sequence (e.g. `imports/drive/drive.mcap`)
+    pub path: String,
+    #[serde(default)]
+    pub format: ImportFormat,
+    /// Only used when importing bundles
+    #[serde(default)]
+    pub on_conflict: OnConflict,
+    #[serde(default)]
+    user_metadata: serde_json::Value,
+}
+
+impl SequenceImport {
+    pub fn user_metadata(&self) -> Result<String, ActionError> {
+        Ok(serde_json::to_string(&self.user_metadata)?)
+    }
+
+    /// Returns `true` if the request provides user metadata
+    pub fn has_user_metadata(&self) -> bool {
+        !self.user_metadata.is_null()
+    }
+}
+
+/// Message used to export a sequence as a bundle written in the store, the path of the
+/// bundle is chosen by the server and returned in the response
+#[derive(Deserialize, Debug)]
+pub struct SequenceExport {
+    pub locator: String,
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Topic
 // ////////////////////////////////////////////////////////////////////////////
@@ -33,6 +99,9 @@ pub struct TopicCreate {
     pub session_uuid: String,
     pub serialization_format: Format,
     pub ontology_tag: String,
+    /// Options overriding the defaults of the serialization format
+    #[serde(default)]
+    pub writer_options: Option<JsonWriterOptions>,
 
     user_metadata: serde_json::Value,
 }
@@ -95,6 +164,16 @@ pub struct ApiKeyCreate {
     pub permissions: String,
     pub expires_at_ns: Option<i64>,
     pub description: String,
+    /// Optional locator scopes restricting the key permissions
+    #[serde(default)]
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+/// Grants `permission` on the resources whose locator starts with `prefix`
+#[derive(Deserialize, Debug)]
+pub struct ApiKeyScope {
+    pub prefix: String,
+    pub permission: String,
 }
 
 #[derive(Deserialize, Debug)]
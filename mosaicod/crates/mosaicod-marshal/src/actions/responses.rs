@@ -57,6 +57,57 @@ impl From<Vec<types::Notification>> for NotificationList {
     }
 }
 
+// ########
+// Sequence
+// ########
+
+/// Response containing the outcome of a sequence import
+#[derive(Serialize, Debug)]
+pub struct SequenceImport {
+    /// Locators of the topics created
+    pub topics: Vec<String>,
+    pub skipped: Vec<SkippedChannel>,
+}
+
+/// Response containing the outcome of a sequence export
+#[derive(Serialize, Debug)]
+pub struct SequenceExport {
+    /// Path of the bundle, relative to the root of the store (e.g.
+    /// `exports/drive/1743000000000000000.tar`)
+    pub path: String,
+    pub size_bytes: usize,
+}
+
+/// Channel of the imported recording that was not converted
+#[derive(Serialize, Debug)]
+pub struct SkippedChannel {
+    pub channel: String,
+    pub reason: String,
+}
+
+// #####
+// Topic
+// #####
+
+/// Response containing the information required to resume a topic upload
+#[derive(Serialize, Debug)]
+pub struct TopicResume {
+    pub resume_token: String,
+    /// Number of data chunks already persisted
+    pub chunks: usize,
+    /// Last timestamp persisted, if the topic contains some data
+    pub last_timestamp_ns: Option<i64>,
+}
+
+/// Response containing the outcome of a topic compaction
+#[derive(Serialize, Debug)]
+pub struct TopicCompact {
+    pub chunks_before: usize,
+    pub chunks_after: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
 // #####
 // Query
 // #####
@@ -64,6 +115,9 @@ impl From<Vec<types::Notification>> for NotificationList {
 #[derive(Serialize, Debug)]
 pub struct Query {
     pub items: Vec<ResponseQueryItem>,
+    /// Cursor to request the next page, omitted on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 /// Holds topic data: locator and optional timestamp.
@@ -73,6 +127,9 @@ pub struct ResponseQueryItemTopic {
     /// Timestamp range will be omitted from the output if it is None.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp_range: Option<(i64, i64)>,
+    /// Matching time intervals, omitted from the output if they were not requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intervals: Option<Vec<(i64, i64)>>,
 }
 
 impl From<types::TopicLocator> for ResponseQueryItemTopic {
@@ -82,6 +139,12 @@ impl From<types::TopicLocator> for ResponseQueryItemTopic {
             timestamp_range: value
                 .timestamp_range
                 .map(|e| (e.start.into(), e.end.into())),
+            intervals: value.intervals.map(|intervals| {
+                intervals
+                    .into_iter()
+                    .map(|e| (e.start.into(), e.end.into()))
+                    .collect()
+            }),
         }
     }
 }
@@ -106,10 +169,18 @@ impl From<types::SequenceTopicGroupSet> for Query {
         let vec: Vec<types::SequenceTopicGroup> = value.into();
         Self {
             items: vec.into_iter().map(Into::into).collect(),
+            cursor: None,
         }
     }
 }
 
+impl Query {
+    pub fn with_cursor(mut self, cursor: Option<String>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+}
+
 // ####
 // Api Key
 // ####
@@ -127,21 +198,38 @@ impl From<auth::Token> for ApiKeyToken {
     }
 }
 
+#[derive(Serialize, Debug)]
+pub struct ApiKeyScope {
+    pub prefix: String,
+    pub permission: String,
+}
+
+impl From<&auth::ApiKeyScope> for ApiKeyScope {
+    fn from(value: &auth::ApiKeyScope) -> Self {
+        Self {
+            prefix: value.prefix.clone(),
+            permission: value.permission.into(),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct ApiKeyStatus {
     pub api_key_fingerprint: String,
     pub description: String,
     pub created_at_ns: i64,
     pub expires_at_ns: Option<i64>,
+    pub scopes: Vec<ApiKeyScope>,
 }
 
 impl From<&auth::ApiKey> for ApiKeyStatus {
     fn from(value: &auth::ApiKey) -> Self {
         Self {
-            api_key_fingerprint: value.token().fingerprint().to_string(),
+            api_key_fingerprint: value.fingerprint().to_string(),
             description: value.description.clone(),
             created_at_ns: value.created_at.as_i64(),
             expires_at_ns: value.expires_at.map(Into::into),
+            scopes: value.scopes.iter().map(Into::into).collect(),
         }
     }
 }
@@ -200,7 +288,11 @@ mod tests {
                 .with_timestamp_range(types::TimestampRange {
                     start: 1000.into(),
                     end: 1001.into(),
-                }),
+                })
+                .with_intervals(vec![types::TimestampRange {
+                    start: 1000.into(),
+                    end: 1001.into(),
+                }]),
             "/my_sequence/topic2/subtopic"
                 .parse::<types::TopicLocator>()
                 .unwrap(),
@@ -213,7 +305,7 @@ mod tests {
 
         dbg!(body.to_string());
 
-        let response_raw = r#"{"sequence":"my_sequence","topics":[{"locator":"my_sequence/topic1/subtopic","timestamp_range":[1000,1001]},{"locator":"my_sequence/topic2/subtopic"}]}"#;
+        let response_raw = r#"{"sequence":"my_sequence","topics":[{"locator":"my_sequence/topic1/subtopic","timestamp_range":[1000,1001],"intervals":[[1000,1001]]},{"locator":"my_sequence/topic2/subtopic"}]}"#;
 
         let body_serialized = body.to_string();
 
@@ -0,0 +1,167 @@
+//! Manifest of a sequence bundle, the archive used to move sequences between deployments.
+//!
+//! A bundle is a tar archive with the following layout:
+//! ```txt,ignore
+//! manifest.json
+//! metadata.json                               (sequence metadata, if any)
+//! topics/[topic name]/metadata.json
+//! topics/[topic name]/data/data-00000.parquet
+//! ```
+//! The manifest is always the first entry of the archive and contains everything required
+//! to re-create the database records of the sequence (sessions, topics, chunks, column
+//! statistics and notifications).
+
+use super::{JsonMetadataBlob, JsonTopicOntologyProperties};
+use mosaicod_core::types::MetadataError;
+use serde::{Deserialize, Serialize};
+
+type Error = MetadataError;
+
+/// Version of the bundle layout produced by this daemon
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Path of the manifest inside a bundle
+pub const BUNDLE_MANIFEST_FILE: &str = "manifest.json";
+
+/// Path of the sequence metadata inside a bundle
+pub const BUNDLE_SEQUENCE_METADATA_FILE: &str = "metadata.json";
+
+/// Returns the folder containing the files of the topic `name` inside a bundle.
+pub fn bundle_topic_folder(name: &str) -> String {
+    format!("topics/{name}")
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleManifest {
+    pub version: u32,
+    pub sequence: JsonBundleSequence,
+    pub sessions: Vec<JsonBundleSession>,
+    pub topics: Vec<JsonBundleTopic>,
+}
+
+impl TryFrom<&[u8]> for JsonBundleManifest {
+    type Error = Error;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_slice(bytes).map_err(|e| Error::DeserializationError(e.to_string())))?
+    }
+}
+
+impl TryInto<Vec<u8>> for &JsonBundleManifest {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        Ok(serde_json::to_vec_pretty(self).map_err(|e| Error::SerializationError(e.to_string())))?
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleSequence {
+    pub locator: String,
+    pub created_at: i64,
+    pub user_metadata: Option<JsonMetadataBlob>,
+    pub notifications: Vec<JsonBundleNotification>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleSession {
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleTopic {
+    /// Name of the topic relative to the sequence (e.g. `camera/front`)
+    pub name: String,
+    /// Index of the session, in [`JsonBundleManifest::sessions`], that created the topic
+    pub session: usize,
+    pub properties: JsonTopicOntologyProperties,
+    pub user_metadata: Option<JsonMetadataBlob>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub info: Option<JsonBundleTopicInfo>,
+    pub notifications: Vec<JsonBundleNotification>,
+    pub chunks: Vec<JsonBundleChunk>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleTopicInfo {
+    pub chunks_number: u64,
+    pub total_bytes: u64,
+    pub start_index_timestamp: i64,
+    pub end_index_timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleNotification {
+    pub notification_type: String,
+    pub msg: Option<String>,
+    pub created_at: i64,
+}
+
+/// Chunk of a topic.
+///
+/// Size, number of rows, timestamp range, ordering and column statistics describe the data
+/// file, when a bundle is imported they are computed again from the file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonBundleChunk {
+    /// Path of the data file inside the bundle
+    pub file: String,
+    pub size_bytes: i64,
+    pub row_count: i64,
+    /// Timestamp range of the chunk, missing in bundles created by older versions
+    #[serde(default)]
+    pub min_timestamp_ns: Option<i64>,
+    #[serde(default)]
+    pub max_timestamp_ns: Option<i64>,
+    /// `true` if the chunk rows are known to be sorted by timestamp
+    #[serde(default)]
+    pub sorted: bool,
+    pub stats: Vec<JsonBundleColumnStats>,
+}
+
+/// Statistics of a column inside a chunk.
+///
+/// Non-finite numeric bounds can't be represented in JSON, they are stored as `null` and
+/// restored as unbounded values.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JsonBundleColumnStats {
+    Numeric {
+        column: String,
+        min: Option<f64>,
+        max: Option<f64>,
+        has_null: bool,
+        has_nan: bool,
+    },
+    Textual {
+        column: String,
+        min: String,
+        max: String,
+        has_null: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_stats() {
+        let stats = JsonBundleColumnStats::Numeric {
+            column: "value".to_owned(),
+            min: None,
+            max: Some(1.5),
+            has_null: false,
+            has_nan: true,
+        };
+
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(
+            json,
+            r#"{"kind":"numeric","column":"value","min":null,"max":1.5,"has_null":false,"has_nan":true}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<JsonBundleColumnStats>(&json).unwrap(),
+            stats
+        );
+    }
+}
@@ -13,10 +13,16 @@ struct GetFlightInfoCmd {
     resource_locator: String,
     timestamp_ns_start: Option<i64>,
     timestamp_ns_end: Option<i64>,
+    fields: Option<Vec<String>>,
+    ontology: Option<serde_json::Value>,
+    align: Option<AlignCmd>,
+    export: Option<ExportCmd>,
 }
 
-impl From<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
-    fn from(value: GetFlightInfoCmd) -> Self {
+impl TryFrom<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
+    type Error = super::Error;
+
+    fn try_from(value: GetFlightInfoCmd) -> Result<Self, Error> {
         let up = value
             .timestamp_ns_end
             .map_or_else(types::Timestamp::unbounded_pos, |e| e.into());
@@ -30,18 +36,192 @@ impl From<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
             ts_range = Some(types::TimestampRange::between(lb, up));
         }
 
-        types::flight::GetFlightInfoCmd {
+        if value.align.is_some() && (value.fields.is_some() || value.ontology.is_some()) {
+            return Err(Error::DeserializationError(
+                "`align` can't be combined with `fields` or `ontology`".to_owned(),
+            ));
+        }
+
+        if value.export.is_some()
+            && (value.fields.is_some() || value.ontology.is_some() || value.align.is_some())
+        {
+            return Err(Error::DeserializationError(
+                "`export` can't be combined with `fields`, `ontology` or `align`".to_owned(),
+            ));
+        }
+
+        Ok(types::flight::GetFlightInfoCmd {
             resource_locator: value.resource_locator,
             timestamp_range: ts_range,
-        }
+            fields: value.fields,
+            ontology_filter: value.ontology.map(|v| v.to_string()),
+            align: value.align.map(TryInto::try_into).transpose()?,
+            export: value.export.map(TryInto::try_into).transpose()?,
+        })
     }
 }
 
 /// Convert a raw flight command into a [`GetFlightInfoCmd`]
 pub fn get_flight_info_cmd(v: &[u8]) -> Result<types::flight::GetFlightInfoCmd, super::Error> {
-    serde_json::from_slice::<GetFlightInfoCmd>(v)
-        .map_err(|e| super::Error::DeserializationError(e.to_string()))
-        .map(|v| v.into())
+    let cmd: types::flight::GetFlightInfoCmd = serde_json::from_slice::<GetFlightInfoCmd>(v)
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))?
+        .try_into()?;
+
+    // Fail early on malformed ontology filters, before they are embedded in tickets
+    if let Some(filter) = &cmd.ontology_filter {
+        super::ontology_filter_from_string(filter)?;
+    }
+
+    Ok(cmd)
+}
+
+/// Non-exported type for deserialize [`types::flight::QueryCmd`]
+#[derive(Deserialize)]
+struct QueryCmd {
+    resource_locator: Option<String>,
+    query: Option<serde_json::Value>,
+}
+
+/// Convert a raw flight command into a [`types::flight::QueryCmd`], returns [`None`] if the
+/// command does not request a query.
+pub fn query_cmd(v: &[u8]) -> Result<Option<types::flight::QueryCmd>, super::Error> {
+    let cmd = serde_json::from_slice::<QueryCmd>(v)
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))?;
+
+    let Some(query) = cmd.query else {
+        return Ok(None);
+    };
+
+    if cmd.resource_locator.is_some() {
+        return Err(Error::DeserializationError(
+            "`query` can't be combined with `resource_locator`".to_owned(),
+        ));
+    }
+
+    // Fail early on malformed queries, before they are embedded in tickets
+    let filter = super::query_filter_from_serde_value(query.clone())?;
+
+    if filter.page.limit.is_some() || filter.page.after.is_some() {
+        return Err(Error::DeserializationError(
+            "streamed queries don't support `limit` and `cursor`".to_owned(),
+        ));
+    }
+
+    Ok(Some(types::flight::QueryCmd {
+        query: query.to_string(),
+    }))
+}
+
+/// Fill policy used when aligning topics
+#[derive(Deserialize, Encode, Decode, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum FillPolicy {
+    Nearest,
+    Previous,
+    Linear,
+}
+
+impl From<FillPolicy> for types::FillPolicy {
+    fn from(value: FillPolicy) -> Self {
+        match value {
+            FillPolicy::Nearest => Self::Nearest,
+            FillPolicy::Previous => Self::Previous,
+            FillPolicy::Linear => Self::Linear,
+        }
+    }
+}
+
+impl From<types::FillPolicy> for FillPolicy {
+    fn from(value: types::FillPolicy) -> Self {
+        match value {
+            types::FillPolicy::Nearest => Self::Nearest,
+            types::FillPolicy::Previous => Self::Previous,
+            types::FillPolicy::Linear => Self::Linear,
+        }
+    }
+}
+
+/// Non-exported type for deserialize [`types::flight::AlignCmd`]
+#[derive(Deserialize)]
+struct AlignCmd {
+    topics: Vec<String>,
+    frequency_hz: f64,
+    fill: FillPolicy,
+}
+
+impl TryFrom<AlignCmd> for types::flight::AlignCmd {
+    type Error = super::Error;
+
+    fn try_from(value: AlignCmd) -> Result<Self, Error> {
+        if value.topics.is_empty() {
+            return Err(Error::DeserializationError(
+                "`align` requires at least a topic".to_owned(),
+            ));
+        }
+
+        // Frequencies above 1GHz would produce a grid step shorter than a nanosecond
+        let period_ns = 1e9 / value.frequency_hz;
+        if !period_ns.is_finite() || period_ns < 1.0 {
+            return Err(Error::DeserializationError(format!(
+                "invalid alignment frequency `{}`, expected a value in (0, 1e9] Hz",
+                value.frequency_hz
+            )));
+        }
+
+        Ok(Self {
+            topics: parse_topic_locators(value.topics)?,
+            period_ns: period_ns.round() as i64,
+            fill: value.fill.into(),
+        })
+    }
+}
+
+/// File format used when exporting sequences
+#[derive(Deserialize, Encode, Decode, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    Mcap,
+}
+
+impl From<ExportFormat> for types::flight::ExportFormat {
+    fn from(value: ExportFormat) -> Self {
+        match value {
+            ExportFormat::Mcap => Self::Mcap,
+        }
+    }
+}
+
+impl From<types::flight::ExportFormat> for ExportFormat {
+    fn from(value: types::flight::ExportFormat) -> Self {
+        match value {
+            types::flight::ExportFormat::Mcap => Self::Mcap,
+        }
+    }
+}
+
+/// Non-exported type for deserialize [`types::flight::ExportCmd`]
+#[derive(Deserialize)]
+struct ExportCmd {
+    format: ExportFormat,
+    topics: Option<Vec<String>>,
+}
+
+impl TryFrom<ExportCmd> for types::flight::ExportCmd {
+    type Error = super::Error;
+
+    fn try_from(value: ExportCmd) -> Result<Self, Error> {
+        Ok(Self {
+            format: value.format.into(),
+            topics: value.topics.map(parse_topic_locators).transpose()?,
+        })
+    }
+}
+
+fn parse_topic_locators(locators: Vec<String>) -> Result<Vec<TopicLocator>, Error> {
+    locators
+        .into_iter()
+        .map(|x| x.parse().map_err(|_| Error::DeserializationError(x)))
+        .collect()
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -51,21 +231,61 @@ pub fn get_flight_info_cmd(v: &[u8]) -> Result<types::flight::GetFlightInfoCmd,
 struct DoPutCmd {
     resource_locator: String,
     topic_uuid: String,
+    resume_token: Option<String>,
+    #[serde(default = "default_finalize")]
+    finalize: bool,
+    timestamp_policy: Option<TimestampPolicy>,
+}
+
+fn default_finalize() -> bool {
+    true
+}
+
+/// Policy applied to invalid timestamps of uploaded data
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TimestampPolicy {
+    Reject,
+    Sort,
+    Warn,
+}
+
+impl From<TimestampPolicy> for types::TimestampPolicy {
+    fn from(value: TimestampPolicy) -> Self {
+        match value {
+            TimestampPolicy::Reject => Self::Reject,
+            TimestampPolicy::Sort => Self::Sort,
+            TimestampPolicy::Warn => Self::Warn,
+        }
+    }
 }
 
-impl From<DoPutCmd> for types::flight::DoPutCmd {
-    fn from(value: DoPutCmd) -> Self {
-        types::flight::DoPutCmd {
+impl TryFrom<DoPutCmd> for types::flight::DoPutCmd {
+    type Error = Error;
+
+    fn try_from(value: DoPutCmd) -> Result<Self, Self::Error> {
+        let resume_token = value
+            .resume_token
+            .map(|t| {
+                t.parse::<types::ResumeToken>()
+                    .map_err(|_| Error::DeserializationError(t))
+            })
+            .transpose()?;
+
+        Ok(types::flight::DoPutCmd {
             resource_locator: value.resource_locator,
             key: value.topic_uuid,
-        }
+            resume_token,
+            finalize: value.finalize,
+            timestamp_policy: value.timestamp_policy.map(Into::into),
+        })
     }
 }
 
 pub fn do_put_cmd(v: &[u8]) -> Result<types::flight::DoPutCmd, super::Error> {
     serde_json::from_slice::<DoPutCmd>(v)
-        .map_err(|e| super::Error::DeserializationError(e.to_string()))
-        .map(|v| v.into())
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))?
+        .try_into()
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -163,23 +383,42 @@ impl TryFrom<SessionAppMetadata> for types::SessionMetadata {
             uuid,
             created_at: value.created_at_ns.into(),
             completed_at: value.completed_at_ns.map(Into::into),
-            topics: value
-                .topics
-                .into_iter()
-                .map(|x| x.parse().map_err(|_| Error::DeserializationError(x)))
-                .collect::<Result<Vec<TopicLocator>, _>>()?,
+            topics: parse_topic_locators(value.topics)?,
         })
     }
 }
 
 // ////////////////////////////////////////////////////////////////////////////
-// TICKET TOPIC
+// TICKETS
 // ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+enum Ticket {
+    Topic(TicketTopic),
+    Aligned(TicketAligned),
+    Export(TicketExport),
+    Query(TicketQuery),
+}
+
+/// Builds a timestamp range out of optional bounds, returns [`None`] if both bounds are missing
+fn timestamp_range_from_ns(
+    start_ns: Option<i64>,
+    end_ns: Option<i64>,
+) -> Option<types::TimestampRange> {
+    let ub: types::Timestamp = end_ns.map_or_else(types::Timestamp::unbounded_pos, |v| v.into());
+    let lb: types::Timestamp = start_ns.map_or_else(types::Timestamp::unbounded_neg, |v| v.into());
+
+    let ts = types::TimestampRange::between(lb, ub);
+
+    if ts.is_unbounded() { None } else { Some(ts) }
+}
+
 #[derive(Encode, Decode)]
 struct TicketTopic {
     locator: String,
     timestamp_ns_start: Option<i64>,
     timestamp_ns_end: Option<i64>,
+    fields: Option<Vec<String>>,
+    ontology_filter: Option<String>,
 }
 
 impl From<types::flight::TicketTopic> for TicketTopic {
@@ -188,6 +427,8 @@ impl From<types::flight::TicketTopic> for TicketTopic {
             locator: value.locator.into(),
             timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
             timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+            fields: value.fields,
+            ontology_filter: value.ontology_filter,
         }
     }
 }
@@ -196,41 +437,172 @@ impl TryFrom<TicketTopic> for types::flight::TicketTopic {
     type Error = super::Error;
 
     fn try_from(value: TicketTopic) -> Result<Self, Error> {
-        let ub: types::Timestamp = value
-            .timestamp_ns_end
-            .map_or_else(types::Timestamp::unbounded_pos, |v| v.into());
-        let lb: types::Timestamp = value
-            .timestamp_ns_start
-            .map_or_else(types::Timestamp::unbounded_neg, |v| v.into());
-
-        let ts = types::TimestampRange::between(lb, ub);
-
-        let timestamp_range = if ts.is_unbounded() { None } else { Some(ts) };
-
         Ok(Self {
             locator: value
                 .locator
                 .parse::<types::TopicLocator>()
                 .map_err(|_| Error::DeserializationError(value.locator))?,
-            timestamp_range,
+            timestamp_range: timestamp_range_from_ns(
+                value.timestamp_ns_start,
+                value.timestamp_ns_end,
+            ),
+            fields: value.fields,
+            ontology_filter: value.ontology_filter,
         })
     }
 }
 
-pub fn ticket_topic_to_binary(tt: types::flight::TicketTopic) -> Result<Vec<u8>, super::Error> {
-    let tt: TicketTopic = tt.into();
+#[derive(Encode, Decode)]
+struct TicketAligned {
+    topics: Vec<String>,
+    period_ns: i64,
+    fill: FillPolicy,
+    timestamp_ns_start: Option<i64>,
+    timestamp_ns_end: Option<i64>,
+}
+
+impl From<types::flight::TicketAligned> for TicketAligned {
+    fn from(value: types::flight::TicketAligned) -> Self {
+        Self {
+            topics: value.topics.into_iter().map(Into::into).collect(),
+            period_ns: value.period_ns,
+            fill: value.fill.into(),
+            timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
+            timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+        }
+    }
+}
+
+impl TryFrom<TicketAligned> for types::flight::TicketAligned {
+    type Error = super::Error;
+
+    fn try_from(value: TicketAligned) -> Result<Self, Error> {
+        // Tickets are provided by clients, so they are checked as the alignment requests
+        if value.period_ns <= 0 {
+            return Err(Error::DeserializationError(format!(
+                "invalid alignment period `{}`ns, expected a positive value",
+                value.period_ns
+            )));
+        }
+
+        let topics = parse_topic_locators(value.topics)?;
+
+        let sequence = |topic: &types::TopicLocator| topic.split('/').next().map(str::to_owned);
+        let Some(first) = topics.first().map(sequence) else {
+            return Err(Error::DeserializationError(
+                "aligned ticket requires at least a topic".to_owned(),
+            ));
+        };
+        if topics.iter().any(|topic| sequence(topic) != first) {
+            return Err(Error::DeserializationError(
+                "aligned topics must belong to the same sequence".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            topics,
+            period_ns: value.period_ns,
+            fill: value.fill.into(),
+            timestamp_range: timestamp_range_from_ns(
+                value.timestamp_ns_start,
+                value.timestamp_ns_end,
+            ),
+        })
+    }
+}
+
+#[derive(Encode, Decode)]
+struct TicketExport {
+    sequence: String,
+    format: ExportFormat,
+    topics: Vec<String>,
+    timestamp_ns_start: Option<i64>,
+    timestamp_ns_end: Option<i64>,
+}
+
+impl From<types::flight::TicketExport> for TicketExport {
+    fn from(value: types::flight::TicketExport) -> Self {
+        Self {
+            sequence: value.sequence.into(),
+            format: value.format.into(),
+            topics: value.topics.into_iter().map(Into::into).collect(),
+            timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
+            timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+        }
+    }
+}
+
+impl TryFrom<TicketExport> for types::flight::TicketExport {
+    type Error = super::Error;
+
+    fn try_from(value: TicketExport) -> Result<Self, Error> {
+        Ok(Self {
+            sequence: value
+                .sequence
+                .parse()
+                .map_err(|_| Error::DeserializationError(value.sequence))?,
+            format: value.format.into(),
+            topics: parse_topic_locators(value.topics)?,
+            timestamp_range: timestamp_range_from_ns(
+                value.timestamp_ns_start,
+                value.timestamp_ns_end,
+            ),
+        })
+    }
+}
+
+#[derive(Encode, Decode)]
+struct TicketQuery {
+    query: String,
+}
+
+impl From<types::flight::TicketQuery> for TicketQuery {
+    fn from(value: types::flight::TicketQuery) -> Self {
+        Self { query: value.query }
+    }
+}
+
+impl From<TicketQuery> for types::flight::TicketQuery {
+    fn from(value: TicketQuery) -> Self {
+        Self { query: value.query }
+    }
+}
+
+fn ticket_to_binary(ticket: Ticket) -> Result<Vec<u8>, super::Error> {
     let config = bincode::config::standard();
 
-    bincode::encode_to_vec(tt, config).map_err(|e| super::Error::SerializationError(e.to_string()))
+    bincode::encode_to_vec(ticket, config)
+        .map_err(|e| super::Error::SerializationError(e.to_string()))
+}
+
+pub fn ticket_topic_to_binary(tt: types::flight::TicketTopic) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Topic(tt.into()))
+}
+
+pub fn ticket_aligned_to_binary(ta: types::flight::TicketAligned) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Aligned(ta.into()))
+}
+
+pub fn ticket_export_to_binary(te: types::flight::TicketExport) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Export(te.into()))
+}
+
+pub fn ticket_query_to_binary(tq: types::flight::TicketQuery) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Query(tq.into()))
 }
 
-pub fn ticket_topic_from_binary(v: &[u8]) -> Result<types::flight::TicketTopic, super::Error> {
+pub fn ticket_from_binary(v: &[u8]) -> Result<types::flight::Ticket, super::Error> {
     let config = bincode::config::standard();
 
-    let (ticket, _): (TicketTopic, usize) = bincode::decode_from_slice(v, config)
+    let (ticket, _): (Ticket, usize) = bincode::decode_from_slice(v, config)
         .map_err(|e| super::Error::DeserializationError(e.to_string()))?;
 
-    ticket.try_into()
+    Ok(match ticket {
+        Ticket::Topic(tt) => types::flight::Ticket::Topic(tt.try_into()?),
+        Ticket::Aligned(ta) => types::flight::Ticket::Aligned(ta.try_into()?),
+        Ticket::Export(te) => types::flight::Ticket::Export(te.try_into()?),
+        Ticket::Query(tq) => types::flight::Ticket::Query(tq.into()),
+    })
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -334,13 +706,17 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: Some(100000),
             timestamp_ns_end: Some(110000),
+            fields: None,
+            ontology: None,
+            align: None,
+            export: None,
         };
 
         let name = src.resource_locator.clone();
         let start = src.timestamp_ns_start.unwrap();
         let end = src.timestamp_ns_end.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), start);
@@ -355,12 +731,16 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: Some(100000),
             timestamp_ns_end: None,
+            fields: None,
+            ontology: None,
+            align: None,
+            export: None,
         };
 
         let name = src.resource_locator.clone();
         let start = src.timestamp_ns_start.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), start);
@@ -375,12 +755,16 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: None,
             timestamp_ns_end: Some(110000),
+            fields: None,
+            ontology: None,
+            align: None,
+            export: None,
         };
 
         let name = src.resource_locator.clone();
         let end = src.timestamp_ns_end.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert!(dest.timestamp_range.as_ref().unwrap().start.is_unbounded());
@@ -395,12 +779,250 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: None,
             timestamp_ns_end: None,
+            fields: None,
+            ontology: None,
+            align: None,
+            export: None,
         };
 
         let name = src.resource_locator.clone();
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert!(dest.timestamp_range.is_none());
     }
+
+    /// Check that the projected fields are correctly parsed from a raw flight command.
+    #[test]
+    fn get_flight_info_cmd_with_fields() {
+        let raw =
+            br#"{"resource_locator": "test_sequence/topic/a", "fields": ["pose.x", "pose.y"]}"#;
+
+        let dest = super::get_flight_info_cmd(raw).unwrap();
+
+        assert_eq!(dest.resource_locator, "test_sequence/topic/a");
+        assert!(dest.timestamp_range.is_none());
+        assert_eq!(dest.fields.unwrap(), vec!["pose.x", "pose.y"]);
+
+        let raw = br#"{"resource_locator": "test_sequence/topic/a"}"#;
+        let dest = super::get_flight_info_cmd(raw).unwrap();
+        assert!(dest.fields.is_none());
+    }
+
+    /// Check that a [`types::flight::TicketTopic`] survives the binary round-trip.
+    #[test]
+    fn ticket_topic_binary_roundtrip() {
+        let src = types::flight::TicketTopic {
+            locator: "test_sequence/topic/a".parse().unwrap(),
+            timestamp_range: Some(types::TimestampRange::between(10.into(), 20.into())),
+            fields: Some(vec!["pose.x".to_owned()]),
+            ontology_filter: Some(r#"{"imu.acc.z":{"$gt":9.8}}"#.to_owned()),
+        };
+
+        let bin = super::ticket_topic_to_binary(src).unwrap();
+        let types::flight::Ticket::Topic(dest) = super::ticket_from_binary(&bin).unwrap() else {
+            panic!("expected a topic ticket");
+        };
+
+        assert_eq!(dest.locator.to_string(), "test_sequence/topic/a");
+        assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), 10);
+        assert_eq!(dest.timestamp_range.as_ref().unwrap().end.as_i64(), 20);
+        assert_eq!(dest.fields.unwrap(), vec!["pose.x"]);
+        assert_eq!(
+            dest.ontology_filter.unwrap(),
+            r#"{"imu.acc.z":{"$gt":9.8}}"#
+        );
+    }
+
+    /// Check that the ontology filter is carried as raw JSON and validated on parsing.
+    #[test]
+    fn get_flight_info_cmd_with_ontology_filter() {
+        let raw = br#"{"resource_locator": "seq/topic", "ontology": {"imu.acc.z": {"$gt": 9.8}}}"#;
+        let dest = super::get_flight_info_cmd(raw).unwrap();
+
+        let group = crate::ontology_filter_from_string(&dest.ontology_filter.unwrap()).unwrap();
+        assert_eq!(group.ontology_tag(), Some("imu"));
+
+        let raw = br#"{"resource_locator": "seq/topic", "ontology": {"imu.acc.z": {"$foo": 9.8}}}"#;
+        assert!(super::get_flight_info_cmd(raw).is_err());
+    }
+
+    /// Check the parsing of an alignment request and the related ticket round-trip.
+    #[test]
+    fn get_flight_info_cmd_with_align() {
+        let raw = br#"{
+            "resource_locator": "seq",
+            "timestamp_ns_start": 100,
+            "align": {"topics": ["seq/imu", "seq/gps"], "frequency_hz": 200, "fill": "linear"}
+        }"#;
+        let cmd = super::get_flight_info_cmd(raw).unwrap();
+        let align = cmd.align.unwrap();
+
+        assert_eq!(align.topics.len(), 2);
+        assert_eq!(align.period_ns, 5_000_000);
+        assert_eq!(align.fill, types::FillPolicy::Linear);
+
+        let src = types::flight::TicketAligned {
+            topics: align.topics,
+            period_ns: align.period_ns,
+            fill: align.fill,
+            timestamp_range: cmd.timestamp_range,
+        };
+
+        let bin = super::ticket_aligned_to_binary(src).unwrap();
+        let types::flight::Ticket::Aligned(dest) = super::ticket_from_binary(&bin).unwrap() else {
+            panic!("expected an aligned ticket");
+        };
+
+        assert_eq!(dest.topics[0].to_string(), "seq/imu");
+        assert_eq!(dest.topics[1].to_string(), "seq/gps");
+        assert_eq!(dest.period_ns, 5_000_000);
+        assert_eq!(dest.fill, types::FillPolicy::Linear);
+        assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), 100);
+        assert!(dest.timestamp_range.as_ref().unwrap().end.is_unbounded());
+    }
+
+    /// Check that malformed alignment requests are rejected.
+    #[test]
+    fn get_flight_info_cmd_with_bad_align() {
+        let bad: [&[u8]; 4] = [
+            br#"{"resource_locator": "seq", "align": {"topics": [], "frequency_hz": 10, "fill": "nearest"}}"#,
+            br#"{"resource_locator": "seq", "align": {"topics": ["seq/a"], "frequency_hz": 0, "fill": "nearest"}}"#,
+            br#"{"resource_locator": "seq", "align": {"topics": ["seq/a"], "frequency_hz": 10, "fill": "cubic"}}"#,
+            br#"{"resource_locator": "seq", "fields": ["x"], "align": {"topics": ["seq/a"], "frequency_hz": 10, "fill": "nearest"}}"#,
+        ];
+
+        for raw in bad {
+            assert!(super::get_flight_info_cmd(raw).is_err());
+        }
+    }
+
+    /// Check that forged aligned tickets are rejected.
+    #[test]
+    fn ticket_aligned_bad() {
+        let ticket = |topics: &[&str], period_ns: i64| super::TicketAligned {
+            topics: topics.iter().map(|t| (*t).to_owned()).collect(),
+            period_ns,
+            fill: super::FillPolicy::Nearest,
+            timestamp_ns_start: None,
+            timestamp_ns_end: None,
+        };
+
+        let good = ticket(&["seq/imu", "seq/gps"], 1000);
+        assert!(types::flight::TicketAligned::try_from(good).is_ok());
+
+        let bad = [
+            ticket(&[], 1000),
+            ticket(&["seq/imu", "other/gps"], 1000),
+            ticket(&["seq/imu"], 0),
+            ticket(&["seq/imu"], -1000),
+        ];
+        for ticket in bad {
+            let bin = super::ticket_to_binary(super::Ticket::Aligned(ticket)).unwrap();
+            assert!(super::ticket_from_binary(&bin).is_err());
+        }
+    }
+
+    /// Check the parsing of an export request and the related ticket round-trip.
+    #[test]
+    fn get_flight_info_cmd_with_export() {
+        let raw = br#"{
+            "resource_locator": "seq",
+            "timestamp_ns_end": 100,
+            "export": {"format": "mcap", "topics": ["seq/imu"]}
+        }"#;
+        let cmd = super::get_flight_info_cmd(raw).unwrap();
+        let export = cmd.export.unwrap();
+
+        assert_eq!(export.format, types::flight::ExportFormat::Mcap);
+        assert_eq!(export.topics.as_ref().unwrap()[0].to_string(), "seq/imu");
+
+        let src = types::flight::TicketExport {
+            sequence: "seq".parse().unwrap(),
+            format: export.format,
+            topics: export.topics.unwrap(),
+            timestamp_range: cmd.timestamp_range,
+        };
+
+        let bin = super::ticket_export_to_binary(src).unwrap();
+        let types::flight::Ticket::Export(dest) = super::ticket_from_binary(&bin).unwrap() else {
+            panic!("expected an export ticket");
+        };
+
+        assert_eq!(dest.sequence.to_string(), "seq");
+        assert_eq!(dest.topics[0].to_string(), "seq/imu");
+        assert!(dest.timestamp_range.as_ref().unwrap().start.is_unbounded());
+        assert_eq!(dest.timestamp_range.as_ref().unwrap().end.as_i64(), 100);
+
+        let bad: [&[u8]; 2] = [
+            br#"{"resource_locator": "seq", "export": {"format": "rosbag"}}"#,
+            br#"{"resource_locator": "seq", "fields": ["x"], "export": {"format": "mcap"}}"#,
+        ];
+
+        for raw in bad {
+            assert!(super::get_flight_info_cmd(raw).is_err());
+        }
+    }
+
+    /// Check the parsing of a query request and the related ticket round-trip.
+    #[test]
+    fn query_cmd_with_ticket() {
+        let raw = br#"{"resource_locator": "seq/topic"}"#;
+        assert!(super::query_cmd(raw).unwrap().is_none());
+
+        let raw = br#"{"query": {"ontology": {"imu.acc.z": {"$gt": 9.8}}}}"#;
+        let cmd = super::query_cmd(raw).unwrap().unwrap();
+
+        let filter = crate::query_filter_from_string(&cmd.query).unwrap();
+        assert!(filter.ontology.is_some());
+
+        let bin =
+            super::ticket_query_to_binary(types::flight::TicketQuery { query: cmd.query }).unwrap();
+        let types::flight::Ticket::Query(dest) = super::ticket_from_binary(&bin).unwrap() else {
+            panic!("expected a query ticket");
+        };
+        assert!(crate::query_filter_from_string(&dest.query).is_ok());
+
+        let bad: [&[u8]; 3] = [
+            br#"{"resource_locator": "seq", "query": {}}"#,
+            br#"{"query": {"ontology": {"imu.acc.z": {"$foo": 9.8}}}}"#,
+            br#"{"query": {"topic": {"locator": {"$match": "imu"}}, "limit": 10}}"#,
+        ];
+
+        for raw in bad {
+            assert!(super::query_cmd(raw).is_err());
+        }
+    }
+
+    /// Check resume token and finalize flag parsing in do_put commands.
+    #[test]
+    fn do_put_cmd_with_resume_token() {
+        let raw = br#"{"resource_locator": "seq/topic", "topic_uuid": "4d1c3c5e-4a4b-4b8c-9a0e-2f8f6d3b1a10"}"#;
+        let cmd = super::do_put_cmd(raw).unwrap();
+        assert!(cmd.resume_token.is_none());
+        assert!(cmd.finalize);
+        assert!(cmd.timestamp_policy.is_none());
+
+        let raw = br#"{
+            "resource_locator": "seq/topic",
+            "topic_uuid": "4d1c3c5e-4a4b-4b8c-9a0e-2f8f6d3b1a10",
+            "resume_token": "4d1c3c5e-4a4b-4b8c-9a0e-2f8f6d3b1a10:3",
+            "finalize": false
+        }"#;
+        let cmd = super::do_put_cmd(raw).unwrap();
+        assert_eq!(cmd.resume_token.unwrap().chunks(), 3);
+        assert!(!cmd.finalize);
+
+        let raw = br#"{"resource_locator": "seq/topic", "topic_uuid": "x", "resume_token": "3"}"#;
+        assert!(super::do_put_cmd(raw).is_err());
+
+        let raw =
+            br#"{"resource_locator": "seq/topic", "topic_uuid": "x", "timestamp_policy": "sort"}"#;
+        let cmd = super::do_put_cmd(raw).unwrap();
+        assert_eq!(cmd.timestamp_policy, Some(types::TimestampPolicy::Sort));
+
+        let raw =
+            br#"{"resource_locator": "seq/topic", "topic_uuid": "x", "timestamp_policy": "drop"}"#;
+        assert!(super::do_put_cmd(raw).is_err());
+    }
 }
@@ -7,6 +7,9 @@ pub enum Format {
     Default,
     Ragged,
     Image,
+    PointCloud,
+    Video,
+    ArrowIpc,
 }
 
 impl From<types::Format> for Format {
@@ -15,6 +18,9 @@ impl From<types::Format> for Format {
             types::Format::Default => Self::Default,
             types::Format::Ragged => Self::Ragged,
             types::Format::Image => Self::Image,
+            types::Format::PointCloud => Self::PointCloud,
+            types::Format::Video => Self::Video,
+            types::Format::ArrowIpc => Self::ArrowIpc,
         }
     }
 }
@@ -25,6 +31,9 @@ impl From<Format> for types::Format {
             Format::Default => types::Format::Default,
             Format::Ragged => types::Format::Ragged,
             Format::Image => types::Format::Image,
+            Format::PointCloud => types::Format::PointCloud,
+            Format::Video => types::Format::Video,
+            Format::ArrowIpc => types::Format::ArrowIpc,
         }
     }
 }
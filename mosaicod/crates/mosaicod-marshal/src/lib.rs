@@ -6,6 +6,12 @@ pub use metadata::*;
 mod format;
 pub use format::*;
 
+mod writer_options;
+pub use writer_options::*;
+
+mod bundle;
+pub use bundle::*;
+
 mod actions;
 pub use actions::*;
 
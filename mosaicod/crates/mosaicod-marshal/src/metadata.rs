@@ -1,4 +1,4 @@
-use super::Format;
+use super::{Format, JsonWriterOptions};
 use mosaicod_core::types::{self, MetadataBlob, MetadataError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -76,6 +76,8 @@ impl JsonSequenceMetadata {
 pub struct JsonTopicOntologyProperties {
     pub serialization_format: Format,
     pub ontology_tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writer_options: Option<JsonWriterOptions>,
 }
 
 impl From<JsonTopicOntologyProperties> for types::TopicOntologyProperties {
@@ -83,6 +85,7 @@ impl From<JsonTopicOntologyProperties> for types::TopicOntologyProperties {
         Self {
             ontology_tag: value.ontology_tag,
             serialization_format: value.serialization_format.into(),
+            writer_options: value.writer_options.map(Into::into).unwrap_or_default(),
         }
     }
 }
@@ -92,6 +95,7 @@ impl From<types::TopicOntologyProperties> for JsonTopicOntologyProperties {
         Self {
             ontology_tag: value.ontology_tag,
             serialization_format: value.serialization_format.into(),
+            writer_options: (!value.writer_options.is_empty()).then(|| value.writer_options.into()),
         }
     }
 }
@@ -158,20 +158,107 @@ impl TryInto<query::Op<query::Value>> for Op {
     }
 }
 
+/// Logical operators, flattened inside each filter domain.
+///
+/// E.g. `{"$or": [{"locator": {"$match": "front"}}, {"locator": {"$match": "rear"}}]}`
+#[derive(Debug, Deserialize)]
+struct Logical<T> {
+    #[serde(rename = "$and")]
+    and: Option<Vec<T>>,
+    #[serde(rename = "$or")]
+    or: Option<Vec<T>>,
+    #[serde(rename = "$not")]
+    not: Option<Box<T>>,
+}
+
+impl<T> Logical<T> {
+    /// Converts the logical operators into query logical nodes, each operand
+    /// is converted using `f`.
+    fn try_into_query<U, F>(self, mut f: F) -> Result<Vec<query::Logical<U>>, query::Error>
+    where
+        F: FnMut(T) -> Result<U, query::Error>,
+    {
+        let mut logical = Vec::new();
+
+        if let Some(operands) = self.and {
+            logical.push(query::Logical::And(try_convert_operands(
+                "$and", operands, &mut f,
+            )?));
+        }
+
+        if let Some(operands) = self.or {
+            logical.push(query::Logical::Or(try_convert_operands(
+                "$or", operands, &mut f,
+            )?));
+        }
+
+        if let Some(operand) = self.not {
+            logical.push(query::Logical::Not(Box::new(f(*operand)?)));
+        }
+
+        Ok(logical)
+    }
+}
+
+fn try_convert_operands<T, U, F>(
+    operator: &str,
+    operands: Vec<T>,
+    f: &mut F,
+) -> Result<Vec<U>, query::Error>
+where
+    F: FnMut(T) -> Result<U, query::Error>,
+{
+    if operands.is_empty() {
+        return Err(query::Error::DeserializationError(format!(
+            "`{operator}` requires at least one operand"
+        )));
+    }
+    operands.into_iter().map(f).collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OrderBy {
+    Name,
+    CreatedAt,
+}
+
+impl From<OrderBy> for query::OrderBy {
+    fn from(value: OrderBy) -> Self {
+        match value {
+            OrderBy::Name => Self::Name,
+            OrderBy::CreatedAt => Self::CreatedAt,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct Query {
     sequence: Option<Sequence>,
     topic: Option<Topic>,
     ontology: Option<Ontology>,
+
+    /// Maximum number of sequences returned
+    limit: Option<usize>,
+    order_by: Option<OrderBy>,
+    /// Cursor returned by the previous page
+    cursor: Option<String>,
 }
 
 impl TryInto<query::Filter> for Query {
     type Error = query::Error;
     fn try_into(self) -> Result<query::Filter, Self::Error> {
+        let page = query::Page::try_new(
+            self.limit,
+            self.order_by.map(Into::into).unwrap_or_default(),
+            self.cursor.map(|c| c.parse()).transpose()?,
+        )?;
+
         Ok(query::Filter {
             sequence: self.sequence.map(|v| v.try_into()).transpose()?,
             topic: self.topic.map(|v| v.try_into()).transpose()?,
             ontology: self.ontology.map(|v| v.try_into()).transpose()?,
+            page,
         })
     }
 }
@@ -179,13 +266,57 @@ impl TryInto<query::Filter> for Query {
 #[derive(Debug, Deserialize)]
 struct Ontology {
     #[serde(flatten)]
-    filter: HashMap<String, Op>,
+    clauses: OntologyClauses,
     include_timestamp_range: Option<bool>,
+    intervals: Option<Intervals>,
+}
+
+/// Options of the matching time intervals returned for each topic
+#[derive(Debug, Deserialize)]
+struct Intervals {
+    /// Maximum distance in nanoseconds between two runs of matching rows merged in the same
+    /// interval
+    max_gap_ns: u64,
+}
+
+impl From<Intervals> for query::IntervalOptions {
+    fn from(value: Intervals) -> Self {
+        Self {
+            max_gap_ns: value.max_gap_ns,
+        }
+    }
 }
 
 impl TryInto<query::OntologyFilter> for Ontology {
     type Error = query::Error;
     fn try_into(self) -> Result<query::OntologyFilter, Self::Error> {
+        let (ontology, logical) = self.clauses.try_into_parts()?;
+
+        let include_timestamp_range = self.include_timestamp_range.unwrap_or_default();
+
+        Ok(
+            query::OntologyFilter::new_with_timestamp_range(ontology, include_timestamp_range)
+                .with_logical(logical)
+                .with_intervals(self.intervals.map(Into::into)),
+        )
+    }
+}
+
+/// Ontology expressions, a map of ontology fields and operations (e.g. `imu.acc.x`)
+/// plus logical operators
+#[derive(Debug, Deserialize)]
+struct OntologyClauses {
+    #[serde(flatten)]
+    logical: Logical<OntologyClauses>,
+    #[serde(flatten)]
+    filter: HashMap<String, Op>,
+}
+
+type OntologyExprs = HashMap<query::OntologyField, query::Op<query::Value>>;
+type OntologyLogical = query::Logical<query::OntologyExprGroup<query::Value>>;
+
+impl OntologyClauses {
+    fn try_into_parts(self) -> Result<(OntologyExprs, Vec<OntologyLogical>), query::Error> {
         let ontology = self
             .filter
             .into_iter()
@@ -197,16 +328,33 @@ impl TryInto<query::OntologyFilter> for Ontology {
 
                 let col = query::OntologyField::try_new(col)?;
 
-                Ok::<(query::OntologyField, query::Op<query::Value>), Self::Error>((col, op))
+                Ok::<(query::OntologyField, query::Op<query::Value>), query::Error>((col, op))
             })
             .collect::<Result<_, _>>()?;
 
-        let include_timestamp_range = self.include_timestamp_range.unwrap_or_default();
+        let logical = self.logical.try_into_query(|clauses| clauses.try_into())?;
+
+        // Each ontology tag is evaluated on a different set of data,
+        // so logical operators can only combine fields of the same ontology tag
+        for node in &logical {
+            let tags = node.ontology_tags();
+            if tags.len() > 1 {
+                return Err(query::Error::mixed_ontology_tags(tags));
+            }
+        }
 
-        Ok(query::OntologyFilter::new_with_timestamp_range(
-            ontology,
-            include_timestamp_range,
-        ))
+        Ok((ontology, logical))
+    }
+}
+
+impl TryInto<query::OntologyExprGroup<query::Value>> for OntologyClauses {
+    type Error = query::Error;
+    fn try_into(self) -> Result<query::OntologyExprGroup<query::Value>, Self::Error> {
+        let (ontology, logical) = self.try_into_parts()?;
+        Ok(
+            query::OntologyExprGroup::new(ontology.into_iter().map(Into::into).collect())
+                .with_logical(logical),
+        )
     }
 }
 
@@ -233,6 +381,8 @@ struct Sequence {
     locator: Option<Op>,
     created_at_ns: Option<Op>,
     user_metadata: Option<HashMap<String, Op>>,
+    #[serde(flatten)]
+    logical: Logical<Sequence>,
 }
 
 impl TryInto<query::SequenceFilter> for Sequence {
@@ -256,6 +406,7 @@ impl TryInto<query::SequenceFilter> for Sequence {
                     err: e,
                 })?,
             user_metadata: convert_user_metadata(self.user_metadata)?,
+            logical: self.logical.try_into_query(|seq| seq.try_into())?,
         })
     }
 }
@@ -267,6 +418,8 @@ pub struct Topic {
     ontology_tag: Option<Op>,
     serialization_format: Option<Op>,
     user_metadata: Option<HashMap<String, Op>>,
+    #[serde(flatten)]
+    logical: Logical<Topic>,
 }
 
 impl TryInto<query::TopicFilter> for Topic {
@@ -311,6 +464,7 @@ impl TryInto<query::TopicFilter> for Topic {
                 })?,
 
             user_metadata: convert_user_metadata(self.user_metadata)?,
+            logical: self.logical.try_into_query(|top| top.try_into())?,
         })
     }
 }
@@ -332,3 +486,154 @@ pub fn query_filter_from_serde_value(v: serde_json::Value) -> Result<query::Filt
         .map_err(|e: query::Error| super::Error::DeserializationError(e.to_string()))?;
     Ok(query)
 }
+
+/// Parses an ontology filter (e.g. `{"imu.acc.z": {"$gt": 9.8}}`) used to filter the rows of a
+/// single topic.
+///
+/// Since the filter is evaluated on the data of a single topic, all its fields need to share
+/// the same ontology tag.
+pub fn ontology_filter_from_string(
+    s: &str,
+) -> Result<query::OntologyExprGroup<query::Value>, super::Error> {
+    let clauses: OntologyClauses =
+        serde_json::from_str(s).map_err(|e| super::Error::DeserializationError(e.to_string()))?;
+    let group: query::OntologyExprGroup<query::Value> = clauses
+        .try_into()
+        .map_err(|e: query::Error| super::Error::DeserializationError(e.to_string()))?;
+
+    let tags = group.ontology_tags();
+    if tags.len() > 1 {
+        let mut tags: Vec<&str> = tags.into_iter().collect();
+        tags.sort();
+        return Err(super::Error::DeserializationError(format!(
+            "ontology filter can't combine different ontology tags ({})",
+            tags.join(", ")
+        )));
+    }
+
+    Ok(group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logical_operators() {
+        let filter = query_filter_from_string(
+            r#"{
+                "topic": {
+                    "ontology_tag": { "$eq": "imu" },
+                    "$not": { "locator": { "$match": "debug" } }
+                },
+                "ontology": {
+                    "$or": [
+                        { "imu.acc.x": { "$gt": 5.0 } },
+                        { "imu.acc.y": { "$gt": 5.0 } }
+                    ],
+                    "include_timestamp_range": true
+                }
+            }"#,
+        )
+        .expect("unable to parse query");
+
+        let topic = filter.topic.unwrap();
+        assert!(topic.ontology_tag.is_some());
+        assert_eq!(topic.logical.len(), 1);
+        match &topic.logical[0] {
+            query::Logical::Not(top) => {
+                assert_eq!(top.name, Some(query::Op::Match("debug".to_owned())));
+            }
+            _ => panic!("expected a `$not` operator"),
+        }
+
+        let ontology = filter.ontology.unwrap();
+        assert!(ontology.include_timestamp_range);
+
+        let group = ontology.into_expr_group();
+        assert!(group.group.is_empty());
+        assert_eq!(group.logical.len(), 1);
+        match &group.logical[0] {
+            query::Logical::Or(groups) => {
+                assert_eq!(groups.len(), 2);
+                assert_eq!(group.logical[0].ontology_tags().len(), 1);
+            }
+            _ => panic!("expected a `$or` operator"),
+        }
+    }
+
+    #[test]
+    fn ontology_intervals() {
+        let filter = query_filter_from_string(
+            r#"{"ontology": {"imu.acc.x": {"$gt": 5.0}, "intervals": {"max_gap_ns": 1000}}}"#,
+        )
+        .unwrap();
+        let ontology = filter.ontology.unwrap();
+        assert_eq!(ontology.intervals.unwrap().max_gap_ns, 1000);
+        assert!(!ontology.include_timestamp_range);
+
+        // missing gap
+        assert!(
+            query_filter_from_string(
+                r#"{"ontology": {"imu.acc.x": {"$gt": 5.0}, "intervals": {}}}"#
+            )
+            .is_err()
+        );
+        // negative gap
+        assert!(
+            query_filter_from_string(
+                r#"{"ontology": {"imu.acc.x": {"$gt": 5.0}, "intervals": {"max_gap_ns": -1}}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn logical_operators_errors() {
+        // Empty operands
+        assert!(query_filter_from_string(r#"{ "sequence": { "$or": [] } }"#).is_err());
+
+        // Logical operators across different ontology tags
+        assert!(
+            query_filter_from_string(
+                r#"{
+                    "ontology": {
+                        "$or": [
+                            { "imu.acc.x": { "$gt": 5.0 } },
+                            { "gps.speed": { "$gt": 5.0 } }
+                        ]
+                    }
+                }"#,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn ontology_filter() {
+        let group = ontology_filter_from_string(
+            r#"{"imu.acc.z": {"$gt": 9.8}, "$or": [{"imu.acc.x": {"$lt": 0}}, {"imu.acc.y": {"$lt": 0}}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(group.ontology_tag(), Some("imu"));
+        assert_eq!(group.group.len(), 1);
+        assert_eq!(group.logical.len(), 1);
+
+        assert!(ontology_filter_from_string("{}").unwrap().is_empty());
+
+        // different ontology tags
+        assert!(
+            ontology_filter_from_string(r#"{"imu.acc.z": {"$gt": 9.8}, "gps.x": {"$gt": 0}}"#)
+                .is_err()
+        );
+
+        // unexpected keys
+        assert!(
+            ontology_filter_from_string(
+                r#"{"imu.acc.z": {"$gt": 9.8}, "include_timestamp_range": true}"#
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use mosaicod_core::types;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+impl From<types::Codec> for Codec {
+    fn from(value: types::Codec) -> Self {
+        match value {
+            types::Codec::Uncompressed => Self::Uncompressed,
+            types::Codec::Snappy => Self::Snappy,
+            types::Codec::Gzip => Self::Gzip,
+            types::Codec::Lz4 => Self::Lz4,
+            types::Codec::Zstd => Self::Zstd,
+            types::Codec::Brotli => Self::Brotli,
+        }
+    }
+}
+
+impl From<Codec> for types::Codec {
+    fn from(value: Codec) -> Self {
+        match value {
+            Codec::Uncompressed => Self::Uncompressed,
+            Codec::Snappy => Self::Snappy,
+            Codec::Gzip => Self::Gzip,
+            Codec::Lz4 => Self::Lz4,
+            Codec::Zstd => Self::Zstd,
+            Codec::Brotli => Self::Brotli,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticsLevel {
+    None,
+    Chunk,
+    Page,
+}
+
+impl From<types::StatisticsLevel> for StatisticsLevel {
+    fn from(value: types::StatisticsLevel) -> Self {
+        match value {
+            types::StatisticsLevel::None => Self::None,
+            types::StatisticsLevel::Chunk => Self::Chunk,
+            types::StatisticsLevel::Page => Self::Page,
+        }
+    }
+}
+
+impl From<StatisticsLevel> for types::StatisticsLevel {
+    fn from(value: StatisticsLevel) -> Self {
+        match value {
+            StatisticsLevel::None => Self::None,
+            StatisticsLevel::Chunk => Self::Chunk,
+            StatisticsLevel::Page => Self::Page,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JsonColumnWriterOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<Codec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<StatisticsLevel>,
+}
+
+impl From<types::ColumnWriterOptions> for JsonColumnWriterOptions {
+    fn from(value: types::ColumnWriterOptions) -> Self {
+        Self {
+            codec: value.codec.map(Into::into),
+            level: value.level,
+            dictionary: value.dictionary,
+            statistics: value.statistics.map(Into::into),
+        }
+    }
+}
+
+impl From<JsonColumnWriterOptions> for types::ColumnWriterOptions {
+    fn from(value: JsonColumnWriterOptions) -> Self {
+        Self {
+            codec: value.codec.map(Into::into),
+            level: value.level,
+            dictionary: value.dictionary,
+            statistics: value.statistics.map(Into::into),
+        }
+    }
+}
+
+/// Writer options of a topic, e.g.
+///
+/// ```json
+/// {
+///     "codec": "zstd",
+///     "level": 9,
+///     "row_group_size": 65536,
+///     "bloom_filter": ["frame_id"],
+///     "columns": {
+///         "data": { "codec": "uncompressed", "statistics": "none" }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JsonWriterOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<Codec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<StatisticsLevel>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<String, JsonColumnWriterOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_group_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bloom_filter: Vec<String>,
+}
+
+impl From<types::WriterOptions> for JsonWriterOptions {
+    fn from(value: types::WriterOptions) -> Self {
+        Self {
+            codec: value.global.codec.map(Into::into),
+            level: value.global.level,
+            dictionary: value.global.dictionary,
+            statistics: value.global.statistics.map(Into::into),
+            columns: value
+                .columns
+                .into_iter()
+                .map(|(name, options)| (name, options.into()))
+                .collect(),
+            row_group_size: value.row_group_size,
+            bloom_filter: value.bloom_filter,
+        }
+    }
+}
+
+impl From<JsonWriterOptions> for types::WriterOptions {
+    fn from(value: JsonWriterOptions) -> Self {
+        Self {
+            global: types::ColumnWriterOptions {
+                codec: value.codec.map(Into::into),
+                level: value.level,
+                dictionary: value.dictionary,
+                statistics: value.statistics.map(Into::into),
+            },
+            columns: value
+                .columns
+                .into_iter()
+                .map(|(name, options)| (name, options.into()))
+                .collect(),
+            row_group_size: value.row_group_size,
+            bloom_filter: value.bloom_filter,
+        }
+    }
+}
+
+impl From<JsonWriterOptions> for serde_json::Value {
+    fn from(value: JsonWriterOptions) -> Self {
+        serde_json::to_value(value).expect("writer options are always serializable")
+    }
+}
+
+impl TryFrom<serde_json::Value> for JsonWriterOptions {
+    type Error = serde_json::Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_options_roundtrip() {
+        let json = r#"{
+            "codec": "zstd",
+            "level": 9,
+            "row_group_size": 65536,
+            "bloom_filter": ["frame_id"],
+            "columns": {
+                "data": { "codec": "uncompressed", "statistics": "none" }
+            }
+        }"#;
+
+        let options: types::WriterOptions = serde_json::from_str::<JsonWriterOptions>(json)
+            .unwrap()
+            .into();
+
+        assert_eq!(options.global.codec, Some(types::Codec::Zstd));
+        assert_eq!(options.global.level, Some(9));
+        assert_eq!(options.global.dictionary, None);
+        assert_eq!(options.row_group_size, Some(65536));
+        assert_eq!(options.bloom_filter, vec!["frame_id".to_owned()]);
+        assert_eq!(
+            options.columns.get("data"),
+            Some(&types::ColumnWriterOptions {
+                codec: Some(types::Codec::Uncompressed),
+                statistics: Some(types::StatisticsLevel::None),
+                ..Default::default()
+            })
+        );
+
+        let value: serde_json::Value = JsonWriterOptions::from(options.clone()).into();
+        let back: types::WriterOptions = JsonWriterOptions::try_from(value).unwrap().into();
+        assert_eq!(back, options);
+
+        // Typos are not silently ignored
+        assert!(serde_json::from_str::<JsonWriterOptions>(r#"{ "codek": "zstd" }"#).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! Decoder for ROS 2 messages serialized using the classic CDR encoding.
+
+use crate::Error;
+use crate::column::Column;
+use crate::ros2msg::{Arity, FieldDef, FieldType, Primitive, Registry};
+
+/// Reads CDR primitive values, honoring alignment and endianness declared in the
+/// encapsulation header.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+macro_rules! read_primitive {
+    ($name:ident, $ty:ty) => {
+        fn $name(&mut self) -> Result<$ty, Error> {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+            self.align(SIZE);
+            let bytes: [u8; SIZE] = self.take(SIZE)?.try_into().expect("slice of right size");
+            Ok(if self.little_endian {
+                <$ty>::from_le_bytes(bytes)
+            } else {
+                <$ty>::from_be_bytes(bytes)
+            })
+        }
+    };
+}
+
+impl<'a> Reader<'a> {
+    fn try_new(data: &'a [u8]) -> Result<Self, Error> {
+        let Some((header, buf)) = data.split_first_chunk::<4>() else {
+            return Err(Error::decode("missing CDR encapsulation header"));
+        };
+
+        let little_endian = match header[1] {
+            0x00 => false,
+            0x01 => true,
+            kind => {
+                return Err(Error::decode(format!(
+                    "unsupported CDR encapsulation kind {kind:#04x}"
+                )));
+            }
+        };
+
+        Ok(Self {
+            buf,
+            pos: 0,
+            little_endian,
+        })
+    }
+
+    /// Alignment is relative to the end of the encapsulation header
+    fn align(&mut self, size: usize) {
+        self.pos = self.pos.div_ceil(size) * size;
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len().saturating_sub(self.pos)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.remaining() {
+            return Err(Error::decode("unexpected end of CDR message"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    read_primitive!(read_i16, i16);
+    read_primitive!(read_u16, u16);
+    read_primitive!(read_i32, i32);
+    read_primitive!(read_u32, u32);
+    read_primitive!(read_i64, i64);
+    read_primitive!(read_u64, u64);
+    read_primitive!(read_f32, f32);
+    read_primitive!(read_f64, f64);
+
+    /// Reads the length of a sequence, checking that it fits in the message
+    fn read_len(&mut self) -> Result<usize, Error> {
+        let len = self.read_u32()? as usize;
+        if len > self.remaining() {
+            return Err(Error::decode("sequence length exceeds message size"));
+        }
+        Ok(len)
+    }
+
+    fn read_string(&mut self) -> Result<&'a str, Error> {
+        let len = self.read_len()?;
+        let bytes = self.take(len)?;
+        // Strings are null terminated
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        std::str::from_utf8(bytes).map_err(|_| Error::decode("string is not valid utf-8"))
+    }
+}
+
+/// Decodes the CDR message `data` appending its fields to `columns`, one column for each
+/// field of the root message of `registry`.
+pub(crate) fn decode(
+    registry: &Registry,
+    data: &[u8],
+    columns: &mut [Column],
+) -> Result<(), Error> {
+    let mut reader = Reader::try_new(data)?;
+    decode_fields(&mut reader, registry, registry.root(), columns)
+}
+
+fn decode_fields(
+    reader: &mut Reader,
+    registry: &Registry,
+    fields: &[FieldDef],
+    columns: &mut [Column],
+) -> Result<(), Error> {
+    for (field, column) in fields.iter().zip(columns.iter_mut()) {
+        decode_field(reader, registry, field, column)?;
+    }
+    Ok(())
+}
+
+fn decode_field(
+    reader: &mut Reader,
+    registry: &Registry,
+    field: &FieldDef,
+    column: &mut Column,
+) -> Result<(), Error> {
+    let len = match field.arity {
+        Arity::Single => return decode_value(reader, registry, &field.field_type, column),
+        Arity::Array(len) => len,
+        Arity::Sequence => reader.read_len()?,
+    };
+
+    match column {
+        Column::Binary(b) if field.is_bytes() => b.append_value(reader.take(len)?),
+        Column::List {
+            offsets, values, ..
+        } => {
+            for _ in 0..len {
+                decode_value(reader, registry, &field.field_type, values)?;
+            }
+            Column::close_list(offsets, len);
+        }
+        _ => unreachable!("column does not match field `{}`", field.name),
+    }
+
+    Ok(())
+}
+
+fn decode_value(
+    reader: &mut Reader,
+    registry: &Registry,
+    field_type: &FieldType,
+    column: &mut Column,
+) -> Result<(), Error> {
+    match (field_type, column) {
+        (FieldType::Primitive(Primitive::Bool), Column::Bool(b)) => {
+            b.append_value(reader.read_u8()? != 0)
+        }
+        (FieldType::Primitive(Primitive::Int8), Column::Int8(b)) => {
+            b.append_value(reader.read_u8()? as i8)
+        }
+        (FieldType::Primitive(Primitive::UInt8), Column::UInt8(b)) => {
+            b.append_value(reader.read_u8()?)
+        }
+        (FieldType::Primitive(Primitive::Int16), Column::Int16(b)) => {
+            b.append_value(reader.read_i16()?)
+        }
+        (FieldType::Primitive(Primitive::UInt16), Column::UInt16(b)) => {
+            b.append_value(reader.read_u16()?)
+        }
+        (FieldType::Primitive(Primitive::Int32), Column::Int32(b)) => {
+            b.append_value(reader.read_i32()?)
+        }
+        (FieldType::Primitive(Primitive::UInt32), Column::UInt32(b)) => {
+            b.append_value(reader.read_u32()?)
+        }
+        (FieldType::Primitive(Primitive::Int64), Column::Int64(b)) => {
+            b.append_value(reader.read_i64()?)
+        }
+        (FieldType::Primitive(Primitive::UInt64), Column::UInt64(b)) => {
+            b.append_value(reader.read_u64()?)
+        }
+        (FieldType::Primitive(Primitive::Float32), Column::Float32(b)) => {
+            b.append_value(reader.read_f32()?)
+        }
+        (FieldType::Primitive(Primitive::Float64), Column::Float64(b)) => {
+            b.append_value(reader.read_f64()?)
+        }
+        (FieldType::String, Column::Utf8(b)) => b.append_value(reader.read_string()?),
+        (FieldType::Message(name), Column::Struct { children, .. }) => {
+            decode_fields(reader, registry, registry.fields(name), children)?
+        }
+        (field_type, _) => unreachable!("column does not match type {field_type:?}"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, AsArray, StructArray};
+    use arrow::datatypes::{Float64Type, Int32Type, UInt32Type};
+
+    /// Minimal CDR little endian writer used to build test messages
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn align(&mut self, size: usize) {
+            while !self.0.len().is_multiple_of(size) {
+                self.0.push(0);
+            }
+        }
+        fn i32(&mut self, v: i32) {
+            self.align(4);
+            self.0.extend(v.to_le_bytes());
+        }
+        fn u32(&mut self, v: u32) {
+            self.align(4);
+            self.0.extend(v.to_le_bytes());
+        }
+        fn f64(&mut self, v: f64) {
+            self.align(8);
+            self.0.extend(v.to_le_bytes());
+        }
+        fn string(&mut self, v: &str) {
+            self.u32(v.len() as u32 + 1);
+            self.0.extend(v.as_bytes());
+            self.0.push(0);
+        }
+        fn bytes(mut self) -> Vec<u8> {
+            let mut data = vec![0x00, 0x01, 0x00, 0x00];
+            data.append(&mut self.0);
+            data
+        }
+    }
+
+    const DEFINITION: &str = "\
+std_msgs/Header header
+float64[] values
+uint8[] data
+================================================================================
+MSG: std_msgs/Header
+builtin_interfaces/Time stamp
+string frame_id
+";
+
+    #[test]
+    fn decode_message() {
+        let registry = Registry::parse("pkg/msg/Sample", DEFINITION).unwrap();
+
+        let mut w = Writer::default();
+        w.i32(10);
+        w.u32(500);
+        w.string("base_link");
+        w.u32(2);
+        w.f64(1.5);
+        w.f64(-2.0);
+        w.u32(3);
+        w.0.extend([7, 8, 9]);
+
+        let fields = registry.arrow_fields();
+        let mut columns: Vec<Column> = fields.iter().map(|f| Column::new(f.data_type())).collect();
+
+        decode(&registry, &w.bytes(), &mut columns).unwrap();
+
+        let arrays: Vec<_> = columns.iter_mut().map(Column::finish).collect();
+        let batch = StructArray::new(fields, arrays, None);
+
+        let header = batch.column(0).as_struct();
+        let stamp = header.column(0).as_struct();
+        assert_eq!(stamp.column(0).as_primitive::<Int32Type>().value(0), 10);
+        assert_eq!(stamp.column(1).as_primitive::<UInt32Type>().value(0), 500);
+        assert_eq!(header.column(1).as_string::<i32>().value(0), "base_link");
+
+        let values = batch.column(1).as_list::<i32>().value(0);
+        assert_eq!(values.as_primitive::<Float64Type>().values(), &[1.5, -2.0]);
+
+        assert_eq!(batch.column(2).as_binary::<i32>().value(0), &[7, 8, 9]);
+        assert_eq!(batch.len(), 1);
+    }
+
+    #[test]
+    fn truncated_message() {
+        let registry = Registry::parse("pkg/msg/Sample", DEFINITION).unwrap();
+        let fields = registry.arrow_fields();
+        let mut columns: Vec<Column> = fields.iter().map(|f| Column::new(f.data_type())).collect();
+
+        let mut w = Writer::default();
+        w.i32(10);
+
+        assert!(decode(&registry, &w.bytes(), &mut columns).is_err());
+    }
+}
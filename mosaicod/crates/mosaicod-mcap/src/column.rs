@@ -0,0 +1,119 @@
+//! Growable arrow columns built out of a [`DataType`].
+//!
+//! Decoders push values directly in the builder tree while walking the message
+//! definition, avoiding any intermediate representation of the messages.
+
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Float32Builder, Float64Builder, Int8Builder,
+    Int16Builder, Int32Builder, Int64Builder, ListArray, StringBuilder, StructArray, UInt8Builder,
+    UInt16Builder, UInt32Builder, UInt64Builder,
+};
+use arrow::buffer::OffsetBuffer;
+use arrow::datatypes::{DataType, FieldRef, Fields};
+use std::sync::Arc;
+
+pub(crate) enum Column {
+    Bool(BooleanBuilder),
+    Int8(Int8Builder),
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    UInt8(UInt8Builder),
+    UInt16(UInt16Builder),
+    UInt32(UInt32Builder),
+    UInt64(UInt64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+    List {
+        field: FieldRef,
+        offsets: Vec<i32>,
+        values: Box<Column>,
+    },
+    Struct {
+        fields: Fields,
+        children: Vec<Column>,
+    },
+}
+
+impl Column {
+    /// Creates an empty column for `data_type`.
+    ///
+    /// Panics if the data type is not produced by the decoders of this crate.
+    pub fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => Self::Bool(BooleanBuilder::new()),
+            DataType::Int8 => Self::Int8(Int8Builder::new()),
+            DataType::Int16 => Self::Int16(Int16Builder::new()),
+            DataType::Int32 => Self::Int32(Int32Builder::new()),
+            DataType::Int64 => Self::Int64(Int64Builder::new()),
+            DataType::UInt8 => Self::UInt8(UInt8Builder::new()),
+            DataType::UInt16 => Self::UInt16(UInt16Builder::new()),
+            DataType::UInt32 => Self::UInt32(UInt32Builder::new()),
+            DataType::UInt64 => Self::UInt64(UInt64Builder::new()),
+            DataType::Float32 => Self::Float32(Float32Builder::new()),
+            DataType::Float64 => Self::Float64(Float64Builder::new()),
+            DataType::Utf8 => Self::Utf8(StringBuilder::new()),
+            DataType::Binary => Self::Binary(BinaryBuilder::new()),
+            DataType::List(field) => Self::List {
+                field: field.clone(),
+                offsets: vec![0],
+                values: Box::new(Self::new(field.data_type())),
+            },
+            DataType::Struct(fields) => Self::Struct {
+                fields: fields.clone(),
+                children: fields.iter().map(|f| Self::new(f.data_type())).collect(),
+            },
+            other => unreachable!("unsupported column type {other}"),
+        }
+    }
+
+    /// Closes the current list element, containing all the values pushed since the
+    /// previous call.
+    pub fn close_list(offsets: &mut Vec<i32>, len: usize) {
+        let last = *offsets.last().unwrap_or(&0);
+        offsets.push(last + len as i32);
+    }
+
+    /// Builds the array containing all the values pushed so far and resets the column.
+    pub fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Bool(b) => Arc::new(b.finish()),
+            Self::Int8(b) => Arc::new(b.finish()),
+            Self::Int16(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::UInt8(b) => Arc::new(b.finish()),
+            Self::UInt16(b) => Arc::new(b.finish()),
+            Self::UInt32(b) => Arc::new(b.finish()),
+            Self::UInt64(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Utf8(b) => Arc::new(b.finish()),
+            Self::Binary(b) => Arc::new(b.finish()),
+            Self::List {
+                field,
+                offsets,
+                values,
+            } => {
+                let offsets = OffsetBuffer::new(std::mem::replace(offsets, vec![0]).into());
+                Arc::new(ListArray::new(
+                    field.clone(),
+                    offsets,
+                    values.finish(),
+                    None,
+                ))
+            }
+            Self::Struct { fields, children } => {
+                let arrays = children.iter_mut().map(Column::finish).collect();
+                Arc::new(StructArray::new(fields.clone(), arrays, None))
+            }
+        }
+    }
+}
+
+/// Builds the item field used by list columns.
+pub(crate) fn list_of(data_type: DataType) -> DataType {
+    DataType::new_list(data_type, false)
+}
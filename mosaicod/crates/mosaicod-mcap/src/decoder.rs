@@ -0,0 +1,228 @@
+use crate::column::Column;
+use crate::{Error, cdr, protobuf, ros2msg};
+use arrow::array::{ArrayRef, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use mosaicod_core::{params, types};
+use std::sync::Arc;
+
+/// Encoding-specific state of a [`ChannelDecoder`]
+enum Encoding {
+    Ros2 {
+        registry: ros2msg::Registry,
+        columns: Vec<Column>,
+    },
+    Protobuf {
+        decoder: protobuf::Decoder,
+        columns: Vec<Column>,
+    },
+    /// JSON messages are buffered since the schema is inferred from the data
+    Json { values: Vec<serde_json::Value> },
+}
+
+/// Converts the messages of an MCAP channel into arrow [`RecordBatch`]es.
+///
+/// Each message becomes a row containing the `timestamp_ns` column, filled with the
+/// message log time, followed by a column for each top-level field of the message.
+///
+/// Supported encodings are ROS 2 CDR (with `ros2msg` schemas), protobuf (with
+/// embedded `FileDescriptorSet` schemas) and JSON.
+pub struct ChannelDecoder {
+    encoding: Encoding,
+    schema_name: String,
+    schema: Option<SchemaRef>,
+    timestamps: Vec<i64>,
+    buffered_bytes: usize,
+}
+
+impl ChannelDecoder {
+    /// Creates a decoder for `channel`.
+    ///
+    /// Returns an [`Error::UnsupportedEncoding`] if the channel encoding can't be converted.
+    pub fn try_new(channel: &mcap::Channel) -> Result<Self, Error> {
+        let schema = channel.schema.as_deref();
+        let schema_name = schema.map(|s| s.name.clone()).unwrap_or_default();
+
+        let (encoding, fields) = match (channel.message_encoding.as_str(), schema) {
+            ("cdr", Some(schema)) if schema.encoding == "ros2msg" => {
+                let definition = std::str::from_utf8(&schema.data)
+                    .map_err(|_| Error::invalid_schema(&schema.name, "not valid utf-8"))?;
+                let registry = ros2msg::Registry::parse(&schema.name, definition)?;
+                let fields = registry.arrow_fields();
+                let encoding = Encoding::Ros2 {
+                    registry,
+                    columns: columns(&fields),
+                };
+                (encoding, Some(fields))
+            }
+            ("protobuf", Some(schema)) if schema.encoding == "protobuf" => {
+                let decoder = protobuf::Decoder::try_new(&schema.name, &schema.data)?;
+                let fields = decoder.arrow_fields().clone();
+                let encoding = Encoding::Protobuf {
+                    decoder,
+                    columns: columns(&fields),
+                };
+                (encoding, Some(fields))
+            }
+            ("json", _) => (Encoding::Json { values: Vec::new() }, None),
+            (encoding, _) => {
+                return Err(Error::unsupported_encoding(&channel.topic, encoding));
+            }
+        };
+
+        let schema = fields
+            .map(|fields| with_timestamp(&schema_name, &fields))
+            .transpose()?;
+
+        Ok(Self {
+            encoding,
+            schema_name,
+            schema,
+            timestamps: Vec::new(),
+            buffered_bytes: 0,
+        })
+    }
+
+    /// Returns the ontology tag used for the topic, derived from the schema name
+    /// (e.g. `sensor_msgs/msg/Imu` becomes `sensor_msgs_imu`).
+    pub fn ontology_tag(&self) -> String {
+        if self.schema_name.is_empty() {
+            return "json".to_owned();
+        }
+
+        let name = self.schema_name.replace("/msg/", "/");
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the serialization format best suited for the decoded data.
+    ///
+    /// Images are stored using the [`types::Format::Image`] format, messages containing
+    /// lists using the [`types::Format::Ragged`] one.
+    pub fn format(&self) -> types::Format {
+        if self.schema_name.ends_with("Image") {
+            return types::Format::Image;
+        }
+
+        let has_lists = self.schema.as_ref().is_some_and(|schema| {
+            schema
+                .fields()
+                .iter()
+                .any(|f| matches!(f.data_type(), DataType::List(_)))
+        });
+
+        if has_lists {
+            types::Format::Ragged
+        } else {
+            types::Format::Default
+        }
+    }
+
+    /// Decodes a message, buffering it until the next [`ChannelDecoder::flush`].
+    ///
+    /// If an error is returned the decoder is left in an inconsistent state and
+    /// should be discarded.
+    pub fn push(&mut self, log_time: u64, data: &[u8]) -> Result<(), Error> {
+        let timestamp = i64::try_from(log_time)
+            .map_err(|_| Error::decode(format!("log time {log_time} out of range")))?;
+
+        match &mut self.encoding {
+            Encoding::Ros2 { registry, columns } => cdr::decode(registry, data, columns)?,
+            Encoding::Protobuf { decoder, columns } => decoder.decode(data, columns)?,
+            Encoding::Json { values } => {
+                let value: serde_json::Value =
+                    serde_json::from_slice(data).map_err(|e| Error::decode(e.to_string()))?;
+                if !value.is_object() {
+                    return Err(Error::decode("JSON messages must be objects"));
+                }
+                values.push(value);
+            }
+        }
+
+        self.timestamps.push(timestamp);
+        self.buffered_bytes += data.len();
+
+        Ok(())
+    }
+
+    /// Size in bytes of the raw messages buffered since the last flush
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Builds a [`RecordBatch`] containing the messages buffered since the last flush.
+    ///
+    /// Returns `None` if there are no buffered messages. All the batches returned by a
+    /// decoder share the same schema.
+    pub fn flush(&mut self) -> Result<Option<RecordBatch>, Error> {
+        if self.timestamps.is_empty() {
+            return Ok(None);
+        }
+
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(std::mem::take(
+            &mut self.timestamps,
+        )))];
+        self.buffered_bytes = 0;
+
+        match &mut self.encoding {
+            Encoding::Ros2 { columns, .. } | Encoding::Protobuf { columns, .. } => {
+                arrays.extend(columns.iter_mut().map(Column::finish));
+            }
+            Encoding::Json { values } => {
+                // The schema inferred from the first batch is kept for the following ones
+                if self.schema.is_none() {
+                    let inferred = arrow::json::reader::infer_json_schema_from_iterator(
+                        values.iter().map(Ok),
+                    )?;
+                    self.schema = Some(with_timestamp(&self.schema_name, inferred.fields())?);
+                }
+
+                let schema = self.schema.as_ref().expect("schema inferred above");
+                let message_schema = Schema::new(schema.fields()[1..].to_vec());
+
+                let mut decoder =
+                    arrow::json::ReaderBuilder::new(Arc::new(message_schema)).build_decoder()?;
+                decoder.serialize(values)?;
+                values.clear();
+
+                if let Some(batch) = decoder.flush()? {
+                    arrays.extend(batch.columns().iter().cloned());
+                }
+            }
+        }
+
+        let schema = self
+            .schema
+            .clone()
+            .expect("schema is known after the first flush");
+
+        Ok(Some(RecordBatch::try_new(schema, arrays)?))
+    }
+}
+
+fn columns(fields: &Fields) -> Vec<Column> {
+    fields.iter().map(|f| Column::new(f.data_type())).collect()
+}
+
+/// Prepends the timestamp column to the message fields
+fn with_timestamp(schema_name: &str, fields: &Fields) -> Result<SchemaRef, Error> {
+    let timestamp = params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP;
+
+    if fields.iter().any(|f| f.name() == timestamp) {
+        return Err(Error::invalid_schema(
+            schema_name,
+            format!("field `{timestamp}` is reserved"),
+        ));
+    }
+
+    let mut all = vec![Arc::new(Field::new(timestamp, DataType::Int64, false))];
+    all.extend(fields.iter().cloned());
+
+    Ok(Arc::new(Schema::new(all)))
+}
@@ -0,0 +1,218 @@
+use crate::Error;
+use arrow::array::{AsArray, RecordBatch};
+use arrow::datatypes::{DataType, Int64Type, Schema};
+use arrow::ipc::convert::IpcSchemaEncoder;
+use base64::Engine;
+use mosaicod_core::params;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// Channel metadata key containing the ontology tag of the exported topic
+pub const METADATA_ONTOLOGY_TAG: &str = "ontology_tag";
+
+/// Channel metadata key containing the arrow schema of the exported topic, encoded as
+/// a base64 IPC flatbuffer
+pub const METADATA_ARROW_SCHEMA: &str = "arrow_schema";
+
+/// Converts arrow [`RecordBatch`]es into JSON messages of an MCAP channel.
+///
+/// Each row becomes a message, using the `timestamp_ns` column as log time and the
+/// remaining columns as message fields. Binary values are encoded as hex strings.
+///
+/// The channel schema is a JSON schema derived from the arrow schema, which is also
+/// recorded as channel metadata along with the ontology tag.
+pub struct ChannelEncoder {
+    schema_name: String,
+    json_schema: Vec<u8>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl ChannelEncoder {
+    /// Creates an encoder for data with the arrow `schema`.
+    ///
+    /// Returns an [`Error::InvalidSchema`] if the schema has no `timestamp_ns` column.
+    pub fn try_new(ontology_tag: &str, schema: &Schema) -> Result<Self, Error> {
+        let timestamp = params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP;
+
+        if schema.field_with_name(timestamp).is_err() {
+            return Err(Error::invalid_schema(
+                ontology_tag,
+                format!("missing `{timestamp}` column"),
+            ));
+        }
+
+        let properties: serde_json::Map<String, Value> = schema
+            .fields()
+            .iter()
+            .filter(|f| f.name() != timestamp)
+            .map(|f| (f.name().clone(), json_schema(f.data_type())))
+            .collect();
+
+        let json_schema = json!({
+            "title": ontology_tag,
+            "type": "object",
+            "properties": properties,
+        });
+
+        let ipc_schema = IpcSchemaEncoder::new().schema_to_fb(schema);
+
+        let metadata = BTreeMap::from([
+            (METADATA_ONTOLOGY_TAG.to_owned(), ontology_tag.to_owned()),
+            (
+                METADATA_ARROW_SCHEMA.to_owned(),
+                base64::engine::general_purpose::STANDARD.encode(ipc_schema.finished_data()),
+            ),
+        ]);
+
+        Ok(Self {
+            schema_name: ontology_tag.to_owned(),
+            json_schema: json_schema.to_string().into_bytes(),
+            metadata,
+        })
+    }
+
+    /// Name of the channel schema
+    pub fn schema_name(&self) -> &str {
+        &self.schema_name
+    }
+
+    /// JSON schema of the channel messages
+    pub fn json_schema(&self) -> &[u8] {
+        &self.json_schema
+    }
+
+    /// Metadata of the channel
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Encodes each row of `batch` as a JSON message, returned along with its log time.
+    pub fn encode(&self, batch: &RecordBatch) -> Result<Vec<(u64, Vec<u8>)>, Error> {
+        let schema = batch.schema();
+        let (index, _) = schema
+            .column_with_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+            .ok_or_else(|| Error::invalid_schema(&self.schema_name, "missing timestamp column"))?;
+
+        let timestamps = batch
+            .column(index)
+            .as_primitive_opt::<Int64Type>()
+            .ok_or_else(|| {
+                Error::invalid_schema(&self.schema_name, "timestamp column is not an int64")
+            })?;
+
+        let fields: Vec<usize> = (0..batch.num_columns()).filter(|i| *i != index).collect();
+        let fields = batch.project(&fields)?;
+
+        let mut writer = arrow::json::WriterBuilder::new()
+            .with_explicit_nulls(true)
+            .build::<_, arrow::json::writer::LineDelimited>(Vec::new());
+        writer.write(&fields)?;
+        writer.finish()?;
+        let rows = writer.into_inner();
+
+        // Line delimited rows can be split on newlines, since they are escaped in strings
+        timestamps
+            .iter()
+            .zip(rows.split(|b| *b == b'\n'))
+            .map(|(timestamp, row)| {
+                let log_time = timestamp
+                    .and_then(|ts| u64::try_from(ts).ok())
+                    .ok_or_else(|| Error::encode(format!("invalid timestamp {timestamp:?}")))?;
+                Ok((log_time, row.to_vec()))
+            })
+            .collect()
+    }
+}
+
+/// Builds the JSON schema describing the values of `data_type`, types without a JSON
+/// counterpart are left unconstrained.
+fn json_schema(data_type: &DataType) -> Value {
+    match data_type {
+        DataType::Boolean => json!({ "type": "boolean" }),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => json!({ "type": "integer" }),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => json!({ "type": "number" }),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => json!({ "type": "string" }),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => {
+            json!({ "type": "string", "contentEncoding": "base16" })
+        }
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::ListView(field)
+        | DataType::LargeListView(field)
+        | DataType::FixedSizeList(field, _) => {
+            json!({ "type": "array", "items": json_schema(field.data_type()) })
+        }
+        DataType::Struct(fields) => {
+            let properties: serde_json::Map<String, Value> = fields
+                .iter()
+                .map(|f| (f.name().clone(), json_schema(f.data_type())))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+        _ => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BinaryArray, Float64Array, Int64Array};
+    use arrow::datatypes::Field;
+    use std::sync::Arc;
+
+    fn batch(timestamps: Vec<i64>) -> RecordBatch {
+        let len = timestamps.len();
+        let schema = Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("raw", DataType::Binary, true),
+        ]);
+
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Float64Array::from_iter_values((0..len).map(|i| i as f64))),
+                Arc::new(BinaryArray::from_iter(
+                    (0..len).map(|i| (i % 2 == 0).then_some(&[0xab_u8][..])),
+                )),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn encode_rows() {
+        let data = batch(vec![10, 20]);
+        let encoder = ChannelEncoder::try_new("sensor", &data.schema()).unwrap();
+
+        let schema: Value = serde_json::from_slice(encoder.json_schema()).unwrap();
+        assert_eq!(schema["properties"]["value"]["type"], "number");
+        assert!(schema["properties"].get("timestamp_ns").is_none());
+        assert_eq!(encoder.metadata()[METADATA_ONTOLOGY_TAG], "sensor");
+
+        let messages = encoder.encode(&data).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].0, 10);
+        assert_eq!(messages[0].1, br#"{"value":0.0,"raw":"ab"}"#);
+        assert_eq!(messages[1].0, 20);
+        assert_eq!(messages[1].1, br#"{"value":1.0,"raw":null}"#);
+    }
+
+    #[test]
+    fn negative_timestamp() {
+        let data = batch(vec![-1]);
+        let encoder = ChannelEncoder::try_new("sensor", &data.schema()).unwrap();
+        assert!(encoder.encode(&data).is_err());
+    }
+}
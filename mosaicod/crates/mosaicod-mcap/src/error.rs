@@ -0,0 +1,55 @@
+use mosaicod_core as core;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("mcap error: {0}")]
+    McapError(#[from] mcap::McapError),
+    #[error("arrow error: {0}")]
+    ArrowError(#[from] arrow::error::ArrowError),
+    #[error("channel `{topic}` uses unsupported encoding `{encoding}`")]
+    UnsupportedEncoding { topic: String, encoding: String },
+    #[error("invalid schema `{name}`: {msg}")]
+    InvalidSchema { name: String, msg: String },
+    #[error("unable to decode message: {0}")]
+    DecodeError(String),
+    #[error("unable to encode message: {0}")]
+    EncodeError(String),
+    #[error("unable to read recording: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+impl Error {
+    pub fn unsupported_encoding(topic: &str, encoding: &str) -> Self {
+        Self::UnsupportedEncoding {
+            topic: topic.to_owned(),
+            encoding: encoding.to_owned(),
+        }
+    }
+
+    pub fn invalid_schema(name: &str, msg: impl Into<String>) -> Self {
+        Self::InvalidSchema {
+            name: name.to_owned(),
+            msg: msg.into(),
+        }
+    }
+
+    pub fn decode(msg: impl Into<String>) -> Self {
+        Self::DecodeError(msg.into())
+    }
+
+    pub fn encode(msg: impl Into<String>) -> Self {
+        Self::EncodeError(msg.into())
+    }
+}
+
+impl core::error::PublicError for Error {
+    fn error(&self) -> core::Error {
+        match self {
+            Self::IoError(_) => core::Error::internal(Some(self.to_string())),
+            // All the other errors are caused by the content of the recording or of the
+            // exported data
+            _ => core::Error::bad_request(self.to_string()),
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Conversion between MCAP recordings (e.g. ROS 2 bags) and arrow data.
+//!
+//! Each channel of the recording is decoded by a [`ChannelDecoder`], producing
+//! record batches that can be written as topic data. In the opposite direction a
+//! [`ChannelEncoder`] converts topic data into messages written by a [`StreamWriter`].
+
+pub mod error;
+pub use error::Error;
+
+mod column;
+
+mod cdr;
+mod protobuf;
+mod ros2msg;
+
+mod decoder;
+pub use decoder::ChannelDecoder;
+
+mod encoder;
+pub use encoder::*;
+
+mod writer;
+pub use writer::StreamWriter;
+
+pub use mcap::{Channel, Message};
+
+use mcap::records::Record;
+use mcap::sans_io::linear_reader::{LinearReadEvent, LinearReader, LinearReaderOptions};
+use std::collections::{HashMap, hash_map::Entry};
+use std::sync::Arc;
+
+/// Iterator over the messages of an MCAP recording, in file order.
+///
+/// The recording is read incrementally from the underlying reader, only the chunk
+/// being decoded is kept in memory. Reading stops at the end of the data section,
+/// the summary is not read.
+pub struct Messages<R> {
+    reader: R,
+    records: LinearReader,
+    schemas: HashMap<u16, Arc<mcap::Schema<'static>>>,
+    channels: HashMap<u16, Arc<Channel<'static>>>,
+    done: bool,
+}
+
+impl<R: std::io::Read> Messages<R> {
+    fn next_message(&mut self) -> Result<Option<Message<'static>>, Error> {
+        while let Some(event) = self.records.next_event() {
+            let (opcode, data) = match event? {
+                LinearReadEvent::ReadRequest(need) => {
+                    let read = self.reader.read(self.records.insert(need))?;
+                    self.records.notify_read(read);
+                    continue;
+                }
+                LinearReadEvent::Record { opcode, data } => (opcode, data),
+            };
+
+            match mcap::parse_record(opcode, data)? {
+                Record::Schema { header, data } => {
+                    if header.id == 0 {
+                        return Err(mcap::McapError::InvalidSchemaId.into());
+                    }
+                    let schema = mcap::Schema {
+                        id: header.id,
+                        name: header.name,
+                        encoding: header.encoding,
+                        data: data.into_owned().into(),
+                    };
+                    match self.schemas.entry(schema.id) {
+                        Entry::Occupied(entry) if **entry.get() != schema => {
+                            return Err(mcap::McapError::ConflictingSchemas(schema.name).into());
+                        }
+                        Entry::Occupied(_) => {}
+                        Entry::Vacant(entry) => {
+                            entry.insert(Arc::new(schema));
+                        }
+                    }
+                }
+                Record::Channel(channel) => {
+                    let schema = match channel.schema_id {
+                        0 => None,
+                        id => Some(self.schemas.get(&id).cloned().ok_or_else(|| {
+                            mcap::McapError::UnknownSchema(channel.topic.clone(), id)
+                        })?),
+                    };
+                    let channel = Channel {
+                        id: channel.id,
+                        topic: channel.topic,
+                        schema,
+                        message_encoding: channel.message_encoding,
+                        metadata: channel.metadata,
+                    };
+                    match self.channels.entry(channel.id) {
+                        Entry::Occupied(entry) if **entry.get() != channel => {
+                            return Err(mcap::McapError::ConflictingChannels(channel.topic).into());
+                        }
+                        Entry::Occupied(_) => {}
+                        Entry::Vacant(entry) => {
+                            entry.insert(Arc::new(channel));
+                        }
+                    }
+                }
+                Record::Message { header, data } => {
+                    let channel = self.channels.get(&header.channel_id).cloned().ok_or(
+                        mcap::McapError::UnknownChannel(header.sequence, header.channel_id),
+                    )?;
+                    return Ok(Some(Message {
+                        channel,
+                        sequence: header.sequence,
+                        log_time: header.log_time,
+                        publish_time: header.publish_time,
+                        data: data.into_owned().into(),
+                    }));
+                }
+                Record::DataEnd(_) | Record::Footer(_) => break,
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<R: std::io::Read> Iterator for Messages<R> {
+    type Item = Result<Message<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_message().transpose();
+        // Stop after the end of the data section or the first error
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+/// Returns an iterator over the messages contained in the MCAP recording read from `reader`.
+pub fn messages<R: std::io::Read>(reader: R) -> Messages<R> {
+    Messages {
+        reader,
+        records: LinearReader::new_with_options(
+            LinearReaderOptions::default().with_validate_chunk_crcs(true),
+        ),
+        schemas: HashMap::new(),
+        channels: HashMap::new(),
+        done: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int64Type;
+    use mosaicod_core::types;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn recording() -> Vec<u8> {
+        let mut writer = mcap::Writer::new(std::io::Cursor::new(Vec::new())).unwrap();
+
+        let json = writer
+            .add_channel(0, "/status", "json", &BTreeMap::new())
+            .unwrap();
+
+        let schema = writer
+            .add_schema(
+                "test.Reading",
+                "protobuf",
+                &protobuf::tests::reading_descriptor_set(),
+            )
+            .unwrap();
+        let proto = writer
+            .add_channel(schema, "/reading", "protobuf", &BTreeMap::new())
+            .unwrap();
+
+        let schema = writer.add_schema("pkg/msg/Raw", "ros1msg", b"").unwrap();
+        let ros1 = writer
+            .add_channel(schema, "/ros1", "ros1", &BTreeMap::new())
+            .unwrap();
+
+        for i in 0..3u64 {
+            let header = |channel_id| mcap::records::MessageHeader {
+                channel_id,
+                sequence: i as u32,
+                log_time: 100 + i,
+                publish_time: 100 + i,
+            };
+            writer
+                .write_to_known_channel(
+                    &header(json),
+                    format!(r#"{{"battery": {}, "mode": "auto"}}"#, i * 10).as_bytes(),
+                )
+                .unwrap();
+            writer
+                .write_to_known_channel(
+                    &header(proto),
+                    &protobuf::tests::reading("imu", &[i as f64]),
+                )
+                .unwrap();
+            writer
+                .write_to_known_channel(&header(ros1), &[0, 1, 2])
+                .unwrap();
+        }
+
+        writer.finish().unwrap();
+        writer.into_inner().into_inner()
+    }
+
+    #[test]
+    fn decode_recording() {
+        let data = recording();
+
+        let mut decoders = HashMap::new();
+        let mut unsupported = Vec::new();
+
+        for message in messages(data.as_slice()) {
+            let message = message.unwrap();
+            let channel = &message.channel;
+
+            if !decoders.contains_key(&channel.topic) {
+                match ChannelDecoder::try_new(channel) {
+                    Ok(decoder) => {
+                        decoders.insert(channel.topic.clone(), decoder);
+                    }
+                    Err(Error::UnsupportedEncoding { topic, .. }) => {
+                        if !unsupported.contains(&topic) {
+                            unsupported.push(topic);
+                        }
+                        continue;
+                    }
+                    Err(e) => panic!("{e}"),
+                }
+            }
+
+            decoders
+                .get_mut(&channel.topic)
+                .unwrap()
+                .push(message.log_time, &message.data)
+                .unwrap();
+        }
+
+        assert_eq!(unsupported, ["/ros1"]);
+
+        let status = decoders.get_mut("/status").unwrap();
+        assert_eq!(status.ontology_tag(), "json");
+        assert_eq!(status.format(), types::Format::Default);
+        let batch = status.flush().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.schema().field(0).name(), "timestamp_ns");
+        let ts = batch.column(0).as_primitive::<Int64Type>();
+        assert_eq!(ts.values(), &[100, 101, 102]);
+        assert!(batch.column_by_name("battery").is_some());
+        assert!(status.flush().unwrap().is_none());
+
+        let reading = decoders.get_mut("/reading").unwrap();
+        assert_eq!(reading.ontology_tag(), "test_reading");
+        assert_eq!(reading.format(), types::Format::Ragged);
+        let batch = reading.flush().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 4);
+    }
+
+    #[test]
+    fn read_recording_incrementally() {
+        let data = recording();
+
+        /// Reads at most one byte at a time
+        struct OneByte<'a>(&'a [u8]);
+        impl std::io::Read for OneByte<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(1);
+                self.0.read(&mut buf[..len])
+            }
+        }
+
+        let expected: Vec<_> = messages(data.as_slice())
+            .map(|m| {
+                let m = m.unwrap();
+                (m.channel.topic.clone(), m.log_time)
+            })
+            .collect();
+        assert_eq!(expected.len(), 9);
+
+        let read: Vec<_> = messages(OneByte(&data))
+            .map(|m| {
+                let m = m.unwrap();
+                (m.channel.topic.clone(), m.log_time)
+            })
+            .collect();
+        assert_eq!(read, expected);
+
+        // A truncated recording returns an error and stops the iteration
+        let truncated = &data[..data.len() / 2];
+        let mut messages = messages(truncated);
+        assert!(messages.by_ref().any(|m| m.is_err()));
+        assert!(messages.next().is_none());
+    }
+}
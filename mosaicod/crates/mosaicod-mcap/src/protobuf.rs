@@ -0,0 +1,274 @@
+//! Decoder for protobuf messages, described by the `FileDescriptorSet` embedded in the
+//! channel schema.
+
+use crate::Error;
+use crate::column::{Column, list_of};
+use arrow::datatypes::{DataType, Field, Fields};
+use prost_reflect::{DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+pub(crate) struct Decoder {
+    descriptor: prost_reflect::MessageDescriptor,
+    fields: Fields,
+}
+
+impl Decoder {
+    /// Builds a decoder for the message `name` defined in the `FileDescriptorSet` `data`.
+    pub fn try_new(name: &str, data: &[u8]) -> Result<Self, Error> {
+        let pool =
+            DescriptorPool::decode(data).map_err(|e| Error::invalid_schema(name, e.to_string()))?;
+
+        let descriptor = pool
+            .get_message_by_name(name)
+            .ok_or_else(|| Error::invalid_schema(name, "message not found in descriptor set"))?;
+
+        let fields = message_fields(&descriptor, &mut Vec::new())?;
+
+        Ok(Self { descriptor, fields })
+    }
+
+    /// Arrow fields of the message
+    pub fn arrow_fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    /// Decodes `data` appending its fields to `columns`, one for each field returned
+    /// by [`Decoder::arrow_fields`].
+    pub fn decode(&self, data: &[u8], columns: &mut [Column]) -> Result<(), Error> {
+        let msg = DynamicMessage::decode(self.descriptor.clone(), data)
+            .map_err(|e| Error::decode(e.to_string()))?;
+        push_message(&msg, &self.fields, columns)
+    }
+}
+
+/// Builds the arrow fields of a message.
+///
+/// Fields containing messages without fields are skipped, since they carry no data.
+fn message_fields(
+    descriptor: &prost_reflect::MessageDescriptor,
+    stack: &mut Vec<String>,
+) -> Result<Fields, Error> {
+    if stack.iter().any(|s| s == descriptor.full_name()) {
+        return Err(Error::invalid_schema(
+            descriptor.full_name(),
+            "recursive messages are not supported",
+        ));
+    }
+    stack.push(descriptor.full_name().to_owned());
+
+    let mut fields = Vec::new();
+    for field in descriptor.fields() {
+        if let Some(data_type) = field_type(&field, stack)? {
+            fields.push(Field::new(field.name(), data_type, false));
+        }
+    }
+
+    stack.pop();
+    Ok(fields.into())
+}
+
+fn field_type(field: &FieldDescriptor, stack: &mut Vec<String>) -> Result<Option<DataType>, Error> {
+    if field.is_map() {
+        let Kind::Message(entry) = field.kind() else {
+            unreachable!("map fields are always messages")
+        };
+        let key = kind_type(&entry.map_entry_key_field().kind(), stack)?;
+        let value = kind_type(&entry.map_entry_value_field().kind(), stack)?;
+        return Ok(key.zip(value).map(|(key, value)| {
+            list_of(DataType::Struct(Fields::from(vec![
+                Field::new("key", key, false),
+                Field::new("value", value, false),
+            ])))
+        }));
+    }
+
+    let data_type = kind_type(&field.kind(), stack)?;
+
+    Ok(if field.is_list() {
+        data_type.map(list_of)
+    } else {
+        data_type
+    })
+}
+
+fn kind_type(kind: &Kind, stack: &mut Vec<String>) -> Result<Option<DataType>, Error> {
+    Ok(Some(match kind {
+        Kind::Double => DataType::Float64,
+        Kind::Float => DataType::Float32,
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 | Kind::Enum(_) => DataType::Int32,
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => DataType::Int64,
+        Kind::Uint32 | Kind::Fixed32 => DataType::UInt32,
+        Kind::Uint64 | Kind::Fixed64 => DataType::UInt64,
+        Kind::Bool => DataType::Boolean,
+        Kind::String => DataType::Utf8,
+        Kind::Bytes => DataType::Binary,
+        Kind::Message(descriptor) => {
+            let fields = message_fields(descriptor, stack)?;
+            if fields.is_empty() {
+                return Ok(None);
+            }
+            DataType::Struct(fields)
+        }
+    }))
+}
+
+fn push_message(
+    msg: &DynamicMessage,
+    fields: &Fields,
+    columns: &mut [Column],
+) -> Result<(), Error> {
+    for (field, column) in fields.iter().zip(columns.iter_mut()) {
+        let value = msg
+            .get_field_by_name(field.name())
+            .ok_or_else(|| Error::decode(format!("missing field `{}`", field.name())))?;
+        push_value(&value, column)?;
+    }
+    Ok(())
+}
+
+fn push_value(value: &Value, column: &mut Column) -> Result<(), Error> {
+    match (value, column) {
+        (Value::Bool(v), Column::Bool(b)) => b.append_value(*v),
+        (Value::I32(v) | Value::EnumNumber(v), Column::Int32(b)) => b.append_value(*v),
+        (Value::I64(v), Column::Int64(b)) => b.append_value(*v),
+        (Value::U32(v), Column::UInt32(b)) => b.append_value(*v),
+        (Value::U64(v), Column::UInt64(b)) => b.append_value(*v),
+        (Value::F32(v), Column::Float32(b)) => b.append_value(*v),
+        (Value::F64(v), Column::Float64(b)) => b.append_value(*v),
+        (Value::String(v), Column::Utf8(b)) => b.append_value(v),
+        (Value::Bytes(v), Column::Binary(b)) => b.append_value(v),
+        (Value::Message(msg), Column::Struct { fields, children }) => {
+            push_message(msg, fields, children)?
+        }
+        (
+            Value::List(items),
+            Column::List {
+                offsets, values, ..
+            },
+        ) => {
+            for item in items {
+                push_value(item, values)?;
+            }
+            Column::close_list(offsets, items.len());
+        }
+        (
+            Value::Map(map),
+            Column::List {
+                offsets, values, ..
+            },
+        ) => {
+            let Column::Struct { children, .. } = values.as_mut() else {
+                unreachable!("map entries are stored as structs")
+            };
+            let [key_column, value_column] = children.as_mut_slice() else {
+                unreachable!("map entries have two fields")
+            };
+
+            // Sort the entries to obtain a deterministic output
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (key, value) in &entries {
+                push_value(&map_key_value(key), key_column)?;
+                push_value(value, value_column)?;
+            }
+            Column::close_list(offsets, entries.len());
+        }
+        (value, _) => return Err(Error::decode(format!("unexpected value {value:?}"))),
+    }
+    Ok(())
+}
+
+fn map_key_value(key: &MapKey) -> Value {
+    match key {
+        MapKey::Bool(v) => Value::Bool(*v),
+        MapKey::I32(v) => Value::I32(*v),
+        MapKey::I64(v) => Value::I64(*v),
+        MapKey::U32(v) => Value::U32(*v),
+        MapKey::U64(v) => Value::U64(*v),
+        MapKey::String(v) => Value::String(v.clone()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use arrow::array::{Array, AsArray, StructArray};
+    use arrow::datatypes::Float64Type;
+    use prost::Message;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    fn field(name: &str, number: i32, ty: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            r#type: Some(ty.into()),
+            label: Some(label.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the encoded `FileDescriptorSet` of the `test.Reading` message
+    pub fn reading_descriptor_set() -> Vec<u8> {
+        let file = FileDescriptorProto {
+            name: Some("reading.proto".to_owned()),
+            package: Some("test".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("Reading".to_owned()),
+                field: vec![
+                    field("sensor", 1, Type::String, Label::Optional),
+                    field("values", 2, Type::Double, Label::Repeated),
+                    field("raw", 3, Type::Bytes, Label::Optional),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        FileDescriptorSet { file: vec![file] }.encode_to_vec()
+    }
+
+    /// Returns an encoded `test.Reading` message
+    pub fn reading(sensor: &str, values: &[f64]) -> Vec<u8> {
+        let pool = DescriptorPool::decode(reading_descriptor_set().as_slice()).unwrap();
+        let mut msg = DynamicMessage::new(pool.get_message_by_name("test.Reading").unwrap());
+        msg.set_field_by_name("sensor", Value::String(sensor.to_owned()));
+        msg.set_field_by_name(
+            "values",
+            Value::List(values.iter().map(|v| Value::F64(*v)).collect()),
+        );
+        msg.encode_to_vec()
+    }
+
+    #[test]
+    fn decode_message() {
+        let decoder = Decoder::try_new("test.Reading", &reading_descriptor_set()).unwrap();
+
+        let fields = decoder.arrow_fields().clone();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[1].data_type(), &list_of(DataType::Float64));
+
+        let mut columns: Vec<Column> = fields.iter().map(|f| Column::new(f.data_type())).collect();
+        decoder
+            .decode(&reading("lidar", &[0.5, 1.0]), &mut columns)
+            .unwrap();
+
+        let arrays = columns.iter_mut().map(Column::finish).collect();
+        let array = StructArray::new(fields, arrays, None);
+
+        assert_eq!(array.len(), 1);
+        assert_eq!(array.column(0).as_string::<i32>().value(0), "lidar");
+        let values = array.column(1).as_list::<i32>().value(0);
+        assert_eq!(values.as_primitive::<Float64Type>().values(), &[0.5, 1.0]);
+        // Unset fields are decoded with their default value
+        assert!(array.column(2).as_binary::<i32>().value(0).is_empty());
+    }
+
+    #[test]
+    fn unknown_message() {
+        assert!(Decoder::try_new("test.Missing", &reading_descriptor_set()).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! Parser for the `ros2msg` schema encoding.
+//!
+//! The schema data contains the definition of the message followed by the definitions
+//! of all the nested message types, each one introduced by a separator line made
+//! of `=` and a `MSG: <package>/<type>` line.
+
+use crate::Error;
+use crate::column::list_of;
+use arrow::datatypes::{DataType, Field, Fields};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Primitive {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+}
+
+impl Primitive {
+    fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "bool" => Self::Bool,
+            "int8" => Self::Int8,
+            // In ROS 2 both `byte` and `char` are unsigned 8 bits values
+            "uint8" | "byte" | "char" => Self::UInt8,
+            "int16" => Self::Int16,
+            "uint16" => Self::UInt16,
+            "int32" => Self::Int32,
+            "uint32" => Self::UInt32,
+            "int64" => Self::Int64,
+            "uint64" => Self::UInt64,
+            "float32" => Self::Float32,
+            "float64" => Self::Float64,
+            _ => return None,
+        })
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Bool => DataType::Boolean,
+            Self::Int8 => DataType::Int8,
+            Self::UInt8 => DataType::UInt8,
+            Self::Int16 => DataType::Int16,
+            Self::UInt16 => DataType::UInt16,
+            Self::Int32 => DataType::Int32,
+            Self::UInt32 => DataType::UInt32,
+            Self::Int64 => DataType::Int64,
+            Self::UInt64 => DataType::UInt64,
+            Self::Float32 => DataType::Float32,
+            Self::Float64 => DataType::Float64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FieldType {
+    Primitive(Primitive),
+    String,
+    /// Nested message, identified by its normalized `<package>/<type>` name
+    Message(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Arity {
+    Single,
+    Array(usize),
+    /// Bounded and unbounded sequences share the same encoding
+    Sequence,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FieldDef {
+    pub name: String,
+    pub field_type: FieldType,
+    pub arity: Arity,
+}
+
+impl FieldDef {
+    /// Arrays of bytes are stored as binary values
+    pub fn is_bytes(&self) -> bool {
+        self.arity != Arity::Single && self.field_type == FieldType::Primitive(Primitive::UInt8)
+    }
+}
+
+/// Message definitions required to decode a message, indexed by normalized name
+#[derive(Debug)]
+pub(crate) struct Registry {
+    root: String,
+    messages: HashMap<String, Vec<FieldDef>>,
+}
+
+impl Registry {
+    /// Parses the `ros2msg` schema `data` of the message `name`.
+    pub fn parse(name: &str, data: &str) -> Result<Self, Error> {
+        let root = normalize(name);
+        let mut messages = HashMap::new();
+
+        // Types always available even if the recorder does not embed them
+        for builtin in ["builtin_interfaces/Time", "builtin_interfaces/Duration"] {
+            messages.insert(
+                builtin.to_owned(),
+                vec![
+                    field("sec", FieldType::Primitive(Primitive::Int32)),
+                    field("nanosec", FieldType::Primitive(Primitive::UInt32)),
+                ],
+            );
+        }
+
+        let mut current = root.clone();
+        let mut lines = Vec::new();
+
+        for line in data.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("==") && trimmed.chars().all(|c| c == '=') {
+                messages.insert(current.clone(), parse_fields(name, &current, &lines)?);
+                lines.clear();
+                current.clear();
+                continue;
+            }
+            if current.is_empty() {
+                if let Some(msg_name) = trimmed.strip_prefix("MSG:") {
+                    current = normalize(msg_name.trim());
+                }
+                continue;
+            }
+            lines.push(line);
+        }
+        if !current.is_empty() {
+            messages.insert(current.clone(), parse_fields(name, &current, &lines)?);
+        }
+
+        let registry = Self { root, messages };
+        registry.check(name)?;
+        Ok(registry)
+    }
+
+    pub fn root(&self) -> &[FieldDef] {
+        self.fields(&self.root)
+    }
+
+    pub fn fields(&self, name: &str) -> &[FieldDef] {
+        self.messages
+            .get(name)
+            .map(Vec::as_slice)
+            .expect("message definitions are checked when the registry is built")
+    }
+
+    /// Arrow fields of the root message
+    pub fn arrow_fields(&self) -> Fields {
+        self.arrow_fields_of(&self.root)
+    }
+
+    fn arrow_fields_of(&self, name: &str) -> Fields {
+        self.fields(name)
+            .iter()
+            .map(|f| Field::new(&f.name, self.arrow_type(f), false))
+            .collect()
+    }
+
+    fn arrow_type(&self, field: &FieldDef) -> DataType {
+        if field.is_bytes() {
+            return DataType::Binary;
+        }
+
+        let data_type = match &field.field_type {
+            FieldType::Primitive(p) => p.data_type(),
+            FieldType::String => DataType::Utf8,
+            FieldType::Message(name) => DataType::Struct(self.arrow_fields_of(name)),
+        };
+
+        match field.arity {
+            Arity::Single => data_type,
+            Arity::Array(_) | Arity::Sequence => list_of(data_type),
+        }
+    }
+
+    /// Ensures that all the referenced types are defined and that there are no cycles
+    fn check(&self, schema_name: &str) -> Result<(), Error> {
+        fn visit(
+            registry: &Registry,
+            schema_name: &str,
+            name: &str,
+            stack: &mut Vec<String>,
+        ) -> Result<(), Error> {
+            let Some(fields) = registry.messages.get(name) else {
+                return Err(Error::invalid_schema(
+                    schema_name,
+                    format!("missing definition of `{name}`"),
+                ));
+            };
+            if stack.iter().any(|s| s == name) {
+                return Err(Error::invalid_schema(
+                    schema_name,
+                    format!("recursive definition of `{name}`"),
+                ));
+            }
+            stack.push(name.to_owned());
+            for f in fields {
+                if let FieldType::Message(nested) = &f.field_type {
+                    visit(registry, schema_name, nested, stack)?;
+                }
+            }
+            stack.pop();
+            Ok(())
+        }
+
+        visit(self, schema_name, &self.root, &mut Vec::new())
+    }
+}
+
+fn field(name: &str, field_type: FieldType) -> FieldDef {
+    FieldDef {
+        name: name.to_owned(),
+        field_type,
+        arity: Arity::Single,
+    }
+}
+
+/// Normalizes a message name to the `<package>/<type>` form, the `msg` folder
+/// is optional in ROS 2 definitions.
+fn normalize(name: &str) -> String {
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [package, "msg", msg_type] => format!("{package}/{msg_type}"),
+        _ => name.to_owned(),
+    }
+}
+
+/// Parses the fields of the message `msg_name`, constants are skipped
+fn parse_fields(schema_name: &str, msg_name: &str, lines: &[&str]) -> Result<Vec<FieldDef>, Error> {
+    let package = msg_name.split('/').next().unwrap_or_default();
+    let mut fields = Vec::new();
+
+    for line in lines {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((type_token, rest)) = line.split_once(char::is_whitespace) else {
+            return Err(Error::invalid_schema(
+                schema_name,
+                format!("invalid field definition `{line}`"),
+            ));
+        };
+
+        // Constants are not part of the serialized message
+        if rest.contains('=') {
+            continue;
+        }
+
+        // Default values, if any, follow the field name
+        let name = rest.split_whitespace().next().unwrap_or_default();
+
+        let (base, arity) = parse_arity(schema_name, type_token)?;
+        // Drop string bounds (e.g. `string<=10`)
+        let base = base.split("<=").next().unwrap_or_default();
+
+        let field_type = if let Some(p) = Primitive::parse(base) {
+            FieldType::Primitive(p)
+        } else {
+            match base {
+                "string" => FieldType::String,
+                "wstring" => {
+                    return Err(Error::invalid_schema(
+                        schema_name,
+                        "`wstring` fields are not supported",
+                    ));
+                }
+                "Header" => FieldType::Message("std_msgs/Header".to_owned()),
+                name if name.contains('/') => FieldType::Message(normalize(name)),
+                name => FieldType::Message(format!("{package}/{name}")),
+            }
+        };
+
+        fields.push(FieldDef {
+            name: name.to_owned(),
+            field_type,
+            arity,
+        });
+    }
+
+    // Empty messages are serialized with a single dummy byte
+    if fields.is_empty() {
+        fields.push(field(
+            "structure_needs_at_least_one_member",
+            FieldType::Primitive(Primitive::UInt8),
+        ));
+    }
+
+    Ok(fields)
+}
+
+/// Splits the array suffix (`[]`, `[N]`, `[<=N]`) from a type token
+fn parse_arity<'a>(schema_name: &str, type_token: &'a str) -> Result<(&'a str, Arity), Error> {
+    let Some((base, suffix)) = type_token.split_once('[') else {
+        return Ok((type_token, Arity::Single));
+    };
+
+    let size = suffix.trim_end_matches(']');
+    if size.is_empty() || size.starts_with("<=") {
+        return Ok((base, Arity::Sequence));
+    }
+
+    let size = size.parse().map_err(|_| {
+        Error::invalid_schema(schema_name, format!("invalid array size in `{type_token}`"))
+    })?;
+
+    Ok((base, Arity::Array(size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMU: &str = "\
+std_msgs/Header header
+geometry_msgs/Quaternion orientation
+float64[9] orientation_covariance # Row major about x, y, z axes
+uint8 STATUS_OK=0
+================================================================================
+MSG: std_msgs/Header
+builtin_interfaces/Time stamp
+string frame_id
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x 0
+float64 y 0
+float64 z 0
+float64 w 1
+";
+
+    #[test]
+    fn parse_nested_definitions() {
+        let registry = Registry::parse("sensor_msgs/msg/Imu", IMU).unwrap();
+
+        let root = registry.root();
+        assert_eq!(root.len(), 3);
+        assert_eq!(
+            root[0].field_type,
+            FieldType::Message("std_msgs/Header".to_owned())
+        );
+        assert_eq!(root[2].arity, Arity::Array(9));
+
+        let fields = registry.arrow_fields();
+        assert_eq!(fields[0].name(), "header");
+        assert_eq!(
+            fields[1].data_type(),
+            &DataType::Struct(
+                ["x", "y", "z", "w"]
+                    .iter()
+                    .map(|n| Field::new(*n, DataType::Float64, false))
+                    .collect()
+            )
+        );
+        assert_eq!(fields[2].data_type(), &list_of(DataType::Float64));
+    }
+
+    #[test]
+    fn bytes_and_bounds() {
+        let registry = Registry::parse(
+            "pkg/msg/Blob",
+            "uint8[] data\nstring<=8 name\nint32[<=4] values",
+        )
+        .unwrap();
+        let fields = registry.arrow_fields();
+        assert_eq!(fields[0].data_type(), &DataType::Binary);
+        assert_eq!(fields[1].data_type(), &DataType::Utf8);
+        assert_eq!(fields[2].data_type(), &list_of(DataType::Int32));
+    }
+
+    #[test]
+    fn missing_definition() {
+        let res = Registry::parse("pkg/msg/Broken", "pkg/Missing value");
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{ChannelEncoder, Error};
+use mcap::records::MessageHeader;
+use mcap::write::NoSeek;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// In-memory sink shared with the MCAP writer, drained while the recording is produced
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().expect("poisoned buffer"))
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .expect("poisoned buffer")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes an MCAP recording without seeking, so that it can be streamed while it is
+/// produced.
+///
+/// Messages are collected in compressed chunks, the bytes of the recording become
+/// available through [`StreamWriter::take`] each time a chunk is completed.
+pub struct StreamWriter {
+    writer: mcap::Writer<NoSeek<Buffer>>,
+    buffer: Buffer,
+}
+
+impl StreamWriter {
+    pub fn try_new() -> Result<Self, Error> {
+        let buffer = Buffer::default();
+
+        let writer = mcap::WriteOptions::new()
+            .library(concat!("mosaicod ", env!("CARGO_PKG_VERSION")))
+            .disable_seeking(true)
+            .create(NoSeek::new(buffer.clone()))?;
+
+        Ok(Self { writer, buffer })
+    }
+
+    /// Adds a channel on `topic` for the messages produced by `encoder`, returning its id.
+    pub fn add_channel(&mut self, topic: &str, encoder: &ChannelEncoder) -> Result<u16, Error> {
+        let schema =
+            self.writer
+                .add_schema(encoder.schema_name(), "jsonschema", encoder.json_schema())?;
+
+        Ok(self
+            .writer
+            .add_channel(schema, topic, "json", encoder.metadata())?)
+    }
+
+    /// Writes a message on a channel returned by [`StreamWriter::add_channel`].
+    pub fn write(
+        &mut self,
+        channel_id: u16,
+        sequence: u32,
+        log_time: u64,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let header = MessageHeader {
+            channel_id,
+            sequence,
+            log_time,
+            publish_time: log_time,
+        };
+
+        Ok(self.writer.write_to_known_channel(&header, data)?)
+    }
+
+    /// Returns the bytes of the recording produced since the last call.
+    pub fn take(&mut self) -> Vec<u8> {
+        self.buffer.take()
+    }
+
+    /// Completes the recording, returning its remaining bytes.
+    pub fn finish(mut self) -> Result<Vec<u8>, Error> {
+        self.writer.finish()?;
+        Ok(self.buffer.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChannelDecoder, METADATA_ONTOLOGY_TAG, messages};
+    use arrow::array::{Float64Array, Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[test]
+    fn write_recording() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("battery", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(Float64Array::from(vec![0.5, 0.4, 0.3])),
+            ],
+        )
+        .unwrap();
+
+        let encoder = ChannelEncoder::try_new("status", &schema).unwrap();
+
+        let mut writer = StreamWriter::try_new().unwrap();
+        let channel = writer.add_channel("/robot/status", &encoder).unwrap();
+
+        let mut data = writer.take();
+        for (sequence, (log_time, message)) in encoder.encode(&batch).unwrap().iter().enumerate() {
+            writer
+                .write(channel, sequence as u32, *log_time, message)
+                .unwrap();
+            data.extend(writer.take());
+        }
+        data.extend(writer.finish().unwrap());
+
+        let mut decoder = None;
+        for message in messages(data.as_slice()) {
+            let message = message.unwrap();
+            assert_eq!(message.channel.topic, "/robot/status");
+            assert_eq!(message.channel.metadata[METADATA_ONTOLOGY_TAG], "status");
+
+            decoder
+                .get_or_insert_with(|| ChannelDecoder::try_new(&message.channel).unwrap())
+                .push(message.log_time, &message.data)
+                .unwrap();
+        }
+
+        // Exported recordings can be imported back, JSON fields are inferred as nullable
+        let decoded = decoder.unwrap().flush().unwrap().unwrap();
+        assert_eq!(decoded.columns(), batch.columns());
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Error, IsSupportedOp, OntologyExprGroup, OntologyField, Op, Value};
+use super::{Error, IsSupportedOp, Logical, OntologyExprGroup, OntologyField, Op, Value};
 
 const EMPTY_CLAUSE: &str = "()";
 
@@ -34,6 +34,56 @@ pub trait CompileClause {
         V: Into<Value> + IsSupportedOp;
 }
 
+/// Specify how compiled clauses are combined by [`Logical`] operators.
+///
+/// Default implementations produce SQL boolean expressions, each operand is wrapped in
+/// parentheses to preserve the precedence of the original filter.
+pub trait CompileLogical {
+    fn compile_and(&mut self, clauses: Vec<CompiledClause>) -> Result<CompiledClause, Error> {
+        Ok(join_clauses(clauses, " AND "))
+    }
+
+    fn compile_or(&mut self, clauses: Vec<CompiledClause>) -> Result<CompiledClause, Error> {
+        // An empty clause matches everything, so does the disjunction
+        if clauses.iter().any(CompiledClause::is_empty) {
+            return Ok(CompiledClause::empty());
+        }
+        Ok(join_clauses(clauses, " OR "))
+    }
+
+    fn compile_not(&mut self, clause: CompiledClause) -> Result<CompiledClause, Error> {
+        if clause.is_empty() {
+            return Err(Error::unsupported_op("$not".to_owned()));
+        }
+        let (clause, values) = clause.into_parts();
+        Ok(CompiledClause::new(format!("NOT ({clause})"), values))
+    }
+}
+
+/// Joins non-empty clauses with the given separator, values are kept in clauses order.
+///
+/// Each clause is wrapped in parentheses, unless it is the only one.
+pub fn join_clauses(clauses: Vec<CompiledClause>, separator: &str) -> CompiledClause {
+    let mut clauses: Vec<CompiledClause> = clauses.into_iter().filter(|c| !c.is_empty()).collect();
+
+    match clauses.len() {
+        0 => CompiledClause::empty(),
+        1 => clauses.remove(0),
+        _ => {
+            let mut joined = Vec::with_capacity(clauses.len());
+            let mut values = Vec::new();
+
+            for clause in clauses {
+                let (clause, mut v) = clause.into_parts();
+                joined.push(format!("({clause})"));
+                values.append(&mut v);
+            }
+
+            CompiledClause::new(format!("({})", joined.join(separator)), values)
+        }
+    }
+}
+
 /// Specify how a given ontology field needs to be formatted
 pub trait OntologyFieldFmt {
     fn ontology_column_fmt(&self, val: &OntologyField) -> String;
@@ -43,6 +93,9 @@ pub trait OntologyFieldFmt {
 pub struct CompilerResult {
     pub clauses: Vec<String>,
     pub values: Vec<Value>,
+
+    /// Number of values associated to each clause
+    values_count: Vec<usize>,
 }
 
 impl CompilerResult {
@@ -50,12 +103,33 @@ impl CompilerResult {
         Self {
             clauses: Vec::new(),
             values: Vec::new(),
+            values_count: Vec::new(),
         }
     }
 
     pub fn is_unfiltered(&self) -> bool {
         self.clauses.is_empty()
     }
+
+    /// Combines all the clauses in AND (see [`CompileLogical::compile_and`]),
+    /// producing a single clause.
+    pub fn into_clause<F>(self, formatter: &mut F) -> Result<CompiledClause, Error>
+    where
+        F: CompileLogical,
+    {
+        let mut values = self.values.into_iter();
+
+        let clauses = self
+            .clauses
+            .into_iter()
+            .zip(self.values_count)
+            .map(|(clause, count)| {
+                CompiledClause::new(clause, values.by_ref().take(count).collect())
+            })
+            .collect();
+
+        formatter.compile_and(clauses)
+    }
 }
 
 #[derive(Debug)]
@@ -94,14 +168,74 @@ impl ClausesCompiler {
         }
 
         // Unwrap here is safe since we checked the error above
-        let (clause, mut values) = result.unwrap().into_parts();
+        self.clause(result.unwrap())
+    }
+
+    /// Appends an already compiled clause
+    pub fn clause(mut self, clause: CompiledClause) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+
+        let (clause, mut values) = clause.into_parts();
 
         self.result.clauses.push(clause);
+        self.result.values_count.push(values.len());
         self.result.values.append(&mut values);
 
         self
     }
 
+    /// Appends a [`Logical`] node.
+    ///
+    /// Each operand of the node is compiled on a dedicated compiler by `compile`, then the
+    /// resulting clauses are combined by `formatter`. An empty result (i.e. a node that
+    /// does not constraint anything) is not appended.
+    pub fn logical<F, T, C>(
+        mut self,
+        logical: Logical<T>,
+        formatter: &mut F,
+        mut compile: C,
+    ) -> Self
+    where
+        F: CompileLogical,
+        C: FnMut(ClausesCompiler, T, &mut F) -> ClausesCompiler,
+    {
+        if self.error.is_some() {
+            return self;
+        }
+
+        let mut compile_operand = |operand: T, formatter: &mut F| {
+            compile(ClausesCompiler::new(), operand, formatter)
+                .compile()?
+                .into_clause(formatter)
+        };
+
+        let result = match logical {
+            Logical::And(operands) => operands
+                .into_iter()
+                .map(|op| compile_operand(op, formatter))
+                .collect::<Result<_, _>>()
+                .and_then(|clauses| formatter.compile_and(clauses)),
+            Logical::Or(operands) => operands
+                .into_iter()
+                .map(|op| compile_operand(op, formatter))
+                .collect::<Result<_, _>>()
+                .and_then(|clauses| formatter.compile_or(clauses)),
+            Logical::Not(operand) => compile_operand(*operand, formatter)
+                .and_then(|clause| formatter.compile_not(clause)),
+        };
+
+        match result {
+            Ok(clause) if clause.is_empty() => self,
+            Ok(clause) => self.clause(clause),
+            Err(err) => {
+                self.error = Some(err);
+                self
+            }
+        }
+    }
+
     // es: field = topic.user_metadata
     pub fn ontology_expr_group<F, V>(
         mut self,
@@ -110,18 +244,26 @@ impl ClausesCompiler {
     ) -> Self
     where
         V: Into<Value> + IsSupportedOp,
-        F: CompileClause + OntologyFieldFmt,
+        F: CompileClause + CompileLogical + OntologyFieldFmt,
     {
         if self.error.is_some() {
             return self;
         }
 
-        for expr in filter.into_iter() {
+        let (group, logical) = filter.into_parts();
+
+        for expr in group {
             let (ontology_field, op) = expr.into_parts();
             let field = formatter.ontology_column_fmt(&ontology_field);
             self = self.expr(&field, op, formatter);
         }
 
+        for node in logical {
+            self = self.logical(node, formatter, |qb, grp, formatter| {
+                qb.ontology_expr_group(grp, formatter)
+            });
+        }
+
         self
     }
 
@@ -13,9 +13,18 @@ pub enum Error {
     #[error("bad field `{field}`")]
     BadField { field: String },
 
+    #[error("unknown field `{field}`")]
+    UnknownField { field: String },
+
+    #[error("logical operators can't combine different ontology tags ({tags})")]
+    MixedOntologyTags { tags: String },
+
     #[error("datafusion backend error")]
     DataFusion(#[from] datafusion::error::DataFusionError),
 
+    #[error("invalid cursor `{0}`")]
+    BadCursor(String),
+
     #[error("not found")]
     NotFound,
 
@@ -24,6 +33,9 @@ pub enum Error {
 
     #[error("store error")]
     StoreError(#[from] mosaicod_store::Error),
+
+    #[error("data file read error")]
+    ReadError(#[from] mosaicod_rw::Error),
 }
 
 impl Error {
@@ -37,10 +49,27 @@ impl Error {
     pub fn bad_field(field_name: String) -> Self {
         Self::BadField { field: field_name }
     }
+
+    pub fn unknown_field(field_name: String) -> Self {
+        Self::UnknownField { field: field_name }
+    }
+
+    pub fn mixed_ontology_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut tags: Vec<&str> = tags.into_iter().collect();
+        tags.sort();
+        Self::MixedOntologyTags {
+            tags: tags.join(", "),
+        }
+    }
 }
 
 impl core::error::PublicError for Error {
     fn error(&self) -> core::Error {
-        core::Error::internal(Some("query engine failed".to_owned()))
+        match self {
+            Self::UnknownField { .. } | Self::BadCursor(_) => {
+                core::Error::bad_request(self.to_string())
+            }
+            _ => core::Error::internal(Some("query engine failed".to_owned())),
+        }
     }
 }
@@ -17,14 +17,23 @@
 //!     An expression is formed by binding a specific identifier (a field name or [`OntologyField`])
 //!     to an [`Op`]. It asserts a rule for that specific field (e.g., *"temperature > 25.0"*).
 //!
+//! -   _Logical_ ([`Logical`]): the boolean composition.
+//!     A [`Logical`] node combines nested filters with `AND`, `OR` and `NOT` operators
+//!     (e.g., *"acceleration.x > 5 OR acceleration.y > 5"*).
+//!
 //! -   _Filter_: the composite query.
 //!     A [`Filter`] is a collection of expressions grouped by domain (Sequence, Topic, Ontology,
 //!     ...).
 //!     It represents the complete set of criteria required to match a specific resource.
 //!
 
+use super::Page;
 use mosaicod_core::types;
-use std::{borrow::Borrow, collections::HashMap, collections::hash_map::Entry};
+use std::{
+    borrow::Borrow,
+    collections::hash_map::Entry,
+    collections::{HashMap, HashSet},
+};
 
 /// Floating point value type alias
 pub type Float = f64;
@@ -284,21 +293,106 @@ impl<T> From<(OntologyField, Op<T>)> for OntologyExpr<T> {
     }
 }
 
+/// Logical composition of filters.
+///
+/// A logical node is always nested inside a filter (or an expression group) and it is
+/// evaluated in AND with the other constraints of the same filter.
+#[derive(Debug, Clone)]
+pub enum Logical<T> {
+    /// Matches if all the inner filters match
+    And(Vec<T>),
+    /// Matches if at least one of the inner filters matches
+    Or(Vec<T>),
+    /// Matches if the inner filter does not match
+    Not(Box<T>),
+}
+
+impl<T> Logical<T> {
+    /// Returns the filters combined by this logical node
+    pub fn operands(&self) -> &[T] {
+        match self {
+            Self::And(v) | Self::Or(v) => v,
+            Self::Not(v) => std::slice::from_ref(v.as_ref()),
+        }
+    }
+}
+
+impl<T> Logical<OntologyExprGroup<T>> {
+    /// Returns the set of ontology tags referenced by this node
+    pub fn ontology_tags(&self) -> HashSet<&str> {
+        self.operands()
+            .iter()
+            .flat_map(|grp| grp.ontology_tags())
+            .collect()
+    }
+
+    /// Returns true if this node, or any node nested inside it, is a [`Logical::Not`]
+    pub fn has_negation(&self) -> bool {
+        matches!(self, Self::Not(_))
+            || self
+                .operands()
+                .iter()
+                .any(|grp| grp.logical.iter().any(Logical::has_negation))
+    }
+}
+
 /// An expression group is defined as a series of ontology fields
-/// with associated operations.
+/// with associated operations, combined in AND with a series of [`Logical`] nodes.
 #[derive(Debug, Clone)]
 pub struct OntologyExprGroup<T> {
     pub group: Vec<OntologyExpr<T>>,
+    pub logical: Vec<Logical<OntologyExprGroup<T>>>,
 }
 
 impl<T> OntologyExprGroup<T> {
     pub fn new(group: Vec<OntologyExpr<T>>) -> Self {
-        Self { group }
+        Self {
+            group,
+            logical: Vec::new(),
+        }
+    }
+
+    pub fn with_logical(mut self, logical: Vec<Logical<OntologyExprGroup<T>>>) -> Self {
+        self.logical = logical;
+        self
+    }
+
+    /// Returns true if the group contains no expressions
+    pub fn is_empty(&self) -> bool {
+        self.group.is_empty() && self.logical.is_empty()
+    }
+
+    /// Returns the set of ontology tags referenced by the group (logical nodes included)
+    pub fn ontology_tags(&self) -> HashSet<&str> {
+        self.group
+            .iter()
+            .map(|expr| expr.ontology_field().ontology_tag())
+            .chain(self.logical.iter().flat_map(|l| l.ontology_tags()))
+            .collect()
+    }
+
+    /// Returns the ontology tag of the first expression found in the group, if any
+    pub fn ontology_tag(&self) -> Option<&str> {
+        self.group
+            .first()
+            .map(|expr| expr.ontology_field().ontology_tag())
+            .or_else(|| {
+                self.logical
+                    .iter()
+                    .flat_map(Logical::operands)
+                    .find_map(OntologyExprGroup::ontology_tag)
+            })
+    }
+
+    pub fn into_parts(self) -> (Vec<OntologyExpr<T>>, Vec<Logical<OntologyExprGroup<T>>>) {
+        (self.group, self.logical)
     }
 
     /// Exports filter data as several expression groupss grouped by ontology tag
-    /// So if the
-    pub fn split_by_ontology_tag(self) -> Vec<OntologyExprGroup<T>> {
+    ///
+    /// Returns an error if a [`Logical`] node combines expressions on different
+    /// ontology tags, since each group is evaluated on data of a single ontology model.
+    pub fn split_by_ontology_tag(self) -> Result<Vec<OntologyExprGroup<T>>, super::Error> {
         let mut map: HashMap<String, OntologyExprGroup<T>> = HashMap::new();
         for expr in self.group {
             let tag = expr.ontology_field().ontology_tag();
@@ -312,22 +406,29 @@ impl<T> OntologyExprGroup<T> {
             }
         }
 
-        map.into_values().collect()
+        for logical in self.logical {
+            let tag = {
+                let tags = logical.ontology_tags();
+                if tags.len() > 1 {
+                    return Err(super::Error::mixed_ontology_tags(tags));
+                }
+                match tags.into_iter().next() {
+                    Some(tag) => tag.to_owned(),
+                    // Empty logical nodes do not constraint any ontology
+                    None => continue,
+                }
+            };
+
+            map.entry(tag).or_default().logical.push(logical);
+        }
+
+        Ok(map.into_values().collect())
     }
 }
 
 impl<T> Default for OntologyExprGroup<T> {
     fn default() -> Self {
-        Self { group: Vec::new() }
-    }
-}
-
-impl<T> IntoIterator for OntologyExprGroup<T> {
-    type Item = OntologyExpr<T>;
-    type IntoIter = std::vec::IntoIter<OntologyExpr<T>>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.group.into_iter()
+        Self::new(Vec::new())
     }
 }
 
@@ -336,9 +437,24 @@ impl<T> IntoIterator for OntologyExprGroup<T> {
 pub struct OntologyFilter {
     ontology: HashMap<OntologyField, Op<Value>>,
 
+    /// Logical nodes evaluated in AND with the `ontology` expressions
+    logical: Vec<Logical<OntologyExprGroup<Value>>>,
+
     /// If enabled the response should include timestamp ranges
     /// for each topic in which the query filter matches
     pub include_timestamp_range: bool,
+
+    /// If set the response should include, for each topic, the time intervals in which the
+    /// query filter matches
+    pub intervals: Option<IntervalOptions>,
+}
+
+/// Options of the time intervals returned for each topic matching an ontology filter
+#[derive(Debug, Clone, Copy)]
+pub struct IntervalOptions {
+    /// Maximum distance in nanoseconds between two runs of matching rows merged in the same
+    /// interval
+    pub max_gap_ns: u64,
 }
 
 impl OntologyFilter {
@@ -346,7 +462,9 @@ impl OntologyFilter {
     pub fn new(v: HashMap<OntologyField, Op<Value>>) -> Self {
         Self {
             ontology: v,
+            logical: Vec::new(),
             include_timestamp_range: false,
+            intervals: None,
         }
     }
 
@@ -356,15 +474,31 @@ impl OntologyFilter {
     ) -> Self {
         Self {
             ontology: v,
+            logical: Vec::new(),
             include_timestamp_range,
+            intervals: None,
         }
     }
 
+    /// Sets the logical nodes of the filter
+    pub fn with_logical(mut self, logical: Vec<Logical<OntologyExprGroup<Value>>>) -> Self {
+        self.logical = logical;
+        self
+    }
+
+    /// Sets the options of the matching time intervals
+    pub fn with_intervals(mut self, intervals: Option<IntervalOptions>) -> Self {
+        self.intervals = intervals;
+        self
+    }
+
     /// Creates an empty Metadata instance.
     pub fn empty() -> Self {
         Self {
             ontology: HashMap::new(),
+            logical: Vec::new(),
             include_timestamp_range: false,
+            intervals: None,
         }
     }
 
@@ -381,6 +515,7 @@ impl OntologyFilter {
                 .into_iter()
                 .map(|(o, v)| OntologyExpr(o, v))
                 .collect(),
+            logical: self.logical,
         }
     }
 }
@@ -449,6 +584,8 @@ pub struct Filter {
     pub sequence: Option<SequenceFilter>,
     pub topic: Option<TopicFilter>,
     pub ontology: Option<OntologyFilter>,
+    /// Page of the matching sequences to return
+    pub page: Page,
 }
 
 impl Filter {
@@ -463,21 +600,26 @@ impl Filter {
         Option<SequenceFilter>,
         Option<TopicFilter>,
         Option<OntologyFilter>,
+        Page,
     ) {
-        (self.sequence, self.topic, self.ontology)
+        (self.sequence, self.topic, self.ontology, self.page)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SequenceFilter {
     pub name: Option<Op<Text>>,
     pub created_at: Option<Op<Timestamp>>,
     pub user_metadata: HashMap<String, Op<Value>>,
+    pub logical: Vec<Logical<SequenceFilter>>,
 }
 
 impl SequenceFilter {
     pub fn is_empty(&self) -> bool {
-        self.name.is_none() && self.created_at.is_none() && self.user_metadata.is_empty()
+        self.name.is_none()
+            && self.created_at.is_none()
+            && self.user_metadata.is_empty()
+            && self.logical.is_empty()
     }
 }
 
@@ -488,6 +630,7 @@ pub struct TopicFilter {
     pub ontology_tag: Option<Op<Text>>,
     pub serialization_format: Option<Op<Text>>,
     pub user_metadata: HashMap<String, Op<Value>>,
+    pub logical: Vec<Logical<TopicFilter>>,
 }
 
 impl TopicFilter {
@@ -497,6 +640,7 @@ impl TopicFilter {
             && self.user_metadata.is_empty()
             && self.ontology_tag.is_none()
             && self.serialization_format.is_none()
+            && self.logical.is_empty()
     }
 }
 
@@ -513,6 +657,10 @@ mod tests {
         assert_eq!(oc.value(), "image.info.height");
     }
 
+    fn expr(field: &str, op: Op<Value>) -> OntologyExpr<Value> {
+        (OntologyField::try_new(field.into()).unwrap(), op).into()
+    }
+
     #[test]
     fn expr_grp_split() {
         let grp = OntologyExprGroup {
@@ -538,9 +686,10 @@ mod tests {
                 )
                     .into(),
             ],
+            logical: Vec::new(),
         };
 
-        let splits = grp.split_by_ontology_tag();
+        let splits = grp.split_by_ontology_tag().unwrap();
 
         dbg!(&splits);
 
@@ -551,4 +700,51 @@ mod tests {
             assert!(ontology_tag == "image" || ontology_tag == "imu");
         }
     }
+
+    #[test]
+    fn expr_grp_split_logical() {
+        let grp = OntologyExprGroup::new(vec![expr("image.width", Op::Eq(Value::Integer(1200)))])
+            .with_logical(vec![
+                Logical::Or(vec![
+                    OntologyExprGroup::new(vec![expr("imu.acc.x", Op::Gt(Value::Float(5.0)))]),
+                    OntologyExprGroup::new(vec![expr("imu.acc.y", Op::Gt(Value::Float(5.0)))]),
+                ]),
+                Logical::Not(Box::new(OntologyExprGroup::new(vec![expr(
+                    "image.height",
+                    Op::Lt(Value::Integer(100)),
+                )]))),
+            ]);
+
+        let splits = grp.split_by_ontology_tag().unwrap();
+        assert_eq!(splits.len(), 2);
+
+        for split in splits {
+            match split.ontology_tag().unwrap() {
+                "image" => {
+                    assert_eq!(split.group.len(), 1);
+                    assert_eq!(split.logical.len(), 1);
+                    assert!(split.logical[0].has_negation());
+                }
+                "imu" => {
+                    assert!(split.group.is_empty());
+                    assert_eq!(split.logical.len(), 1);
+                    assert!(!split.logical[0].has_negation());
+                }
+                tag => panic!("unexpected ontology tag `{tag}`"),
+            }
+        }
+    }
+
+    #[test]
+    fn expr_grp_split_mixed_tags() {
+        let grp = OntologyExprGroup::<Value>::default().with_logical(vec![Logical::Or(vec![
+            OntologyExprGroup::new(vec![expr("imu.acc.x", Op::Gt(Value::Float(5.0)))]),
+            OntologyExprGroup::new(vec![expr("gps.speed", Op::Gt(Value::Float(5.0)))]),
+        ])]);
+
+        assert!(matches!(
+            grp.split_by_ontology_tag(),
+            Err(crate::Error::MixedOntologyTags { .. })
+        ));
+    }
 }
@@ -6,6 +6,9 @@ pub use filter::*;
 mod placeholder;
 pub use placeholder::*;
 
+mod page;
+pub use page::*;
+
 mod builder;
 pub use builder::*;
 
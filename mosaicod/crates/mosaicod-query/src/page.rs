@@ -0,0 +1,273 @@
+//! # Query Result Pagination
+//!
+//! Query results are groups of topics sharing the same sequence. Pages are built over
+//! sequences: a [`Page`] selects an ordering of the matching sequences, the number of sequences
+//! to return and a [`Cursor`] pointing to the last sequence of the previous page.
+//!
+//! Cursors are keyset-based, so pages stay consistent even if sequences are created or deleted
+//! between two requests.
+
+use super::Error;
+use mosaicod_core::types;
+use std::cmp::Ordering;
+
+/// Ordering of the sequences returned by a query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderBy {
+    /// Sequences are sorted by locator name
+    #[default]
+    Name,
+    /// Sequences are sorted by creation time, sequences created at the same time are sorted by
+    /// locator name
+    CreatedAt,
+}
+
+impl std::fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name => write!(f, "name"),
+            Self::CreatedAt => write!(f, "created_at"),
+        }
+    }
+}
+
+/// Opaque position inside the ordered sequences of a query result.
+///
+/// A cursor holds the sort key of the last sequence of a page, the next page starts right
+/// after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cursor {
+    Name(String),
+    CreatedAt(types::Timestamp, String),
+}
+
+impl Cursor {
+    /// Builds the cursor pointing to `group` for the given ordering
+    pub fn from_group(order_by: OrderBy, group: &types::SequenceTopicGroup) -> Self {
+        let name = group.sequence.to_string();
+        match order_by {
+            OrderBy::Name => Self::Name(name),
+            OrderBy::CreatedAt => Self::CreatedAt(group.created_at.unwrap_or_default(), name),
+        }
+    }
+
+    /// Ordering used to build the cursor
+    pub fn order_by(&self) -> OrderBy {
+        match self {
+            Self::Name(_) => OrderBy::Name,
+            Self::CreatedAt(..) => OrderBy::CreatedAt,
+        }
+    }
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "name:{name}"),
+            Self::CreatedAt(ts, name) => write!(f, "created_at:{}:{name}", ts.as_i64()),
+        }
+    }
+}
+
+impl std::str::FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::BadCursor(s.to_owned());
+
+        let (order_by, key) = s.split_once(':').ok_or_else(invalid)?;
+
+        match order_by {
+            "name" if !key.is_empty() => Ok(Self::Name(key.to_owned())),
+            "created_at" => {
+                let (ts, name) = key.split_once(':').ok_or_else(invalid)?;
+                if name.is_empty() {
+                    return Err(invalid());
+                }
+                let ts: i64 = ts.parse().map_err(|_| invalid())?;
+                Ok(Self::CreatedAt(ts.into(), name.to_owned()))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Selects a page of the sequences matching a query
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    /// Maximum number of sequences to return, [`None`] returns all the sequences
+    pub limit: Option<usize>,
+    pub order_by: OrderBy,
+    /// Only sequences after the cursor are returned
+    pub after: Option<Cursor>,
+}
+
+impl Page {
+    /// Returns a new page selecting the sequences after `cursor`.
+    ///
+    /// Fails if the cursor was built for a different ordering.
+    pub fn try_new(
+        limit: Option<usize>,
+        order_by: OrderBy,
+        after: Option<Cursor>,
+    ) -> Result<Self, Error> {
+        if let Some(cursor) = &after
+            && cursor.order_by() != order_by
+        {
+            return Err(Error::BadCursor(format!(
+                "{cursor} (requested ordering is `{order_by}`)"
+            )));
+        }
+
+        Ok(Self {
+            limit,
+            order_by,
+            after,
+        })
+    }
+
+    /// Compares two groups using the page ordering
+    pub fn cmp_groups(
+        &self,
+        a: &types::SequenceTopicGroup,
+        b: &types::SequenceTopicGroup,
+    ) -> Ordering {
+        let by_name = || sequence_name(a).cmp(sequence_name(b));
+        match self.order_by {
+            OrderBy::Name => by_name(),
+            OrderBy::CreatedAt => a.created_at.cmp(&b.created_at).then_with(by_name),
+        }
+    }
+
+    /// Sorts `groups`, drops the groups up to the cursor and keeps at most `limit` groups.
+    ///
+    /// If more groups are available after the page, a cursor to request the next page is
+    /// returned along with the page.
+    pub fn apply(
+        &self,
+        groups: types::SequenceTopicGroupSet,
+    ) -> (types::SequenceTopicGroupSet, Option<Cursor>) {
+        let mut groups: Vec<types::SequenceTopicGroup> = groups.into();
+
+        groups.sort_unstable_by(|a, b| self.cmp_groups(a, b));
+
+        if let Some(cursor) = &self.after {
+            groups.retain(|grp| self.is_after(grp, cursor));
+        }
+
+        let mut next = None;
+        if let Some(limit) = self.limit
+            && groups.len() > limit
+        {
+            groups.truncate(limit);
+            next = groups
+                .last()
+                .map(|grp| Cursor::from_group(self.order_by, grp));
+        }
+
+        (groups.into(), next)
+    }
+
+    fn is_after(&self, group: &types::SequenceTopicGroup, cursor: &Cursor) -> bool {
+        let name = sequence_name(group);
+        match cursor {
+            Cursor::Name(after) => name > after.as_str(),
+            Cursor::CreatedAt(ts, after) => {
+                let created_at = group.created_at.unwrap_or_default();
+                (created_at, name) > (*ts, after.as_str())
+            }
+        }
+    }
+}
+
+fn sequence_name(group: &types::SequenceTopicGroup) -> &str {
+    &group.sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, created_at: i64) -> types::SequenceTopicGroup {
+        types::SequenceTopicGroup::new(name.parse().unwrap(), vec![])
+            .with_created_at(created_at.into())
+    }
+
+    fn names(groups: types::SequenceTopicGroupSet) -> Vec<String> {
+        let groups: Vec<types::SequenceTopicGroup> = groups.into();
+        groups.into_iter().map(|g| g.sequence.to_string()).collect()
+    }
+
+    fn groups() -> types::SequenceTopicGroupSet {
+        vec![
+            group("seq_c", 10),
+            group("seq_a", 30),
+            group("seq_d", 20),
+            group("seq_b", 10),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn cursor_roundtrip() {
+        for cursor in [
+            Cursor::Name("seq/a".to_owned()),
+            Cursor::CreatedAt(42.into(), "seq_a".to_owned()),
+        ] {
+            assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+        }
+
+        assert!("".parse::<Cursor>().is_err());
+        assert!("name:".parse::<Cursor>().is_err());
+        assert!("size:seq_a".parse::<Cursor>().is_err());
+        assert!("created_at:seq_a".parse::<Cursor>().is_err());
+        assert!("created_at:abc:seq_a".parse::<Cursor>().is_err());
+    }
+
+    #[test]
+    fn page_by_name() {
+        let page = Page::try_new(Some(2), OrderBy::Name, None).unwrap();
+        let (first, cursor) = page.apply(groups());
+        assert_eq!(names(first), vec!["seq_a", "seq_b"]);
+        assert_eq!(cursor, Some(Cursor::Name("seq_b".to_owned())));
+
+        let page = Page::try_new(Some(2), OrderBy::Name, cursor).unwrap();
+        let (second, cursor) = page.apply(groups());
+        assert_eq!(names(second), vec!["seq_c", "seq_d"]);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn page_by_creation_time() {
+        let page = Page::try_new(Some(1), OrderBy::CreatedAt, None).unwrap();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = Page {
+                after: cursor,
+                ..page.clone()
+            };
+            let (groups, next) = page.apply(groups());
+            seen.extend(names(groups));
+            if next.is_none() {
+                break;
+            }
+            cursor = next;
+        }
+
+        assert_eq!(seen, vec!["seq_b", "seq_c", "seq_d", "seq_a"]);
+    }
+
+    #[test]
+    fn page_cursor_ordering_mismatch() {
+        assert!(
+            Page::try_new(
+                None,
+                OrderBy::CreatedAt,
+                Some(Cursor::Name("seq_a".to_owned()))
+            )
+            .is_err()
+        );
+    }
+}
@@ -4,24 +4,36 @@
 //!
 //! The engine integrates directly with the configured [`store::Store`] to resolve
 //! paths and access data sources like Parquet files efficiently.
-use super::{Error, OntologyExprGroup, OntologyField, Op, Value};
-use arrow::datatypes::{Schema, SchemaRef};
+use super::{Error, Logical, OntologyExprGroup, OntologyField, Op, Value};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::listing::{ListingTable, ListingTableConfig, ListingTableUrl};
 use datafusion::execution::SendableRecordBatchStream;
 use datafusion::execution::disk_manager::DiskManagerBuilder;
 use datafusion::execution::memory_pool::FairSpillPool;
 use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
 use datafusion::functions::core::expr_ext::FieldAccessor;
-use datafusion::functions_aggregate::expr_fn::{max, min};
+use datafusion::functions_aggregate::expr_fn::{count, max, min};
+use datafusion::functions_aggregate::sum::sum_udaf;
+use datafusion::functions_window::expr_fn::lag;
+use datafusion::logical_expr::{ExprFunctionExt, WindowFunctionDefinition, expr::WindowFunction};
 use datafusion::prelude::*;
 use datafusion::scalar::ScalarValue;
 use log::trace;
 use mosaicod_core::{params, types};
-use mosaicod_rw::ToParquetProperties;
+use mosaicod_rw::{ToListingOptions, ToProperties};
 use mosaicod_store as store;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
+mod align;
+pub use align::*;
+
+mod list;
+use list::array_any_between;
+
+mod video;
+
 pub type TimeseriesEngineRef = Arc<TimeseriesEngine>;
 
 pub struct TimeseriesEngine {
@@ -66,28 +78,37 @@ impl TimeseriesEngine {
         format: types::Format,
         batch_size: Option<usize>,
     ) -> Result<TimeseriesResult, Error> {
-        // Use Parquet format strategy for listing options
-        let parquet_strategy = format
-            .to_parquet_properties()
-            .expect("TimeseriesGateway::read requires a Parquet-based format");
-        let listing_options = parquet_strategy.listing_options();
+        self.read_files(&[path], format, batch_size, false).await
+    }
 
-        let mut conf = SessionConfig::new();
-        if let Some(batch_size) = batch_size {
-            conf = conf.with_batch_size(batch_size);
+    /// Read time-series data from a list of paths, each one being a data file or a folder of
+    /// data files. Data files must share the same schema.
+    ///
+    /// If `ordered` is set, the data files are assumed to be sorted by timestamp and
+    /// non-overlapping when taken in the provided order (files of a folder are taken in name
+    /// order). They are scanned one after the other in a single partition, so the query engine
+    /// can stream them without sorting the whole dataset. The ordering is still verified against
+    /// the files statistics, falling back to a global sort if it can't be proven.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `paths` is empty.
+    pub async fn read_files<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+        format: types::Format,
+        batch_size: Option<usize>,
+        ordered: bool,
+    ) -> Result<TimeseriesResult, Error> {
+        let mut config = self.session_config(batch_size);
+        if ordered {
+            config = config.with_target_partitions(1);
         }
-
-        let ctx = SessionContext::new_with_config_rt(conf, self.runtime.clone());
+        let ctx = SessionContext::new_with_config_rt(config, self.runtime.clone());
 
         // we use `data` as internal reference for this context
-        ctx.register_listing_table(
-            "data",
-            self.datafile_url(path)?,
-            listing_options,
-            None,
-            None,
-        )
-        .await?;
+        self.register_data(&ctx, "data", paths, format, ordered)
+            .await?;
 
         let select = format!(
             "SELECT * FROM data ORDER BY {}",
@@ -99,6 +120,77 @@ impl TimeseriesEngine {
         Ok(TimeseriesResult { data_frame: df })
     }
 
+    fn session_config(&self, batch_size: Option<usize>) -> SessionConfig {
+        let mut conf = SessionConfig::new();
+        if let Some(batch_size) = batch_size {
+            conf = conf.with_batch_size(batch_size);
+        }
+        conf
+    }
+
+    fn session_context(&self, batch_size: Option<usize>) -> SessionContext {
+        SessionContext::new_with_config_rt(self.session_config(batch_size), self.runtime.clone())
+    }
+
+    /// Registers all the data files in `paths` as the table `name` of the provided context.
+    ///
+    /// If `sorted` is set, each data file is declared as sorted by timestamp.
+    async fn register_data<P: AsRef<Path>>(
+        &self,
+        ctx: &SessionContext,
+        name: &str,
+        paths: &[P],
+        format: types::Format,
+        sorted: bool,
+    ) -> Result<(), Error> {
+        // Frames of video topics need to be decoded, so their data files can't be listed
+        if format.to_properties().video_coding() {
+            let table =
+                video::VideoTable::try_new(self.store.clone(), paths, format, sorted).await?;
+
+            // Decoded frames are much larger than the stored ones, so batches are bounded
+            // to keep query results within the target message size
+            if let Some(rows) = table.max_batch_rows() {
+                let state = ctx.state_ref();
+                let mut state = state.write();
+                let config = state.config_mut();
+                config.options_mut().execution.batch_size = config.batch_size().min(rows);
+            }
+
+            ctx.register_table(name, Arc::new(table))?;
+            return Ok(());
+        }
+
+        // Parquet and Arrow IPC files are listed with the options of their format strategy
+        let mut listing_options = format.to_listing_options();
+
+        if sorted {
+            listing_options = listing_options
+                .with_collect_stat(true)
+                .with_file_sort_order(vec![vec![
+                    col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP).sort(true, false),
+                ]]);
+        }
+
+        let urls = paths
+            .iter()
+            .map(|path| Ok(ListingTableUrl::parse(self.datafile_url(path)?)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // Data files share the same schema, so it is inferred from the first path only
+        let schema = listing_options
+            .infer_schema(&ctx.state(), urls.first().expect("at least a path to read"))
+            .await?;
+
+        let config = ListingTableConfig::new_with_multi_paths(urls)
+            .with_listing_options(listing_options)
+            .with_schema(schema);
+
+        ctx.register_table(name, Arc::new(ListingTable::try_new(config)?))?;
+
+        Ok(())
+    }
+
     fn datafile_url(&self, path: impl AsRef<Path>) -> Result<url::Url, Error> {
         Ok(self
             .store
@@ -141,11 +233,32 @@ impl TimeseriesResult {
         Ok(self)
     }
 
+    /// Projects the data on the provided (possibly nested, dotted) field paths.
+    ///
+    /// Projected columns are named after the full path (e.g. `pose.position.x`), while the
+    /// timestamp column is always retained. Since the projection is pushed down into the query
+    /// plan, columns not requested are never read from the underlying files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::UnknownField`] if a path does not exist in the data schema.
+    pub fn project(self, fields: &[String]) -> Result<Self, Error> {
+        // Fail early if some fields are not part of the schema
+        projected_schema(self.data_frame.schema().as_arrow(), fields)?;
+
+        let exprs = std::iter::once(col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP))
+            .chain(projection_paths(fields).map(|path| unfold_path(path).alias(path)));
+
+        Ok(TimeseriesResult {
+            data_frame: self.data_frame.select(exprs.collect::<Vec<_>>())?,
+        })
+    }
+
     pub fn filter<V>(self, filter: OntologyExprGroup<V>) -> Result<Self, Error>
     where
         V: Into<Value>,
     {
-        let expr = expr_group_to_df_expr(filter);
+        let expr = expr_group_to_df_expr(filter, self.data_frame.schema().as_arrow())?;
 
         let data_frame = if let Some(expr) = expr {
             trace!("filter expression: {}", expr);
@@ -210,6 +323,173 @@ impl TimeseriesResult {
 
         Err(Error::NotFound)
     }
+
+    /// Returns the timestamp range and the number of the rows matching the current query,
+    /// [`None`] if no row matches.
+    pub async fn rows_summary(self) -> Result<Option<RowsSummary>, Error> {
+        let stats = self.data_frame.aggregate(
+            vec![],
+            vec![
+                min(col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)),
+                max(col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)),
+                count(lit(1)),
+            ],
+        )?;
+
+        let batches = stats.collect().await?;
+
+        let Some(batch) = batches.first() else {
+            return Ok(None);
+        };
+
+        let rows = match ScalarValue::try_from_array(batch.column(2), 0)? {
+            ScalarValue::Int64(Some(rows)) if rows > 0 => rows as u64,
+            _ => return Ok(None),
+        };
+
+        let ts_min = scalar_value_to_timestamp(ScalarValue::try_from_array(batch.column(0), 0)?);
+        let ts_max = scalar_value_to_timestamp(ScalarValue::try_from_array(batch.column(1), 0)?);
+
+        let (Some(ts_min), Some(ts_max)) = (ts_min, ts_max) else {
+            return Err(Error::bad_field(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned(),
+            ));
+        };
+
+        Ok(Some(RowsSummary {
+            timestamp_range: types::TimestampRange::between(ts_min, ts_max),
+            rows,
+        }))
+    }
+
+    /// Returns the time intervals in which `filter` holds, sorted by timestamp.
+    ///
+    /// The filter is evaluated as a boolean column over all the rows, taken in timestamp
+    /// order, so an interval is a run of consecutive matching rows: a matching row starts a
+    /// new run if the previous row (found with a `lag` window) doesn't match, and the running
+    /// count of run starts identifies the run of each row. Runs closer than `max_gap_ns` are
+    /// then merged with [`merge_intervals`].
+    ///
+    /// The filter must not be applied to the current query with [`TimeseriesResult::filter`],
+    /// otherwise the non-matching rows splitting the runs would be lost.
+    pub async fn intervals<V>(
+        self,
+        filter: OntologyExprGroup<V>,
+        max_gap_ns: u64,
+    ) -> Result<Vec<RowsSummary>, Error>
+    where
+        V: Into<Value>,
+    {
+        let ts = || col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+        let order = || vec![ts().sort(true, false)];
+
+        // Rows where the filter evaluates to null don't match
+        let matches = expr_group_to_df_expr(filter, self.data_frame.schema().as_arrow())?
+            .map(|expr| expr.is_true())
+            .unwrap_or(lit(true));
+
+        let prev_matches = lag(
+            col("matches"),
+            Some(1),
+            Some(ScalarValue::Boolean(Some(false))),
+        )
+        .order_by(order())
+        .build()?
+        .alias("prev_matches");
+
+        let is_start = when(col("matches").and(col("prev_matches").not()), lit(1_i64))
+            .otherwise(lit(0_i64))?;
+
+        let run = Expr::from(WindowFunction::new(
+            WindowFunctionDefinition::AggregateUDF(sum_udaf()),
+            vec![col("is_start")],
+        ))
+        .order_by(order())
+        .build()?
+        .alias("run");
+
+        let runs = self
+            .data_frame
+            .select(vec![ts(), matches.alias("matches")])?
+            .window(vec![prev_matches])?
+            .with_column("is_start", is_start)?
+            .window(vec![run])?
+            .filter(col("matches"))?
+            .aggregate(
+                vec![col("run")],
+                vec![
+                    min(ts()).alias("start"),
+                    max(ts()).alias("end"),
+                    count(lit(1)).alias("rows"),
+                ],
+            )?
+            .select(vec![col("start"), col("end"), col("rows")])?;
+
+        let mut result = Vec::new();
+        for batch in runs.collect().await? {
+            for row in 0..batch.num_rows() {
+                let start =
+                    scalar_value_to_timestamp(ScalarValue::try_from_array(batch.column(0), row)?);
+                let end =
+                    scalar_value_to_timestamp(ScalarValue::try_from_array(batch.column(1), row)?);
+                let rows = ScalarValue::try_from_array(batch.column(2), row)?;
+
+                let (Some(start), Some(end), ScalarValue::Int64(Some(rows))) = (start, end, rows)
+                else {
+                    return Err(Error::bad_field(
+                        params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned(),
+                    ));
+                };
+
+                result.push(RowsSummary {
+                    timestamp_range: types::TimestampRange::between(start, end),
+                    rows: rows as u64,
+                });
+            }
+        }
+
+        Ok(merge_intervals(result, max_gap_ns))
+    }
+}
+
+/// Merges the overlapping intervals and the intervals closer than `max_gap_ns`, returning
+/// the intervals sorted by timestamp.
+fn merge_intervals(mut intervals: Vec<RowsSummary>, max_gap_ns: u64) -> Vec<RowsSummary> {
+    intervals.sort_unstable_by_key(|i| i.timestamp_range.start);
+
+    let mut merged: Vec<RowsSummary> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        if let Some(last) = merged.last_mut() {
+            let gap = interval.timestamp_range.start.as_i64() - last.timestamp_range.end.as_i64();
+            if gap <= 0 || gap.unsigned_abs() <= max_gap_ns {
+                *last = last.clone().merge(interval);
+                continue;
+            }
+        }
+        merged.push(interval);
+    }
+
+    merged
+}
+
+/// Timestamp range and number of the rows of a query result
+#[derive(Debug, Clone)]
+pub struct RowsSummary {
+    pub timestamp_range: types::TimestampRange,
+    pub rows: u64,
+}
+
+impl RowsSummary {
+    /// Combines the summaries of two disjoint sets of rows
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            timestamp_range: types::TimestampRange::between(
+                self.timestamp_range.start.min(other.timestamp_range.start),
+                self.timestamp_range.end.max(other.timestamp_range.end),
+            ),
+            rows: self.rows + other.rows,
+        }
+    }
 }
 
 fn scalar_value_to_timestamp(value: ScalarValue) -> Option<types::Timestamp> {
@@ -219,8 +499,68 @@ fn scalar_value_to_timestamp(value: ScalarValue) -> Option<types::Timestamp> {
     }
 }
 
+/// Builds the schema resulting from the projection of `fields` over `schema`.
+///
+/// See [`TimeseriesResult::project`] for the projection rules.
+///
+/// # Errors
+///
+/// Returns an [`Error::UnknownField`] if a path does not exist in `schema`.
+pub fn projected_schema(schema: &Schema, fields: &[String]) -> Result<Schema, Error> {
+    let timestamp = schema
+        .field_with_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+        .map_err(|_| {
+            Error::unknown_field(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned())
+        })?;
+
+    let mut projected = vec![timestamp.clone()];
+    for path in projection_paths(fields) {
+        projected.push(field_from_path(schema, path)?);
+    }
+
+    Ok(Schema::new(projected))
+}
+
+/// Returns the unique field paths to project, skipping the timestamp column since it is
+/// always part of the projection.
+fn projection_paths(fields: &[String]) -> impl Iterator<Item = &str> {
+    let mut seen = HashSet::new();
+    fields.iter().map(String::as_str).filter(move |path| {
+        *path != params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP && seen.insert(*path)
+    })
+}
+
+/// Resolves a dotted path (e.g. `pose.position.x`) into a field named after the full path.
+/// The resulting field is nullable if any of the traversed fields is nullable.
+fn field_from_path(schema: &Schema, path: &str) -> Result<Field, Error> {
+    let unknown = || Error::unknown_field(path.to_owned());
+
+    let mut subfields = path.split('.');
+    // By construction split returns at least a value
+    let mut field = schema
+        .field_with_name(subfields.next().unwrap_or_default())
+        .map_err(|_| unknown())?;
+    let mut nullable = field.is_nullable();
+
+    // *Note*: only structs fields can be traversed
+    for subfield in subfields {
+        let DataType::Struct(children) = field.data_type() else {
+            return Err(unknown());
+        };
+        let (_, child) = children.find(subfield).ok_or_else(unknown)?;
+        nullable |= child.is_nullable();
+        field = child.as_ref();
+    }
+
+    Ok(field.clone().with_name(path).with_nullable(nullable))
+}
+
 fn unfold_field(field: &OntologyField) -> Expr {
-    let mut fields = field.field().split(".");
+    unfold_path(field.field())
+}
+
+fn unfold_path(path: &str) -> Expr {
+    let mut fields = path.split(".");
     // By construction fields needs to have at least a value
     let mut col = col(fields.next().unwrap());
     for s in fields {
@@ -229,15 +569,33 @@ fn unfold_field(field: &OntologyField) -> Expr {
     col
 }
 
-fn expr_group_to_df_expr<V>(filter: OntologyExprGroup<V>) -> Option<Expr>
+/// Returns `true` if `path` resolves to a list field in `schema`.
+fn is_list_path(schema: &Schema, path: &str) -> bool {
+    field_from_path(schema, path).is_ok_and(|field| {
+        matches!(
+            field.data_type(),
+            DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(..)
+        )
+    })
+}
+
+fn expr_group_to_df_expr<V>(
+    filter: OntologyExprGroup<V>,
+    schema: &Schema,
+) -> Result<Option<Expr>, Error>
 where
     V: Into<Value>,
 {
-    let mut ret: Option<Expr> = None;
+    let (group, logical) = filter.into_parts();
 
-    for expr in filter.into_iter() {
+    let exprs = group.into_iter().map(|expr| {
         let (field, op) = expr.into_parts();
-        let expr = match op {
+
+        if is_list_path(schema, field.field()) {
+            return list_op_to_df_expr(&field, op);
+        }
+
+        Ok(match op {
             Op::Eq(v) => Some(unfold_field(&field).eq(value_to_df_expr(v.into()))),
             Op::Neq(v) => Some(unfold_field(&field).not_eq(value_to_df_expr(v.into()))),
             Op::Leq(v) => Some(unfold_field(&field).lt_eq(value_to_df_expr(v.into()))),
@@ -261,18 +619,97 @@ where
                 Some(unfold_field(&field).in_list(list, false))
             }
             Op::Match(v) => Some(unfold_field(&field).like(value_to_df_expr(v.into()))),
-        };
+        })
+    });
 
-        if let Some(expr) = expr {
-            if ret.is_none() {
-                ret = Some(expr);
-            } else {
-                ret = Some(ret.unwrap().and(expr));
-            }
+    let logical_exprs = logical
+        .into_iter()
+        .map(|logical| logical_to_df_expr(logical, schema));
+
+    Ok(exprs
+        .chain(logical_exprs)
+        .collect::<Result<Vec<_>, Error>>()?
+        .into_iter()
+        .flatten()
+        .reduce(|acc, expr| acc.and(expr)))
+}
+
+/// Converts an operation on a list field (e.g. the coordinates of a point cloud scan) into a
+/// datafusion expression.
+///
+/// Comparisons match a row if any item of the list satisfies them, so they are evaluated on
+/// the list bounds, while `$between` checks each item of the list.
+fn list_op_to_df_expr<V>(field: &OntologyField, op: Op<V>) -> Result<Option<Expr>, Error>
+where
+    V: Into<Value>,
+{
+    let list = || unfold_field(field);
+    let lower = || array_min(list());
+    let upper = || array_max(list());
+
+    Ok(match op {
+        Op::Eq(v) => Some(array_has(list(), value_to_df_expr(v.into()))),
+        Op::Leq(v) => Some(lower().lt_eq(value_to_df_expr(v.into()))),
+        Op::Lt(v) => Some(lower().lt(value_to_df_expr(v.into()))),
+        Op::Geq(v) => Some(upper().gt_eq(value_to_df_expr(v.into()))),
+        Op::Gt(v) => Some(upper().gt(value_to_df_expr(v.into()))),
+        Op::Ex => None,  // no-op
+        Op::Nex => None, // no-op
+        Op::Between(range) => {
+            let vmin: Value = range.min.into();
+            let vmax: Value = range.max.into();
+            Some(array_any_between(
+                list(),
+                value_to_df_expr(vmin),
+                value_to_df_expr(vmax),
+            ))
         }
-    }
+        Op::In(items) => {
+            let items = items
+                .into_iter()
+                .map(|v| value_to_df_expr(v.into()))
+                .collect();
+            Some(array_has_any(list(), make_array(items)))
+        }
+        Op::Neq(_) | Op::Match(_) => {
+            return Err(Error::unsupported_op(field.field().to_owned()));
+        }
+    })
+}
+
+/// Converts a [`Logical`] node into a datafusion expression.
+///
+/// Operands without any expression (e.g. only `$ex` or `$nex`) are handled as no-op, so they
+/// are skipped in `$and` and they make the whole `$or` node a no-op, while they can't be
+/// negated by `$not` (as in the SQL clauses built by [`crate::CompileLogical`]).
+fn logical_to_df_expr<V>(
+    logical: Logical<OntologyExprGroup<V>>,
+    schema: &Schema,
+) -> Result<Option<Expr>, Error>
+where
+    V: Into<Value>,
+{
+    let exprs = |groups: Vec<OntologyExprGroup<V>>| {
+        groups
+            .into_iter()
+            .map(|group| expr_group_to_df_expr(group, schema))
+            .collect::<Result<Vec<_>, Error>>()
+    };
 
-    ret
+    Ok(match logical {
+        Logical::And(groups) => exprs(groups)?
+            .into_iter()
+            .flatten()
+            .reduce(|acc, expr| acc.and(expr)),
+        Logical::Or(groups) => exprs(groups)?
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .and_then(|exprs| exprs.into_iter().reduce(|acc, expr| acc.or(expr))),
+        Logical::Not(group) => match expr_group_to_df_expr(*group, schema)? {
+            Some(expr) => Some(expr.not()),
+            None => return Err(Error::unsupported_op("$not".to_owned())),
+        },
+    })
 }
 
 fn value_to_df_expr(v: Value) -> Expr {
@@ -306,6 +743,138 @@ mod tests {
         store.write_to_path(file_path, buffer).await.unwrap();
     }
 
+    /// Writes a parquet file with 5 rows, starting from timestamp `first`.
+    async fn write_timestamps_file(store: &store::Store, file_path: &str, first: i64) {
+        use ::arrow::array::{Int64Array, RecordBatch};
+        use parquet::arrow::arrow_writer::ArrowWriter;
+
+        let schema = arrow::testing::dummy_batch().schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from_iter_values(first..first + 5)),
+                Arc::new(Int64Array::from_iter_values(0..5)),
+            ],
+        )
+        .unwrap();
+
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        store.write_to_path(file_path, buffer).await.unwrap();
+    }
+
+    /// Checks that ordered reads skip the global sort only if the files do not overlap
+    #[tokio::test]
+    async fn timeseries_read_ordered() {
+        use datafusion::physical_plan::displayable;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        // (folder, first timestamp of each file, global sort expected)
+        let cases = [
+            ("ordered", [0, 10, 20], false),
+            ("overlapping", [0, 2, 20], true),
+        ];
+
+        for (folder, firsts, sorted) in cases {
+            for (i, first) in firsts.into_iter().enumerate() {
+                write_timestamps_file(&store, &format!("{folder}/data-{i:05}.parquet"), first)
+                    .await;
+            }
+
+            let res = ts_gw
+                .read_files(&[format!("{folder}/")], types::Format::Default, None, true)
+                .await
+                .unwrap();
+
+            let plan = res.data_frame.clone().create_physical_plan().await.unwrap();
+            let plan = displayable(plan.as_ref()).indent(false).to_string();
+            assert_eq!(plan.contains("SortExec"), sorted, "{folder}: {plan}");
+
+            let batches = res.data_frame.collect().await.unwrap();
+            let timestamps: Vec<i64> = batches
+                .iter()
+                .flat_map(|batch| {
+                    batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<::arrow::array::Int64Array>()
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                })
+                .collect();
+            assert_eq!(timestamps.len(), 15);
+            assert!(timestamps.is_sorted(), "{folder}: {timestamps:?}");
+        }
+    }
+
+    /// Checks that video topics are read with decoded frames, seeking the requested range
+    #[tokio::test]
+    async fn timeseries_video() {
+        use ::arrow::array::AsArray;
+        use datafusion::physical_plan::displayable;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        // Two chunks of 40 frames, with timestamps from 10000 every 10ns
+        let batch = arrow::testing::dummy_video_batch(80, 32, 32);
+        for i in 0..2 {
+            let mut encoder =
+                mosaicod_rw::InMemoryChunkEncoder::try_new(batch.schema(), types::Format::Video)
+                    .unwrap();
+            encoder.write(&batch.slice(i * 40, 40)).unwrap();
+            let (buffer, _, _) = encoder.finalize().unwrap();
+            store
+                .write_to_path(format!("video/data-{i:05}.parquet"), buffer)
+                .await
+                .unwrap();
+        }
+
+        let res = ts_gw
+            .read_files(&["video/"], types::Format::Video, None, true)
+            .await
+            .unwrap()
+            .filter_by_timestamp_range(types::TimestampRange::between(10500.into(), 10700.into()))
+            .unwrap();
+
+        let plan = res.data_frame.clone().create_physical_plan().await.unwrap();
+        let plan = displayable(plan.as_ref()).indent(false).to_string();
+        assert!(!plan.contains("SortExec"), "{plan}");
+
+        let batches = res.data_frame.collect().await.unwrap();
+        let rows: Vec<(i64, usize)> = batches
+            .iter()
+            .flat_map(|batch| {
+                let timestamps = batch
+                    .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+                    .unwrap()
+                    .as_primitive::<::arrow::datatypes::Int64Type>()
+                    .clone();
+                let data = batch
+                    .column_by_name("data")
+                    .unwrap()
+                    .as_binary::<i32>()
+                    .clone();
+                (0..batch.num_rows())
+                    .map(move |row| (timestamps.value(row), data.value(row).len()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let expected: Vec<(i64, usize)> = (50..70).map(|i| (10000 + 10 * i, 32 * 32 * 3)).collect();
+        assert_eq!(rows, expected);
+    }
+
     /// Writes a local parquet file and tries to read and retrieve data in the correct timestamp
     /// range
     #[tokio::test]
@@ -340,4 +909,344 @@ mod tests {
         assert_eq!(ts_range.start, 10010.into());
         assert_eq!(ts_range.end, 10020.into());
     }
+
+    /// Checks the timestamp range and row count of the rows matching a filter
+    #[tokio::test]
+    async fn timeseries_rows_summary() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let file_path = "dummy_file.parquet";
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+
+        write_dummy_file(&store, file_path).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let filter = |min: i64, max: i64| {
+            OntologyExprGroup::new(vec![
+                (
+                    OntologyField::try_new("tag.value".to_owned()).unwrap(),
+                    Op::Between(Range::try_new(min, max).unwrap()),
+                )
+                    .into(),
+            ])
+        };
+
+        let res = ts_gw
+            .read(file_path, types::Format::Default, None)
+            .await
+            .unwrap()
+            .filter(filter(3, 5))
+            .unwrap();
+
+        let summary = res.rows_summary().await.unwrap().unwrap();
+        assert_eq!(summary.timestamp_range.start, 10010.into());
+        assert_eq!(summary.timestamp_range.end, 10020.into());
+        assert_eq!(summary.rows, 3);
+
+        let merged = summary.merge(RowsSummary {
+            timestamp_range: types::TimestampRange::between(9000.into(), 9500.into()),
+            rows: 2,
+        });
+        assert_eq!(merged.timestamp_range.start, 9000.into());
+        assert_eq!(merged.timestamp_range.end, 10020.into());
+        assert_eq!(merged.rows, 5);
+
+        let res = ts_gw
+            .read(file_path, types::Format::Default, None)
+            .await
+            .unwrap()
+            .filter(filter(100, 200))
+            .unwrap();
+
+        assert!(res.rows_summary().await.unwrap().is_none());
+    }
+
+    /// Checks the time intervals of the rows matching a filter
+    #[tokio::test]
+    async fn timeseries_intervals() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let file_path = "dummy_file.parquet";
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+
+        write_dummy_file(&store, file_path).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let intervals = |max_gap_ns: u64| {
+            let ts_gw = &ts_gw;
+            async move {
+                // Rows at 10000, 10005, 10020, 10025 and 10030
+                let expr_grp = OntologyExprGroup::new(vec![
+                    (
+                        OntologyField::try_new("tag.value".to_owned()).unwrap(),
+                        Op::In(vec![1, 2, 5, 6, 7]),
+                    )
+                        .into(),
+                ]);
+                ts_gw
+                    .read(file_path, types::Format::Default, None)
+                    .await
+                    .unwrap()
+                    .intervals(expr_grp, max_gap_ns)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|i| {
+                        (
+                            i.timestamp_range.start.as_i64(),
+                            i.timestamp_range.end.as_i64(),
+                            i.rows,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            intervals(5).await,
+            vec![(10000, 10005, 2), (10020, 10030, 3)]
+        );
+        assert_eq!(intervals(15).await, vec![(10000, 10030, 5)]);
+        // Consecutive matching rows belong to the same interval regardless of their distance
+        assert_eq!(
+            intervals(0).await,
+            vec![(10000, 10005, 2), (10020, 10030, 3)]
+        );
+
+        // Intervals of different chunks are merged as if computed at once
+        let interval = |start: i64, end: i64, rows: u64| RowsSummary {
+            timestamp_range: types::TimestampRange::between(start.into(), end.into()),
+            rows,
+        };
+        let merged = merge_intervals(
+            vec![
+                interval(10, 10, 1),
+                interval(0, 0, 1),
+                interval(5, 5, 1),
+                interval(30, 40, 3),
+            ],
+            5,
+        );
+        let merged: Vec<_> = merged
+            .into_iter()
+            .map(|i| {
+                (
+                    i.timestamp_range.start.as_i64(),
+                    i.timestamp_range.end.as_i64(),
+                    i.rows,
+                )
+            })
+            .collect();
+        assert_eq!(merged, vec![(0, 10, 3), (30, 40, 3)]);
+    }
+
+    /// Checks that logical operators are correctly applied to the timeseries data
+    #[tokio::test]
+    async fn timeseries_logical_filter() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let file_path = "dummy_file.parquet";
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+
+        write_dummy_file(&store, file_path).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let field = || OntologyField::try_new("tag.value".to_owned()).unwrap();
+
+        // value <= 2 OR value >= 6 -> [1, 2, 6, 7]
+        let expr_grp = OntologyExprGroup::<i64>::default().with_logical(vec![Logical::Or(vec![
+            OntologyExprGroup::new(vec![(field(), Op::Leq(2)).into()]),
+            OntologyExprGroup::new(vec![(field(), Op::Geq(6)).into()]),
+        ])]);
+
+        let res = ts_gw
+            .read(file_path, types::Format::Default, None)
+            .await
+            .unwrap()
+            .filter(expr_grp)
+            .unwrap();
+
+        assert_eq!(res.count().await.unwrap(), 4);
+
+        // value >= 3 AND NOT (value == 5) -> [3, 4, 6, 7]
+        let expr_grp =
+            OntologyExprGroup::new(vec![(field(), Op::Geq(3)).into()]).with_logical(vec![
+                Logical::Not(Box::new(OntologyExprGroup::new(vec![
+                    (field(), Op::Eq(5)).into(),
+                ]))),
+            ]);
+
+        let res = ts_gw
+            .read(file_path, types::Format::Default, None)
+            .await
+            .unwrap()
+            .filter(expr_grp)
+            .unwrap();
+
+        assert_eq!(res.count().await.unwrap(), 4);
+
+        // NOT (exists value) can't be evaluated, as in the SQL filters
+        let expr_grp = OntologyExprGroup::<i64>::default().with_logical(vec![Logical::Not(
+            Box::new(OntologyExprGroup::new(vec![(field(), Op::Ex).into()])),
+        )]);
+
+        assert!(
+            ts_gw
+                .read(file_path, types::Format::Default, None)
+                .await
+                .unwrap()
+                .filter(expr_grp)
+                .is_err()
+        );
+    }
+
+    /// Checks that filters on list fields match the rows with any item satisfying them
+    #[tokio::test]
+    async fn timeseries_list_filter() {
+        use parquet::arrow::arrow_writer::ArrowWriter;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let file_path = "point_cloud.parquet";
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+
+        // Two scans, x = [0.1, 1.1, -0.1] and x = [-1.5, 3.0]
+        let batch = arrow::testing::dummy_point_cloud_batch();
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        store.write_to_path(file_path, buffer).await.unwrap();
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let field = || OntologyField::try_new("lidar.x".to_owned()).unwrap();
+
+        let count = async |op: Op<f64>| -> Result<usize, Error> {
+            ts_gw
+                .read(file_path, types::Format::PointCloud, None)
+                .await?
+                .filter(OntologyExprGroup::new(vec![(field(), op).into()]))?
+                .count()
+                .await
+        };
+
+        assert_eq!(count(Op::Lt(-1.0)).await.unwrap(), 1);
+        assert_eq!(count(Op::Geq(1.1)).await.unwrap(), 2);
+        assert_eq!(count(Op::Eq(3.0)).await.unwrap(), 1);
+        assert_eq!(
+            count(Op::Between(Range { min: 1.0, max: 2.0 }))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            count(Op::Between(Range {
+                min: -2.0,
+                max: 0.0
+            }))
+            .await
+            .unwrap(),
+            2
+        );
+        // No item within the range, even if the second scan spans it
+        assert_eq!(
+            count(Op::Between(Range { min: 1.5, max: 2.0 }))
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(count(Op::Neq(0.1)).await.is_err());
+    }
+
+    /// Checks that nested fields are correctly projected and unknown fields are rejected
+    #[tokio::test]
+    async fn timeseries_projection() {
+        use ::arrow::array::{Float64Array, Int64Array, StringArray, StructArray};
+        use ::arrow::datatypes::Fields;
+        use parquet::arrow::arrow_writer::ArrowWriter;
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let file_path = "pose_file.parquet";
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+
+        let pose_fields = Fields::from(vec![
+            Field::new("x", DataType::Float64, false),
+            Field::new("y", DataType::Float64, false),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("pose", DataType::Struct(pose_fields.clone()), true),
+            Field::new("blob", DataType::Utf8, false),
+        ]));
+        let batch = ::arrow::array::RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+                Arc::new(StructArray::new(
+                    pose_fields,
+                    vec![
+                        Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+                        Arc::new(Float64Array::from(vec![4.0, 5.0, 6.0])),
+                    ],
+                    None,
+                )),
+                Arc::new(StringArray::from(vec!["a", "b", "c"])),
+            ],
+        )
+        .unwrap();
+
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        store.write_to_path(file_path, buffer).await.unwrap();
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let fields = vec!["pose.x".to_owned(), "timestamp_ns".to_owned()];
+
+        let res = ts_gw
+            .read(file_path, types::Format::Default, None)
+            .await
+            .unwrap()
+            .project(&fields)
+            .unwrap();
+
+        let schema = res.schema_with_metadata(HashMap::new());
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["timestamp_ns", "pose.x"]);
+        assert!(schema.field(1).is_nullable());
+
+        let batches = res.data_frame.collect().await.unwrap();
+        let values = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(values.values(), &[1.0, 2.0, 3.0]);
+
+        for bad in ["pose.z", "blob.x", "missing", ""] {
+            let res = ts_gw
+                .read(file_path, types::Format::Default, None)
+                .await
+                .unwrap()
+                .project(&[bad.to_owned()]);
+
+            assert!(matches!(res, Err(Error::UnknownField { .. })), "{bad}");
+        }
+    }
 }
@@ -0,0 +1,481 @@
+//! Time alignment of several timeseries on a common timestamp grid.
+//!
+//! For each point of the grid and each timeseries, the samples right before (or at) and right
+//! after the grid point are located using window functions over the union of the grid and the
+//! timeseries timestamps. The matching samples are then joined back to build the aligned values
+//! according to the requested [`types::FillPolicy`].
+use super::{Error, TimeseriesEngine, TimeseriesResult};
+use arrow::datatypes::{DataType, Field};
+use datafusion::common::{Column, JoinType};
+use datafusion::functions::core::expr_ext::FieldAccessor;
+use datafusion::functions::core::expr_fn::{coalesce, named_struct};
+use datafusion::prelude::*;
+use mosaicod_core::{params, types};
+use std::path::PathBuf;
+
+/// Name of the grid table registered in the query context
+const GRID_TABLE: &str = "grid";
+/// Name of the column holding the aligned values of a source
+const VALUES_COLUMN: &str = "values";
+
+/// A timeseries taking part in an aligned read.
+pub struct AlignSource {
+    /// Name of the column holding the source values in the aligned data
+    pub name: String,
+    /// Folder containing the data files of the source
+    pub path: PathBuf,
+    pub format: types::Format,
+}
+
+/// Regular grid of timestamps on which timeseries are aligned.
+pub struct AlignGrid {
+    /// Grid bounds, both included
+    pub range: types::TimestampRange,
+    /// Distance in nanoseconds between two consecutive grid points
+    pub period_ns: i64,
+}
+
+impl AlignGrid {
+    /// Returns the number of points of the grid
+    pub fn size(&self) -> u64 {
+        let (start, end) = (self.range.start.as_i64(), self.range.end.as_i64());
+        if end < start || self.period_ns <= 0 {
+            return 0;
+        }
+
+        // Computed on 128 bits since the bounds may span the whole `i64` range
+        let size = (end as i128 - start as i128) / self.period_ns as i128 + 1;
+        u64::try_from(size).unwrap_or(u64::MAX)
+    }
+}
+
+impl TimeseriesEngine {
+    /// Reads several timeseries aligned on a common timestamp grid.
+    ///
+    /// The result contains the grid points as timestamp column and, for each source, a struct
+    /// column named after the source holding the source fields filled according to `fill`.
+    /// Grid points without a suitable sample (e.g. before the first sample of a source when
+    /// using [`types::FillPolicy::Previous`]) have `null` fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::BadField`] if a source has no field other than the timestamp.
+    pub async fn read_aligned(
+        &self,
+        sources: Vec<AlignSource>,
+        grid: AlignGrid,
+        fill: types::FillPolicy,
+        batch_size: Option<usize>,
+    ) -> Result<TimeseriesResult, Error> {
+        let ts = params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP;
+
+        let ctx = self.session_context(batch_size);
+
+        let grid_df = ctx
+            .sql(&format!(
+                "SELECT value AS {ts} FROM generate_series({}, {}, {})",
+                grid.range.start.as_i64(),
+                grid.range.end.as_i64(),
+                grid.period_ns
+            ))
+            .await?;
+        ctx.register_table(GRID_TABLE, grid_df.into_view())?;
+
+        let mut data_frame = ctx.table(GRID_TABLE).await?.alias(GRID_TABLE)?;
+        let mut columns = vec![qualified_col(GRID_TABLE, ts)];
+
+        for (i, source) in sources.into_iter().enumerate() {
+            let table = format!("source_{i}");
+            self.register_data(&ctx, &table, &[&source.path], source.format, false)
+                .await?;
+
+            let alias = format!("aligned_{i}");
+            let aligned = align_source(&ctx, &table, &source.name, fill)
+                .await?
+                .alias(&alias)?;
+
+            data_frame = data_frame.join_on(
+                aligned,
+                JoinType::Left,
+                [qualified_col(GRID_TABLE, ts).eq(qualified_col(&alias, ts))],
+            )?;
+            columns.push(qualified_col(&alias, VALUES_COLUMN).alias(source.name));
+        }
+
+        let data_frame = data_frame
+            .sort(vec![qualified_col(GRID_TABLE, ts).sort(true, false)])?
+            .select(columns)?;
+
+        Ok(TimeseriesResult { data_frame })
+    }
+}
+
+/// Aligns the data of `table` on the grid, returning the grid timestamps and a struct column
+/// with the aligned values.
+async fn align_source(
+    ctx: &SessionContext,
+    table: &str,
+    name: &str,
+    fill: types::FillPolicy,
+) -> Result<DataFrame, Error> {
+    let ts = params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP;
+
+    // For each grid point find the timestamp of the last sample before (or at) the grid point
+    // and of the first sample after it. Sorting by `is_grid` places samples before the grid
+    // points sharing the same timestamp, so exact matches are considered previous samples.
+    let matches = ctx
+        .sql(&format!(
+            "SELECT ts AS {ts}, prev_ts, next_ts FROM (
+                SELECT ts, is_grid,
+                    last_value(src) IGNORE NULLS OVER (
+                        ORDER BY ts, is_grid ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
+                    ) AS prev_ts,
+                    first_value(src) IGNORE NULLS OVER (
+                        ORDER BY ts, is_grid ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING
+                    ) AS next_ts
+                FROM (
+                    SELECT {ts} AS ts, CAST(NULL AS BIGINT) AS src, 1 AS is_grid FROM {GRID_TABLE}
+                    UNION ALL
+                    SELECT {ts} AS ts, {ts} AS src, 0 AS is_grid FROM {table}
+                )
+            ) WHERE is_grid = 1"
+        ))
+        .await?
+        .alias("matches")?;
+
+    // Samples sharing the same timestamp are collapsed, so each grid point matches a single row
+    let samples = ctx
+        .sql(&format!(
+            "SELECT DISTINCT ON ({ts}) * FROM {table} ORDER BY {ts}"
+        ))
+        .await?;
+
+    let fields: Vec<Field> = samples
+        .schema()
+        .fields()
+        .iter()
+        .filter(|f| f.name() != ts)
+        .map(|f| f.as_ref().clone())
+        .collect();
+
+    if fields.is_empty() {
+        return Err(Error::bad_field(name.to_owned()));
+    }
+
+    let matches_col = |name: &str| qualified_col("matches", name);
+
+    let (data_frame, values) = match fill {
+        types::FillPolicy::Previous => {
+            let data_frame = matches.join_on(
+                samples.alias("prev")?,
+                JoinType::Left,
+                [matches_col("prev_ts").eq(qualified_col("prev", ts))],
+            )?;
+
+            let values = struct_expr(&fields, |f| qualified_col("prev", f.name()));
+            (data_frame, values)
+        }
+
+        types::FillPolicy::Nearest => {
+            let nearest_ts = when(
+                matches_col("next_ts")
+                    .is_null()
+                    .or(matches_col("prev_ts").is_not_null().and(
+                        (matches_col(ts) - matches_col("prev_ts"))
+                            .lt_eq(matches_col("next_ts") - matches_col(ts)),
+                    )),
+                matches_col("prev_ts"),
+            )
+            .otherwise(matches_col("next_ts"))?;
+
+            let data_frame = matches.join_on(
+                samples.alias("nearest")?,
+                JoinType::Left,
+                [nearest_ts.eq(qualified_col("nearest", ts))],
+            )?;
+
+            let values = struct_expr(&fields, |f| qualified_col("nearest", f.name()));
+            (data_frame, values)
+        }
+
+        types::FillPolicy::Linear => {
+            let data_frame = matches
+                .join_on(
+                    samples.clone().alias("prev")?,
+                    JoinType::Left,
+                    [matches_col("prev_ts").eq(qualified_col("prev", ts))],
+                )?
+                .join_on(
+                    samples.alias("next")?,
+                    JoinType::Left,
+                    [matches_col("next_ts").eq(qualified_col("next", ts))],
+                )?;
+
+            // Since exact matches are previous samples, `next_ts` is always greater than
+            // `prev_ts` when both are available
+            let weight = cast(matches_col(ts) - matches_col("prev_ts"), DataType::Float64)
+                / cast(
+                    matches_col("next_ts") - matches_col("prev_ts"),
+                    DataType::Float64,
+                );
+
+            let values = struct_expr(&fields, |f| {
+                interpolate(
+                    f,
+                    qualified_col("prev", f.name()),
+                    qualified_col("next", f.name()),
+                    &weight,
+                )
+            });
+            (data_frame, values)
+        }
+    };
+
+    Ok(data_frame.select(vec![matches_col(ts), values.alias(VALUES_COLUMN)])?)
+}
+
+/// Linearly interpolates the numeric values of `field` between `prev` and `next`.
+///
+/// Numeric values are converted to `Float64` and fallback to `prev` if `next` is not
+/// available, struct fields are interpolated recursively while any other value is taken
+/// from `prev`.
+fn interpolate(field: &Field, prev: Expr, next: Expr, weight: &Expr) -> Expr {
+    match field.data_type() {
+        dt if dt.is_numeric() => {
+            let prev = cast(prev, DataType::Float64);
+            let next = cast(next, DataType::Float64);
+            coalesce(vec![
+                prev.clone() + (next - prev.clone()) * weight.clone(),
+                prev,
+            ])
+        }
+        DataType::Struct(children) => struct_expr(children.iter().map(AsRef::as_ref), |c| {
+            interpolate(
+                c,
+                prev.clone().field(c.name().as_str()),
+                next.clone().field(c.name().as_str()),
+                weight,
+            )
+        }),
+        _ => prev,
+    }
+}
+
+/// Builds a struct expression with the provided fields, computing each value with `value`.
+fn struct_expr<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    value: impl Fn(&Field) -> Expr,
+) -> Expr {
+    named_struct(
+        fields
+            .into_iter()
+            .flat_map(|f| [lit(f.name().as_str()), value(f)])
+            .collect(),
+    )
+}
+
+/// Builds a column expression referring to a column of the `relation` table,
+/// without parsing the column name.
+fn qualified_col(relation: &str, name: &str) -> Expr {
+    Expr::Column(Column::new(Some(relation), name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, AsArray, Float64Array, Int64Array, RecordBatch, StructArray};
+    use arrow::datatypes::{Fields, Float64Type, Int64Type, Schema};
+    use mosaicod_core::traits::AsyncWriteToPath;
+    use mosaicod_store as store;
+    use parquet::arrow::arrow_writer::ArrowWriter;
+    use std::sync::Arc;
+
+    async fn write_batch(store: &store::Store, file_path: &str, batch: RecordBatch) {
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        store.write_to_path(file_path, buffer).await.unwrap();
+    }
+
+    #[test]
+    fn grid_size() {
+        let grid = |start: i64, end: i64, period_ns| AlignGrid {
+            range: types::TimestampRange::between(start.into(), end.into()),
+            period_ns,
+        };
+
+        assert_eq!(grid(0, 20, 10).size(), 3);
+        assert_eq!(grid(0, 25, 10).size(), 3);
+        assert_eq!(grid(5, 5, 10).size(), 1);
+        assert_eq!(grid(10, 0, 10).size(), 0);
+        assert_eq!(grid(i64::MIN, i64::MAX, 1).size(), u64::MAX);
+    }
+
+    fn timestamp_field() -> Field {
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        )
+    }
+
+    /// Writes two sources:
+    /// - `a/` with an integer `value` at timestamps `[0, 10, 20]`
+    /// - `b/` with a `pose.x` float at timestamps `[5, 15]`
+    async fn write_sources(store: &store::Store) {
+        let schema = Arc::new(Schema::new(vec![
+            timestamp_field(),
+            Field::new("value", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![0, 10, 20])),
+                Arc::new(Int64Array::from(vec![0, 10, 20])),
+            ],
+        )
+        .unwrap();
+        write_batch(store, "a/data.parquet", batch).await;
+
+        let pose_fields = Fields::from(vec![Field::new("x", DataType::Float64, false)]);
+        let schema = Arc::new(Schema::new(vec![
+            timestamp_field(),
+            Field::new("pose", DataType::Struct(pose_fields.clone()), false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![5, 15])),
+                Arc::new(StructArray::new(
+                    pose_fields,
+                    vec![Arc::new(Float64Array::from(vec![1.0, 3.0]))],
+                    None,
+                )),
+            ],
+        )
+        .unwrap();
+        write_batch(store, "b/data.parquet", batch).await;
+    }
+
+    /// Reads the sources aligned on the grid `[0, 5, 10, 15, 20]`, returning the values of
+    /// `a.value` and `b.pose.x` (as `f64`).
+    async fn aligned_values(
+        fill: types::FillPolicy,
+    ) -> (Vec<i64>, Vec<Option<f64>>, Vec<Option<f64>>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_sources(&store).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let sources = ["a", "b"]
+            .into_iter()
+            .map(|name| AlignSource {
+                name: name.to_owned(),
+                path: name.into(),
+                format: types::Format::Default,
+            })
+            .collect();
+
+        let grid = AlignGrid {
+            range: types::TimestampRange::between(0.into(), 20.into()),
+            period_ns: 5,
+        };
+
+        let res = ts_gw.read_aligned(sources, grid, fill, None).await.unwrap();
+
+        let batches = res.data_frame.collect().await.unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+
+        let timestamps = batch
+            .column(0)
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec();
+
+        let a = batch.column_by_name("a").unwrap().as_struct();
+        let a =
+            arrow::compute::cast(a.column_by_name("value").unwrap(), &DataType::Float64).unwrap();
+        let a = a.as_primitive::<Float64Type>().iter().collect();
+
+        let b = batch.column_by_name("b").unwrap().as_struct();
+        let b = b.column_by_name("pose").unwrap().as_struct();
+        let b = b.column_by_name("x").unwrap().as_primitive::<Float64Type>();
+        let b = (0..b.len())
+            .map(|i| b.is_valid(i).then(|| b.value(i)))
+            .collect();
+
+        (timestamps, a, b)
+    }
+
+    #[tokio::test]
+    async fn align_previous() {
+        let (ts, a, b) = aligned_values(types::FillPolicy::Previous).await;
+
+        assert_eq!(ts, vec![0, 5, 10, 15, 20]);
+        assert_eq!(
+            a,
+            vec![Some(0.0), Some(0.0), Some(10.0), Some(10.0), Some(20.0)]
+        );
+        assert_eq!(b, vec![None, Some(1.0), Some(1.0), Some(3.0), Some(3.0)]);
+    }
+
+    #[tokio::test]
+    async fn align_nearest() {
+        let (ts, a, b) = aligned_values(types::FillPolicy::Nearest).await;
+
+        assert_eq!(ts, vec![0, 5, 10, 15, 20]);
+        assert_eq!(
+            a,
+            vec![Some(0.0), Some(0.0), Some(10.0), Some(10.0), Some(20.0)]
+        );
+        assert_eq!(
+            b,
+            vec![Some(1.0), Some(1.0), Some(1.0), Some(3.0), Some(3.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn align_linear() {
+        let (ts, a, b) = aligned_values(types::FillPolicy::Linear).await;
+
+        assert_eq!(ts, vec![0, 5, 10, 15, 20]);
+        assert_eq!(
+            a,
+            vec![Some(0.0), Some(5.0), Some(10.0), Some(15.0), Some(20.0)]
+        );
+        assert_eq!(b, vec![None, Some(1.0), Some(2.0), Some(3.0), Some(3.0)]);
+    }
+
+    /// Sources without overlapping data produce an empty grid
+    #[tokio::test]
+    async fn align_empty_grid() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_sources(&store).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let sources = vec![AlignSource {
+            name: "a".to_owned(),
+            path: "a".into(),
+            format: types::Format::Default,
+        }];
+
+        let grid = AlignGrid {
+            range: types::TimestampRange::between(20.into(), 10.into()),
+            period_ns: 5,
+        };
+
+        let res = ts_gw
+            .read_aligned(sources, grid, types::FillPolicy::Previous, None)
+            .await
+            .unwrap();
+
+        assert_eq!(res.count().await.unwrap(), 0);
+    }
+}
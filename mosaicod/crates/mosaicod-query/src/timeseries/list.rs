@@ -0,0 +1,122 @@
+//! Functions used to evaluate the ontology filters on list fields (e.g. the coordinates of a
+//! point cloud scan), when the builtin array functions are not enough.
+use arrow::array::{Array, ArrayRef, ArrowPrimitiveType, AsArray, BooleanArray, ListArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type};
+use datafusion::common::{Result, exec_err};
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility,
+};
+use datafusion::prelude::Expr;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Returns an expression checking if any item of `list` lies in the range `[min, max]`.
+pub fn array_any_between(list: Expr, min: Expr, max: Expr) -> Expr {
+    ScalarUDF::from(ArrayAnyBetween::new()).call(vec![list, min, max])
+}
+
+/// Scalar function `array_any_between(list, min, max)`, returning `true` if any item of the
+/// list lies in the range `[min, max]`.
+///
+/// Items and bounds are compared as `Float64` if any of them is a floating point value, as
+/// `Int64` otherwise.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ArrayAnyBetween {
+    signature: Signature,
+}
+
+impl ArrayAnyBetween {
+    fn new() -> Self {
+        Self {
+            signature: Signature::any(3, Volatility::Immutable),
+        }
+    }
+}
+
+impl ScalarUDFImpl for ArrayAnyBetween {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "array_any_between"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(&args.args)?;
+        let Ok([list, min, max]) = <[ArrayRef; 3]>::try_from(arrays) else {
+            return exec_err!("{} expects 3 arguments", self.name());
+        };
+
+        let item_type = match list.data_type() {
+            DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+                item.data_type()
+            }
+            other => return exec_err!("{} expects a list, found {other}", self.name()),
+        };
+
+        let common_type = if [item_type, min.data_type(), max.data_type()]
+            .into_iter()
+            .any(DataType::is_floating)
+        {
+            DataType::Float64
+        } else {
+            DataType::Int64
+        };
+
+        let list = cast(
+            &list,
+            &DataType::List(Arc::new(Field::new_list_field(common_type.clone(), true))),
+        )?;
+        let list = list.as_list::<i32>();
+        let min = cast(&min, &common_type)?;
+        let max = cast(&max, &common_type)?;
+
+        let result = match common_type {
+            DataType::Float64 => any_between::<Float64Type>(list, &min, &max),
+            _ => any_between::<Int64Type>(list, &min, &max),
+        };
+
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+}
+
+/// Checks, for each row, if any item of the list lies in the range of the row bounds.
+///
+/// Null items never match, while rows with a null list or bound are null.
+fn any_between<T>(list: &ListArray, min: &ArrayRef, max: &ArrayRef) -> BooleanArray
+where
+    T: ArrowPrimitiveType,
+{
+    let values = list.values().as_primitive::<T>();
+    let min = min.as_primitive::<T>();
+    let max = max.as_primitive::<T>();
+    let offsets = list.value_offsets();
+
+    (0..list.len())
+        .map(|row| {
+            if list.is_null(row) || min.is_null(row) || max.is_null(row) {
+                return None;
+            }
+
+            let (min, max) = (min.value(row), max.value(row));
+            let items = offsets[row] as usize..offsets[row + 1] as usize;
+
+            Some(items.into_iter().any(|i| {
+                values.is_valid(i) && {
+                    let value = values.value(i);
+                    value >= min && value <= max
+                }
+            }))
+        })
+        .collect()
+}
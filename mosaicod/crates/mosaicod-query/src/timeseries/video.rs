@@ -0,0 +1,275 @@
+//! Reading of video topics.
+//!
+//! The frames of video topics are stored encoded, so their data files can't be scanned as
+//! plain Parquet files. The [`VideoTable`] reads them through [`rw::ChunkReader`], decoding
+//! the frames back to their uploaded layout. When the query has a lower bound on the
+//! timestamp, each data file is decoded starting from the keyframe preceding it.
+use super::Error;
+use arrow::datatypes::SchemaRef;
+use async_trait::async_trait;
+use datafusion::catalog::streaming::StreamingTable;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::datasource::TableType;
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{BinaryExpr, Operator, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::PartitionStream;
+use datafusion::prelude::*;
+use datafusion::scalar::ScalarValue;
+use mosaicod_core::{params, types};
+use mosaicod_rw::{self as rw, ToProperties};
+use mosaicod_store as store;
+use std::any::Any;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Number of decoded batches buffered ahead of the query engine
+const BUFFERED_BATCHES: usize = 2;
+
+/// Table holding the data files of a video topic.
+///
+/// Data files are read one after the other in a single partition. If `sorted` is set, the
+/// files are assumed to be sorted by timestamp and non-overlapping in the provided order,
+/// and the table is declared as sorted. Files of a folder are taken in name order, so sorted
+/// tables should be created from the list of their data files.
+pub struct VideoTable {
+    store: Arc<store::Store>,
+    files: Vec<String>,
+    format: types::Format,
+    schema: SchemaRef,
+    max_batch_rows: Option<usize>,
+    sorted: bool,
+}
+
+impl VideoTable {
+    pub async fn try_new<P: AsRef<Path>>(
+        store: Arc<store::Store>,
+        paths: &[P],
+        format: types::Format,
+        sorted: bool,
+    ) -> Result<Self, Error> {
+        let extension = format.to_properties().as_extension();
+
+        let mut files = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            if store.exists(path).await? {
+                files.push(path.to_string_lossy().into_owned());
+            } else {
+                let mut folder = store.list(path, Some(&extension)).await?;
+                folder.sort();
+                files.extend(folder);
+            }
+        }
+
+        // Data files share the same schema, so it is read from the first file only
+        let first = files.first().ok_or(Error::NotFound)?;
+        let buffer = store.read_bytes(first).await?;
+        let reader = rw::ChunkReader::new(format, buffer.into())?;
+
+        Ok(Self {
+            store,
+            files,
+            format,
+            schema: reader.schema(),
+            max_batch_rows: reader.max_batch_rows(),
+            sorted,
+        })
+    }
+
+    /// Returns the maximum number of decoded rows per batch, estimated from the first data
+    /// file, see [`rw::ChunkReader::max_batch_rows`].
+    pub fn max_batch_rows(&self) -> Option<usize> {
+        self.max_batch_rows
+    }
+}
+
+impl std::fmt::Debug for VideoTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoTable")
+            .field("files", &self.files)
+            .field("format", &self.format)
+            .field("sorted", &self.sorted)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TableProvider for VideoTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    /// Lower bounds on the timestamp are used to seek the data files, but rows before the
+    /// bound can still be returned, so they are reported as inexact.
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| match timestamp_lower_bound(filter) {
+                Some(_) => TableProviderFilterPushDown::Inexact,
+                None => TableProviderFilterPushDown::Unsupported,
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        // Frames are decoded only if the query needs them
+        let decode = self
+            .schema
+            .index_of(rw::video::DATA_COLUMN)
+            .is_ok_and(|index| projection.is_none_or(|projection| projection.contains(&index)));
+
+        let partition = VideoPartition {
+            store: self.store.clone(),
+            files: self.files.clone(),
+            format: self.format,
+            schema: self.schema.clone(),
+            start: filters.iter().filter_map(timestamp_lower_bound).max(),
+            decode,
+        };
+
+        let mut table = StreamingTable::try_new(self.schema.clone(), vec![Arc::new(partition)])?;
+        if self.sorted {
+            table = table.with_sort_order(vec![
+                col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP).sort(true, false),
+            ]);
+        }
+
+        table.scan(state, projection, &[], limit).await
+    }
+}
+
+/// Stream of the rows of the data files of a [`VideoTable`]
+struct VideoPartition {
+    store: Arc<store::Store>,
+    files: Vec<String>,
+    format: types::Format,
+    schema: SchemaRef,
+    /// Lower bound of the requested timestamps, if any
+    start: Option<i64>,
+    /// If not set, frames are returned encoded
+    decode: bool,
+}
+
+impl std::fmt::Debug for VideoPartition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoPartition")
+            .field("files", &self.files)
+            .field("start", &self.start)
+            .field("decode", &self.decode)
+            .finish()
+    }
+}
+
+impl PartitionStream for VideoPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), BUFFERED_BATCHES);
+        let tx = builder.tx();
+
+        let store = self.store.clone();
+        let files = self.files.clone();
+        let (format, start, decode) = (self.format, self.start, self.decode);
+
+        builder.spawn(async move {
+            for file in files {
+                let buffer = bytes::Bytes::from(store.read_bytes(&file).await.map_err(external)?);
+
+                // Decoding is CPU bound, so it runs outside of the async runtime
+                let tx = tx.clone();
+                let sent = tokio::task::spawn_blocking(move || -> DataFusionResult<bool> {
+                    let reader = match (decode, start) {
+                        // Read as a plain Parquet file, keeping the frames encoded
+                        (false, _) => rw::ChunkReader::new(types::Format::Default, buffer),
+                        (true, Some(start)) => rw::ChunkReader::seek(format, buffer, start),
+                        (true, None) => rw::ChunkReader::new(format, buffer),
+                    }
+                    .map_err(external)?;
+
+                    for batch in reader {
+                        if tx.blocking_send(batch.map_err(external)).is_err() {
+                            // The receiver has been dropped, no more rows are needed
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                })
+                .await
+                .map_err(external)??;
+
+                if !sent {
+                    break;
+                }
+            }
+            Ok(())
+        });
+
+        builder.build()
+    }
+}
+
+fn external(err: impl std::error::Error + Send + Sync + 'static) -> DataFusionError {
+    DataFusionError::External(Box::new(err))
+}
+
+/// Returns the lower bound on the timestamp set by `filter`, if it is a comparison between
+/// the timestamp column and an integer literal.
+fn timestamp_lower_bound(filter: &Expr) -> Option<i64> {
+    let Expr::BinaryExpr(BinaryExpr { left, op, right }) = filter else {
+        return None;
+    };
+
+    let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(column), Expr::Literal(value, _)) => (column, *op, value),
+        (Expr::Literal(value, _), Expr::Column(column)) => (column, op.swap()?, value),
+        _ => return None,
+    };
+
+    if column.name != params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP {
+        return None;
+    }
+
+    match (op, value) {
+        (Operator::GtEq, ScalarValue::Int64(Some(value))) => Some(*value),
+        (Operator::Gt, ScalarValue::Int64(Some(value))) => value.checked_add(1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_bound() {
+        let ts = || col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+
+        assert_eq!(timestamp_lower_bound(&ts().gt_eq(lit(100i64))), Some(100));
+        assert_eq!(timestamp_lower_bound(&ts().gt(lit(100i64))), Some(101));
+        assert_eq!(timestamp_lower_bound(&lit(100i64).lt_eq(ts())), Some(100));
+        assert_eq!(timestamp_lower_bound(&ts().lt(lit(100i64))), None);
+        assert_eq!(timestamp_lower_bound(&col("data").gt_eq(lit(100i64))), None);
+    }
+}
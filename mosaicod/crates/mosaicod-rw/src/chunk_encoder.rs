@@ -1,14 +1,16 @@
-use super::{Error, writer::Writer};
+use super::{Error, ToProperties, chunk_reader::Reader, video, writer::Writer};
 use arrow::{array::RecordBatch, datatypes::Schema, datatypes::SchemaRef};
 use mosaicod_core::types;
 use mosaicod_ext;
+use parquet::file::metadata::KeyValue;
 use std::sync::Arc;
 
-/// Metadata about a finalized chunk, including size and row count.
+/// Metadata about a finalized chunk, including size, row count and timestamps.
 #[derive(Debug, Clone)]
 pub struct ChunkMetadata {
     pub size_bytes: usize,
     pub row_count: usize,
+    pub timestamps: types::TimestampStats,
 }
 
 /// The [`InMemoryChunkEncoder`] is used to encode [`RecordBatch`] instances into a single in-memory block,
@@ -21,8 +23,11 @@ pub struct InMemoryChunkEncoder {
     pub format: types::Format,
     writer: Writer,
     stats: types::OntologyModelStats,
+    timestamps: types::TimestampStats,
     schema: SchemaRef,
     row_count: usize,
+    /// Encoder of the frames of video topics
+    frames: Option<video::FrameEncoder>,
 }
 
 impl InMemoryChunkEncoder {
@@ -31,12 +36,27 @@ impl InMemoryChunkEncoder {
     /// This fallible constructor initializes an appropriate underlying writer
     /// based on the provided `format`.
     pub fn try_new(schema: Arc<Schema>, format: types::Format) -> Result<Self, Error> {
+        Self::try_new_with_options(schema, format, &types::WriterOptions::default())
+    }
+
+    /// Creates a new [`InMemoryChunkEncoder`] for a specific serialization format, overriding
+    /// the format defaults with the writer `options` of the topic.
+    pub fn try_new_with_options(
+        schema: Arc<Schema>,
+        format: types::Format,
+        options: &types::WriterOptions,
+    ) -> Result<Self, Error> {
         Ok(InMemoryChunkEncoder {
-            writer: Writer::new(&schema, format)?,
+            writer: Writer::new(&schema, format, options)?,
             format,
-            stats: mosaicod_ext::arrow::ontology_model_stats_from_schema(&schema),
+            stats: empty_statistics(&schema, format),
+            timestamps: types::TimestampStats::new(),
             schema,
             row_count: 0,
+            frames: format
+                .to_properties()
+                .video_coding()
+                .then(video::FrameEncoder::new),
         })
     }
 
@@ -45,15 +65,52 @@ impl InMemoryChunkEncoder {
     /// The [`RecordBatch`] is serialized according to the writer's format, and the internal statistics
     /// are updated based on the data in the batch. The method returns an error if the serialization fails
     pub fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
-        match &mut self.writer {
-            Writer::Parquet(writer) => {
-                mosaicod_ext::arrow::ontology_model_stats_inspect_record_batch(
-                    &mut self.stats,
-                    batch,
-                )?;
+        let quantized;
+        let batch = match self.format.to_properties().quantization_step() {
+            Some(step) => {
+                quantized = mosaicod_ext::arrow::quantize_record_batch(batch, step)?;
+                &quantized
+            }
+            None => batch,
+        };
 
-                writer.write(batch)?;
+        let encoded;
+        let batch = match &mut self.frames {
+            Some(frames) => {
+                encoded = frames.encode(batch)?;
+                &encoded
             }
+            None => batch,
+        };
+
+        self.write_stored(batch)
+    }
+
+    /// Appends all the rows of a chunk previously encoded with the same format.
+    ///
+    /// The rows are written as they are stored in the chunk, so the frames of video topics are
+    /// copied without being decoded and encoded again, which would degrade them.
+    pub fn append_chunk(&mut self, buffer: bytes::Bytes) -> Result<(), Error> {
+        let (batches, keyframes) = Reader::try_new(self.format, buffer)?.read_stored()?;
+
+        if let Some(frames) = &mut self.frames {
+            frames.append_encoded(&batches, keyframes.as_ref());
+        }
+
+        for batch in &batches {
+            self.write_stored(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a batch ready to be stored, updating the statistics
+    fn write_stored(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        mosaicod_ext::arrow::ontology_model_stats_inspect_record_batch(&mut self.stats, batch)?;
+        mosaicod_ext::arrow::timestamp_stats_inspect_record_batch(&mut self.timestamps, batch);
+
+        match &mut self.writer {
+            Writer::Parquet(writer) => writer.write(batch)?,
+            Writer::ArrowIpc(writer) => writer.write(batch)?,
         }
         self.row_count += batch.num_rows();
         Ok(())
@@ -65,16 +122,14 @@ impl InMemoryChunkEncoder {
     }
 
     pub fn take_statistics(&mut self) -> types::OntologyModelStats {
-        std::mem::replace(
-            &mut self.stats,
-            mosaicod_ext::arrow::ontology_model_stats_from_schema(&self.schema),
-        )
+        std::mem::replace(&mut self.stats, empty_statistics(&self.schema, self.format))
     }
 
     /// Returns a mutable reference to the internal buffer containing the serialized data.
     pub fn buffer_mut(&mut self) -> &mut Vec<u8> {
         match &mut self.writer {
             Writer::Parquet(writer) => writer.inner_mut(),
+            Writer::ArrowIpc(writer) => writer.get_mut(),
         }
     }
 
@@ -82,12 +137,15 @@ impl InMemoryChunkEncoder {
     pub fn buffer(&self) -> &Vec<u8> {
         match &self.writer {
             Writer::Parquet(writer) => writer.inner(),
+            Writer::ArrowIpc(writer) => writer.get_ref(),
         }
     }
 
     pub fn memory_size(&self) -> usize {
         match &self.writer {
             Writer::Parquet(writer) => writer.memory_size(),
+            // Batches are written right away, so the buffer is the only memory in use
+            Writer::ArrowIpc(writer) => writer.get_ref().len(),
         }
     }
 
@@ -96,22 +154,45 @@ impl InMemoryChunkEncoder {
     /// This method must be called to complete the writing process. It consumes the writer object,
     /// preventing any further writes.
     ///
-    /// Returns the serialized buffer, column statistics, and chunk metadata (size and row count).
+    /// Returns the serialized buffer, column statistics, and chunk metadata (size, row count and
+    /// timestamps).
     pub fn finalize(self) -> Result<(Vec<u8>, types::OntologyModelStats, ChunkMetadata), Error> {
         // We are calling `finish` since the implementation is the same as
         // close but takes no ownership of the writer. And we return the internal data buffer.
         let row_count = self.row_count;
         let buffer = match self.writer {
-            Writer::Parquet(w) => w.buffer()?,
+            Writer::Parquet(mut w) => {
+                if let Some(index) = self.frames.and_then(|f| f.keyframe_index()) {
+                    w.append_key_value_metadata(KeyValue::new(
+                        video::KEYFRAME_INDEX_METADATA_KEY.to_owned(),
+                        index.to_string(),
+                    ));
+                }
+                w.buffer()?
+            }
+            Writer::ArrowIpc(w) => w.buffer()?,
         };
         let metadata = ChunkMetadata {
             size_bytes: buffer.len(),
             row_count,
+            timestamps: self.timestamps,
         };
         Ok((buffer, self.stats, metadata))
     }
 }
 
+/// Returns the empty statistics of `schema` for the given format.
+pub(crate) fn empty_statistics(
+    schema: &SchemaRef,
+    format: types::Format,
+) -> types::OntologyModelStats {
+    if format.to_properties().list_items_statistics() {
+        mosaicod_ext::arrow::ontology_model_stats_from_schema_with_lists(schema)
+    } else {
+        mosaicod_ext::arrow::ontology_model_stats_from_schema(schema)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -259,4 +340,155 @@ mod tests {
         assert_eq!(metadata.row_count, 3);
         assert_eq!(metadata.size_bytes, buffer.len());
     }
+
+    #[test]
+    fn point_cloud_encoder() {
+        use parquet::basic::Encoding;
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let batch = mosaicod_ext::arrow::testing::dummy_point_cloud_batch();
+
+        let mut writer = InMemoryChunkEncoder::try_new(batch.schema(), types::Format::PointCloud)
+            .expect("Failed to create point cloud encoder");
+        writer.write(&batch).expect("Failed to write batch");
+
+        // List columns provide the bounding box of the points
+        if let Some(types::Stats::Numeric(s)) = writer.statistics().cols.get("x") {
+            assert!((s.min - -1.5).abs() < params::EPSILON);
+            assert!((s.max - 3.0).abs() < params::EPSILON);
+        } else {
+            panic!("Missing or incorrect type for x stats");
+        }
+
+        let (buffer, _, metadata) = writer.finalize().expect("Failed to finalize writer");
+        assert_eq!(metadata.row_count, 2);
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(buffer)).unwrap();
+        let row_group = reader.metadata().row_group(0);
+        for column in row_group.columns() {
+            let byte_stream_split = column
+                .encodings()
+                .any(|encoding| encoding == Encoding::BYTE_STREAM_SPLIT);
+            assert_eq!(
+                byte_stream_split,
+                column.column_path().string() != params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                "unexpected encodings for column {}",
+                column.column_path()
+            );
+        }
+    }
+
+    #[test]
+    fn video_encoder() {
+        use crate::{ChunkReader, video};
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let interval = params::params().video_keyframe_interval.value as usize;
+
+        // Two batches of frames spanning more than two keyframe intervals
+        let batch = mosaicod_ext::arrow::testing::dummy_video_batch(2 * interval + 10, 32, 32);
+        let (first, second) = (
+            batch.slice(0, interval / 2),
+            batch.slice(interval / 2, batch.num_rows() - interval / 2),
+        );
+
+        let mut writer = InMemoryChunkEncoder::try_new(batch.schema(), types::Format::Video)
+            .expect("Failed to create video encoder");
+        writer.write(&first).expect("Failed to write batch");
+        writer.write(&second).expect("Failed to write batch");
+
+        let (buffer, _, metadata) = writer.finalize().expect("Failed to finalize writer");
+        assert_eq!(metadata.row_count, batch.num_rows());
+
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            bytes::Bytes::from(buffer.clone()),
+        )
+        .unwrap();
+        let index = reader
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kv| {
+                kv.iter()
+                    .find(|kv| kv.key == video::KEYFRAME_INDEX_METADATA_KEY)
+                    .and_then(|kv| kv.value.clone())
+            })
+            .expect("Missing keyframe index");
+        let timestamp = |row: usize| 10000 + 10 * row as i64;
+        assert_eq!(
+            index,
+            format!(
+                "{}:0,{}:{interval},{}:{}",
+                timestamp(0),
+                timestamp(interval),
+                timestamp(2 * interval),
+                2 * interval
+            )
+        );
+
+        // Seeking starts from the keyframe preceding the timestamp
+        let rows: usize = ChunkReader::seek(
+            types::Format::Video,
+            buffer.clone().into(),
+            timestamp(interval + 5),
+        )
+        .unwrap()
+        .map(|batch| batch.unwrap().num_rows())
+        .sum();
+        assert_eq!(rows, batch.num_rows() - interval);
+
+        // Frames are decoded back to the uploaded layout, in batches bounded by the frame size
+        let reader = ChunkReader::new(types::Format::Video, buffer.into()).unwrap();
+        assert_eq!(
+            reader.max_batch_rows(),
+            Some(params::params().target_message_size.value / (32 * 3 * 32))
+        );
+        let decoded: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        let decoded = arrow::compute::concat_batches(&batch.schema(), &decoded).unwrap();
+        assert_eq!(decoded.num_rows(), batch.num_rows());
+        assert_eq!(
+            decoded
+                .column_by_name(video::DATA_COLUMN)
+                .unwrap()
+                .to_data()
+                .len(),
+            batch.num_rows()
+        );
+    }
+
+    #[test]
+    fn arrow_ipc_encoder() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let batch = create_test_batch();
+        let options = types::WriterOptions {
+            global: types::ColumnWriterOptions {
+                codec: Some(types::Codec::Lz4),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut writer = InMemoryChunkEncoder::try_new_with_options(
+            batch.schema(),
+            types::Format::ArrowIpc,
+            &options,
+        )
+        .expect("Failed to create Arrow IPC encoder");
+        writer.write(&batch).expect("Failed to write batch");
+        writer.write(&batch).expect("Failed to write batch");
+
+        // Statistics are collected as for Parquet formats
+        let (buffer, stats, metadata) = writer.finalize().expect("Failed to finalize writer");
+        assert_eq!(metadata.row_count, 2 * batch.num_rows());
+        assert!(matches!(
+            stats.cols.get("pose.x"),
+            Some(types::Stats::Numeric(s)) if (s.max - 0.3).abs() < params::EPSILON
+        ));
+
+        let reader = crate::ChunkReader::new(types::Format::ArrowIpc, buffer.into()).unwrap();
+        assert_eq!(reader.schema(), batch.schema());
+        let read: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(read, vec![batch.clone(), batch]);
+    }
 }
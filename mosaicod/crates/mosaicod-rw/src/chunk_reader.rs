@@ -1,39 +1,237 @@
-use super::Error;
-use arrow::datatypes::SchemaRef;
+use super::{
+    ChunkMetadata, Error, ToIpcProperties, ToProperties, chunk_encoder::empty_statistics, video,
+};
+use arrow::ipc::reader::FileReader;
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
 use mosaicod_core::types;
-use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use parquet::arrow::arrow_reader::{
+    ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder, RowSelection, RowSelector,
+};
+use parquet::errors::ParquetError;
+
+/// Number of rows per batch used by the Parquet reader when not bounded
+const DEFAULT_BATCH_SIZE: usize = 1024;
 
 pub enum Reader {
     /// Parquet file format https://parquet.apache.org/docs/file-format/
     Parquet {
         reader: ParquetRecordBatchReader,
         schema: SchemaRef,
+        /// Maximum number of rows per batch, if bounded
+        max_batch_rows: Option<usize>,
+        /// Keyframe index of video chunks, see [`video::KeyframeIndex`]
+        keyframes: Option<String>,
     },
+
+    /// Arrow IPC file format https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format
+    ArrowIpc(FileReader<std::io::Cursor<bytes::Bytes>>),
 }
 
 impl Reader {
-    pub fn try_new(_format: types::Format, buffer: bytes::Bytes) -> Result<Self, Error> {
-        let builder = ParquetRecordBatchReaderBuilder::try_new(buffer)?;
+    pub fn try_new(format: types::Format, buffer: bytes::Bytes) -> Result<Self, Error> {
+        Self::try_new_from_row(format, buffer, |_| Ok(0))
+    }
+
+    /// Creates a reader skipping the rows before the one returned by `first_row`,
+    /// which receives the key-value metadata of the file.
+    fn try_new_from_row(
+        format: types::Format,
+        buffer: bytes::Bytes,
+        first_row: impl FnOnce(Option<&str>) -> Result<usize, Error>,
+    ) -> Result<Self, Error> {
+        // Arrow IPC files have no keyframe index, so they are always read from the first row
+        if format.to_ipc_properties().is_some() {
+            return Ok(Self::ArrowIpc(FileReader::try_new(
+                std::io::Cursor::new(buffer),
+                None,
+            )?));
+        }
+
+        let mut builder = ParquetRecordBatchReaderBuilder::try_new(buffer)?;
+
+        let max_batch_rows = format
+            .to_properties()
+            .video_coding()
+            .then(|| video::max_decoded_rows(builder.metadata()))
+            .flatten();
+        if let Some(rows) = max_batch_rows {
+            builder = builder.with_batch_size(rows.min(DEFAULT_BATCH_SIZE));
+        }
+
+        let keyframes = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kv| {
+                kv.iter()
+                    .find(|kv| kv.key == video::KEYFRAME_INDEX_METADATA_KEY)
+                    .and_then(|kv| kv.value.clone())
+            });
+
+        let skip = first_row(keyframes.as_deref())?;
+        if skip > 0 {
+            let rows = builder.metadata().file_metadata().num_rows() as usize;
+            builder = builder.with_row_selection(RowSelection::from(vec![
+                RowSelector::skip(skip.min(rows)),
+                RowSelector::select(rows.saturating_sub(skip)),
+            ]));
+        }
+
         Ok(Self::Parquet {
             schema: builder.schema().clone(),
             reader: builder.build()?,
+            max_batch_rows,
+            keyframes,
         })
     }
+
+    /// Reads all the batches of the chunk as they are stored, i.e. without decoding the frames
+    /// of video topics, along with the keyframe index of the chunk (if any).
+    pub(crate) fn read_stored(
+        self,
+    ) -> Result<(Vec<RecordBatch>, Option<video::KeyframeIndex>), Error> {
+        match self {
+            Self::Parquet {
+                reader, keyframes, ..
+            } => Ok((
+                reader
+                    .collect::<Result<_, _>>()
+                    .map_err(ParquetError::from)?,
+                keyframes.map(|k| k.parse()).transpose()?,
+            )),
+            Self::ArrowIpc(reader) => Ok((reader.collect::<Result<_, _>>()?, None)),
+        }
+    }
+}
+
+/// Reads a chunk, returning the statistics of its columns and its metadata, as computed by
+/// [`InMemoryChunkEncoder`](crate::InMemoryChunkEncoder) when the chunk was encoded.
+///
+/// The frames of video topics are not decoded.
+pub fn statistics(
+    format: types::Format,
+    buffer: bytes::Bytes,
+) -> Result<(types::OntologyModelStats, ChunkMetadata), Error> {
+    let size_bytes = buffer.len();
+    let reader = Reader::try_new(format, buffer)?;
+
+    let schema = match &reader {
+        Reader::Parquet { schema, .. } => schema.clone(),
+        Reader::ArrowIpc(reader) => reader.schema(),
+    };
+    let mut stats = empty_statistics(&schema, format);
+    let mut timestamps = types::TimestampStats::new();
+    let mut row_count = 0;
+
+    let mut inspect = |batch: RecordBatch| -> Result<(), Error> {
+        row_count += batch.num_rows();
+        mosaicod_ext::arrow::ontology_model_stats_inspect_record_batch(&mut stats, &batch)?;
+        mosaicod_ext::arrow::timestamp_stats_inspect_record_batch(&mut timestamps, &batch);
+        Ok(())
+    };
+
+    match reader {
+        Reader::Parquet { reader, .. } => {
+            for batch in reader {
+                inspect(batch.map_err(ParquetError::from)?)?;
+            }
+        }
+        Reader::ArrowIpc(reader) => {
+            for batch in reader {
+                inspect(batch?)?;
+            }
+        }
+    }
+
+    Ok((
+        stats,
+        ChunkMetadata {
+            size_bytes,
+            row_count,
+            timestamps,
+        },
+    ))
 }
+
 pub struct ChunkReader {
     reader: Reader,
+    /// Decoder of the frames of video topics
+    frames: Option<video::FrameDecoder>,
 }
 
 impl ChunkReader {
     pub fn new(format: types::Format, buffer: bytes::Bytes) -> Result<Self, Error> {
         Ok(Self {
             reader: Reader::try_new(format, buffer)?,
+            frames: Self::frame_decoder(format)?,
         })
     }
 
+    /// Creates a reader returning the rows from `timestamp_ns` on.
+    ///
+    /// For video topics the reading starts from the keyframe preceding `timestamp_ns`,
+    /// so rows before `timestamp_ns` can still be returned and need to be filtered out
+    /// by the caller. Other formats, and chunks without a keyframe index, are read from
+    /// the first row.
+    pub fn seek(
+        format: types::Format,
+        buffer: bytes::Bytes,
+        timestamp_ns: i64,
+    ) -> Result<Self, Error> {
+        let frames = Self::frame_decoder(format)?;
+        let reader =
+            Reader::try_new_from_row(format, buffer, |keyframes| match (&frames, keyframes) {
+                (Some(_), Some(keyframes)) => Ok(keyframes
+                    .parse::<video::KeyframeIndex>()?
+                    .seek(timestamp_ns)),
+                _ => Ok(0),
+            })?;
+
+        Ok(Self { reader, frames })
+    }
+
+    fn frame_decoder(format: types::Format) -> Result<Option<video::FrameDecoder>, Error> {
+        format
+            .to_properties()
+            .video_coding()
+            .then(video::FrameDecoder::try_new)
+            .transpose()
+    }
+
     pub fn schema(&self) -> SchemaRef {
         match &self.reader {
             Reader::Parquet { schema, .. } => schema.clone(),
+            Reader::ArrowIpc(reader) => reader.schema(),
+        }
+    }
+
+    /// Returns the maximum number of rows of the read batches, if bounded.
+    ///
+    /// Batches of decoded frames are bounded to stay within
+    /// [`params::Params::target_message_size`](mosaicod_core::params::Params::target_message_size).
+    pub fn max_batch_rows(&self) -> Option<usize> {
+        match &self.reader {
+            Reader::Parquet { max_batch_rows, .. } => *max_batch_rows,
+            Reader::ArrowIpc(_) => None,
+        }
+    }
+}
+
+impl Iterator for ChunkReader {
+    type Item = Result<RecordBatch, Error>;
+
+    /// Decodes the next [`RecordBatch`] of the chunk
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = match &mut self.reader {
+            Reader::Parquet { reader, .. } => reader
+                .next()
+                .map(|batch| batch.map_err(|e| ParquetError::from(e).into())),
+            Reader::ArrowIpc(reader) => reader.next().map(|batch| batch.map_err(Error::from)),
+        };
+
+        match &mut self.frames {
+            Some(frames) => batch.map(|batch| batch.and_then(|batch| frames.decode(&batch))),
+            None => batch,
         }
     }
 }
@@ -1,6 +1,6 @@
 use super::*;
 use arrow::{array::RecordBatch, datatypes::SchemaRef};
-use mosaicod_core::{traits, types};
+use mosaicod_core::{metrics as metric_names, traits, types};
 use std::time::Instant;
 use tracing::debug;
 
@@ -19,6 +19,9 @@ pub struct SerializedChunk {
 pub struct ChunkWriter<W> {
     format: types::Format,
 
+    /// Options overriding the defaults of the format
+    options: types::WriterOptions,
+
     /// Schema is here, even if can be deducted from a record batch to impose that a chunked writer
     /// can work on a single schema.
     schema: SchemaRef,
@@ -44,22 +47,41 @@ impl<W> ChunkWriter<W> {
         Self {
             write_target: target,
             format,
+            options: types::WriterOptions::default(),
             schema,
             chunk_count: 0,
             path_provider: Box::new(path_provider),
         }
     }
 
+    /// Overrides the defaults of the format with the writer `options` of the topic.
+    pub fn with_writer_options(mut self, options: types::WriterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Starts numbering the chunks from `chunk_count`, used to append data to
+    /// a location already containing `chunk_count` chunks.
+    pub fn with_chunk_count(mut self, chunk_count: usize) -> Self {
+        self.chunk_count = chunk_count;
+        self
+    }
+
     /// Writes a [`RecordBatch`] into the chunked writer.
     ///
     /// The [`ChunkWriter`] will internally manage the creation of chunks
     /// based on the serialization format and the maximum chunk size (if any).
+    #[tracing::instrument(name = "chunk_write", skip_all, fields(chunk = self.chunk_count))]
     pub async fn write<A>(&mut self, batch: RecordBatch) -> Result<SerializedChunk, Error>
     where
         A: traits::AsyncWriteToPath,
         W: AsRef<A>,
     {
-        let mut writer = InMemoryChunkEncoder::try_new(self.schema.clone(), self.format)?;
+        let mut writer = InMemoryChunkEncoder::try_new_with_options(
+            self.schema.clone(),
+            self.format,
+            &self.options,
+        )?;
 
         let encoding_time = Instant::now();
 
@@ -72,16 +94,22 @@ impl<W> ChunkWriter<W> {
         .map_err(|e| Error::BlockingOperationError(e.to_string()))??;
 
         let buffer_len = buffer.len();
-        let encoding_time_ms = encoding_time.elapsed().as_millis();
+        let encoding_time = encoding_time.elapsed();
         let store_time = Instant::now();
 
         let target_path = self.store(buffer).await?;
         self.chunk_count += 1;
 
+        let store_time = store_time.elapsed();
+
+        metrics::histogram!(metric_names::CHUNK_ENCODING_SECONDS)
+            .record(encoding_time.as_secs_f64());
+        metrics::histogram!(metric_names::CHUNK_STORE_SECONDS).record(store_time.as_secs_f64());
+
         debug!(
             target = "chunk encoding",
-            encoding_ms = encoding_time_ms,
-            store_ms = store_time.elapsed().as_millis(),
+            encoding_ms = encoding_time.as_millis(),
+            store_ms = store_time.as_millis(),
             store_path = target_path.to_string_lossy().to_string(),
             buffer_size_kb = buffer_len / 1000
         );
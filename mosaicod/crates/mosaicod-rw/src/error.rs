@@ -15,10 +15,37 @@ pub enum Error {
     Unsupported,
     #[error("blocking operation failed: {0}")]
     BlockingOperationError(String),
+    #[error("unsupported video frame: {0}")]
+    UnsupportedFrame(String),
+    #[error("video codec error: {0}")]
+    VideoCodecError(String),
+    #[error("invalid writer options: {0}")]
+    InvalidWriterOptions(String),
+}
+
+impl Error {
+    pub fn unsupported_frame(msg: String) -> Self {
+        Self::UnsupportedFrame(msg)
+    }
+
+    pub fn invalid_writer_options(msg: String) -> Self {
+        Self::InvalidWriterOptions(msg)
+    }
+}
+
+impl From<arrow::error::ArrowError> for Error {
+    fn from(err: arrow::error::ArrowError) -> Self {
+        Self::ArrowError(err.into())
+    }
 }
 
 impl core::error::PublicError for Error {
     fn error(&self) -> core::Error {
-        core::Error::internal(None)
+        match self {
+            Self::UnsupportedFrame(_) | Self::InvalidWriterOptions(_) => {
+                core::Error::bad_request(self.to_string())
+            }
+            _ => core::Error::internal(None),
+        }
     }
 }
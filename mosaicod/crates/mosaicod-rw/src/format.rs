@@ -5,12 +5,18 @@
 //! configurations. Each format variant has its own strategy that defines compression settings,
 //! file extensions, and reading options.
 
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::IpcWriteOptions;
+use datafusion::datasource::file_format::arrow::ArrowFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::listing::ListingOptions;
 use mosaicod_core::{params, traits::AsExtension, types};
 use parquet::{
-    basic::{Compression, ZstdLevel},
-    file::properties::{EnabledStatistics, WriterProperties, WriterVersion},
+    arrow::ArrowSchemaConverter,
+    basic::{Compression, Encoding, Type, ZstdLevel},
+    file::properties::{
+        EnabledStatistics, WriterProperties, WriterPropertiesBuilder, WriterVersion,
+    },
     schema::types::ColumnPath,
 };
 use std::sync::Arc;
@@ -30,6 +36,24 @@ use std::sync::Arc;
 pub trait FormatProperties: AsExtension + Send + Sync {
     /// Returns a human-readable name for this format strategy.
     fn name(&self) -> &'static str;
+
+    /// Returns `true` if the statistics of list columns are computed over their items,
+    /// otherwise list columns have no statistics.
+    fn list_items_statistics(&self) -> bool {
+        false
+    }
+
+    /// Returns the step used to quantize floating point values before writing them,
+    /// [`None`] if values are stored as they are.
+    fn quantization_step(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns `true` if the frames of the topic are encoded as a video stream, see
+    /// [`crate::video`].
+    fn video_coding(&self) -> bool {
+        false
+    }
 }
 
 /// Strategy trait for Parquet-based storage formats.
@@ -41,6 +65,14 @@ pub trait ParquetFormatProperties: FormatProperties {
     /// Returns the Parquet writer properties configured for this format.
     fn writer_properties(&self) -> WriterProperties;
 
+    /// Returns the Parquet writer properties configured for this format and tuned for the
+    /// columns of `schema`.
+    ///
+    /// Defaults to [`ParquetFormatProperties::writer_properties`].
+    fn schema_writer_properties(&self, _schema: &Schema) -> WriterProperties {
+        self.writer_properties()
+    }
+
     /// Returns DataFusion ListingOptions configured for reading files in this format.
     fn listing_options(&self) -> ListingOptions;
 
@@ -58,6 +90,26 @@ pub trait ParquetFormatProperties: FormatProperties {
     }
 }
 
+/// Strategy trait for Arrow IPC based storage formats.
+///
+/// Extends `FormatStrategy` with the configuration of formats that store data as Arrow IPC
+/// files (also known as Feather v2), which are written and read without any encoding step.
+pub trait IpcFormatProperties: FormatProperties {
+    /// Returns the IPC writer options configured for this format.
+    fn write_options(&self) -> IpcWriteOptions;
+
+    /// Returns DataFusion ListingOptions configured for reading files in this format.
+    fn listing_options(&self) -> ListingOptions;
+
+    /// Preallocates memory for the internal writer buffer to avoid dynamic resizing.
+    ///
+    /// Batches are written as they are, so the size of a chunk is close to the in-memory
+    /// size of its batches.
+    fn buffer_capacity(&self) -> usize {
+        ((params::params().chunk_target_size.value as f64) * 1.1) as usize
+    }
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Formats Implementation
 // ////////////////////////////////////////////////////////////////////////////
@@ -143,15 +195,17 @@ impl ParquetFormatProperties for RaggedFormatProperties {
 
 /// Format properties for images and dense multi-dimensional arrays.
 ///
-/// Uses maximum ZSTD compression (level 22) since:
-/// - Image data is written once and read many times
-/// - Higher compression ratio reduces storage costs
-/// - Decompression speed is less critical than compression ratio
+/// Uses ZSTD compression at a moderate level (5) since:
+/// - Image payloads are large, so even a moderate level noticeably reduces storage costs
+/// - Higher levels compress images only marginally better at a much higher write cost
+/// - Decompression speed of ZSTD does not depend on the level
+///
+/// Topics needing a different trade-off can override the codec and level with their
+/// writer options.
 pub struct ImageFormatProperties;
 
 impl ImageFormatProperties {
-    /// Maximum ZSTD compression level for best compression ratio.
-    /// Suitable for write-once, read-many image data.
+    /// ZSTD compression level, trading compression ratio for write throughput.
     const COMPRESSION_LEVEL: i32 = 5;
 }
 
@@ -199,6 +253,171 @@ impl ParquetFormatProperties for ImageFormatProperties {
     }
 }
 
+/// Format properties for point clouds, where each row holds a scan as lists of floating
+/// point values (e.g. `x`, `y`, `z`, `intensity`).
+///
+/// - Floating point columns use byte-stream-split encoding, grouping the bytes of the same
+///   significance so that ZSTD can compress them effectively
+/// - Values can be quantized before encoding, see [`params::Params::point_cloud_quantization_step`]
+/// - Statistics of list columns are computed over the points, providing the bounding box of
+///   each chunk
+pub struct PointCloudFormatProperties;
+
+impl PointCloudFormatProperties {
+    /// Byte-stream-split data compresses well at low ZSTD levels, so a fast level is used to
+    /// keep the ingestion of high-rate sensors cheap.
+    const COMPRESSION_LEVEL: i32 = 3;
+}
+
+impl AsExtension for PointCloudFormatProperties {
+    fn as_extension(&self) -> String {
+        params::ext::PARQUET.to_owned()
+    }
+}
+
+impl FormatProperties for PointCloudFormatProperties {
+    fn name(&self) -> &'static str {
+        "point_cloud"
+    }
+
+    fn list_items_statistics(&self) -> bool {
+        true
+    }
+
+    fn quantization_step(&self) -> Option<f64> {
+        let step = params::params().point_cloud_quantization_step.value;
+        (step > 0.0).then_some(step)
+    }
+}
+
+impl PointCloudFormatProperties {
+    fn builder(&self) -> WriterPropertiesBuilder {
+        let ts_path = ColumnPath::from(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+
+        WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .set_compression(Compression::ZSTD(
+                ZstdLevel::try_new(Self::COMPRESSION_LEVEL).expect("valid ZSTD compression level"),
+            ))
+            .set_dictionary_enabled(false)
+            .set_statistics_enabled(EnabledStatistics::None)
+            // Timestamp column: uncompressed for fast seeking
+            .set_column_compression(ts_path.clone(), Compression::UNCOMPRESSED)
+            .set_column_statistics_enabled(ts_path.clone(), EnabledStatistics::Page)
+            .set_column_bloom_filter_enabled(ts_path, true)
+    }
+}
+
+impl ParquetFormatProperties for PointCloudFormatProperties {
+    fn writer_properties(&self) -> WriterProperties {
+        self.builder().build()
+    }
+
+    fn schema_writer_properties(&self, schema: &Schema) -> WriterProperties {
+        let mut builder = self.builder();
+
+        // If the schema can't be converted the writer will fail anyway
+        if let Ok(descriptor) = ArrowSchemaConverter::new().convert(schema) {
+            for column in descriptor.columns() {
+                if matches!(column.physical_type(), Type::FLOAT | Type::DOUBLE) {
+                    builder = builder
+                        .set_column_encoding(column.path().clone(), Encoding::BYTE_STREAM_SPLIT);
+                }
+            }
+        }
+
+        builder.build()
+    }
+
+    fn listing_options(&self) -> ListingOptions {
+        ListingOptions::new(Arc::new(ParquetFormat::default()))
+            .with_file_extension(format!(".{}", self.as_extension()))
+    }
+}
+
+/// Format properties for camera streams, where consecutive raw frames are encoded as an
+/// H.264 stream, see [`crate::video`].
+///
+/// - The Parquet file is only a container, encoded frames are stored uncompressed since
+///   they would not benefit from a further compression
+/// - Other columns (e.g. frame size and encoding) are highly repetitive, so they are
+///   dictionary encoded
+pub struct VideoFormatProperties;
+
+impl AsExtension for VideoFormatProperties {
+    fn as_extension(&self) -> String {
+        params::ext::PARQUET.to_owned()
+    }
+}
+
+impl FormatProperties for VideoFormatProperties {
+    fn name(&self) -> &'static str {
+        "video"
+    }
+
+    fn video_coding(&self) -> bool {
+        true
+    }
+}
+
+impl ParquetFormatProperties for VideoFormatProperties {
+    fn writer_properties(&self) -> WriterProperties {
+        let ts_path = ColumnPath::from(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+        let data_path = ColumnPath::from(crate::video::DATA_COLUMN);
+
+        WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            // Encoded frames: no compression, dictionary or statistics
+            .set_column_compression(data_path.clone(), Compression::UNCOMPRESSED)
+            .set_column_dictionary_enabled(data_path.clone(), false)
+            .set_column_statistics_enabled(data_path, EnabledStatistics::None)
+            // Timestamp column: uncompressed for fast seeking
+            .set_column_compression(ts_path.clone(), Compression::UNCOMPRESSED)
+            .set_column_statistics_enabled(ts_path.clone(), EnabledStatistics::Page)
+            .set_column_bloom_filter_enabled(ts_path, true)
+            .build()
+    }
+
+    fn listing_options(&self) -> ListingOptions {
+        ListingOptions::new(Arc::new(ParquetFormat::default()))
+            .with_file_extension(format!(".{}", self.as_extension()))
+    }
+}
+
+/// Format properties for small, high-rate records (e.g. joint states or IMU samples).
+///
+/// For such topics the cost of the Parquet encoding dominates both ingestion and reads, so
+/// data is stored as uncompressed Arrow IPC files:
+/// - Batches are written and read back without any encoding or decompression step
+/// - Files are larger than the Parquet ones, and have no statistics to skip data within a
+///   chunk, chunks are still skipped by the timestamp ranges and ontology statistics
+///   stored in the database
+pub struct ArrowIpcFormatProperties;
+
+impl AsExtension for ArrowIpcFormatProperties {
+    fn as_extension(&self) -> String {
+        params::ext::ARROW.to_owned()
+    }
+}
+
+impl FormatProperties for ArrowIpcFormatProperties {
+    fn name(&self) -> &'static str {
+        "arrow_ipc"
+    }
+}
+
+impl IpcFormatProperties for ArrowIpcFormatProperties {
+    fn write_options(&self) -> IpcWriteOptions {
+        IpcWriteOptions::default()
+    }
+
+    fn listing_options(&self) -> ListingOptions {
+        ListingOptions::new(Arc::new(ArrowFormat))
+            .with_file_extension(format!(".{}", self.as_extension()))
+    }
+}
+
 /// Returns the base properties for this format variant.
 ///
 /// Use this method when you only need format-agnostic behavior like
@@ -223,6 +442,9 @@ fn as_format_property(format: &types::Format) -> Box<dyn FormatProperties> {
         types::Format::Default => Box::new(DefaultFormatProperties),
         types::Format::Ragged => Box::new(RaggedFormatProperties),
         types::Format::Image => Box::new(ImageFormatProperties),
+        types::Format::PointCloud => Box::new(PointCloudFormatProperties),
+        types::Format::Video => Box::new(VideoFormatProperties),
+        types::Format::ArrowIpc => Box::new(ArrowIpcFormatProperties),
     }
 }
 
@@ -259,7 +481,9 @@ fn as_parquet_properties(format: &types::Format) -> Option<Box<dyn ParquetFormat
         types::Format::Default => Some(Box::new(DefaultFormatProperties)),
         types::Format::Ragged => Some(Box::new(RaggedFormatProperties)),
         types::Format::Image => Some(Box::new(ImageFormatProperties)),
-        // Future non-Parquet formats would return None here
+        types::Format::PointCloud => Some(Box::new(PointCloudFormatProperties)),
+        types::Format::Video => Some(Box::new(VideoFormatProperties)),
+        types::Format::ArrowIpc => None,
     }
 }
 
@@ -269,6 +493,52 @@ impl ToParquetProperties for types::Format {
     }
 }
 
+/// Returns the Arrow IPC specific properties if this format uses Arrow IPC storage.
+///
+/// # Example
+///
+/// ```
+/// use mosaicod_core::types::Format;
+/// use mosaicod_rw::ToIpcProperties;
+///
+/// if let Some(props) = Format::ArrowIpc.to_ipc_properties() {
+///     let wopts = props.write_options();
+///     let loptions = props.listing_options();
+/// }
+/// ```
+pub trait ToIpcProperties {
+    fn to_ipc_properties(&self) -> Option<Box<dyn IpcFormatProperties>>;
+}
+
+fn as_ipc_properties(format: &types::Format) -> Option<Box<dyn IpcFormatProperties>> {
+    match format {
+        types::Format::ArrowIpc => Some(Box::new(ArrowIpcFormatProperties)),
+        _ => None,
+    }
+}
+
+impl ToIpcProperties for types::Format {
+    fn to_ipc_properties(&self) -> Option<Box<dyn IpcFormatProperties>> {
+        as_ipc_properties(self)
+    }
+}
+
+/// Returns the DataFusion listing options used to read the data files of this format
+/// variant, regardless of the underlying storage.
+pub trait ToListingOptions {
+    fn to_listing_options(&self) -> ListingOptions;
+}
+
+impl ToListingOptions for types::Format {
+    fn to_listing_options(&self) -> ListingOptions {
+        match (self.to_parquet_properties(), self.to_ipc_properties()) {
+            (Some(props), _) => props.listing_options(),
+            (None, Some(props)) => props.listing_options(),
+            (None, None) => unreachable!("format `{self}` has no storage properties"),
+        }
+    }
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // TEST
 // ////////////////////////////////////////////////////////////////////////////
@@ -284,6 +554,9 @@ mod tests {
         assert_eq!(Format::Default.to_properties().name(), "default");
         assert_eq!(Format::Ragged.to_properties().name(), "ragged");
         assert_eq!(Format::Image.to_properties().name(), "image");
+        assert_eq!(Format::PointCloud.to_properties().name(), "point_cloud");
+        assert_eq!(Format::Video.to_properties().name(), "video");
+        assert_eq!(Format::ArrowIpc.to_properties().name(), "arrow_ipc");
     }
 
     #[test]
@@ -291,6 +564,8 @@ mod tests {
         assert_eq!(Format::Default.to_properties().as_extension(), "parquet");
         assert_eq!(Format::Ragged.to_properties().as_extension(), "parquet");
         assert_eq!(Format::Image.to_properties().as_extension(), "parquet");
+        assert_eq!(Format::PointCloud.to_properties().as_extension(), "parquet");
+        assert_eq!(Format::ArrowIpc.to_properties().as_extension(), "arrow");
     }
 
     #[test]
@@ -330,5 +605,31 @@ mod tests {
         assert!(Format::Default.to_parquet_properties().is_some());
         assert!(Format::Ragged.to_parquet_properties().is_some());
         assert!(Format::Image.to_parquet_properties().is_some());
+        assert!(Format::PointCloud.to_parquet_properties().is_some());
+        assert!(Format::Video.to_parquet_properties().is_some());
+        assert!(Format::ArrowIpc.to_parquet_properties().is_none());
+    }
+
+    #[test]
+    fn as_ipc_returns_some_for_ipc_formats() {
+        assert!(Format::ArrowIpc.to_ipc_properties().is_some());
+        assert!(Format::Default.to_ipc_properties().is_none());
+        assert!(Format::Video.to_ipc_properties().is_none());
+    }
+
+    #[test]
+    fn point_cloud_float_columns_encoding() {
+        let batch = mosaicod_ext::arrow::testing::dummy_point_cloud_batch();
+
+        let props = Format::PointCloud
+            .to_parquet_properties()
+            .unwrap()
+            .schema_writer_properties(&batch.schema());
+
+        let x_path = ColumnPath::from(vec!["x".to_owned(), "list".to_owned(), "item".to_owned()]);
+        assert_eq!(props.encoding(&x_path), Some(Encoding::BYTE_STREAM_SPLIT));
+
+        let ts_path = ColumnPath::from(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+        assert_eq!(props.encoding(&ts_path), None);
     }
 }
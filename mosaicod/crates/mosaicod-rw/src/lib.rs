@@ -11,8 +11,12 @@ pub use chunk_encoder::{ChunkMetadata, InMemoryChunkEncoder};
 
 mod writer;
 
+pub mod writer_options;
+
 pub mod chunk_writer;
 pub use chunk_writer::{ChunkWriter, SerializedChunk};
 
 pub mod chunk_reader;
 pub use chunk_reader::ChunkReader;
+
+pub mod video;
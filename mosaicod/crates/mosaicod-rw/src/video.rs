@@ -0,0 +1,710 @@
+//! Video coding of camera frames.
+//!
+//! The raw frames of a video topic are encoded as an H.264 stream, each chunk being an
+//! independent stream starting with a keyframe. Every row keeps the encoded frame in place
+//! of the raw one, so a chunk is still a Parquet file with the schema of the uploaded data.
+//!
+//! The frames are expected in the layout of the image ontology:
+//!
+//! | Column     | Content                                                        |
+//! | ---------- | -------------------------------------------------------------- |
+//! | `data`     | Raw pixels, `height` rows of `stride` bytes                    |
+//! | `width`    | Frame width in pixels, it must be even                         |
+//! | `height`   | Frame height in pixels, it must be even                        |
+//! | `stride`   | Size in bytes of a row of pixels                               |
+//! | `encoding` | Pixel encoding, either `rgb8`, `bgr8`, `mono8` or `8UC1`       |
+//! | `format`   | Optional, when present it must be `raw`                        |
+//!
+//! Frames larger than the H.264 level 5.2 limits are rejected, see `MAX_FRAME_PIXELS` and
+//! `MAX_FRAME_DIMENSION`.
+//!
+//! The rows holding a keyframe are recorded in a [`KeyframeIndex`] saved in the Parquet
+//! metadata of the chunk, allowing to start decoding from the keyframe preceding a timestamp.
+
+use super::Error;
+use arrow::array::{Array, ArrayRef, AsArray, BinaryBuilder, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int64Type};
+use mosaicod_core::params;
+use openh264::OpenH264API;
+use openh264::decoder::{DecodedYUV, Decoder, DecoderConfig};
+use openh264::encoder::{
+    Encoder, EncoderConfig, FrameType, IntraFramePeriod, QpRange, RateControlMode,
+};
+use openh264::formats::{RgbSliceU8, YUVBuffer, YUVSource};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::statistics::Statistics;
+use std::sync::Arc;
+
+pub const DATA_COLUMN: &str = "data";
+pub const WIDTH_COLUMN: &str = "width";
+pub const HEIGHT_COLUMN: &str = "height";
+pub const STRIDE_COLUMN: &str = "stride";
+pub const ENCODING_COLUMN: &str = "encoding";
+pub const FORMAT_COLUMN: &str = "format";
+
+/// Parquet metadata key holding the [`KeyframeIndex`] of a chunk
+pub const KEYFRAME_INDEX_METADATA_KEY: &str = "mosaico:video:keyframes";
+
+/// Maximum quantization parameter supported by H.264
+const MAX_QUANTIZATION: u8 = 51;
+
+/// Maximum number of pixels of a frame supported by the encoder, i.e. 36864 macroblocks
+/// of 16x16 pixels as defined by H.264 level 5.2
+const MAX_FRAME_PIXELS: usize = 36864 * 256;
+
+/// Maximum width and height of a frame, i.e. 543 macroblocks as defined by H.264 level 5.2
+const MAX_FRAME_DIMENSION: usize = 543 * 16;
+
+/// Pixel encodings supported by the video coding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PixelEncoding {
+    Rgb8,
+    Bgr8,
+    Mono8,
+}
+
+impl PixelEncoding {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rgb8" => Some(Self::Rgb8),
+            "bgr8" => Some(Self::Bgr8),
+            "mono8" | "8UC1" => Some(Self::Mono8),
+            _ => None,
+        }
+    }
+
+    fn channels(&self) -> usize {
+        match self {
+            Self::Rgb8 | Self::Bgr8 => 3,
+            Self::Mono8 => 1,
+        }
+    }
+}
+
+/// Geometry of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameLayout {
+    width: usize,
+    height: usize,
+    stride: usize,
+    encoding: PixelEncoding,
+}
+
+impl FrameLayout {
+    /// Size in bytes of a row of pixels, without padding
+    fn row_size(&self) -> Result<usize, Error> {
+        self.width
+            .checked_mul(self.encoding.channels())
+            .ok_or_else(|| Error::unsupported_frame("frame row size overflow".to_owned()))
+    }
+
+    /// Size in bytes of the frame, including the padding at the end of each row
+    fn size(&self) -> Result<usize, Error> {
+        self.stride
+            .checked_mul(self.height)
+            .ok_or_else(|| Error::unsupported_frame("frame size overflow".to_owned()))
+    }
+}
+
+/// Columns describing the frames of a batch
+struct FrameColumns {
+    data: ArrayRef,
+    width: ArrayRef,
+    height: ArrayRef,
+    stride: ArrayRef,
+    encoding: ArrayRef,
+    format: Option<ArrayRef>,
+}
+
+impl FrameColumns {
+    fn try_new(batch: &RecordBatch) -> Result<Self, Error> {
+        let column = |name: &str, data_type: &DataType| -> Result<ArrayRef, Error> {
+            let array = batch
+                .column_by_name(name)
+                .ok_or_else(|| Error::unsupported_frame(format!("missing `{name}` column")))?;
+            cast(array, data_type)
+                .map_err(|_| Error::unsupported_frame(format!("invalid `{name}` column type")))
+        };
+
+        Ok(Self {
+            data: column(DATA_COLUMN, &DataType::Binary)?,
+            width: column(WIDTH_COLUMN, &DataType::Int64)?,
+            height: column(HEIGHT_COLUMN, &DataType::Int64)?,
+            stride: column(STRIDE_COLUMN, &DataType::Int64)?,
+            encoding: column(ENCODING_COLUMN, &DataType::Utf8)?,
+            format: batch
+                .column_by_name(FORMAT_COLUMN)
+                .map(|_| column(FORMAT_COLUMN, &DataType::Utf8))
+                .transpose()?,
+        })
+    }
+
+    /// Returns the frame data of a row, [`None`] if the row holds no frame
+    fn data(&self, row: usize) -> Option<&[u8]> {
+        let data = self.data.as_binary::<i32>();
+        data.is_valid(row).then(|| data.value(row))
+    }
+
+    /// Returns the layout of the frame of a row
+    fn layout(&self, row: usize) -> Result<FrameLayout, Error> {
+        let int = |array: &ArrayRef, name: &str| -> Result<usize, Error> {
+            let array = array.as_primitive::<Int64Type>();
+            array
+                .is_valid(row)
+                .then(|| array.value(row))
+                .and_then(|value| usize::try_from(value).ok())
+                .ok_or_else(|| Error::unsupported_frame(format!("invalid `{name}` at row {row}")))
+        };
+
+        if let Some(format) = &self.format {
+            let format = format.as_string::<i32>();
+            if format.is_valid(row) && format.value(row) != "raw" {
+                return Err(Error::unsupported_frame(format!(
+                    "frame format `{}` at row {row}, only `raw` frames are supported",
+                    format.value(row)
+                )));
+            }
+        }
+
+        let encoding = self.encoding.as_string::<i32>();
+        let encoding_name = if encoding.is_valid(row) {
+            encoding.value(row)
+        } else {
+            ""
+        };
+        let encoding = PixelEncoding::from_name(encoding_name).ok_or_else(|| {
+            Error::unsupported_frame(format!(
+                "pixel encoding `{encoding_name}` at row {row}, supported encodings are `rgb8`, `bgr8`, `mono8` and `8UC1`"
+            ))
+        })?;
+
+        let layout = FrameLayout {
+            width: int(&self.width, WIDTH_COLUMN)?,
+            height: int(&self.height, HEIGHT_COLUMN)?,
+            stride: int(&self.stride, STRIDE_COLUMN)?,
+            encoding,
+        };
+
+        if layout.width == 0
+            || layout.height == 0
+            || !layout.width.is_multiple_of(2)
+            || !layout.height.is_multiple_of(2)
+        {
+            return Err(Error::unsupported_frame(format!(
+                "frame size {}x{} at row {row}, width and height must be even and not zero",
+                layout.width, layout.height
+            )));
+        }
+
+        if layout.width > MAX_FRAME_DIMENSION
+            || layout.height > MAX_FRAME_DIMENSION
+            || layout.width * layout.height > MAX_FRAME_PIXELS
+        {
+            return Err(Error::unsupported_frame(format!(
+                "frame size {}x{} at row {row}, frames are limited to {MAX_FRAME_PIXELS} pixels and {MAX_FRAME_DIMENSION} pixels per side",
+                layout.width, layout.height
+            )));
+        }
+
+        if layout.stride < layout.row_size()? {
+            return Err(Error::unsupported_frame(format!(
+                "stride {} at row {row} is smaller than the frame row size",
+                layout.stride
+            )));
+        }
+
+        Ok(layout)
+    }
+}
+
+/// Replaces the frame data column of `batch` with `data`, keeping its original type
+fn replace_data(batch: &RecordBatch, data: ArrayRef) -> Result<RecordBatch, Error> {
+    let index = batch.schema().index_of(DATA_COLUMN)?;
+    let data_type = batch.schema().field(index).data_type().clone();
+
+    let mut columns = batch.columns().to_vec();
+    columns[index] = cast(&data, &data_type)?;
+
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
+
+/// Converts a raw frame into the YUV 4:2:0 layout used by the encoder
+fn frame_to_yuv(data: &[u8], layout: &FrameLayout) -> Result<YUVBuffer, Error> {
+    let row_size = layout.row_size()?;
+    // The padding of the last row can be omitted
+    let min_size = layout
+        .stride
+        .checked_mul(layout.height - 1)
+        .and_then(|size| size.checked_add(row_size))
+        .ok_or_else(|| Error::unsupported_frame("frame size overflow".to_owned()))?;
+    if data.len() < min_size {
+        return Err(Error::unsupported_frame(format!(
+            "frame data of {} bytes is too short for its size",
+            data.len()
+        )));
+    }
+
+    // Drop the padding at the end of each row, if any
+    let mut pixels: Vec<u8> = data
+        .chunks(layout.stride)
+        .take(layout.height)
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect();
+
+    let dimensions = (layout.width, layout.height);
+    Ok(match layout.encoding {
+        PixelEncoding::Rgb8 => YUVBuffer::from_rgb8_source(RgbSliceU8::new(&pixels, dimensions)),
+        PixelEncoding::Bgr8 => {
+            pixels
+                .chunks_exact_mut(3)
+                .for_each(|pixel| pixel.swap(0, 2));
+            YUVBuffer::from_rgb8_source(RgbSliceU8::new(&pixels, dimensions))
+        }
+        PixelEncoding::Mono8 => {
+            // Luma only, chroma planes are set to the neutral value
+            let mut yuv = pixels;
+            yuv.resize(layout.width * layout.height * 3 / 2, 128);
+            YUVBuffer::from_vec(yuv, layout.width, layout.height)
+        }
+    })
+}
+
+/// Converts a decoded frame back into the raw layout of the uploaded frame
+fn yuv_to_frame(yuv: &DecodedYUV, layout: &FrameLayout) -> Result<Vec<u8>, Error> {
+    if yuv.dimensions() != (layout.width, layout.height) {
+        return Err(Error::VideoCodecError(format!(
+            "decoded frame size {:?} does not match {}x{}",
+            yuv.dimensions(),
+            layout.width,
+            layout.height
+        )));
+    }
+
+    let row_size = layout.row_size()?;
+    let pixels = match layout.encoding {
+        PixelEncoding::Rgb8 | PixelEncoding::Bgr8 => {
+            let mut pixels = vec![0; row_size * layout.height];
+            yuv.write_rgb8(&mut pixels);
+            if layout.encoding == PixelEncoding::Bgr8 {
+                pixels
+                    .chunks_exact_mut(3)
+                    .for_each(|pixel| pixel.swap(0, 2));
+            }
+            pixels
+        }
+        PixelEncoding::Mono8 => {
+            let y_stride = yuv.strides().0;
+            yuv.y()
+                .chunks(y_stride)
+                .take(layout.height)
+                .flat_map(|row| &row[..row_size])
+                .copied()
+                .collect()
+        }
+    };
+
+    if layout.stride == row_size {
+        return Ok(pixels);
+    }
+
+    // Restore the padding of the uploaded rows
+    let mut frame = vec![0; layout.size()?];
+    for (dst, src) in frame.chunks_mut(layout.stride).zip(pixels.chunks(row_size)) {
+        dst[..row_size].copy_from_slice(src);
+    }
+    Ok(frame)
+}
+
+/// Returns the maximum number of decoded frames of a chunk fitting in
+/// [`params::Params::target_message_size`], [`None`] if the chunk has no statistics about
+/// the frame sizes.
+///
+/// Raw frames are much larger than the encoded ones, so batches of decoded frames need to be
+/// smaller than the ones read from the chunk.
+pub fn max_decoded_rows(metadata: &ParquetMetaData) -> Option<usize> {
+    let max = |name: &str| -> Option<usize> {
+        metadata
+            .row_groups()
+            .iter()
+            .flat_map(|row_group| row_group.columns())
+            .filter(|column| column.column_path().string() == name)
+            .filter_map(|column| match column.statistics()? {
+                Statistics::Int32(s) => s.max_opt().map(|v| *v as i64),
+                Statistics::Int64(s) => s.max_opt().copied(),
+                _ => None,
+            })
+            .max()
+            .and_then(|v| usize::try_from(v).ok())
+    };
+
+    let frame_size = max(STRIDE_COLUMN)?.saturating_mul(max(HEIGHT_COLUMN)?);
+    Some((params::params().target_message_size.value / frame_size.max(1)).max(1))
+}
+
+/// Index of the keyframes of a chunk, as `(timestamp, row)` pairs sorted by row.
+///
+/// The index is only built for chunks with sorted timestamps, since in that case all the
+/// rows after a timestamp follow the keyframe preceding it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyframeIndex(Vec<(i64, usize)>);
+
+impl KeyframeIndex {
+    /// Returns the row of the last keyframe with a timestamp not greater than `timestamp`,
+    /// decoding can start from this row to get all the frames from `timestamp` on.
+    pub fn seek(&self, timestamp: i64) -> usize {
+        self.0
+            .iter()
+            .take_while(|(ts, _)| *ts <= timestamp)
+            .last()
+            .map_or(0, |(_, row)| *row)
+    }
+}
+
+impl std::fmt::Display for KeyframeIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(ts, row)| format!("{ts}:{row}"))
+            .collect();
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+impl std::str::FromStr for KeyframeIndex {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::VideoCodecError(format!("invalid keyframe index `{s}`"));
+
+        if s.is_empty() {
+            return Ok(Self::default());
+        }
+
+        s.split(',')
+            .map(|entry| {
+                let (ts, row) = entry.split_once(':').ok_or_else(invalid)?;
+                Ok((
+                    ts.parse().map_err(|_| invalid())?,
+                    row.parse().map_err(|_| invalid())?,
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Encodes the frames of the batches of a chunk into an H.264 stream
+pub struct FrameEncoder {
+    encoder: Option<(Encoder, (usize, usize))>,
+    keyframe_interval: u32,
+    quantization: u8,
+    frames_since_keyframe: u32,
+    rows: usize,
+    keyframes: Vec<(i64, usize)>,
+    last_timestamp: Option<i64>,
+    sorted: bool,
+}
+
+impl FrameEncoder {
+    /// Creates a new encoder, configured with [`params::Params::video_keyframe_interval`]
+    /// and [`params::Params::video_quantization`].
+    pub fn new() -> Self {
+        let params = params::params();
+        Self {
+            encoder: None,
+            keyframe_interval: params.video_keyframe_interval.value.max(1),
+            quantization: params.video_quantization.value.min(MAX_QUANTIZATION),
+            frames_since_keyframe: 0,
+            rows: 0,
+            keyframes: Vec::new(),
+            last_timestamp: None,
+            sorted: true,
+        }
+    }
+
+    /// Returns the encoder for frames of the given size, a new stream is started (with a
+    /// keyframe) if the size changes.
+    fn encoder(&mut self, dimensions: (usize, usize)) -> Result<&mut Encoder, Error> {
+        if self
+            .encoder
+            .as_ref()
+            .is_none_or(|(_, dim)| *dim != dimensions)
+        {
+            let config = EncoderConfig::new()
+                .skip_frames(false)
+                .rate_control_mode(RateControlMode::Off)
+                .qp(QpRange::new(self.quantization, self.quantization))
+                .intra_frame_period(IntraFramePeriod::from_num_frames(self.keyframe_interval));
+            let encoder = Encoder::with_api_config(OpenH264API::from_source(), config)?;
+            self.encoder = Some((encoder, dimensions));
+        }
+
+        // The encoder has been set above
+        Ok(&mut self.encoder.as_mut().expect("encoder initialized").0)
+    }
+
+    /// Encodes the frames of `batch`, returning the batch with the encoded frames in place
+    /// of the raw ones.
+    ///
+    /// Rows without a frame are left empty.
+    pub fn encode(&mut self, batch: &RecordBatch) -> Result<RecordBatch, Error> {
+        let frames = FrameColumns::try_new(batch)?;
+        let timestamps = batch
+            .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+            .map(|ts| cast(ts, &DataType::Int64))
+            .transpose()?;
+        let timestamp = |row: usize| {
+            timestamps
+                .as_ref()
+                .map(|ts| ts.as_primitive::<Int64Type>())
+                .and_then(|ts| ts.is_valid(row).then(|| ts.value(row)))
+        };
+
+        let mut data = BinaryBuilder::new();
+        for row in 0..batch.num_rows() {
+            let ts = timestamp(row);
+            self.track_timestamp(ts);
+
+            let Some(frame) = frames.data(row) else {
+                data.append_null();
+                continue;
+            };
+
+            let layout = frames.layout(row)?;
+            let yuv = frame_to_yuv(frame, &layout)?;
+
+            let force_keyframe = self.frames_since_keyframe >= self.keyframe_interval;
+            let encoder = self.encoder((layout.width, layout.height))?;
+            if force_keyframe {
+                encoder.force_intra_frame();
+            }
+            let (frame_type, bitstream) = {
+                let bitstream = encoder.encode(&yuv)?;
+                (bitstream.frame_type(), bitstream.to_vec())
+            };
+
+            if matches!(frame_type, FrameType::IDR) {
+                self.keyframes
+                    .push((ts.unwrap_or_default(), self.rows + row));
+                self.frames_since_keyframe = 0;
+            }
+            self.frames_since_keyframe += 1;
+
+            data.append_value(bitstream);
+        }
+        self.rows += batch.num_rows();
+
+        replace_data(batch, Arc::new(data.finish()))
+    }
+
+    /// Appends the rows of an already encoded stream, e.g. the frames of another chunk, whose
+    /// keyframes are listed in `keyframes` (relative to the first row of `batches`).
+    ///
+    /// If `keyframes` is [`None`] the keyframes of the stream are unknown, and no index is
+    /// returned by [`FrameEncoder::keyframe_index`]. Frames encoded afterwards start a new
+    /// stream.
+    pub fn append_encoded(&mut self, batches: &[RecordBatch], keyframes: Option<&KeyframeIndex>) {
+        match keyframes {
+            Some(keyframes) => self
+                .keyframes
+                .extend(keyframes.0.iter().map(|(ts, row)| (*ts, self.rows + row))),
+            None => self.sorted = false,
+        }
+
+        for batch in batches {
+            let timestamps = batch
+                .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+                .and_then(|ts| cast(ts, &DataType::Int64).ok());
+            for row in 0..batch.num_rows() {
+                self.track_timestamp(
+                    timestamps
+                        .as_ref()
+                        .map(|ts| ts.as_primitive::<Int64Type>())
+                        .and_then(|ts| ts.is_valid(row).then(|| ts.value(row))),
+                );
+            }
+            self.rows += batch.num_rows();
+        }
+
+        self.encoder = None;
+        self.frames_since_keyframe = 0;
+    }
+
+    /// Records the timestamp of the next row, the index is dropped as soon as a timestamp is
+    /// missing or not sorted.
+    fn track_timestamp(&mut self, ts: Option<i64>) {
+        self.sorted &= match (self.last_timestamp, ts) {
+            (Some(last), Some(ts)) => last <= ts,
+            (_, None) => false,
+            _ => true,
+        };
+        self.last_timestamp = ts.or(self.last_timestamp);
+    }
+
+    /// Returns the index of the keyframes encoded so far, [`None`] if the timestamps of the
+    /// encoded rows are not sorted.
+    pub fn keyframe_index(&self) -> Option<KeyframeIndex> {
+        self.sorted.then(|| KeyframeIndex(self.keyframes.clone()))
+    }
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes the frames of the batches of a chunk, produced by a [`FrameEncoder`]
+pub struct FrameDecoder {
+    decoder: Decoder,
+}
+
+impl FrameDecoder {
+    pub fn try_new() -> Result<Self, Error> {
+        Ok(Self {
+            decoder: Decoder::with_api_config(OpenH264API::from_source(), DecoderConfig::new())?,
+        })
+    }
+
+    /// Decodes the frames of `batch`, returning the batch with the raw frames in place of
+    /// the encoded ones.
+    pub fn decode(&mut self, batch: &RecordBatch) -> Result<RecordBatch, Error> {
+        let frames = FrameColumns::try_new(batch)?;
+
+        let mut data = BinaryBuilder::new();
+        for row in 0..batch.num_rows() {
+            let Some(frame) = frames.data(row) else {
+                data.append_null();
+                continue;
+            };
+
+            let layout = frames.layout(row)?;
+            let yuv = self
+                .decoder
+                .decode(frame)?
+                .ok_or_else(|| Error::VideoCodecError(format!("no frame decoded at row {row}")))?;
+
+            data.append_value(yuv_to_frame(&yuv, &layout)?);
+        }
+
+        replace_data(batch, Arc::new(data.finish()))
+    }
+}
+
+impl From<openh264::Error> for Error {
+    fn from(err: openh264::Error) -> Self {
+        Self::VideoCodecError(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyframe_index() {
+        let index: KeyframeIndex = "100:0,160:6,220:12".parse().unwrap();
+        assert_eq!(index.to_string(), "100:0,160:6,220:12");
+
+        assert_eq!(index.seek(50), 0);
+        assert_eq!(index.seek(100), 0);
+        assert_eq!(index.seek(200), 6);
+        assert_eq!(index.seek(1000), 12);
+
+        assert_eq!(
+            "".parse::<KeyframeIndex>().unwrap(),
+            KeyframeIndex::default()
+        );
+        assert!("100".parse::<KeyframeIndex>().is_err());
+    }
+
+    #[test]
+    fn encode_decode() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let batch = mosaicod_ext::arrow::testing::dummy_video_batch(8, 64, 48);
+
+        let mut encoder = FrameEncoder::new();
+        let encoded = encoder.encode(&batch).unwrap();
+
+        let raw_size = batch
+            .column_by_name(DATA_COLUMN)
+            .unwrap()
+            .get_array_memory_size();
+        let encoded_size = encoded
+            .column_by_name(DATA_COLUMN)
+            .unwrap()
+            .get_array_memory_size();
+        assert!(encoded_size * 4 < raw_size);
+
+        // A single stream starting with a keyframe
+        assert_eq!(
+            encoder.keyframe_index(),
+            Some(KeyframeIndex(vec![(10000, 0)]))
+        );
+
+        let decoded = FrameDecoder::try_new().unwrap().decode(&encoded).unwrap();
+        assert_eq!(decoded.schema(), batch.schema());
+
+        let original = batch
+            .column_by_name(DATA_COLUMN)
+            .unwrap()
+            .as_binary::<i32>();
+        let decoded = decoded
+            .column_by_name(DATA_COLUMN)
+            .unwrap()
+            .as_binary::<i32>();
+        for row in 0..batch.num_rows() {
+            assert_eq!(original.value(row).len(), decoded.value(row).len());
+
+            // Encoding is lossy, but the frames must stay close to the originals
+            let error: u64 = original
+                .value(row)
+                .iter()
+                .zip(decoded.value(row))
+                .map(|(a, b)| a.abs_diff(*b) as u64)
+                .sum();
+            let mean_error = error as f64 / original.value(row).len() as f64;
+            assert!(mean_error < 8.0, "mean error {mean_error} at row {row}");
+        }
+    }
+
+    #[test]
+    fn unsupported_frames() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let batch = mosaicod_ext::arrow::testing::dummy_video_batch(2, 63, 48);
+        assert!(matches!(
+            FrameEncoder::new().encode(&batch),
+            Err(Error::UnsupportedFrame(_))
+        ));
+
+        // Frames larger than the encoder limits
+        let batch = mosaicod_ext::arrow::testing::dummy_video_batch(1, MAX_FRAME_DIMENSION + 2, 2);
+        assert!(matches!(
+            FrameEncoder::new().encode(&batch),
+            Err(Error::UnsupportedFrame(_))
+        ));
+
+        // Strides overflowing the frame size
+        let batch = mosaicod_ext::arrow::testing::dummy_video_batch(2, 64, 48);
+        let index = batch.schema().index_of(STRIDE_COLUMN).unwrap();
+        let mut fields = batch.schema().fields().to_vec();
+        fields[index] = Arc::new(arrow::datatypes::Field::new(
+            STRIDE_COLUMN,
+            DataType::Int64,
+            false,
+        ));
+        let mut columns = batch.columns().to_vec();
+        columns[index] = Arc::new(arrow::array::Int64Array::from(vec![i64::MAX; 2]));
+        let batch =
+            RecordBatch::try_new(Arc::new(arrow::datatypes::Schema::new(fields)), columns).unwrap();
+        assert!(matches!(
+            FrameEncoder::new().encode(&batch),
+            Err(Error::UnsupportedFrame(_))
+        ));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::ToParquetProperties;
+use crate::{ToIpcProperties, ToParquetProperties};
 
-use super::Error;
+use super::{Error, writer_options};
 use arrow::datatypes::Schema;
+use arrow::ipc::writer::FileWriter;
 use mosaicod_core::types;
 use parquet::arrow::ArrowWriter;
 use std::sync::Arc;
@@ -28,19 +29,62 @@ impl ParquetWriter {
     }
 }
 
+pub struct IpcWriter(FileWriter<Vec<u8>>);
+
+impl std::ops::Deref for IpcWriter {
+    type Target = FileWriter<Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for IpcWriter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl IpcWriter {
+    pub fn buffer(self) -> Result<Vec<u8>, Error> {
+        Ok(self.0.into_inner()?)
+    }
+}
+
 pub enum Writer {
     /// Parquet file format <https://parquet.apache.org/docs/file-format/>
     /// (cabba) TODO: evaluate `AsyncArrowWriter`
     Parquet(ParquetWriter),
+
+    /// Arrow IPC file format <https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format>
+    ArrowIpc(IpcWriter),
 }
 
 impl Writer {
-    pub fn new(schema: &Arc<Schema>, format: types::Format) -> Result<Self, Error> {
+    pub fn new(
+        schema: &Arc<Schema>,
+        format: types::Format,
+        options: &types::WriterOptions,
+    ) -> Result<Self, Error> {
+        if let Some(ipc_strategy) = format.to_ipc_properties() {
+            let write_options = writer_options::apply_ipc(ipc_strategy.write_options(), options)?;
+
+            return Ok(Self::ArrowIpc(IpcWriter(FileWriter::try_new_with_options(
+                Vec::with_capacity(ipc_strategy.buffer_capacity()),
+                schema,
+                write_options,
+            )?)));
+        }
+
         let parquet_strategy = format
             .to_parquet_properties()
-            .expect("Writer::new requires a Parquet-based format");
+            .expect("Writer::new requires a Parquet or Arrow IPC based format");
 
-        let props = parquet_strategy.writer_properties();
+        let props = writer_options::apply(
+            parquet_strategy.schema_writer_properties(schema),
+            schema,
+            options,
+        )?;
 
         Ok(Self::Parquet(ParquetWriter(ArrowWriter::try_new(
             Vec::with_capacity(parquet_strategy.buffer_capacity()),
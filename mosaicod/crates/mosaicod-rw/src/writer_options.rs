@@ -0,0 +1,396 @@
+//! Writer options of topics.
+//!
+//! Writer options override the [`WriterProperties`] provided by the format properties of a
+//! topic. Global settings replace the format defaults, while the settings the format sets on
+//! single columns (e.g. the uncompressed timestamp column) are only replaced by column
+//! settings.
+//!
+//! Arrow IPC formats only support the `uncompressed`, `lz4` and `zstd` codecs on the whole
+//! file, see [`apply_ipc`].
+
+use super::{Error, ToIpcProperties};
+use arrow::datatypes::Schema;
+use arrow::ipc::{CompressionType, writer::IpcWriteOptions};
+use mosaicod_core::types;
+use parquet::{
+    arrow::ArrowSchemaConverter,
+    basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel},
+    file::properties::{EnabledStatistics, WriterProperties},
+    schema::types::ColumnPath,
+};
+
+/// Checks that the writer options are consistent and supported by `format`, without
+/// checking the field names that can only be resolved against the schema of the data.
+pub fn validate(options: &types::WriterOptions, format: types::Format) -> Result<(), Error> {
+    if format.to_ipc_properties().is_some() {
+        return ipc_compression(options).map(|_| ());
+    }
+
+    compression(&options.global)?;
+
+    for (name, column) in &options.columns {
+        check_field_name(name)?;
+        compression(column)
+            .map_err(|e| Error::invalid_writer_options(format!("column `{name}`: {e}")))?;
+    }
+
+    for name in &options.bloom_filter {
+        check_field_name(name)?;
+    }
+
+    if options.row_group_size == Some(0) {
+        return Err(Error::invalid_writer_options(
+            "`row_group_size` must be greater than zero".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Applies the writer options on top of `props`, resolving field names against `schema`.
+pub(crate) fn apply(
+    props: WriterProperties,
+    schema: &Schema,
+    options: &types::WriterOptions,
+) -> Result<WriterProperties, Error> {
+    if options.is_empty() {
+        return Ok(props);
+    }
+
+    validate(options, types::Format::Default)?;
+
+    let descriptor = ArrowSchemaConverter::new().convert(schema)?;
+    let leaves: Vec<ColumnPath> = descriptor
+        .columns()
+        .iter()
+        .map(|column| column.path().clone())
+        .collect();
+
+    let mut builder = props.into_builder();
+
+    let global = &options.global;
+    if let Some(compression) = compression(global)? {
+        builder = builder.set_compression(compression);
+    }
+    if let Some(dictionary) = global.dictionary {
+        builder = builder.set_dictionary_enabled(dictionary);
+    }
+    if let Some(statistics) = global.statistics {
+        builder = builder.set_statistics_enabled(statistics_level(statistics));
+    }
+    if let Some(rows) = options.row_group_size {
+        builder = builder.set_max_row_group_row_count(Some(rows));
+    }
+
+    for (name, column) in &options.columns {
+        let compression = compression(column)?;
+        for path in field_columns(&leaves, name)? {
+            if let Some(compression) = compression {
+                builder = builder.set_column_compression(path.clone(), compression);
+            }
+            if let Some(dictionary) = column.dictionary {
+                builder = builder.set_column_dictionary_enabled(path.clone(), dictionary);
+            }
+            if let Some(statistics) = column.statistics {
+                builder = builder
+                    .set_column_statistics_enabled(path.clone(), statistics_level(statistics));
+            }
+        }
+    }
+
+    for name in &options.bloom_filter {
+        for path in field_columns(&leaves, name)? {
+            builder = builder.set_column_bloom_filter_enabled(path, true);
+        }
+    }
+
+    Ok(builder.build())
+}
+
+/// Applies the writer options on top of the `write_options` of an Arrow IPC format.
+pub(crate) fn apply_ipc(
+    write_options: IpcWriteOptions,
+    options: &types::WriterOptions,
+) -> Result<IpcWriteOptions, Error> {
+    match ipc_compression(options)? {
+        Some(compression) => Ok(write_options.try_with_compression(compression)?),
+        None => Ok(write_options),
+    }
+}
+
+/// Returns the compression set by the options of an Arrow IPC format, if any. Arrow IPC
+/// files are compressed as a whole, so only the global codec is supported.
+fn ipc_compression(
+    options: &types::WriterOptions,
+) -> Result<Option<Option<CompressionType>>, Error> {
+    let unsupported = |option: &str| {
+        Err(Error::invalid_writer_options(format!(
+            "`{option}` is not supported by Arrow IPC formats"
+        )))
+    };
+
+    let global = &options.global;
+    if global.level.is_some() {
+        return unsupported("level");
+    }
+    if global.dictionary.is_some() {
+        return unsupported("dictionary");
+    }
+    if global.statistics.is_some() {
+        return unsupported("statistics");
+    }
+    if !options.columns.is_empty() {
+        return unsupported("columns");
+    }
+    if options.row_group_size.is_some() {
+        return unsupported("row_group_size");
+    }
+    if !options.bloom_filter.is_empty() {
+        return unsupported("bloom_filter");
+    }
+
+    match global.codec {
+        None => Ok(None),
+        Some(types::Codec::Uncompressed) => Ok(Some(None)),
+        Some(types::Codec::Lz4) => Ok(Some(Some(CompressionType::LZ4_FRAME))),
+        Some(types::Codec::Zstd) => Ok(Some(Some(CompressionType::ZSTD))),
+        Some(codec) => Err(Error::invalid_writer_options(format!(
+            "codec `{codec:?}` is not supported by Arrow IPC formats"
+        ))),
+    }
+}
+
+fn check_field_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.split('.').any(str::is_empty) {
+        return Err(Error::invalid_writer_options(format!(
+            "invalid field name `{name}`"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the leaf columns of the field `name`, i.e. the column itself or all the columns
+/// nested in it.
+fn field_columns(leaves: &[ColumnPath], name: &str) -> Result<Vec<ColumnPath>, Error> {
+    let parts: Vec<&str> = name.split('.').collect();
+
+    let columns: Vec<ColumnPath> = leaves
+        .iter()
+        .filter(|leaf| {
+            let path = leaf.parts();
+            path.len() >= parts.len() && path.iter().zip(&parts).all(|(a, b)| a == b)
+        })
+        .cloned()
+        .collect();
+
+    if columns.is_empty() {
+        return Err(Error::invalid_writer_options(format!(
+            "unknown field `{name}`"
+        )));
+    }
+
+    Ok(columns)
+}
+
+/// Returns the compression set by the options, if any
+fn compression(options: &types::ColumnWriterOptions) -> Result<Option<Compression>, Error> {
+    let invalid_level = |err: parquet::errors::ParquetError| {
+        Error::invalid_writer_options(format!("invalid compression level, {err}"))
+    };
+    let unsigned_level = |level: i32| {
+        u32::try_from(level).map_err(|_| {
+            Error::invalid_writer_options(format!("invalid compression level {level}"))
+        })
+    };
+
+    let compression = match (options.codec, options.level) {
+        (None, None) => return Ok(None),
+        (None, Some(_)) => {
+            return Err(Error::invalid_writer_options(
+                "`level` requires a `codec`".to_owned(),
+            ));
+        }
+        (Some(types::Codec::Uncompressed), None) => Compression::UNCOMPRESSED,
+        (Some(types::Codec::Snappy), None) => Compression::SNAPPY,
+        (Some(types::Codec::Lz4), None) => Compression::LZ4_RAW,
+        (Some(codec), Some(_))
+            if matches!(
+                codec,
+                types::Codec::Uncompressed | types::Codec::Snappy | types::Codec::Lz4
+            ) =>
+        {
+            return Err(Error::invalid_writer_options(format!(
+                "codec `{codec:?}` has no compression level"
+            )));
+        }
+        (Some(types::Codec::Gzip), level) => Compression::GZIP(match level {
+            Some(level) => GzipLevel::try_new(unsigned_level(level)?).map_err(invalid_level)?,
+            None => GzipLevel::default(),
+        }),
+        (Some(types::Codec::Brotli), level) => Compression::BROTLI(match level {
+            Some(level) => BrotliLevel::try_new(unsigned_level(level)?).map_err(invalid_level)?,
+            None => BrotliLevel::default(),
+        }),
+        (Some(types::Codec::Zstd), level) => Compression::ZSTD(match level {
+            Some(level) => ZstdLevel::try_new(level).map_err(invalid_level)?,
+            None => ZstdLevel::default(),
+        }),
+        (Some(_), Some(_)) => unreachable!("codecs without levels are handled above"),
+    };
+
+    Ok(Some(compression))
+}
+
+fn statistics_level(level: types::StatisticsLevel) -> EnabledStatistics {
+    match level {
+        types::StatisticsLevel::None => EnabledStatistics::None,
+        types::StatisticsLevel::Chunk => EnabledStatistics::Chunk,
+        types::StatisticsLevel::Page => EnabledStatistics::Page,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ToParquetProperties;
+    use arrow::datatypes::{DataType, Field, Fields};
+    use mosaicod_core::params;
+
+    fn options(codec: types::Codec, level: Option<i32>) -> types::WriterOptions {
+        types::WriterOptions {
+            global: types::ColumnWriterOptions {
+                codec: Some(codec),
+                level,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_levels() {
+        let validate =
+            |options: &types::WriterOptions| super::validate(options, types::Format::Default);
+
+        assert!(validate(&options(types::Codec::Zstd, Some(22))).is_ok());
+        assert!(validate(&options(types::Codec::Zstd, Some(23))).is_err());
+        assert!(validate(&options(types::Codec::Gzip, Some(9))).is_ok());
+        assert!(validate(&options(types::Codec::Gzip, Some(-1))).is_err());
+        assert!(validate(&options(types::Codec::Snappy, Some(1))).is_err());
+        assert!(validate(&options(types::Codec::Lz4, None)).is_ok());
+
+        let level_only = types::WriterOptions {
+            global: types::ColumnWriterOptions {
+                level: Some(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(validate(&level_only).is_err());
+
+        let no_rows = types::WriterOptions {
+            row_group_size: Some(0),
+            ..Default::default()
+        };
+        assert!(validate(&no_rows).is_err());
+    }
+
+    #[test]
+    fn validate_ipc() {
+        let validate =
+            |options: &types::WriterOptions| super::validate(options, types::Format::ArrowIpc);
+
+        assert!(validate(&types::WriterOptions::default()).is_ok());
+        assert!(validate(&options(types::Codec::Lz4, None)).is_ok());
+        assert!(validate(&options(types::Codec::Zstd, None)).is_ok());
+        assert!(validate(&options(types::Codec::Zstd, Some(3))).is_err());
+        assert!(validate(&options(types::Codec::Snappy, None)).is_err());
+
+        let bloom_filter = types::WriterOptions {
+            bloom_filter: vec!["value".to_owned()],
+            ..Default::default()
+        };
+        assert!(validate(&bloom_filter).is_err());
+
+        assert!(
+            apply_ipc(
+                IpcWriteOptions::default(),
+                &options(types::Codec::Lz4, None)
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn apply_options() {
+        let schema = Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("label", DataType::Utf8, false),
+            Field::new(
+                "pose",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("x", DataType::Float64, false),
+                    Field::new("y", DataType::Float64, false),
+                ])),
+                false,
+            ),
+        ]);
+        let ts_path = ColumnPath::from(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP);
+        let label_path = ColumnPath::from("label");
+
+        let props = types::Format::Image
+            .to_parquet_properties()
+            .unwrap()
+            .writer_properties();
+
+        let mut options = options(types::Codec::Zstd, Some(19));
+        options.row_group_size = Some(1000);
+        options.bloom_filter = vec!["label".to_owned()];
+        options.columns.insert(
+            "pose".to_owned(),
+            types::ColumnWriterOptions {
+                codec: Some(types::Codec::Snappy),
+                statistics: Some(types::StatisticsLevel::Page),
+                ..Default::default()
+            },
+        );
+
+        let props = apply(props, &schema, &options).unwrap();
+
+        // Global settings replace the format defaults
+        assert_eq!(
+            props.compression(&label_path),
+            Compression::ZSTD(ZstdLevel::try_new(19).unwrap())
+        );
+        assert_eq!(props.max_row_group_row_count(), Some(1000));
+        assert!(props.bloom_filter_properties(&label_path).is_some());
+
+        // Column settings of the format are kept
+        assert_eq!(props.compression(&ts_path), Compression::UNCOMPRESSED);
+
+        // Column settings apply to nested fields
+        for field in ["x", "y"] {
+            let path = ColumnPath::new(vec!["pose".to_owned(), field.to_owned()]);
+            assert_eq!(props.compression(&path), Compression::SNAPPY);
+            assert_eq!(props.statistics_enabled(&path), EnabledStatistics::Page);
+        }
+
+        // Unknown fields are rejected
+        let unknown = types::WriterOptions {
+            bloom_filter: vec!["missing".to_owned()],
+            ..Default::default()
+        };
+        let props = types::Format::Default
+            .to_parquet_properties()
+            .unwrap()
+            .writer_properties();
+        assert!(matches!(
+            apply(props, &schema, &unknown),
+            Err(Error::InvalidWriterOptions(_))
+        ));
+    }
+}
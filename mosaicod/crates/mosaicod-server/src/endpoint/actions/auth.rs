@@ -2,26 +2,41 @@ use crate::error::*;
 use log::info;
 use mosaicod_core::types;
 use mosaicod_facade as facade;
-use mosaicod_marshal::ActionResponse;
+use mosaicod_marshal::{self as marshal, ActionResponse};
 
 /// Creates a new api key with the given name and metadata.
 pub async fn api_key_create(
     ctx: &facade::Context,
     permissions: String,
+    scopes: Vec<marshal::requests::ApiKeyScope>,
     expires_at: Option<types::Timestamp>,
     description: String,
 ) -> Result<ActionResponse> {
     info!("requested new api key");
 
-    let auth = facade::Auth::create(
+    let scopes = scopes
+        .into_iter()
+        .map(|scope| {
+            Ok(types::auth::ApiKeyScope::new(
+                &scope.prefix,
+                scope.permission.parse()?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let token = types::auth::Token::new();
+
+    facade::Auth::create(
+        &token,
         permissions.parse()?,
+        scopes,
         description,
         expires_at,
         ctx.db.clone(),
     )
     .await?;
 
-    Ok(ActionResponse::api_key_create(auth.api_key().key.into()))
+    Ok(ActionResponse::api_key_create(token.into()))
 }
 
 /// Returns the status for the given api key.
@@ -36,5 +51,6 @@ pub async fn api_key_revoke(ctx: &facade::Context, fingerprint: &str) -> Result<
     info!("requested api key revocation");
     let auth = facade::Auth::try_from_fingerprint(fingerprint, ctx.db.clone()).await?;
     auth.delete().await?;
+    ctx.api_key_verifier.forget(fingerprint);
     Ok(ActionResponse::api_key_revoke())
 }
@@ -1,22 +1,38 @@
 //! Query-related actions.
 
 use crate::error::*;
+use crate::middleware::AuthContext;
 use log::{info, trace};
 use mosaicod_facade as facade;
 use mosaicod_marshal::{self as marshal, ActionResponse};
 
 /// Executes a query and returns matching groups.
-pub async fn execute(ctx: &facade::Context, query: serde_json::Value) -> Result<ActionResponse> {
+///
+/// Only the topics the caller is allowed to read are searched, so that neither the groups nor
+/// the cursor refer to sequences out of the caller scopes.
+pub async fn execute(
+    ctx: &facade::Context,
+    query: serde_json::Value,
+    auth_ctx: &AuthContext,
+) -> Result<ActionResponse> {
     info!("performing a query");
 
     let filter = marshal::query_filter_from_serde_value(query)?;
 
     trace!("query filter: {:?}", filter);
 
-    let groups =
-        facade::Query::query(filter, ctx.timeseries_querier.clone(), ctx.db.clone()).await?;
+    let facade::QueryResult { groups, cursor } = facade::Query::query(
+        filter,
+        auth_ctx.scopes(),
+        ctx.timeseries_querier.clone(),
+        ctx.db.clone(),
+    )
+    .await?;
 
     trace!("groups found: {:?}", groups);
 
-    Ok(ActionResponse::Query(groups.into()))
+    let response = marshal::responses::Query::from(groups)
+        .with_cursor(cursor.map(|cursor| cursor.to_string()));
+
+    Ok(ActionResponse::Query(response))
 }
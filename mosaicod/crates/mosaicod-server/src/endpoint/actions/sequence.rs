@@ -1,5 +1,6 @@
 //! Sequence-related actions
 use crate::error::{Error, Result};
+use futures::TryStreamExt;
 use log::{info, trace, warn};
 use mosaicod_core::types::{self, MetadataBlob};
 use mosaicod_facade as facade;
@@ -31,6 +32,111 @@ pub async fn create(
     Ok(ActionResponse::sequence_create())
 }
 
+/// Imports the MCAP recording or the sequence bundle stored at `path` as a new sequence.
+///
+/// The path must be inside the `exports` or `imports` folder of a sequence, see
+/// [`types::TransferPath`].
+pub async fn import(
+    ctx: &facade::Context,
+    data: marshal::requests::SequenceImport,
+) -> Result<ActionResponse> {
+    info!(
+        "requested import of `{}` as resource {}",
+        data.path, data.locator
+    );
+
+    let locator = data.locator.parse::<types::SequenceLocator>()?;
+    let path = data.path.parse::<types::TransferPath>()?;
+
+    let user_mdata = if data.has_user_metadata() {
+        Some(marshal::JsonMetadataBlob::try_from_str(
+            &data.user_metadata()?,
+        )?)
+    } else {
+        None
+    };
+
+    let report = match data.format {
+        marshal::requests::ImportFormat::Mcap => {
+            let reader = ctx.store.blocking_reader(&path).await?;
+            facade::import::mcap(ctx, locator, user_mdata, reader).await?
+        }
+        marshal::requests::ImportFormat::Bundle => {
+            let reader = ctx.store.blocking_reader(&path).await?;
+            facade::import::bundle(
+                ctx,
+                Some(locator),
+                user_mdata,
+                reader,
+                data.on_conflict.into(),
+            )
+            .await?
+        }
+    };
+
+    trace!(
+        "imported {} topics, skipped {}",
+        report.topics.len(),
+        report.skipped.len()
+    );
+
+    Ok(ActionResponse::sequence_import(
+        marshal::responses::SequenceImport {
+            topics: report.topics.iter().map(ToString::to_string).collect(),
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|(channel, reason)| marshal::responses::SkippedChannel { channel, reason })
+                .collect(),
+        },
+    ))
+}
+
+/// Exports a sequence as a bundle written in the `exports` folder of the sequence, returning
+/// the path of the bundle.
+pub async fn export(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("requested export of resource {}", locator);
+
+    let locator = locator.parse::<types::SequenceLocator>()?;
+    let path = types::TransferPath::new_export(&locator);
+
+    let mut stream = facade::export::bundle(ctx, locator).await?;
+
+    // The bundle is uploaded while it is produced, without keeping it in memory
+    let mut writer = ctx.store.writer(&path);
+
+    let written: Result<usize> = async {
+        let mut size_bytes = 0;
+        while let Some(bytes) = stream.try_next().await? {
+            size_bytes += bytes.len();
+            writer.write(bytes).await?;
+        }
+        Ok(size_bytes)
+    }
+    .await;
+
+    let size_bytes = match written {
+        Ok(size_bytes) => size_bytes,
+        Err(e) => {
+            if let Err(e) = writer.abort().await {
+                warn!("unable to abort the upload of bundle `{}`: {}", path, e);
+            }
+            return Err(e);
+        }
+    };
+
+    writer.finish().await?;
+
+    trace!("written bundle `{}` ({} bytes)", path, size_bytes);
+
+    Ok(ActionResponse::sequence_export(
+        marshal::responses::SequenceExport {
+            path: path.into(),
+            size_bytes,
+        },
+    ))
+}
+
 /// Deletes an unlocked sequence.
 pub async fn delete(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
     warn!("requested deletion of resource {}", name);
@@ -13,6 +13,7 @@ pub async fn create(
     session_uuid: String,
     serialization_format: types::Format,
     ontology_tag: String,
+    writer_options: types::WriterOptions,
     user_metadata_str: &str,
 ) -> Result<ActionResponse> {
     info!("requested resource {} creation", name);
@@ -27,6 +28,7 @@ pub async fn create(
         types::TopicOntologyProperties {
             serialization_format,
             ontology_tag,
+            writer_options,
         },
         Some(user_mdata),
     );
@@ -63,6 +65,61 @@ pub async fn delete(ctx: &facade::Context, locator: String) -> Result<ActionResp
     Ok(ActionResponse::Empty)
 }
 
+/// Returns the token required to resume the upload of an unlocked topic, along with
+/// the amount of data already persisted.
+pub async fn resume(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("requested resume of resource `{}` upload", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let resume_token = facade::topic::resume_token(ctx, &topic_handle).await?;
+
+    let mdata = facade::topic::metadata(ctx, &topic_handle).await?;
+    let format = mdata.ontology_metadata.properties.serialization_format;
+
+    let last_timestamp_ns = if resume_token.chunks() > 0 {
+        let range = facade::topic::timestamp_range(ctx, &topic_handle, format).await?;
+        (!range.is_unbounded()).then(|| range.end.as_i64())
+    } else {
+        None
+    };
+
+    Ok(ActionResponse::TopicResume(
+        marshal::responses::TopicResume {
+            resume_token: resume_token.to_string(),
+            chunks: resume_token.chunks(),
+            last_timestamp_ns,
+        },
+    ))
+}
+
+/// Merges the chunks of a locked topic into larger chunks.
+pub async fn compact(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("requested compaction of resource `{}`", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let mut topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let report = facade::topic::compact(ctx, &mut topic_handle).await?;
+
+    info!(
+        "resource `{}` compacted from {} to {} chunks",
+        locator, report.chunks_before, report.chunks_after
+    );
+
+    Ok(ActionResponse::TopicCompact(
+        marshal::responses::TopicCompact {
+            chunks_before: report.chunks_before,
+            chunks_after: report.chunks_after,
+            bytes_before: report.bytes_before,
+            bytes_after: report.bytes_after,
+        },
+    ))
+}
+
 /// Creates a notification for a topic.
 pub async fn notification_create(
     ctx: &facade::Context,
@@ -6,9 +6,13 @@
 use super::actions::{misc, query as query_action, sequence, session, topic};
 use crate::endpoint::actions::auth;
 use crate::error::Result;
-use mosaicod_core::{self as core, types::auth::Permission};
+use crate::middleware::AuthContext;
+use mosaicod_core::{
+    self as core,
+    types::{self, auth::Permission},
+};
 use mosaicod_facade as facade;
-use mosaicod_marshal::{ActionRequest, ActionResponse};
+use mosaicod_marshal::{ActionRequest, ActionResponse, requests};
 
 /// Dispatches a Flight action request to the appropriate handler.
 ///
@@ -17,9 +21,11 @@ use mosaicod_marshal::{ActionRequest, ActionResponse};
 pub async fn do_action(
     ctx: &facade::Context,
     action: ActionRequest,
-    perm: &Permission,
+    auth_ctx: &AuthContext,
 ) -> Result<ActionResponse> {
-    if !has_permissions(&action, perm) {
+    if !has_scoped_permissions(ctx, &action, auth_ctx).await
+        || !has_source_permissions(&action, auth_ctx)
+    {
         Err(core::Error::unauthorized())?;
     }
 
@@ -31,6 +37,8 @@ pub async fn do_action(
             sequence::create(ctx, data.locator, user_metadata.as_str()).await
         }
         ActionRequest::SequenceDelete(data) => sequence::delete(ctx, data.locator).await,
+        ActionRequest::SequenceImport(data) => sequence::import(ctx, data).await,
+        ActionRequest::SequenceExport(data) => sequence::export(ctx, data.locator).await,
         ActionRequest::SequenceNotificationCreate(data) => {
             sequence::notification_create(ctx, data.locator, data.notification_type, data.msg).await
         }
@@ -57,11 +65,14 @@ pub async fn do_action(
                 data.session_uuid,
                 data.serialization_format.into(),
                 data.ontology_tag,
+                data.writer_options.map(Into::into).unwrap_or_default(),
                 user_metadata.as_str(),
             )
             .await
         }
         ActionRequest::TopicDelete(data) => topic::delete(ctx, data.locator).await,
+        ActionRequest::TopicResume(data) => topic::resume(ctx, data.locator).await,
+        ActionRequest::TopicCompact(data) => topic::compact(ctx, data.locator).await,
         ActionRequest::TopicNotificationCreate(data) => {
             topic::notification_create(ctx, data.locator, data.notification_type, data.msg).await
         }
@@ -74,7 +85,7 @@ pub async fn do_action(
 
         // /////
         // Query
-        ActionRequest::Query(data) => query_action::execute(ctx, data.query).await,
+        ActionRequest::Query(data) => query_action::execute(ctx, data.query, auth_ctx).await,
 
         // ////
         // Api Key
@@ -82,6 +93,7 @@ pub async fn do_action(
             auth::api_key_create(
                 ctx,
                 data.permissions,
+                data.scopes,
                 data.expires_at_ns.map(Into::into),
                 data.description,
             )
@@ -102,12 +114,122 @@ pub async fn do_action(
     }
 }
 
+/// Return true if the permissions granted on the resource targeted by the action
+/// allow to perform it, false otherwise
+async fn has_scoped_permissions(
+    ctx: &facade::Context,
+    action: &ActionRequest,
+    auth_ctx: &AuthContext,
+) -> bool {
+    if !auth_ctx.is_scoped() {
+        return has_permissions(action, auth_ctx.permissions());
+    }
+
+    match action {
+        // Scoped keys can't manage other keys, otherwise they could create an unscoped one
+        ActionRequest::ApiKeyCreate(_)
+        | ActionRequest::ApiKeyStatus(_)
+        | ActionRequest::ApiKeyRevoke(_) => false,
+
+        // Query results are filtered according to the scopes
+        ActionRequest::Query(_) | ActionRequest::Version(_) => {
+            has_permissions(action, auth_ctx.permissions())
+        }
+
+        ActionRequest::SessionFinalize(data) => {
+            session_permissions(ctx, action, auth_ctx, &data.session_uuid).await
+        }
+        ActionRequest::SessionDelete(data) => {
+            session_permissions(ctx, action, auth_ctx, &data.session_uuid).await
+        }
+
+        ActionRequest::SequenceCreate(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::SequenceDelete(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::SequenceImport(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::SequenceExport(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::SequenceNotificationCreate(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+        ActionRequest::SequenceNotificationList(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+        ActionRequest::SequenceNotificationPurge(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+        ActionRequest::SessionCreate(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::TopicCreate(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::TopicDelete(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::TopicResume(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::TopicCompact(data) => locator_permissions(action, auth_ctx, &data.locator),
+        ActionRequest::TopicNotificationCreate(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+        ActionRequest::TopicNotificationList(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+        ActionRequest::TopicNotificationPurge(data) => {
+            locator_permissions(action, auth_ctx, &data.locator)
+        }
+    }
+}
+
+/// Return true if the files read by the action are readable with the permissions granted,
+/// false otherwise.
+///
+/// Imported files are stored in the folder of a sequence, which must be readable. Invalid
+/// paths are rejected by the action handler.
+fn has_source_permissions(action: &ActionRequest, auth_ctx: &AuthContext) -> bool {
+    match action {
+        ActionRequest::SequenceImport(data) => match data.path.parse::<types::TransferPath>() {
+            Ok(path) => auth_ctx.allows(path.sequence(), Permission::can_read),
+            Err(_) => true,
+        },
+        _ => true,
+    }
+}
+
+/// Checks the permissions granted on `locator` against the ones required by `action`
+fn locator_permissions(action: &ActionRequest, auth_ctx: &AuthContext, locator: &str) -> bool {
+    auth_ctx
+        .permissions_on(locator)
+        .is_some_and(|perm| has_permissions(action, &perm))
+}
+
+/// Checks the permissions granted on the sequence owning the session `session_uuid`.
+///
+/// If the session can't be resolved the check is left to the action handler, which
+/// will fail anyway.
+async fn session_permissions(
+    ctx: &facade::Context,
+    action: &ActionRequest,
+    auth_ctx: &AuthContext,
+    session_uuid: &str,
+) -> bool {
+    let Ok(uuid) = session_uuid.parse::<types::Uuid>() else {
+        return has_permissions(action, auth_ctx.permissions());
+    };
+
+    match facade::session::Handle::try_from_uuid(ctx, &uuid).await {
+        Ok(handle) => locator_permissions(action, auth_ctx, handle.sequence_locator()),
+        Err(_) => has_permissions(action, auth_ctx.permissions()),
+    }
+}
+
 /// Return true if the requested action matches the permissions, false otherwise
 fn has_permissions(action: &ActionRequest, perm: &Permission) -> bool {
     match action {
         ActionRequest::SequenceCreate(_) => perm.can_write(),
+        // Replacing an existing sequence deletes its data
+        ActionRequest::SequenceImport(data) => {
+            perm.can_write()
+                && (data.on_conflict != requests::OnConflict::Replace || perm.can_delete())
+        }
         ActionRequest::SequenceNotificationCreate(_) => perm.can_write(),
+        // Bundles are written in the store
+        ActionRequest::SequenceExport(_) => perm.can_write(),
         ActionRequest::TopicCreate(_) => perm.can_write(),
+        ActionRequest::TopicResume(_) => perm.can_write(),
+        ActionRequest::TopicCompact(_) => perm.can_write(),
         ActionRequest::TopicNotificationCreate(_) => perm.can_write(),
         ActionRequest::SessionCreate(_) => perm.can_write(),
         ActionRequest::SessionFinalize(_) => perm.can_write(),
@@ -1,4 +1,11 @@
 use crate::error::Result;
+use crate::middleware::AuthContext;
+use arrow::array::{
+    BinaryArray, Int64Array, Int64Builder, ListBuilder, RecordBatch, StringArray, StructBuilder,
+    UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::error::ArrowError;
 use arrow_flight::{
     Ticket,
     encode::{FlightDataEncoder, FlightDataEncoderBuilder},
@@ -6,14 +13,60 @@ use arrow_flight::{
 };
 use futures::TryStreamExt;
 use log::{debug, info, trace};
-use mosaicod_core as core;
-use mosaicod_core::types;
+use mosaicod_core::types::{self, auth::Permission};
+use mosaicod_core::{self as core, params};
 use mosaicod_facade as facade;
 use mosaicod_marshal as marshal;
+use mosaicod_query as query;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Name of the column containing the exported file content
+const EXPORT_DATA_COLUMN: &str = "data";
+
+/// Names of the columns of the streamed query results
+const QUERY_SEQUENCE_COLUMN: &str = "sequence";
+const QUERY_TOPIC_COLUMN: &str = "topic";
+const QUERY_FIRST_TS_COLUMN: &str = "first_ts";
+const QUERY_LAST_TS_COLUMN: &str = "last_ts";
+const QUERY_MATCH_COUNT_COLUMN: &str = "match_count";
+const QUERY_INTERVALS_COLUMN: &str = "intervals";
 
-pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataEncoder> {
-    let ticket = marshal::flight::ticket_topic_from_binary(&ticket.ticket)?;
+pub async fn do_get(
+    ctx: &facade::Context,
+    ticket: Ticket,
+    auth_ctx: &AuthContext,
+) -> Result<FlightDataEncoder> {
+    match marshal::flight::ticket_from_binary(&ticket.ticket)? {
+        types::flight::Ticket::Topic(ticket) => {
+            auth_ctx.authorize(&ticket.locator, Permission::can_read)?;
+            do_get_topic(ctx, ticket).await
+        }
+        types::flight::Ticket::Aligned(ticket) => {
+            for topic in &ticket.topics {
+                auth_ctx.authorize(topic, Permission::can_read)?;
+            }
+            do_get_aligned(ctx, ticket).await
+        }
+        types::flight::Ticket::Export(ticket) => {
+            for topic in &ticket.topics {
+                auth_ctx.authorize(topic, Permission::can_read)?;
+            }
+            do_get_export(ctx, ticket).await
+        }
+        types::flight::Ticket::Query(ticket) => {
+            if !auth_ctx.permissions().can_read() {
+                Err(core::Error::unauthorized())?;
+            }
+            do_get_query(ctx, ticket, auth_ctx).await
+        }
+    }
+}
 
+async fn do_get_topic(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketTopic,
+) -> Result<FlightDataEncoder> {
     info!("requesting data for ticket `{}`", ticket.locator);
 
     // Create topic handle
@@ -28,34 +81,36 @@ pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataE
 
     let batch_size = facade::topic::compute_optimal_batch_size(ctx, &topic_handle).await?;
 
-    let path_in_store = topic_handle
-        .path_in_store()
-        .ok_or(core::error::Error::internal(Some(format!(
-            "Path in store not set for topic {}",
-            topic_handle.locator()
-        ))))?;
+    if let Some(ts_range) = &ticket.timestamp_range {
+        debug!("requesting timestamp range {}", ts_range);
+    }
 
-    let mut query_result = ctx
-        .timeseries_querier
-        .read(
-            &path_in_store.path_data_folder(topic_handle.uuid()),
-            metadata.ontology_metadata.properties.serialization_format,
-            Some(batch_size),
-        )
-        .await?;
-
-    // Append JSON metadata to original data schema
+    let mut query_result = facade::topic::read(
+        ctx,
+        &topic_handle,
+        metadata.ontology_metadata.properties.serialization_format,
+        Some(batch_size),
+        ticket.timestamp_range,
+    )
+    .await?;
+
+    if let Some(filter) = ticket.ontology_filter {
+        let filter = ontology_filter(&filter, &metadata.ontology_metadata.properties.ontology_tag)?;
+        query_result = query_result.filter(filter)?;
+    }
+
+    if let Some(fields) = ticket.fields {
+        debug!("requesting fields {:?}", fields);
+        query_result = query_result.project(&fields)?;
+    }
+
+    // Append JSON metadata to the (projected) data schema
     let metadata = marshal::JsonTopicMetadata::from(metadata);
     let flatten_mdata = metadata.ontology_metadata.to_flat_hashmap()?;
 
     let schema = query_result.schema_with_metadata(flatten_mdata);
     trace!("{:?}", schema);
 
-    if let Some(ts_range) = ticket.timestamp_range {
-        debug!("requesting timestamp range {}", ts_range);
-        query_result = query_result.filter_by_timestamp_range(ts_range)?;
-    }
-
     // Get data stream from query result
     let stream = query_result.stream().await?;
 
@@ -66,3 +121,300 @@ pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataE
         .with_schema(schema)
         .build(stream))
 }
+
+async fn do_get_aligned(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketAligned,
+) -> Result<FlightDataEncoder> {
+    info!(
+        "requesting aligned data for topics {:?}",
+        ticket
+            .topics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    );
+
+    let query_result = aligned_query(ctx, &ticket).await?;
+
+    let schema = query_result.schema_with_metadata(HashMap::new());
+    trace!("{:?}", schema);
+
+    let stream = query_result
+        .stream()
+        .await?
+        .map_err(|e| FlightError::ExternalError(Box::new(e)));
+
+    Ok(FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(stream))
+}
+
+async fn do_get_export(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketExport,
+) -> Result<FlightDataEncoder> {
+    info!(
+        "requesting {:?} export of sequence `{}`",
+        ticket.format, ticket.sequence
+    );
+
+    let stream = match ticket.format {
+        types::flight::ExportFormat::Mcap => {
+            facade::export::mcap(
+                ctx,
+                ticket.sequence,
+                Some(ticket.topics),
+                ticket.timestamp_range,
+            )
+            .await?
+        }
+    };
+
+    let schema = export_schema(ticket.format);
+
+    // Each piece of the exported file is sent as a single row batch
+    let batch_schema = schema.clone();
+    let stream = stream
+        .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+        .and_then(move |bytes| {
+            let batch = RecordBatch::try_new(
+                batch_schema.clone(),
+                vec![Arc::new(BinaryArray::from_vec(vec![&bytes]))],
+            );
+            futures::future::ready(batch.map_err(FlightError::Arrow))
+        });
+
+    Ok(FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(stream))
+}
+
+async fn do_get_query(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketQuery,
+    auth_ctx: &AuthContext,
+) -> Result<FlightDataEncoder> {
+    info!("streaming query results");
+
+    let filter = marshal::query_filter_from_string(&ticket.query)?;
+
+    trace!("query filter: {:?}", filter);
+
+    // Only the topics the caller is allowed to read are searched
+    let stream = facade::Query::stream(
+        filter,
+        auth_ctx.scopes(),
+        ctx.timeseries_querier.clone(),
+        ctx.db.clone(),
+    )
+    .await?;
+
+    let schema = query_schema();
+
+    let batch_schema = schema.clone();
+    let stream = stream
+        .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+        .try_filter_map(move |matches| {
+            let batch = if matches.is_empty() {
+                Ok(None)
+            } else {
+                query_batch(batch_schema.clone(), matches)
+                    .map(Some)
+                    .map_err(FlightError::Arrow)
+            };
+            futures::future::ready(batch)
+        });
+
+    Ok(FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(stream))
+}
+
+/// Schema of the data streamed by a query, containing a row for each matching topic.
+///
+/// Timestamps and match count are only set when the query has an ontology filter, intervals
+/// are only set when requested by the ontology filter.
+pub(super) fn query_schema() -> SchemaRef {
+    Arc::new(Schema::new_with_metadata(
+        vec![
+            Field::new(QUERY_SEQUENCE_COLUMN, DataType::Utf8, false),
+            Field::new(QUERY_TOPIC_COLUMN, DataType::Utf8, false),
+            Field::new(QUERY_FIRST_TS_COLUMN, DataType::Int64, true),
+            Field::new(QUERY_LAST_TS_COLUMN, DataType::Int64, true),
+            Field::new(QUERY_MATCH_COUNT_COLUMN, DataType::UInt64, true),
+            Field::new(
+                QUERY_INTERVALS_COLUMN,
+                DataType::List(Arc::new(Field::new_list_field(
+                    DataType::Struct(interval_fields()),
+                    true,
+                ))),
+                true,
+            ),
+        ],
+        HashMap::from([("mosaico:context".to_owned(), "query".to_owned())]),
+    ))
+}
+
+fn query_batch(
+    schema: SchemaRef,
+    matches: Vec<facade::TopicMatch>,
+) -> std::result::Result<RecordBatch, ArrowError> {
+    let sequences: StringArray = matches
+        .iter()
+        .map(|m| Some(m.sequence.to_string()))
+        .collect();
+    let topics: StringArray = matches.iter().map(|m| Some(m.topic.to_string())).collect();
+    let first_ts: Int64Array = matches
+        .iter()
+        .map(|m| m.timestamp_range.as_ref().map(|r| r.start.as_i64()))
+        .collect();
+    let last_ts: Int64Array = matches
+        .iter()
+        .map(|m| m.timestamp_range.as_ref().map(|r| r.end.as_i64()))
+        .collect();
+    let match_count: UInt64Array = matches.iter().map(|m| m.match_count).collect();
+
+    let mut intervals = ListBuilder::new(StructBuilder::from_fields(interval_fields(), 0));
+    for m in &matches {
+        let Some(topic_intervals) = &m.intervals else {
+            intervals.append_null();
+            continue;
+        };
+        let values = intervals.values();
+        for interval in topic_intervals {
+            values
+                .field_builder::<Int64Builder>(0)
+                .expect("interval start builder")
+                .append_value(interval.start.as_i64());
+            values
+                .field_builder::<Int64Builder>(1)
+                .expect("interval end builder")
+                .append_value(interval.end.as_i64());
+            values.append(true);
+        }
+        intervals.append(true);
+    }
+
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(sequences),
+            Arc::new(topics),
+            Arc::new(first_ts),
+            Arc::new(last_ts),
+            Arc::new(match_count),
+            Arc::new(intervals.finish()),
+        ],
+    )
+}
+
+/// Fields of a matching interval, timestamps of the first and last row of the interval
+fn interval_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("start", DataType::Int64, false),
+        Field::new("end", DataType::Int64, false),
+    ])
+}
+
+/// Schema of the data streamed when exporting a sequence, containing a binary column
+/// with the content of the exported file.
+pub(super) fn export_schema(format: types::flight::ExportFormat) -> SchemaRef {
+    let format = match format {
+        types::flight::ExportFormat::Mcap => "mcap",
+    };
+
+    Arc::new(Schema::new_with_metadata(
+        vec![Field::new(EXPORT_DATA_COLUMN, DataType::Binary, false)],
+        HashMap::from([
+            ("mosaico:context".to_owned(), "export".to_owned()),
+            ("mosaico:export_format".to_owned(), format.to_owned()),
+        ]),
+    ))
+}
+
+/// Builds the query aligning the ticket topics.
+///
+/// The alignment grid spans the time interval in which all the topics have data,
+/// limited by the ticket timestamp range (if any).
+pub(super) async fn aligned_query(
+    ctx: &facade::Context,
+    ticket: &types::flight::TicketAligned,
+) -> Result<query::TimeseriesResult> {
+    let (mut start, mut end) = match &ticket.timestamp_range {
+        // Ticket timestamp range end is excluded, while grid bounds are both included
+        Some(ts_range) if !ts_range.end.is_unbounded() => {
+            (ts_range.start.as_i64(), ts_range.end.as_i64() - 1)
+        }
+        Some(ts_range) => (ts_range.start.as_i64(), ts_range.end.as_i64()),
+        None => (i64::MIN, i64::MAX),
+    };
+
+    let mut sources = Vec::with_capacity(ticket.topics.len());
+
+    for topic_locator in &ticket.topics {
+        let topic_handle =
+            facade::topic::Handle::try_from_locator(ctx, topic_locator.clone()).await?;
+
+        let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
+        let info = facade::topic::data_info(ctx, &topic_handle).await?;
+
+        start = start.max(info.timestamp_range.start.as_i64());
+        end = end.min(info.timestamp_range.end.as_i64());
+
+        let path_in_store = topic_handle
+            .path_in_store()
+            .ok_or(core::error::Error::internal(Some(format!(
+                "Path in store not set for topic {}",
+                topic_handle.locator()
+            ))))?;
+
+        sources.push(query::AlignSource {
+            name: topic_locator.to_string(),
+            path: path_in_store.path_data_folder(topic_handle.uuid()),
+            format: metadata.ontology_metadata.properties.serialization_format,
+        });
+    }
+
+    debug!(
+        "alignment grid {start} -> {end} every {}ns",
+        ticket.period_ns
+    );
+
+    let grid = query::AlignGrid {
+        range: types::TimestampRange::between(start.into(), end.into()),
+        period_ns: ticket.period_ns,
+    };
+
+    let max_grid_size = params::params().max_aligned_grid_size.value;
+    if grid.size() > max_grid_size as u64 {
+        return Err(core::Error::bad_request(format!(
+            "alignment grid of {} points exceeds the maximum of {max_grid_size} points, \
+             reduce the frequency or the timestamp range",
+            grid.size()
+        )))?;
+    }
+
+    Ok(ctx
+        .timeseries_querier
+        .read_aligned(sources, grid, ticket.fill, None)
+        .await?)
+}
+
+/// Parses a ticket ontology filter, checking that it targets the provided topic ontology tag.
+pub(super) fn ontology_filter(
+    filter: &str,
+    ontology_tag: &str,
+) -> Result<query::OntologyExprGroup<query::Value>> {
+    let filter = marshal::ontology_filter_from_string(filter)?;
+
+    if let Some(tag) = filter.ontology_tag()
+        && tag != ontology_tag
+    {
+        Err(core::Error::bad_request(format!(
+            "ontology filter targets `{tag}` while topic ontology tag is `{ontology_tag}`"
+        )))?;
+    }
+
+    Ok(filter)
+}
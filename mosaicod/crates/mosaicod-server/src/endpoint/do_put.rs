@@ -1,13 +1,13 @@
 use crate::error::{Error, Result};
+use crate::middleware::AuthContext;
 use arrow::datatypes::SchemaRef;
 use arrow_flight::decode::{DecodedFlightData, DecodedPayload, FlightDataDecoder};
 use arrow_flight::flight_descriptor::DescriptorType;
 use futures::TryStreamExt;
-use mosaicod_core as core;
-use mosaicod_core::types;
+use mosaicod_core::types::{self, auth::Permission};
+use mosaicod_core::{self as core, metrics as metric_names};
 use mosaicod_facade as facade;
 use mosaicod_marshal as marshal;
-use mosaicod_rw as rw;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info};
@@ -24,8 +24,13 @@ impl std::ops::Deref for DoPutContext {
     }
 }
 
-pub async fn do_put(ctx: DoPutContext, decoder: &mut FlightDataDecoder) -> Result<()> {
+pub async fn do_put(
+    ctx: DoPutContext,
+    decoder: &mut FlightDataDecoder,
+    auth_ctx: &AuthContext,
+) -> Result<()> {
     let (cmd, schema) = extract_command_and_schema_from_header_message(decoder).await?;
+    auth_ctx.authorize(&cmd.resource_locator, Permission::can_write)?;
     do_put_topic_data(ctx, decoder, schema, cmd).await
 }
 
@@ -91,32 +96,52 @@ async fn do_put_topic_data(
     let topic_handle = facade::topic::Handle::try_from_locator(&ctx, topic_locator).await?;
 
     // perform the match between received uuid string and topic uuid
-    let topic_uuid = topic_handle.uuid().clone();
     let received_uuid: types::Uuid = uuid_str
         .parse()
         .map_err(|_| Error::invalid_uuid(uuid_str))?;
 
-    if received_uuid != topic_uuid {
+    if &received_uuid != topic_handle.uuid() {
         Err(core::Error::unauthorized())?
     }
 
     let mdata = facade::topic::metadata(&ctx, &topic_handle).await?;
 
-    // Setup the callback that will be used to create the database record for the data catalog
-    // and prepare variables that will be moved in the closure
-    let ontology_tag = mdata.ontology_metadata.properties.ontology_tag;
     let serialization_format = mdata.ontology_metadata.properties.serialization_format;
 
-    let mut writer =
-        facade::topic::writer(ctx.clone(), topic_handle, serialization_format, schema)?;
+    // If the topic already contains data the upload is resumed from the next chunk,
+    // provided that the resume token matches
+    let mut writer = facade::topic::writer(
+        ctx.clone(),
+        topic_handle,
+        serialization_format,
+        schema,
+        cmd.resume_token,
+    )
+    .await?;
+
+    if let Some(policy) = cmd.timestamp_policy {
+        writer = writer.with_timestamp_policy(policy);
+    }
 
     // Consume all batches
     debug!("ready to receive batches");
-    while let Some(data) = decoder
-        .try_next()
-        .await
-        .map_err(core::Error::stream_error)?
-    {
+    loop {
+        let data = match decoder.try_next().await {
+            Ok(Some(data)) => data,
+            Ok(None) => break,
+            Err(e) => {
+                // Persist the batches received before the failure, so that a resumed upload
+                // can continue from the last timestamp reported by `topic_resume`
+                let _permit = ctx
+                    .concurrent_writes_semaphore
+                    .acquire()
+                    .await
+                    .map_err(|_| Error::semaphore_closed())?;
+                writer.flush().await?;
+                Err(core::Error::stream_error(e))?
+            }
+        };
+
         match data.payload {
             DecodedPayload::RecordBatch(batch) => {
                 debug!(
@@ -127,37 +152,49 @@ async fn do_put_topic_data(
                     batch_physical_size = batch.get_array_memory_size() / 1_000_000,
                 );
 
+                metrics::counter!(metric_names::INGESTED_BYTES_TOTAL)
+                    .increment(data.inner.data_body.len() as u64);
+                metrics::counter!(metric_names::INGESTED_ROWS_TOTAL)
+                    .increment(batch.num_rows() as u64);
+
                 // Trying to acquire a semaphore to limit the total amount of concurrent writes
                 // run by this instance. This is done in order to bound memory consumption and
                 // to limit CPU-bound operations.
                 //
-                // Since the `.write()` will encode-and-serialize in a single operation it is safe
-                // to acquire the semaphore without causing deadlocks.
+                // Since the `.write()` will encode-and-serialize the accumulated batches in a single
+                // operation it is safe to acquire the semaphore without causing deadlocks.
+                let wait_time = Instant::now();
                 let permit = ctx
                     .concurrent_writes_semaphore
                     .acquire()
                     .await
                     .map_err(|_| Error::semaphore_closed())?;
-                let serialized_chunk = writer.write(batch).await?;
+                metrics::histogram!(metric_names::WRITE_SEMAPHORE_WAIT_SECONDS)
+                    .record(wait_time.elapsed().as_secs_f64());
+                writer.write(batch).await?;
                 drop(permit);
-
-                on_chunk_created(
-                    &ctx,
-                    &topic_uuid,
-                    &ontology_tag,
-                    serialized_chunk.path,
-                    serialized_chunk.ontology_stats,
-                    serialized_chunk.metadata,
-                )
-                .await?;
             }
             DecodedPayload::Schema(_) => Err(core::Error::unsupported_stream_message())?,
             DecodedPayload::None => Err(core::Error::unsupported_stream_message())?,
         }
     }
 
+    // Batches still buffered by the writer are encoded in the last chunk
+    let permit = ctx
+        .concurrent_writes_semaphore
+        .acquire()
+        .await
+        .map_err(|_| Error::semaphore_closed())?;
+
+    if !cmd.finalize {
+        writer.flush().await?;
+        debug!("topic left unlocked, more data can be appended");
+        return Ok(());
+    }
+
     let time = Instant::now();
     writer.finalize().await?;
+    drop(permit);
     debug!(
         target = "topic finalization",
         finalize_ms = time.elapsed().as_millis()
@@ -165,29 +202,3 @@ async fn do_put_topic_data(
 
     Ok(())
 }
-
-async fn on_chunk_created(
-    ctx: &DoPutContext,
-    topic_uuid: &types::Uuid,
-    ontology_tag: &str,
-    target_path: impl AsRef<std::path::Path>,
-    cstats: types::OntologyModelStats,
-    chunk_metadata: rw::ChunkMetadata,
-) -> Result<()> {
-    let mut handle = facade::Chunk::create(
-        topic_uuid,
-        &target_path,
-        chunk_metadata.size_bytes as i64,
-        chunk_metadata.row_count as i64,
-        &ctx.inner,
-    )
-    .await?;
-
-    handle
-        .push_ontology_model_stats(ontology_tag, cstats)
-        .await?;
-
-    handle.finalize().await?;
-
-    Ok(())
-}
@@ -1,4 +1,5 @@
 use crate::error::Result;
+use crate::middleware::AuthContext;
 use arrow::datatypes::{Field, Schema};
 use arrow_flight::{
     FlightDescriptor, FlightEndpoint, FlightInfo, Ticket, flight_descriptor::DescriptorType,
@@ -9,19 +10,34 @@ use mosaicod_core::{
     self as core,
     error::BoxPublicError,
     params,
-    types::{self, TopicOntologyMetadata},
+    types::{self, TopicOntologyMetadata, auth::Permission},
 };
 use mosaicod_facade as facade;
 use mosaicod_facade::Context;
 use mosaicod_marshal as marshal;
 use mosaicod_marshal::{JsonMetadataBlob, flight};
+use mosaicod_query as query;
+use std::collections::HashMap;
 
 /// Message provided when an error occurs when building flight info data
 const UNABLE_TO_BUILD_FLIGHT_INFO: &str = "unable to build flight info data";
 
-pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> Result<FlightInfo> {
+pub async fn get_flight_info(
+    ctx: &facade::Context,
+    desc: FlightDescriptor,
+    auth_ctx: &AuthContext,
+) -> Result<FlightInfo> {
     match desc.r#type() {
         DescriptorType::Cmd => {
+            if let Some(cmd) = marshal::flight::query_cmd(&desc.cmd)? {
+                // Query results are filtered according to the scopes
+                if !auth_ctx.permissions().can_read() {
+                    Err(core::Error::unauthorized())?;
+                }
+
+                return query_flight_info(&desc, cmd);
+            }
+
             let cmd = marshal::flight::get_flight_info_cmd(&desc.cmd)?;
             let resource_name = &cmd.resource_locator;
 
@@ -29,6 +45,8 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
 
             let locator = resource_name.parse::<types::Locator>()?;
 
+            auth_ctx.authorize(&locator, Permission::can_read)?;
+
             match locator.kind {
                 types::ResourceKind::Sequence => {
                     let sequence_locator: types::SequenceLocator = locator.into();
@@ -36,6 +54,33 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                     let sequence_handle =
                         facade::sequence::Handle::try_from_locator(ctx, sequence_locator).await?;
 
+                    if let Some(align) = cmd.align {
+                        for topic in &align.topics {
+                            auth_ctx.authorize(topic, Permission::can_read)?;
+                        }
+
+                        return aligned_flight_info(
+                            ctx,
+                            &desc,
+                            &sequence_handle,
+                            align,
+                            cmd.timestamp_range,
+                        )
+                        .await;
+                    }
+
+                    if let Some(export) = cmd.export {
+                        return export_flight_info(
+                            ctx,
+                            &desc,
+                            &sequence_handle,
+                            export,
+                            cmd.timestamp_range,
+                            auth_ctx,
+                        )
+                        .await;
+                    }
+
                     let metadata = facade::sequence::metadata(ctx, &sequence_handle).await?;
 
                     trace!(
@@ -58,22 +103,42 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                     trace!("{} generating endpoints", sequence_handle.locator());
                     let topics = facade::sequence::topic_list(ctx, &sequence_handle).await?;
 
+                    // Topics with a different ontology tag than the one targeted by the
+                    // ontology filter are excluded from the endpoints
+                    let filter_tag = cmd
+                        .ontology_filter
+                        .as_deref()
+                        .map(marshal::ontology_filter_from_string)
+                        .transpose()?
+                        .and_then(|f| f.ontology_tag().map(str::to_owned));
+
                     // Populate endpoints
                     let endpoints = stream::iter(topics)
+                        // Topics the caller is not allowed to read are excluded from the endpoints
+                        .filter(|topic_handle| {
+                            futures::future::ready(
+                                auth_ctx.allows(topic_handle.locator(), Permission::can_read),
+                            )
+                        })
                         .map(async |topic_handle: facade::topic::Handle| {
+                            let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
+
+                            if let Some(tag) = &filter_tag
+                                && *tag != metadata.ontology_metadata.properties.ontology_tag
+                            {
+                                return Ok(None);
+                            }
+
                             let ticket = types::flight::TicketTopic {
                                 locator: topic_handle.locator().clone(),
                                 timestamp_range: cmd.timestamp_range.clone(),
+                                fields: cmd.fields.clone(),
+                                ontology_filter: cmd.ontology_filter.clone(),
                             };
 
-                            let topic_app_mdata = build_topic_app_metadata(
-                                facade::topic::metadata(ctx, &topic_handle)
-                                    .await?
-                                    .properties,
-                                &topic_handle,
-                                ctx,
-                            )
-                            .await;
+                            let topic_app_mdata =
+                                build_topic_app_metadata(metadata.properties, &topic_handle, ctx)
+                                    .await;
 
                             let e = FlightEndpoint::new()
                                 .with_ticket(Ticket {
@@ -81,10 +146,10 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                                 })
                                 .with_app_metadata(topic_app_mdata);
 
-                            Ok::<FlightEndpoint, BoxPublicError>(e)
+                            Ok::<Option<FlightEndpoint>, BoxPublicError>(Some(e))
                         })
                         .buffer_unordered(params::MAX_BUFFERED_FUTURES)
-                        .try_collect::<Vec<FlightEndpoint>>()
+                        .try_collect::<Vec<Option<FlightEndpoint>>>()
                         .await?;
 
                     // Get sequence metadata and convert it to flight appmetadata.
@@ -98,7 +163,7 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                             core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned()))
                         })?;
 
-                    for endpoint in endpoints {
+                    for endpoint in endpoints.into_iter().flatten() {
                         flight_info = flight_info.with_endpoint(endpoint);
                     }
 
@@ -107,6 +172,18 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                 }
 
                 types::ResourceKind::Topic => {
+                    if cmd.align.is_some() {
+                        return Err(core::Error::bad_request(
+                            "topics alignment requires a sequence locator".to_owned(),
+                        ))?;
+                    }
+
+                    if cmd.export.is_some() {
+                        return Err(core::Error::bad_request(
+                            "export requires a sequence locator".to_owned(),
+                        ))?;
+                    }
+
                     let topic_locator: types::TopicLocator = locator.into();
 
                     let topic_handle =
@@ -114,9 +191,18 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
 
                     let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
 
+                    if let Some(filter) = &cmd.ontology_filter {
+                        super::do_get::ontology_filter(
+                            filter,
+                            &metadata.ontology_metadata.properties.ontology_tag,
+                        )?;
+                    }
+
                     let ticket = types::flight::TicketTopic {
                         locator: topic_handle.locator().clone(),
                         timestamp_range: cmd.timestamp_range,
+                        fields: cmd.fields.clone(),
+                        ontology_filter: cmd.ontology_filter.clone(),
                     };
 
                     // building a single endpoint for topic data
@@ -137,6 +223,7 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
                     let schema = topic_arrow_schema_with_metadata(
                         metadata.ontology_metadata,
                         &topic_handle,
+                        cmd.fields.as_deref(),
                         ctx,
                     )
                     .await?;
@@ -160,6 +247,125 @@ pub async fn get_flight_info(ctx: &facade::Context, desc: FlightDescriptor) -> R
     }
 }
 
+/// Builds the flight info of a time-aligned read of several topics of a sequence,
+/// returning a single endpoint streaming the aligned data.
+async fn aligned_flight_info(
+    ctx: &facade::Context,
+    desc: &FlightDescriptor,
+    sequence_handle: &facade::sequence::Handle,
+    align: types::flight::AlignCmd,
+    timestamp_range: Option<types::TimestampRange>,
+) -> Result<FlightInfo> {
+    let sequence_prefix = format!("{}/", sequence_handle.locator());
+
+    if let Some(topic) = align
+        .topics
+        .iter()
+        .find(|topic| !topic.starts_with(&sequence_prefix))
+    {
+        return Err(core::Error::bad_request(format!(
+            "topic `{topic}` doesn't belong to sequence `{}`",
+            sequence_handle.locator()
+        )))?;
+    }
+
+    let ticket = types::flight::TicketAligned {
+        topics: align.topics,
+        period_ns: align.period_ns,
+        fill: align.fill,
+        timestamp_range,
+    };
+
+    trace!("{} building aligned schema", sequence_handle.locator());
+    let schema = super::do_get::aligned_query(ctx, &ticket)
+        .await?
+        .schema_with_metadata(HashMap::new());
+
+    let endpoint = FlightEndpoint::new().with_ticket(Ticket {
+        ticket: marshal::flight::ticket_aligned_to_binary(ticket)?.into(),
+    });
+
+    FlightInfo::new()
+        .with_descriptor(desc.clone())
+        .with_endpoint(endpoint)
+        .try_with_schema(&schema)
+        .map_err(|_| core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned())).into())
+}
+
+/// Builds the flight info of the export of a sequence as a single file, returning a
+/// single endpoint streaming the file content.
+///
+/// If no topic is requested all the sequence topics readable by the caller are exported.
+async fn export_flight_info(
+    ctx: &facade::Context,
+    desc: &FlightDescriptor,
+    sequence_handle: &facade::sequence::Handle,
+    export: types::flight::ExportCmd,
+    timestamp_range: Option<types::TimestampRange>,
+    auth_ctx: &AuthContext,
+) -> Result<FlightInfo> {
+    let topics = match export.topics {
+        Some(topics) => {
+            let sequence_prefix = format!("{}/", sequence_handle.locator());
+
+            for topic in &topics {
+                if !topic.starts_with(&sequence_prefix) {
+                    return Err(core::Error::bad_request(format!(
+                        "topic `{topic}` doesn't belong to sequence `{}`",
+                        sequence_handle.locator()
+                    )))?;
+                }
+                auth_ctx.authorize(topic, Permission::can_read)?;
+            }
+
+            topics
+        }
+        None => facade::sequence::topic_list(ctx, sequence_handle)
+            .await?
+            .into_iter()
+            .map(|handle| handle.locator().clone())
+            .filter(|topic| auth_ctx.allows(topic, Permission::can_read))
+            .collect(),
+    };
+
+    let ticket = types::flight::TicketExport {
+        sequence: sequence_handle.locator().clone(),
+        format: export.format,
+        topics,
+        timestamp_range,
+    };
+
+    let schema = super::do_get::export_schema(export.format);
+
+    let endpoint = FlightEndpoint::new().with_ticket(Ticket {
+        ticket: marshal::flight::ticket_export_to_binary(ticket)?.into(),
+    });
+
+    FlightInfo::new()
+        .with_descriptor(desc.clone())
+        .with_endpoint(endpoint)
+        .try_with_schema(&schema)
+        .map_err(|_| core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned())).into())
+}
+
+/// Builds the flight info of a query, returning a single endpoint streaming the topics
+/// matching the query.
+fn query_flight_info(desc: &FlightDescriptor, cmd: types::flight::QueryCmd) -> Result<FlightInfo> {
+    trace!("query: {}", cmd.query);
+
+    let ticket = types::flight::TicketQuery { query: cmd.query };
+
+    let endpoint = FlightEndpoint::new().with_ticket(Ticket {
+        ticket: marshal::flight::ticket_query_to_binary(ticket)?.into(),
+    });
+
+    FlightInfo::new()
+        .with_descriptor(desc.clone())
+        .with_endpoint(endpoint)
+        .try_with_schema(&super::do_get::query_schema())
+        .map_err(|_| core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned())).into())
+}
+
 /// Build topic app_metadata.
 async fn build_topic_app_metadata(
     metadata_props: types::TopicMetadataProperties,
@@ -176,9 +382,13 @@ async fn build_topic_app_metadata(
 }
 
 /// Utility function to create an arrow schema with metadata for the given Topic.
+///
+/// If `fields` are provided the schema is projected on them, failing if some field is not
+/// part of the topic schema.
 async fn topic_arrow_schema_with_metadata(
     ontology_metadata: TopicOntologyMetadata<JsonMetadataBlob>,
     topic_handle: &facade::topic::Handle,
+    fields: Option<&[String]>,
     context: &Context,
 ) -> Result<Schema> {
     trace!(
@@ -208,8 +418,13 @@ async fn topic_arrow_schema_with_metadata(
     let json_ontology_metadata = marshal::JsonTopicOntologyMetadata::from(ontology_metadata);
     let flatten_ontology_metadata = json_ontology_metadata.to_flat_hashmap()?;
 
-    Ok(Schema::new_with_metadata(
-        schema.fields().clone(),
-        flatten_ontology_metadata,
-    ))
+    let fields = match fields {
+        // Topics without data have an empty schema, nothing to project
+        Some(fields) if !schema.fields().is_empty() => {
+            query::projected_schema(&schema, fields)?.fields
+        }
+        _ => schema.fields().clone(),
+    };
+
+    Ok(Schema::new_with_metadata(fields, flatten_ontology_metadata))
 }
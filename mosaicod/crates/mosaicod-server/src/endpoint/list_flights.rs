@@ -2,10 +2,11 @@
 //!
 //! Returns a stream of all available sequences when queried at the root level.
 use crate::error::*;
+use crate::middleware::AuthContext;
 use arrow_flight::{Criteria, FlightDescriptor, FlightEndpoint, FlightInfo, Ticket};
 use futures::stream::BoxStream;
 use log::{info, trace};
-use mosaicod_core as core;
+use mosaicod_core::{self as core, types::auth::Permission};
 use mosaicod_facade as facade;
 
 /// Lists all available flights (sequences) in the database.
@@ -13,9 +14,11 @@ use mosaicod_facade as facade;
 /// When clients query with an empty or root path ("" or "/"), this function
 /// returns a streamed list of all sequences. Each sequence is represented
 /// as a minimal `FlightInfo` containing only the sequence identifier.
+/// Sequences the caller is not allowed to read are not listed.
 pub async fn list_flights(
     ctx: &facade::Context,
     criteria: Criteria,
+    auth_ctx: &AuthContext,
 ) -> Result<BoxStream<'static, Result<FlightInfo>>> {
     // Validate criteria - only root-level queries are supported
     let expression = String::from_utf8_lossy(&criteria.expression);
@@ -35,6 +38,7 @@ pub async fn list_flights(
     // Convert each sequence locator to a minimal FlightInfo
     let flight_infos: Vec<Result<FlightInfo>> = sequences
         .into_iter()
+        .filter(|sequence_handle| auth_ctx.allows(sequence_handle.locator(), Permission::can_read))
         .map(|sequence_handle| {
             let sequence_name = sequence_handle.locator().to_string();
 
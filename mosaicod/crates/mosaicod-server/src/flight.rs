@@ -1,6 +1,7 @@
 use super::{
     error::{PublicErrorGrpcExt, Result, ToStatusExt},
     middleware,
+    telemetry::{self, RpcTimer},
 };
 use crate::endpoint;
 use arrow_flight::{
@@ -65,6 +66,9 @@ pub struct Config {
 
     /// Enable gzip encoding in gRPC
     gzip: bool,
+
+    /// If this option is `Some` the server will export Prometheus metrics on this address
+    metrics: Option<std::net::SocketAddr>,
 }
 
 impl Config {
@@ -75,6 +79,7 @@ impl Config {
             tls: None,
             enable_api_key_management: false,
             gzip: false,
+            metrics: None,
         }
     }
 
@@ -92,6 +97,11 @@ impl Config {
     pub fn enable_api_key_management(&mut self) {
         self.enable_api_key_management = true;
     }
+
+    /// Enables the Prometheus metrics exporter, listening on `addr`.
+    pub fn metrics(&mut self, addr: std::net::SocketAddr) {
+        self.metrics = Some(addr);
+    }
 }
 
 /// Start mosaico Apache Arrow Flight service
@@ -103,7 +113,19 @@ pub async fn start(
 ) -> std::result::Result<(), Box<dyn std::error::Error>> {
     let addr = format!("{}:{}", config.host, config.port).parse()?;
 
-    let mut flight_service = MosaicodFlight::try_new(store, db.clone())?;
+    let db_pool_sampler = if let Some(metrics_addr) = config.metrics {
+        telemetry::install(metrics_addr)?;
+        info!("metrics exported on http://{metrics_addr}/metrics");
+        Some(tokio::spawn(telemetry::sample_db_pool(db.clone())))
+    } else {
+        None
+    };
+
+    let mut flight_service = MosaicodFlight::try_new(store, db)?;
+
+    // The middleware shares the context of the service, so revoked keys are dropped from
+    // the cached verifications
+    let mut auth_layer = middleware::AuthLayer::new(flight_service.context());
 
     if config.enable_api_key_management {
         flight_service.enable_api_key_manegement();
@@ -111,14 +133,15 @@ pub async fn start(
 
     let mut svc = FlightServiceServer::new(flight_service);
 
-    let mut auth_layer = middleware::AuthLayer::new(db);
-
     // If API key management is disabled define a custom permission with all permissions
     // and enable permissions passthrough in the auth middleware
     if !config.enable_api_key_management {
         auth_layer = auth_layer.with_permission_passthrough(types::auth::Permission::Manage);
     }
-    let layer = tower::ServiceBuilder::new().layer(auth_layer).into_inner();
+    let layer = tower::ServiceBuilder::new()
+        .layer(middleware::TraceContextLayer)
+        .layer(auth_layer)
+        .into_inner();
 
     let mut builder = Server::builder();
 
@@ -167,13 +190,15 @@ pub async fn start(
         server.serve(addr).await?;
     }
 
+    if let Some(sampler) = db_pool_sampler {
+        sampler.abort();
+    }
+
     Ok(())
 }
 
 struct MosaicodFlight {
-    store: store::StoreRef,
-    db: db::Database,
-    ts_gw: query::TimeseriesEngineRef,
+    ctx: facade::Context,
 
     api_key_management: bool,
 
@@ -192,9 +217,7 @@ impl MosaicodFlight {
         );
 
         Ok(MosaicodFlight {
-            store,
-            db,
-            ts_gw,
+            ctx: facade::Context::new(store, db, ts_gw),
             api_key_management: false,
             concurrent_writes_semaphore: Arc::new(tokio::sync::Semaphore::new(
                 params::params().max_concurrent_writes.value,
@@ -207,7 +230,7 @@ impl MosaicodFlight {
     }
 
     pub fn context(&self) -> facade::Context {
-        facade::Context::new(self.store.clone(), self.db.clone(), self.ts_gw.clone())
+        self.ctx.clone()
     }
 }
 
@@ -232,7 +255,7 @@ impl MosaicodFlight {
 
         let desc = request.into_inner();
 
-        let info = endpoint::get_flight_info(&self.context(), desc).await?;
+        let info = endpoint::get_flight_info(&self.context(), desc, &auth_ctx).await?;
 
         Ok(Response::new(info))
     }
@@ -249,7 +272,7 @@ impl MosaicodFlight {
 
         let criteria = request.into_inner();
 
-        let stream = endpoint::list_flights(&self.context(), criteria).await?;
+        let stream = endpoint::list_flights(&self.context(), criteria, &auth_ctx).await?;
 
         // Convert the returned stream inner result error to tonis::Status
         let stream = stream.map(|item| item.log_to_status());
@@ -265,7 +288,7 @@ impl MosaicodFlight {
 
         let ticket = request.into_inner();
 
-        let data_stream = endpoint::do_get(&self.context(), ticket).await?;
+        let data_stream = endpoint::do_get(&self.context(), ticket, &auth_ctx).await?;
 
         // map data stream error (flight error) to a tonic one
         let out_stream = data_stream
@@ -292,7 +315,7 @@ impl MosaicodFlight {
             concurrent_writes_semaphore: self.concurrent_writes_semaphore.clone(),
         };
 
-        endpoint::do_put(ctx, &mut decoder).await?;
+        endpoint::do_put(ctx, &mut decoder, &auth_ctx).await?;
 
         Ok(Response::new(Box::pin(futures::stream::empty())))
     }
@@ -300,13 +323,15 @@ impl MosaicodFlight {
     async fn impl_do_action(
         &self,
         request: Request<FlightAction>,
+        timer: &mut RpcTimer,
     ) -> Result<Response<DoActionStream>> {
         let auth_ctx = auth_context(&request)?;
 
         let action = request.into_inner();
         let action = marshal::ActionRequest::try_new(action.r#type.as_str(), &action.body)?;
+        timer.set_action(action.name());
 
-        let response = endpoint::do_action(&self.context(), action, auth_ctx.permissions()).await?;
+        let response = endpoint::do_action(&self.context(), action, &auth_ctx).await?;
 
         let bytes = response.bytes()?;
 
@@ -341,16 +366,20 @@ impl FlightService for MosaicodFlight {
         &self,
         request: Request<Criteria>,
     ) -> std::result::Result<Response<Self::ListFlightsStream>, Status> {
-        let resp = self.impl_list_flights(request).await.log_to_status()?;
-        Ok(resp)
+        let timer = RpcTimer::start("list_flights");
+        let resp = self.impl_list_flights(request).await.log_to_status();
+        timer.finish(&resp);
+        resp
     }
 
     async fn get_flight_info(
         &self,
         request: Request<FlightDescriptor>,
     ) -> std::result::Result<Response<FlightInfo>, Status> {
-        let resp = self.impl_get_flight_info(request).await.log_to_status()?;
-        Ok(resp)
+        let timer = RpcTimer::start("get_flight_info");
+        let resp = self.impl_get_flight_info(request).await.log_to_status();
+        timer.finish(&resp);
+        resp
     }
 
     async fn poll_flight_info(
@@ -375,24 +404,33 @@ impl FlightService for MosaicodFlight {
         &self,
         request: Request<Ticket>,
     ) -> std::result::Result<Response<Self::DoGetStream>, Status> {
-        let resp = self.impl_do_get(request).await.log_to_status()?;
-        Ok(resp)
+        let timer = RpcTimer::start("do_get");
+        let resp = self.impl_do_get(request).await.log_to_status();
+        timer.finish(&resp);
+        resp
     }
 
     async fn do_put(
         &self,
         request: Request<Streaming<FlightData>>,
     ) -> std::result::Result<Response<Self::DoPutStream>, Status> {
-        let resp = self.impl_do_put(request).await.log_to_status()?;
-        Ok(resp)
+        let timer = RpcTimer::start("do_put");
+        let resp = self.impl_do_put(request).await.log_to_status();
+        timer.finish(&resp);
+        resp
     }
 
     async fn do_action(
         &self,
         request: Request<FlightAction>,
     ) -> std::result::Result<Response<Self::DoActionStream>, Status> {
-        let resp = self.impl_do_action(request).await.log_to_status()?;
-        Ok(resp)
+        let mut timer = RpcTimer::start("do_action");
+        let resp = self
+            .impl_do_action(request, &mut timer)
+            .await
+            .log_to_status();
+        timer.finish(&resp);
+        resp
     }
 
     async fn list_actions(
@@ -1,6 +1,7 @@
 mod core;
 mod endpoint;
 mod middleware;
+mod telemetry;
 
 pub mod flight;
 pub use core::Server;
@@ -1,11 +1,12 @@
 use crate::error::{PublicErrorGrpcExt, Result};
 use mosaicod_core::{self as core, types};
-use mosaicod_db as db;
 use mosaicod_facade as facade;
 use std::{
+    net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
 };
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
 use tower::{Layer, Service};
 
 // Skeleton from: https://github.com/hyperium/tonic/blob/master/examples/src/tower/server.rs
@@ -14,17 +15,55 @@ use tower::{Layer, Service};
 #[derive(Clone)]
 pub struct AuthContext {
     permissions: types::auth::Permission,
+
+    /// Locator scopes restricting the permissions, if empty permissions apply to every resource
+    scopes: Vec<types::auth::ApiKeyScope>,
 }
 
 impl AuthContext {
+    /// Returns the highest permissions granted, regardless of the resource
     pub fn permissions(&self) -> &types::auth::Permission {
         &self.permissions
     }
+
+    /// Returns `true` if the permissions are restricted to some locator scopes
+    pub fn is_scoped(&self) -> bool {
+        !self.scopes.is_empty()
+    }
+
+    /// Returns the locator scopes restricting the permissions, empty if not scoped
+    pub fn scopes(&self) -> &[types::auth::ApiKeyScope] {
+        &self.scopes
+    }
+
+    /// Returns the permissions granted on the resource identified by `locator`, if any
+    pub fn permissions_on(&self, locator: &str) -> Option<types::auth::Permission> {
+        types::auth::scoped_permission(self.permissions, &self.scopes, locator)
+    }
+
+    /// Returns `true` if the permissions granted on `locator` satisfy `check`
+    pub fn allows(&self, locator: &str, check: impl Fn(&types::auth::Permission) -> bool) -> bool {
+        self.permissions_on(locator)
+            .is_some_and(|perm| check(&perm))
+    }
+
+    /// Returns an [`core::Error::unauthorized`] error if the permissions granted on `locator`
+    /// don't satisfy `check`
+    pub fn authorize(
+        &self,
+        locator: &str,
+        check: impl Fn(&types::auth::Permission) -> bool,
+    ) -> Result<()> {
+        if !self.allows(locator, check) {
+            Err(core::Error::unauthorized())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
 pub struct AuthLayer {
-    db: db::Database,
+    ctx: facade::Context,
 
     /// If permissions passthrough is enabled no auth check is performed
     /// and a fake permission token with all permission is
@@ -33,9 +72,9 @@ pub struct AuthLayer {
 }
 
 impl AuthLayer {
-    pub fn new(db: db::Database) -> Self {
+    pub fn new(ctx: facade::Context) -> Self {
         Self {
-            db,
+            ctx,
             permissions_passthrough: None,
         }
     }
@@ -55,7 +94,7 @@ impl<S> Layer<S> for AuthLayer {
     fn layer(&self, service: S) -> Self::Service {
         AuthMiddleware {
             inner: service,
-            db: self.db.clone(),
+            ctx: self.ctx.clone(),
             permissions_passthrough: self.permissions_passthrough,
         }
     }
@@ -64,7 +103,7 @@ impl<S> Layer<S> for AuthLayer {
 #[derive(Clone)]
 pub struct AuthMiddleware<S> {
     inner: S,
-    db: db::Database,
+    ctx: facade::Context,
     permissions_passthrough: Option<types::auth::Permission>,
 }
 
@@ -93,7 +132,10 @@ where
         if let Some(permissions) = self.permissions_passthrough {
             // Inject permissions to bypass api key management
             Box::pin(async move {
-                req.extensions_mut().insert(AuthContext { permissions });
+                req.extensions_mut().insert(AuthContext {
+                    permissions,
+                    scopes: Vec::new(),
+                });
 
                 let response = inner.call(req).await?;
 
@@ -107,7 +149,8 @@ where
                 .unwrap_or_default()
                 .to_string();
 
-            let db = self.db.clone();
+            let client = remote_addr(&req).map(|addr| addr.ip());
+            let ctx = self.ctx.clone();
 
             Box::pin(async move {
                 let auth_ctx_result: Result<AuthContext> = async {
@@ -117,10 +160,14 @@ where
 
                     let token: types::auth::Token = token.parse()?;
 
-                    let fauth = facade::Auth::try_from_fingerprint(token.fingerprint(), db).await?;
+                    let api_key =
+                        facade::Auth::try_from_token(&token, client, &ctx.api_key_verifier, ctx.db)
+                            .await?
+                            .into_api_key();
 
                     Ok(AuthContext {
-                        permissions: fauth.into_api_key().permission,
+                        permissions: api_key.permission,
+                        scopes: api_key.scopes,
                     })
                 }
                 .await;
@@ -141,3 +188,17 @@ where
         }
     }
 }
+
+/// Returns the address of the client that sent `req`, if known
+fn remote_addr<B>(req: &http::Request<B>) -> Option<SocketAddr> {
+    let extensions = req.extensions();
+
+    extensions
+        .get::<TcpConnectInfo>()
+        .and_then(TcpConnectInfo::remote_addr)
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .and_then(|info| info.get_ref().remote_addr())
+        })
+}
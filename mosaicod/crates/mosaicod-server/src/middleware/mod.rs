@@ -1,2 +1,5 @@
 mod auth;
 pub use auth::*;
+
+mod trace;
+pub use trace::*;
@@ -0,0 +1,139 @@
+//! Prometheus exporter and helpers used to record the daemon metrics.
+//!
+//! See [`mosaicod_core::metrics`] for the list of exported metrics.
+use log::debug;
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder};
+use mosaicod_core::metrics as metric_names;
+use mosaicod_db as db;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Buckets (in seconds) of the latency histograms
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Interval between two samples of the database pool utilisation
+const DB_POOL_SAMPLING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Installs the global Prometheus recorder and starts an HTTP listener on `addr`
+/// exposing the metrics at `/metrics`.
+///
+/// This function needs to be called from a Tokio runtime.
+pub fn install(addr: SocketAddr) -> Result<(), BuildError> {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_owned()), LATENCY_BUCKETS)?
+        .install()?;
+
+    describe();
+
+    debug!("metrics exporter listening on {addr}");
+
+    Ok(())
+}
+
+/// Registers the descriptions of the exported metrics
+fn describe() {
+    metrics::describe_counter!(metric_names::RPC_REQUESTS_TOTAL, "Flight RPCs served");
+    metrics::describe_histogram!(
+        metric_names::RPC_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "Time required to serve a Flight RPC"
+    );
+    metrics::describe_counter!(
+        metric_names::INGESTED_BYTES_TOTAL,
+        metrics::Unit::Bytes,
+        "Bytes of record batches received by do_put"
+    );
+    metrics::describe_counter!(
+        metric_names::INGESTED_ROWS_TOTAL,
+        "Rows of record batches received by do_put"
+    );
+    metrics::describe_histogram!(
+        metric_names::CHUNK_ENCODING_SECONDS,
+        metrics::Unit::Seconds,
+        "Time required to encode a chunk"
+    );
+    metrics::describe_histogram!(
+        metric_names::CHUNK_STORE_SECONDS,
+        metrics::Unit::Seconds,
+        "Time required to write an encoded chunk to the store"
+    );
+    metrics::describe_histogram!(
+        metric_names::QUERY_GROUP_SECONDS,
+        metrics::Unit::Seconds,
+        "Time required to search the chunks matching an ontology expression group"
+    );
+    metrics::describe_histogram!(
+        metric_names::WRITE_SEMAPHORE_WAIT_SECONDS,
+        metrics::Unit::Seconds,
+        "Time spent waiting for a concurrent write permit"
+    );
+    metrics::describe_gauge!(
+        metric_names::DB_POOL_CONNECTIONS,
+        "Connections opened by the database pool"
+    );
+    metrics::describe_gauge!(
+        metric_names::DB_POOL_IDLE_CONNECTIONS,
+        "Idle connections of the database pool"
+    );
+    metrics::describe_gauge!(
+        metric_names::DB_POOL_MAX_CONNECTIONS,
+        "Maximum number of connections of the database pool"
+    );
+}
+
+/// Periodically samples the database pool utilisation, this function never returns.
+pub async fn sample_db_pool(db: db::Database) {
+    let mut interval = tokio::time::interval(DB_POOL_SAMPLING_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let status = db.pool_status();
+        metrics::gauge!(metric_names::DB_POOL_CONNECTIONS).set(status.connections);
+        metrics::gauge!(metric_names::DB_POOL_IDLE_CONNECTIONS).set(status.idle_connections as f64);
+        metrics::gauge!(metric_names::DB_POOL_MAX_CONNECTIONS).set(status.max_connections);
+    }
+}
+
+/// Records the outcome and the duration of a Flight RPC.
+pub struct RpcTimer {
+    rpc: &'static str,
+    action: Option<&'static str>,
+    start: Instant,
+}
+
+impl RpcTimer {
+    pub fn start(rpc: &'static str) -> Self {
+        Self {
+            rpc,
+            action: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Sets the name of the action served by a `do_action` RPC
+    pub fn set_action(&mut self, action: &'static str) {
+        self.action = Some(action);
+    }
+
+    /// Records the metrics of the RPC, using the gRPC code of `result`
+    pub fn finish<T>(self, result: &std::result::Result<T, tonic::Status>) {
+        let code = match result {
+            Ok(_) => tonic::Code::Ok,
+            Err(status) => status.code(),
+        };
+
+        let mut labels = vec![("rpc", self.rpc.to_owned())];
+        if let Some(action) = self.action {
+            labels.push(("action", action.to_owned()));
+        }
+
+        metrics::histogram!(metric_names::RPC_DURATION_SECONDS, &labels)
+            .record(self.start.elapsed().as_secs_f64());
+
+        labels.push(("code", format!("{code:?}")));
+        metrics::counter!(metric_names::RPC_REQUESTS_TOTAL, &labels).increment(1);
+    }
+}
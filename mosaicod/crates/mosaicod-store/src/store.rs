@@ -1,17 +1,22 @@
 //! This module provides the [`Store`], the application's core client for interacting
-//! with S3-compatible object storage services providing
+//! with S3-compatible, Google Cloud Storage and Azure Blob Storage services providing
 //! essential CRUD (Create, Read, Update, Delete) methods for byte-level data access.
 
 use datafusion::execution::object_store::{DefaultObjectStoreRegistry, ObjectStoreRegistry};
-use futures::stream::TryStreamExt;
+use futures::stream::{BoxStream, TryStreamExt};
 use log::trace;
 use mosaicod_core::traits;
 use object_store::{
-    ObjectStore, ObjectStoreExt, PutPayload, aws::AmazonS3Builder, local::LocalFileSystem,
+    ClientOptions, ObjectStore, ObjectStoreExt, PutPayload, aws::AmazonS3Builder,
+    azure::MicrosoftAzureBuilder, buffered::BufWriter, gcp::GoogleCloudStorageBuilder,
+    local::LocalFileSystem,
 };
 use parquet::arrow::async_reader::ParquetObjectReader;
+use std::future::poll_fn;
+use std::pin::Pin;
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::io::AsyncWrite;
 use url::Url;
 
 #[derive(Error, Debug)]
@@ -67,16 +72,72 @@ impl S3Config {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct GcsConfig {
+    /// Bucket name.
+    pub bucket: String,
+    /// Base url of the service, if empty the Google Cloud Storage endpoint is used.
+    /// Can be used to connect to an emulator (e.g. `fake-gcs-server`).
+    pub endpoint: String,
+    /// Path of the JSON key of the service account. If empty the application default
+    /// credentials are used, or no credentials at all when a custom `endpoint` is set.
+    pub service_account_path: String,
+}
+
+impl GcsConfig {
+    /// Returns an error is the configuration contains empty required fields.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.bucket.is_empty() {
+            return Err(Error::BadConfiguration("bucket".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AzureConfig {
+    /// Container name.
+    pub container: String,
+    /// Storage account name.
+    pub account: String,
+    /// Access key of the storage account.
+    pub access_key: String,
+    /// Url of the storage account, if empty `https://{account}.blob.core.windows.net`
+    /// is used. Can be used to connect to an emulator (e.g. Azurite).
+    pub endpoint: String,
+}
+
+impl AzureConfig {
+    /// Returns an error is the configuration contains empty required fields.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.container.is_empty() {
+            return Err(Error::BadConfiguration("container".to_owned()));
+        }
+        if self.account.is_empty() {
+            return Err(Error::BadConfiguration("account".to_owned()));
+        }
+        if self.access_key.is_empty() {
+            return Err(Error::BadConfiguration("access key".to_owned()));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum StoreTarget {
     Filesystem(String),
     S3Compatible(String),
+    /// Google Cloud Storage bucket
+    Gcs(String),
+    /// Azure Blob Storage container
+    Azure(String),
 }
 
 /// Implements the object storage client for the application.
 ///
 /// It provides methods to read, write, list, and delete byte-level data
-/// from S3-compatible object storage services or local filesystem.
+/// from S3-compatible, Google Cloud Storage and Azure Blob Storage services
+/// or local filesystem.
 #[derive(Debug, Clone)]
 pub struct Store {
     pub url_schema: Url,
@@ -142,6 +203,73 @@ impl Store {
         })
     }
 
+    pub fn try_from_gcs_store(config: GcsConfig) -> Result<Self, Error> {
+        trace!(
+            "creating object driver for a google cloud storage bucket: {}",
+            config.bucket
+        );
+
+        let bucket_url = Url::parse(&format!("gs://{}", config.bucket))?;
+
+        let mut builder = GoogleCloudStorageBuilder::new().with_bucket_name(&config.bucket);
+
+        if !config.service_account_path.is_empty() {
+            builder = builder.with_service_account_path(&config.service_account_path);
+        }
+
+        if !config.endpoint.is_empty() {
+            builder = builder
+                .with_base_url(&config.endpoint)
+                .with_client_options(ClientOptions::new().with_allow_http(true))
+                // Emulators do not check credentials
+                .with_skip_signature(config.service_account_path.is_empty());
+        }
+
+        let storage = Arc::new(builder.build()?);
+
+        // Create object store registry (for datafusion support)
+        let registry = Arc::new(DefaultObjectStoreRegistry::default());
+        registry.register_store(&bucket_url, storage.clone());
+
+        Ok(Self {
+            url_schema: bucket_url,
+            target: StoreTarget::Gcs(config.bucket),
+            driver: storage.clone(),
+            registry,
+        })
+    }
+
+    pub fn try_from_azure_store(config: AzureConfig) -> Result<Self, Error> {
+        trace!(
+            "creating object driver for an azure blob storage container: {}",
+            config.container
+        );
+
+        let container_url = Url::parse(&format!("az://{}", config.container))?;
+
+        let mut builder = MicrosoftAzureBuilder::new()
+            .with_account(&config.account)
+            .with_container_name(&config.container)
+            .with_access_key(config.access_key);
+
+        if !config.endpoint.is_empty() {
+            builder = builder.with_endpoint(config.endpoint).with_allow_http(true);
+        }
+
+        let storage = Arc::new(builder.build()?);
+
+        // Create object store registry (for datafusion support)
+        let registry = Arc::new(DefaultObjectStoreRegistry::default());
+        registry.register_store(&container_url, storage.clone());
+
+        Ok(Self {
+            url_schema: container_url,
+            target: StoreTarget::Azure(config.container),
+            driver: storage.clone(),
+            registry,
+        })
+    }
+
     pub fn registry(&self) -> Arc<dyn ObjectStoreRegistry> {
         self.registry.clone()
     }
@@ -150,6 +278,7 @@ impl Store {
         &self.target
     }
 
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn read_bytes(&self, path: impl AsRef<std::path::Path>) -> Result<Vec<u8>, Error> {
         Ok(self
             .driver
@@ -160,6 +289,25 @@ impl Store {
             .into())
     }
 
+    /// Returns a reader streaming the content of the object at `path`.
+    ///
+    /// The reader blocks the calling thread while waiting for data, so it must be used
+    /// outside the async runtime (e.g. in [`tokio::task::spawn_blocking`]).
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn blocking_reader(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<BlockingReader, Error> {
+        let stream = self.driver.get(&to_object_path(&path)).await?.into_stream();
+
+        Ok(BlockingReader {
+            stream,
+            buffer: bytes::Bytes::new(),
+            runtime: tokio::runtime::Handle::current(),
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn write_bytes(
         &self,
         path: impl AsRef<std::path::Path>,
@@ -172,10 +320,21 @@ impl Store {
         Ok(())
     }
 
+    /// Returns a writer uploading an object to `path` as the data is written, large
+    /// objects are uploaded in multiple parts.
+    ///
+    /// The object is created only when [`StoreWriter::finish`] is called.
+    pub fn writer(&self, path: impl AsRef<std::path::Path>) -> StoreWriter {
+        StoreWriter {
+            inner: BufWriter::new(self.driver.clone(), to_object_path(&path)),
+        }
+    }
+
     /// Returns a list of elements located at the given `path`.
     ///
     /// If an extension is provided, the results will be filtered to include only
     /// the elements whose extension matches exactly.es exactly
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn list(
         &self,
         path: impl AsRef<std::path::Path>,
@@ -206,6 +365,7 @@ impl Store {
         Ok(locations)
     }
 
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn exists(&self, path: impl AsRef<std::path::Path>) -> Result<bool, Error> {
         match self.driver.head(&to_object_path(&path)).await {
             Ok(_) => Ok(true),
@@ -214,17 +374,20 @@ impl Store {
         }
     }
 
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn size(&self, path: impl AsRef<std::path::Path>) -> Result<usize, Error> {
         let head = self.driver.head(&to_object_path(&path)).await?;
 
         Ok(head.size as usize)
     }
 
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn delete(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
         Ok(self.driver.delete(&to_object_path(&path)).await?)
     }
 
     /// Deletes recursively all objects under a given path
+    #[tracing::instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn delete_recursive(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
         let mut list_stream = self.driver.list(Some(&to_object_path(&path)));
 
@@ -240,6 +403,56 @@ impl Store {
     }
 }
 
+/// Blocking reader over the content of an object, created by [`Store::blocking_reader`].
+pub struct BlockingReader {
+    stream: BoxStream<'static, object_store::Result<bytes::Bytes>>,
+    /// Data received from the store and not read yet
+    buffer: bytes::Bytes,
+    runtime: tokio::runtime::Handle,
+}
+
+impl std::io::Read for BlockingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.buffer.is_empty() {
+            match self
+                .runtime
+                .block_on(self.stream.try_next())
+                .map_err(std::io::Error::other)?
+            {
+                Some(bytes) => self.buffer = bytes,
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.buffer.len());
+        buf[..len].copy_from_slice(&self.buffer.split_to(len));
+
+        Ok(len)
+    }
+}
+
+/// Writer uploading an object to the store, created by [`Store::writer`].
+pub struct StoreWriter {
+    inner: BufWriter,
+}
+
+impl StoreWriter {
+    /// Appends `bytes` to the object, uploading a part if enough data has been buffered.
+    pub async fn write(&mut self, bytes: impl Into<bytes::Bytes>) -> Result<(), Error> {
+        Ok(self.inner.put(bytes.into()).await?)
+    }
+
+    /// Uploads the remaining data and creates the object.
+    pub async fn finish(mut self) -> Result<(), Error> {
+        Ok(poll_fn(|cx| Pin::new(&mut self.inner).poll_shutdown(cx)).await?)
+    }
+
+    /// Discards the data written, cleaning up the parts already uploaded.
+    pub async fn abort(mut self) -> Result<(), Error> {
+        Ok(self.inner.abort().await?)
+    }
+}
+
 impl traits::AsyncWriteToPath for Store {
     #[expect(
         clippy::manual_async_fn,
@@ -350,4 +563,104 @@ mod test {
 
         assert_eq!(buffer, read_buffer);
     }
+
+    /// Checks that the blocking reader returns the whole content of an object
+    #[tokio::test]
+    async fn test_blocking_reader() {
+        let store = testing::Store::new_random_on_tmp().unwrap();
+
+        let buffer: Vec<u8> = (0..100_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        store.write_bytes("blob", buffer.clone()).await.unwrap();
+
+        let mut reader = store.blocking_reader("blob").await.unwrap();
+        let read_buffer = tokio::task::spawn_blocking(move || {
+            let mut read_buffer = Vec::new();
+            std::io::Read::read_to_end(&mut reader, &mut read_buffer).unwrap();
+            read_buffer
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(buffer, read_buffer);
+    }
+
+    /// Checks that the data written by a writer is uploaded only when finished
+    #[tokio::test]
+    async fn test_writer() {
+        let store = testing::Store::new_random_on_tmp().unwrap();
+
+        let mut writer = store.writer("blob");
+        for i in 0..100u8 {
+            writer.write(vec![i; 100_000]).await.unwrap();
+        }
+        assert!(!store.exists("blob").await.unwrap());
+        writer.finish().await.unwrap();
+
+        let read_buffer = store.read_bytes("blob").await.unwrap();
+        assert_eq!(read_buffer.len(), 100 * 100_000);
+        assert!(
+            read_buffer
+                .chunks(100_000)
+                .enumerate()
+                .all(|(i, chunk)| chunk.iter().all(|b| *b as usize == i))
+        );
+
+        let mut writer = store.writer("aborted");
+        writer.write(vec![0; 100_000]).await.unwrap();
+        writer.abort().await.unwrap();
+        assert!(!store.exists("aborted").await.unwrap());
+    }
+
+    /// Checks that remote stores are registered in the object store registry with their url
+    /// schema, so that datafusion can resolve the data files.
+    ///
+    /// No request is sent to the services, the endpoints of the local emulators are used.
+    #[test]
+    fn test_remote_stores_registry() {
+        let gcs = Store::try_from_gcs_store(GcsConfig {
+            bucket: "mosaico".to_owned(),
+            endpoint: "http://localhost:4443".to_owned(),
+            service_account_path: "".to_owned(),
+        })
+        .unwrap();
+        assert_eq!(gcs.url_schema.as_str(), "gs://mosaico");
+        assert!(matches!(gcs.target(), StoreTarget::Gcs(bucket) if bucket == "mosaico"));
+        let url = gcs.url_schema.join("seq/topic/data/0.parquet").unwrap();
+        assert!(gcs.registry().get_store(&url).is_ok());
+
+        let azure = Store::try_from_azure_store(AzureConfig {
+            container: "mosaico".to_owned(),
+            account: "devstoreaccount1".to_owned(),
+            access_key: "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==".to_owned(),
+            endpoint: "http://127.0.0.1:10000/devstoreaccount1".to_owned(),
+        })
+        .unwrap();
+        assert_eq!(azure.url_schema.as_str(), "az://mosaico");
+        assert!(matches!(azure.target(), StoreTarget::Azure(container) if container == "mosaico"));
+        let url = azure.url_schema.join("seq/topic/data/0.parquet").unwrap();
+        assert!(azure.registry().get_store(&url).is_ok());
+
+        // Stores are not shared between different url schemas
+        assert!(gcs.registry().get_store(&url).is_err());
+    }
+
+    #[test]
+    fn test_remote_stores_config() {
+        let gcs = GcsConfig {
+            bucket: "".to_owned(),
+            endpoint: "".to_owned(),
+            service_account_path: "".to_owned(),
+        };
+        assert!(matches!(gcs.validate(), Err(Error::BadConfiguration(field)) if field == "bucket"));
+
+        let azure = AzureConfig {
+            container: "mosaico".to_owned(),
+            account: "".to_owned(),
+            access_key: "key".to_owned(),
+            endpoint: "".to_owned(),
+        };
+        assert!(
+            matches!(azure.validate(), Err(Error::BadConfiguration(field)) if field == "account")
+        );
+    }
 }
@@ -1,11 +1,13 @@
 use super::common::{ActionResponse, Client};
+use arrow_flight::decode::FlightRecordBatchStream;
 use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
 use arrow_flight::{Action, FlightDescriptor, FlightInfo, PutResult};
 use mosaicod_core::types;
 use tonic::Streaming;
 
 use arrow::array::RecordBatch;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 
 /// Create a new sequence.
 /// Returns the `key` of the newly created sequence, this key is required to perform action
@@ -74,6 +76,93 @@ pub async fn sequence_delete(client: &mut Client, locator: &str) {
     }
 }
 
+/// Imports the MCAP recording stored at `path` as a new sequence, returns the locators of
+/// the imported topics.
+pub async fn sequence_import(
+    client: &mut Client,
+    locator: &str,
+    path: &str,
+) -> Result<Vec<String>, tonic::Status> {
+    let action = Action {
+        r#type: "sequence_import".to_owned(),
+        body: serde_json::json!({ "locator": locator, "path": path, "user_metadata": {} })
+            .to_string()
+            .into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut topics = Vec::new();
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "sequence_import");
+
+        topics = r.response["topics"]
+            .as_array()
+            .expect("missing imported topics")
+            .iter()
+            .map(|topic| topic.as_str().unwrap().to_owned())
+            .collect();
+    }
+
+    Ok(topics)
+}
+
+/// Exports a sequence as a bundle written in the store, returns the path of the bundle.
+pub async fn sequence_export(client: &mut Client, locator: &str) -> Result<String, tonic::Status> {
+    let action = Action {
+        r#type: "sequence_export".to_owned(),
+        body: serde_json::json!({ "locator": locator }).to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut path = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "sequence_export");
+
+        path = r.response["path"].as_str().map(ToOwned::to_owned);
+    }
+
+    Ok(path.expect("missing bundle path"))
+}
+
+/// Imports the bundle stored at `path` as a new sequence, returns the locators of the
+/// imported topics.
+pub async fn sequence_import_bundle(
+    client: &mut Client,
+    locator: &str,
+    path: &str,
+) -> Result<Vec<String>, tonic::Status> {
+    let action = Action {
+        r#type: "sequence_import".to_owned(),
+        body: serde_json::json!({ "locator": locator, "path": path, "format": "bundle" })
+            .to_string()
+            .into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut topics = Vec::new();
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "sequence_import");
+
+        topics = r.response["topics"]
+            .as_array()
+            .expect("missing imported topics")
+            .iter()
+            .map(|topic| topic.as_str().unwrap().to_owned())
+            .collect();
+    }
+
+    Ok(topics)
+}
+
 pub async fn session_create(client: &mut Client, sequence_name: &str) -> types::Uuid {
     let action = Action {
         r#type: "session_create".to_owned(),
@@ -177,6 +266,39 @@ pub async fn topic_create(
     key: &types::Uuid,
     topic_name: &str,
     json_metadata: Option<&str>,
+) -> Result<types::Uuid, tonic::Status> {
+    topic_create_with_format(client, key, topic_name, "default", json_metadata).await
+}
+
+/// Create a new topic using the given serialization format.
+/// Returns the `key` of the newly created topic, this key is required to upload topic data.
+pub async fn topic_create_with_format(
+    client: &mut Client,
+    key: &types::Uuid,
+    topic_name: &str,
+    serialization_format: &str,
+    json_metadata: Option<&str>,
+) -> Result<types::Uuid, tonic::Status> {
+    topic_create_with_writer_options(
+        client,
+        key,
+        topic_name,
+        serialization_format,
+        json_metadata,
+        None,
+    )
+    .await
+}
+
+/// Create a new topic using the given serialization format and writer options.
+/// Returns the `key` of the newly created topic, this key is required to upload topic data.
+pub async fn topic_create_with_writer_options(
+    client: &mut Client,
+    key: &types::Uuid,
+    topic_name: &str,
+    serialization_format: &str,
+    json_metadata: Option<&str>,
+    json_writer_options: Option<&str>,
 ) -> Result<types::Uuid, tonic::Status> {
     let action = Action {
         r#type: "topic_create".to_owned(),
@@ -185,14 +307,17 @@ pub async fn topic_create(
         {{
             "locator": "{name}",
             "session_uuid": "{key}",
-            "serialization_format": "default",
+            "serialization_format": "{format}",
             "ontology_tag": "mock",
-            "user_metadata": {mdata}
+            "user_metadata": {mdata},
+            "writer_options": {options}
         }}
         "#,
             name = topic_name,
             key = key,
+            format = serialization_format,
             mdata = json_metadata.unwrap_or("{}"),
+            options = json_writer_options.unwrap_or("null"),
         )
         .into(),
     };
@@ -250,6 +375,67 @@ pub async fn do_put(
     client.do_put(flight_data_stream).await
 }
 
+/// Uploads data to a topic, optionally resuming a previous upload with `resume_token`.
+/// If `finalize` is `false` the topic is left unlocked at the end of the stream.
+pub async fn do_put_resumable(
+    client: &mut Client,
+    topic_uuid: &types::Uuid,
+    topic_name: &str,
+    batches: Vec<RecordBatch>,
+    resume_token: Option<&str>,
+    finalize: bool,
+) -> Result<tonic::Response<Streaming<PutResult>>, tonic::Status> {
+    let input_stream = futures::stream::iter(batches.into_iter().map(Ok));
+
+    let cmd = serde_json::json!({
+        "resource_locator": topic_name,
+        "topic_uuid": topic_uuid.to_string(),
+        "resume_token": resume_token,
+        "finalize": finalize,
+    });
+
+    let flight_data_stream = FlightDataEncoderBuilder::new()
+        .with_flight_descriptor(Some(FlightDescriptor::new_cmd(cmd.to_string())))
+        .build(input_stream)
+        .map(|v| v.unwrap());
+
+    client.do_put(flight_data_stream).await
+}
+
+/// Returns the resume token and the number of chunks already uploaded for a topic.
+pub async fn topic_resume(
+    client: &mut Client,
+    topic_name: &str,
+) -> Result<(String, u64), tonic::Status> {
+    let action = Action {
+        r#type: "topic_resume".to_owned(),
+        body: format!(r#"{{ "locator": "{}" }}"#, topic_name).into(),
+    };
+
+    dbg!(&action);
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut resume = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_resume");
+
+        resume = Some((
+            r.response["resume_token"]
+                .as_str()
+                .expect("Error casting resume token to string")
+                .to_owned(),
+            r.response["chunks"]
+                .as_u64()
+                .expect("Error casting chunks into an u64"),
+        ));
+    }
+
+    Ok(resume.expect("unable to read resume token"))
+}
+
 pub async fn server_version(client: &mut Client) {
     let action = Action {
         r#type: "version".to_owned(),
@@ -308,6 +494,57 @@ pub async fn get_flight_info(
     Ok(info)
 }
 
+/// Downloads the data of a topic.
+pub async fn do_get(
+    client: &mut Client,
+    topic_name: &str,
+) -> Result<Vec<RecordBatch>, tonic::Status> {
+    let info = get_flight_info(client, topic_name).await?;
+
+    let ticket = info
+        .endpoint
+        .first()
+        .and_then(|e| e.ticket.clone())
+        .expect("Missing topic ticket");
+
+    let stream = client.do_get(ticket).await?.into_inner();
+
+    FlightRecordBatchStream::new_from_flight_data(
+        stream.map_err(|e| FlightError::Tonic(Box::new(e))),
+    )
+    .try_collect()
+    .await
+    .map_err(|e| tonic::Status::internal(e.to_string()))
+}
+
+/// Runs a streamed query, returning the batches containing the matching topics.
+pub async fn query_stream(
+    client: &mut Client,
+    query: &str,
+) -> Result<Vec<RecordBatch>, tonic::Status> {
+    let cmd = format!(r#"{{ "query": {query} }}"#);
+
+    let info = client
+        .get_flight_info(FlightDescriptor::new_cmd(cmd))
+        .await?
+        .into_inner();
+
+    let ticket = info
+        .endpoint
+        .first()
+        .and_then(|e| e.ticket.clone())
+        .expect("Missing query ticket");
+
+    let stream = client.do_get(ticket).await?.into_inner();
+
+    FlightRecordBatchStream::new_from_flight_data(
+        stream.map_err(|e| FlightError::Tonic(Box::new(e))),
+    )
+    .try_collect()
+    .await
+    .map_err(|e| tonic::Status::internal(e.to_string()))
+}
+
 pub async fn api_key_create(
     client: &mut Client,
     permissions: types::auth::Permission,
@@ -352,6 +589,92 @@ pub async fn api_key_create(
     Ok(api_key_token.expect("unable to read api key token"))
 }
 
+pub async fn api_key_create_scoped(
+    client: &mut Client,
+    permissions: types::auth::Permission,
+    scopes: &[types::auth::ApiKeyScope],
+) -> Result<types::auth::Token, tonic::Status> {
+    let scopes = scopes
+        .iter()
+        .map(|scope| {
+            serde_json::json!({
+                "prefix": scope.prefix,
+                "permission": String::from(scope.permission),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let action = Action {
+        r#type: "api_key_create".to_owned(),
+        body: serde_json::json!({
+            "permissions": String::from(permissions),
+            "description": "scoped api key",
+            "expires_at_ns": null,
+            "scopes": scopes,
+        })
+        .to_string()
+        .into(),
+    };
+
+    dbg!(&action);
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut api_key_token: Option<types::auth::Token> = None;
+
+    while let Some(result) = stream.message().await.expect("Problem while streaming") {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "api_key_create");
+
+        api_key_token = Some(
+            r.response["api_key_token"]
+                .as_str()
+                .expect("Error casting api key token to string")
+                .parse()
+                .unwrap(),
+        );
+    }
+
+    Ok(api_key_token.expect("unable to read api key token"))
+}
+
+/// Returns the scopes of an api key, formatted as `<prefix>:<permission>`
+pub async fn api_key_scopes(
+    client: &mut Client,
+    fingerprint: &str,
+) -> Result<Vec<String>, tonic::Status> {
+    let action = Action {
+        r#type: "api_key_status".to_owned(),
+        body: serde_json::json!({ "api_key_fingerprint": fingerprint })
+            .to_string()
+            .into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut scopes = Vec::new();
+
+    while let Some(result) = stream.message().await.expect("Problem while streaming") {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "api_key_status");
+
+        scopes = r.response["scopes"]
+            .as_array()
+            .expect("Error casting api key scopes to array")
+            .iter()
+            .map(|scope| {
+                format!(
+                    "{}:{}",
+                    scope["prefix"].as_str().unwrap(),
+                    scope["permission"].as_str().unwrap()
+                )
+            })
+            .collect();
+    }
+
+    Ok(scopes)
+}
+
 pub async fn api_key_status(
     client: &mut Client,
     fingerprint: &str,
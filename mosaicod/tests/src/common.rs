@@ -1,7 +1,7 @@
 use std::fs;
 
 use arrow_flight::flight_service_client::FlightServiceClient;
-use mosaicod_core::params;
+use mosaicod_core::{params, types};
 use mosaicod_db as db;
 use mosaicod_server::{self as server, flight::ShutdownNotifier};
 use mosaicod_store as store;
@@ -41,6 +41,7 @@ async fn start_server(
     pool: sqlx::Pool<db::DatabaseType>,
     shutdown: ShutdownNotifier,
     tls: Option<server::flight::TlsConfig>,
+    api_key_management: bool,
 ) -> tokio::task::JoinHandle<()> {
     // Ensure that params are loaded
     params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
@@ -53,6 +54,10 @@ async fn start_server(
         config.tls(tls);
     }
 
+    if api_key_management {
+        config.enable_api_key_management();
+    }
+
     let handle = tokio::task::spawn(async move {
         if let Err(err) = server::flight::start(
             config,
@@ -78,6 +83,7 @@ pub struct ServerBuilder {
     port: u16,
     pool: sqlx::Pool<db::DatabaseType>,
     tls: Option<server::flight::TlsConfig>,
+    api_key_management: bool,
 }
 
 impl ServerBuilder {
@@ -87,6 +93,7 @@ impl ServerBuilder {
             port,
             pool,
             tls: None,
+            api_key_management: false,
         }
     }
 
@@ -98,6 +105,13 @@ impl ServerBuilder {
         self
     }
 
+    /// Require API keys for every operation, the keys can be created with
+    /// [`create_api_key`].
+    pub fn enable_api_key_management(mut self) -> Self {
+        self.api_key_management = true;
+        self
+    }
+
     pub async fn build(self) -> Server {
         let shutdown = ShutdownNotifier::default();
         Server {
@@ -107,6 +121,7 @@ impl ServerBuilder {
                 self.pool,
                 shutdown.clone(),
                 self.tls,
+                self.api_key_management,
             )
             .await,
             shutdown,
@@ -141,9 +156,50 @@ impl Server {
     }
 }
 
+/// Creates an API key with the given `permissions` directly in the database, used to
+/// bootstrap servers with API key management enabled.
+pub async fn create_api_key(
+    pool: &sqlx::Pool<db::DatabaseType>,
+    permissions: types::auth::Permission,
+) -> types::auth::Token {
+    let database = db::testing::Database::new(pool.clone());
+    let token = types::auth::Token::new();
+
+    let mut tx = database.transaction().await.unwrap();
+    db::api_key_create(
+        &mut tx,
+        types::ApiKey::new(&token, permissions, "test api key".to_owned(), None),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    token
+}
+
+/// Adds the API key token, if any, to every request sent by the client
+#[derive(Clone)]
+pub struct ApiKeyInterceptor {
+    token: Option<tonic::metadata::AsciiMetadataValue>,
+}
+
+impl tonic::service::Interceptor for ApiKeyInterceptor {
+    fn call(&mut self, mut req: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+        if let Some(token) = &self.token {
+            req.metadata_mut()
+                .insert("mosaico-api-key-token", token.clone());
+        }
+        Ok(req)
+    }
+}
+
+type Channel =
+    tonic::service::interceptor::InterceptedService<tonic::transport::Channel, ApiKeyInterceptor>;
+
 pub struct ClientBuilder {
     url: url::Url,
     tls: Option<tonic::transport::ClientTlsConfig>,
+    api_key: Option<types::auth::Token>,
 }
 
 impl ClientBuilder {
@@ -153,9 +209,16 @@ impl ClientBuilder {
                 .parse()
                 .expect("unable to convert host"),
             tls: None,
+            api_key: None,
         }
     }
 
+    /// Authenticate the requests of the client with the API key `token`
+    pub fn with_api_key(mut self, token: types::auth::Token) -> Self {
+        self.api_key = Some(token);
+        self
+    }
+
     pub fn enable_tls(mut self) -> Self {
         let cert_str = fs::read(TLS_CA_FILE).expect("Unable to read certificate");
         let cert = tonic::transport::Certificate::from_pem(cert_str);
@@ -201,7 +264,13 @@ impl ClientBuilder {
             }
         });
 
-        let client = FlightServiceClient::new(channel);
+        let interceptor = ApiKeyInterceptor {
+            token: self
+                .api_key
+                .map(|token| token.to_string().parse().expect("invalid api key token")),
+        };
+
+        let client = FlightServiceClient::with_interceptor(channel, interceptor);
 
         Client { client }
     }
@@ -209,13 +278,13 @@ impl ClientBuilder {
 
 /// A dummy client that communicates to mosaicod.
 pub struct Client {
-    client: FlightServiceClient<tonic::transport::Channel>,
+    client: FlightServiceClient<Channel>,
 }
 
 impl Client {}
 
 impl std::ops::Deref for Client {
-    type Target = FlightServiceClient<tonic::transport::Channel>;
+    type Target = FlightServiceClient<Channel>;
     fn deref(&self) -> &Self::Target {
         &self.client
     }
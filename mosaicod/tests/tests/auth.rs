@@ -2,6 +2,7 @@
 
 use mosaicod_core::types;
 use mosaicod_db as db;
+use mosaicod_ext as ext;
 use tests::{self, actions, common};
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
@@ -184,3 +185,216 @@ async fn test_api_key_revoke(pool: sqlx::Pool<db::DatabaseType>) {
 
     server.shutdown().await;
 }
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_api_key_scopes(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let scopes = vec!["fleet_a_:write".parse().unwrap(), ":read".parse().unwrap()];
+
+    let api_key_token =
+        actions::api_key_create_scoped(&mut client, types::auth::Permission::Write, &scopes)
+            .await
+            .unwrap();
+
+    let mut scopes = actions::api_key_scopes(&mut client, api_key_token.fingerprint())
+        .await
+        .unwrap();
+    scopes.sort();
+
+    assert_eq!(scopes, vec![":read", "fleet_a_:write"]);
+
+    // Scopes can't grant more than the key permissions
+    let scopes = vec!["fleet_a_:delete".parse().unwrap()];
+
+    assert!(
+        actions::api_key_create_scoped(&mut client, types::auth::Permission::Write, &scopes)
+            .await
+            .is_err()
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_import_paths(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let manage_key = common::create_api_key(&pool, types::auth::Permission::Manage).await;
+    let write_key = common::create_api_key(&pool, types::auth::Permission::Write).await;
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_api_key_management()
+        .build()
+        .await;
+
+    let mut writer = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(write_key)
+        .build()
+        .await;
+
+    // Only files in the imports folder of a sequence can be imported
+    for path in [
+        "sq_test_sequence/tp_my_topic/data/data-00000.parquet",
+        "imports/test_sequence/../../sq_test_sequence/metadata.json",
+        "/imports/test_sequence/recording.mcap",
+        "imports/recording.mcap",
+    ] {
+        assert_eq!(
+            actions::sequence_import(&mut writer, "imported_sequence", path)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::InvalidArgument,
+        );
+    }
+
+    // Importing requires to read the folder of the sequence owning the file
+    let mut admin = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(manage_key)
+        .build()
+        .await;
+    let scoped_key = actions::api_key_create_scoped(
+        &mut admin,
+        types::auth::Permission::Write,
+        &["imported_:write".parse().unwrap()],
+    )
+    .await
+    .unwrap();
+    let mut scoped = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(scoped_key)
+        .build()
+        .await;
+    assert_eq!(
+        actions::sequence_import(
+            &mut scoped,
+            "imported_sequence",
+            "imports/test_sequence/recording.mcap"
+        )
+        .await
+        .unwrap_err()
+        .code(),
+        tonic::Code::PermissionDenied,
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_export_permissions(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let manage_key = common::create_api_key(&pool, types::auth::Permission::Manage).await;
+    let write_key = common::create_api_key(&pool, types::auth::Permission::Write).await;
+    let read_key = common::create_api_key(&pool, types::auth::Permission::Read).await;
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_api_key_management()
+        .build()
+        .await;
+
+    let mut writer = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(write_key)
+        .build()
+        .await;
+    let mut reader = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(read_key)
+        .build()
+        .await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut writer, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut writer, sequence_name).await;
+    let uuid = actions::topic_create(&mut writer, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let mut response = actions::do_put(&mut writer, &uuid, topic_name, batches, false)
+        .await
+        .unwrap()
+        .into_inner();
+    while response.message().await.unwrap().is_some() {}
+    actions::session_finalize(&mut writer, &session_uuid)
+        .await
+        .unwrap();
+
+    let uploaded = actions::do_get(&mut reader, topic_name).await.unwrap();
+
+    // Exports write in the store, read-only keys can't request them
+    assert_eq!(
+        actions::sequence_export(&mut reader, sequence_name)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::PermissionDenied,
+    );
+
+    // The bundle is written in the exports folder of the sequence, leaving the data untouched
+    let path = actions::sequence_export(&mut writer, sequence_name)
+        .await
+        .unwrap();
+    assert!(path.starts_with("exports/test_sequence/"));
+
+    let downloaded = actions::do_get(&mut reader, topic_name).await.unwrap();
+    assert_eq!(downloaded.len(), uploaded.len());
+    for (downloaded, uploaded) in downloaded.iter().zip(&uploaded) {
+        assert_eq!(downloaded.columns(), uploaded.columns());
+    }
+
+    // Only files in the exports and imports folders can be imported
+    for path in [
+        "sq_test_sequence/metadata.json",
+        "exports/test_sequence/../../sq_test_sequence/metadata.json",
+        "/exports/test_sequence/bundle.tar",
+    ] {
+        assert_eq!(
+            actions::sequence_import_bundle(&mut writer, "imported_sequence", path)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::InvalidArgument,
+        );
+    }
+
+    // Importing requires to read the folder of the sequence owning the file
+    let mut admin = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(manage_key)
+        .build()
+        .await;
+    let scoped_key = actions::api_key_create_scoped(
+        &mut admin,
+        types::auth::Permission::Write,
+        &["imported_:write".parse().unwrap()],
+    )
+    .await
+    .unwrap();
+    let mut scoped = common::ClientBuilder::new(common::HOST, port)
+        .with_api_key(scoped_key)
+        .build()
+        .await;
+    assert_eq!(
+        actions::sequence_import_bundle(&mut scoped, "imported_sequence", &path)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::PermissionDenied,
+    );
+
+    let topics = actions::sequence_import_bundle(&mut writer, "imported_sequence", &path)
+        .await
+        .unwrap();
+    assert_eq!(topics, vec!["imported_sequence/my_topic"]);
+
+    server.shutdown().await;
+}
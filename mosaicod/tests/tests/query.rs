@@ -0,0 +1,262 @@
+#![allow(unused_crate_dependencies)]
+
+use arrow::array::{AsArray, RecordBatch};
+use arrow::datatypes::{Int64Type, UInt64Type};
+use mosaicod_db as db;
+use mosaicod_ext as ext;
+use tests::{self, actions, common};
+
+/// Collects `(topic, first_ts, last_ts, match_count)` rows from the query results
+fn query_rows(batches: &[RecordBatch]) -> Vec<(String, i64, i64, u64)> {
+    let mut rows = Vec::new();
+    for batch in batches {
+        let topics = batch.column_by_name("topic").unwrap().as_string::<i32>();
+        let first_ts = batch
+            .column_by_name("first_ts")
+            .unwrap()
+            .as_primitive::<Int64Type>();
+        let last_ts = batch
+            .column_by_name("last_ts")
+            .unwrap()
+            .as_primitive::<Int64Type>();
+        let match_count = batch
+            .column_by_name("match_count")
+            .unwrap()
+            .as_primitive::<UInt64Type>();
+
+        for i in 0..batch.num_rows() {
+            rows.push((
+                topics.value(i).to_owned(),
+                first_ts.value(i),
+                last_ts.value(i),
+                match_count.value(i),
+            ));
+        }
+    }
+    rows.sort();
+    rows
+}
+
+/// Collects the `(start, end)` pairs of the intervals of each row of the query results
+fn query_intervals(batches: &[RecordBatch]) -> Vec<Vec<(i64, i64)>> {
+    let mut rows = Vec::new();
+    for batch in batches {
+        let intervals = batch.column_by_name("intervals").unwrap().as_list::<i32>();
+        for i in 0..batch.num_rows() {
+            let topic_intervals = intervals.value(i);
+            let topic_intervals = topic_intervals.as_struct();
+            let start = topic_intervals.column(0).as_primitive::<Int64Type>();
+            let end = topic_intervals.column(1).as_primitive::<Int64Type>();
+            rows.push(
+                (0..start.len())
+                    .map(|j| (start.value(j), end.value(j)))
+                    .collect(),
+            );
+        }
+    }
+    rows
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn query_stream(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    for (sequence_name, batch) in [
+        ("seq_a", ext::arrow::testing::dummy_batch()),
+        ("seq_b", ext::arrow::testing::dummy_empty_batch()),
+    ] {
+        actions::sequence_create(&mut client, sequence_name, None)
+            .await
+            .unwrap();
+        let session_uuid = actions::session_create(&mut client, sequence_name).await;
+
+        let topic_name = format!("{sequence_name}/my_topic");
+        let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+            .await
+            .unwrap();
+
+        let response = actions::do_put(&mut client, &uuid, &topic_name, vec![batch], false)
+            .await
+            .unwrap();
+        if response.into_inner().message().await.unwrap().is_some() {
+            panic!("Received a not-empty response!");
+        }
+
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .unwrap();
+    }
+
+    let batches = actions::query_stream(&mut client, r#"{"ontology": {"mock.value": {"$gt": 4}}}"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        query_rows(&batches),
+        vec![("seq_a/my_topic".to_owned(), 10020, 10030, 3)]
+    );
+
+    let batches =
+        actions::query_stream(&mut client, r#"{"ontology": {"mock.value": {"$gt": 10}}}"#)
+            .await
+            .unwrap();
+    assert!(query_rows(&batches).is_empty());
+
+    let batches = actions::query_stream(
+        &mut client,
+        r#"{
+            "ontology": {
+                "$or": [{"mock.value": {"$lt": 3}}, {"mock.value": {"$gt": 4}}],
+                "intervals": {"max_gap_ns": 5}
+            }
+        }"#,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        query_rows(&batches),
+        vec![("seq_a/my_topic".to_owned(), 10000, 10030, 5)]
+    );
+    assert_eq!(
+        query_intervals(&batches),
+        vec![vec![(10000, 10005), (10020, 10030)]]
+    );
+
+    // Pagination is not supported by streamed queries
+    assert_eq!(
+        actions::query_stream(
+            &mut client,
+            r#"{"ontology": {"mock.value": {"$gt": 4}}, "limit": 1}"#
+        )
+        .await
+        .unwrap_err()
+        .code(),
+        tonic::Code::InvalidArgument,
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn query_point_cloud(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "seq_lidar";
+    let topic_name = "seq_lidar/lidar";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create_with_format(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "point_cloud",
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Two scans, x = [0.1, 1.1, -0.1] and x = [-1.5, 3.0]
+    let batches = vec![ext::arrow::testing::dummy_point_cloud_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Scans having at least a point with x < -1
+    let batches = actions::query_stream(&mut client, r#"{"ontology": {"mock.x": {"$lt": -1.0}}}"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        query_rows(&batches),
+        vec![(topic_name.to_owned(), 10010, 10010, 1)]
+    );
+
+    // Scans having at least a point in the range, the second scan only overlaps it
+    let batches = actions::query_stream(
+        &mut client,
+        r#"{"ontology": {"mock.x": {"$between": [1.0, 2.0]}}}"#,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        query_rows(&batches),
+        vec![(topic_name.to_owned(), 10000, 10000, 1)]
+    );
+
+    // No point falls outside the chunk bounding box
+    let batches = actions::query_stream(&mut client, r#"{"ontology": {"mock.x": {"$gt": 5.0}}}"#)
+        .await
+        .unwrap();
+    assert!(query_rows(&batches).is_empty());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn query_arrow_ipc(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "seq_imu";
+    let topic_name = "seq_imu/imu";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create_with_format(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "arrow_ipc",
+        None,
+    )
+    .await
+    .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Chunk statistics and rows are read as for Parquet topics
+    let batches = actions::query_stream(&mut client, r#"{"ontology": {"mock.value": {"$gt": 4}}}"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        query_rows(&batches),
+        vec![(topic_name.to_owned(), 10020, 10030, 3)]
+    );
+
+    server.shutdown().await;
+}
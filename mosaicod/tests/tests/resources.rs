@@ -418,6 +418,142 @@ async fn do_put(pool: sqlx::Pool<db::DatabaseType>) {
     server.shutdown().await;
 }
 
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn do_put_resume(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    // Nothing uploaded yet, the upload starts from the first chunk
+    let (token, chunks) = actions::topic_resume(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(chunks, 0);
+
+    // Upload some data leaving the topic unlocked
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put_resumable(&mut client, &uuid, topic_name, batches, Some(&token), false)
+        .await
+        .unwrap();
+
+    let (token, chunks) = actions::topic_resume(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(chunks, 1);
+
+    // Appending data without a resume token (or with a stale one) should fail
+    for stale in [None, Some(format!("{uuid}:0"))] {
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        assert_eq!(
+            actions::do_put_resumable(
+                &mut client,
+                &uuid,
+                topic_name,
+                batches,
+                stale.as_deref(),
+                true
+            )
+            .await
+            .unwrap_err()
+            .code(),
+            tonic::Code::InvalidArgument,
+        );
+    }
+
+    // Resume the upload and lock the topic
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put_resumable(&mut client, &uuid, topic_name, batches, Some(&token), true)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        actions::topic_resume(&mut client, topic_name)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::FailedPrecondition,
+    );
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn do_put_stream_error(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow_flight::encode::FlightDataEncoderBuilder;
+    use arrow_flight::{FlightData, FlightDescriptor};
+    use futures::TryStreamExt;
+
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    // A valid batch followed by a message that can't be decoded
+    let cmd = serde_json::json!({
+        "resource_locator": topic_name,
+        "topic_uuid": uuid.to_string(),
+    });
+    let mut flight_data: Vec<FlightData> = FlightDataEncoderBuilder::new()
+        .with_flight_descriptor(Some(FlightDescriptor::new_cmd(cmd.to_string())))
+        .build(futures::stream::iter([Ok(
+            ext::arrow::testing::dummy_batch(),
+        )]))
+        .try_collect()
+        .await
+        .unwrap();
+    flight_data.push(FlightData {
+        data_header: b"not a message".to_vec().into(),
+        ..Default::default()
+    });
+
+    let result = match client.do_put(futures::stream::iter(flight_data)).await {
+        Ok(response) => response.into_inner().message().await.map(|_| ()),
+        Err(status) => Err(status),
+    };
+    assert!(result.is_err());
+
+    // The batch received before the error was persisted
+    let (_, chunks) = actions::topic_resume(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(chunks, 1);
+
+    server.shutdown().await;
+}
+
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
 async fn session_finalize(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
@@ -576,3 +712,299 @@ async fn get_server_version(pool: sqlx::Pool<db::DatabaseType>) {
 
     server.shutdown().await;
 }
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn point_cloud_roundtrip(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/lidar";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create_with_format(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "point_cloud",
+        None,
+    )
+    .await
+    .unwrap();
+
+    let batch = ext::arrow::testing::dummy_point_cloud_batch();
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch.clone()], false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let batches: Vec<_> = batches.into_iter().filter(|b| b.num_rows() > 0).collect();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].num_rows(), batch.num_rows());
+    for column in ["timestamp_ns", "x", "y", "z"] {
+        assert_eq!(
+            batches[0].column_by_name(column),
+            batch.column_by_name(column),
+            "column {column} differs"
+        );
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn video_roundtrip(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::AsArray;
+
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/camera";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid =
+        actions::topic_create_with_format(&mut client, &session_uuid, topic_name, "video", None)
+            .await
+            .unwrap();
+
+    // Frames with odd sizes can't be encoded
+    let batches = vec![ext::arrow::testing::dummy_video_batch(2, 63, 48)];
+    let status = match actions::do_put(&mut client, &uuid, topic_name, batches, false).await {
+        Ok(response) => response.into_inner().message().await.unwrap_err(),
+        Err(status) => status,
+    };
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let batch = ext::arrow::testing::dummy_video_batch(10, 64, 48);
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch.clone()], false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let batches: Vec<_> = batches.into_iter().filter(|b| b.num_rows() > 0).collect();
+    let received = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(received.num_rows(), batch.num_rows());
+    assert_eq!(
+        received.column_by_name("timestamp_ns"),
+        batch.column_by_name("timestamp_ns")
+    );
+
+    // Frames are lossy encoded, but they must stay close to the uploaded ones
+    let original = batch.column_by_name("data").unwrap().as_binary::<i32>();
+    let decoded = arrow::compute::cast(
+        received.column_by_name("data").unwrap(),
+        &arrow::datatypes::DataType::Binary,
+    )
+    .unwrap();
+    let decoded = decoded.as_binary::<i32>();
+    for row in 0..batch.num_rows() {
+        assert_eq!(original.value(row).len(), decoded.value(row).len());
+        let error: u64 = original
+            .value(row)
+            .iter()
+            .zip(decoded.value(row))
+            .map(|(a, b)| a.abs_diff(*b) as u64)
+            .sum();
+        assert!(error / (original.value(row).len() as u64) < 8);
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn topic_writer_options(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+
+    // Inconsistent options are rejected on creation
+    for options in [
+        r#"{ "codec": "zstd", "level": 23 }"#,
+        r#"{ "codec": "snappy", "level": 1 }"#,
+        r#"{ "level": 3 }"#,
+        r#"{ "row_group_size": 0 }"#,
+        r#"{ "codek": "zstd" }"#,
+    ] {
+        let status = actions::topic_create_with_writer_options(
+            &mut client,
+            &session_uuid,
+            "test_sequence/invalid",
+            "default",
+            None,
+            Some(options),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument, "{options}");
+    }
+
+    // Field names are checked against the uploaded data, the failed upload leaves the
+    // topic unlocked, so it is created in a session that is never finalized
+    let topic_name = "test_sequence/unknown_field";
+    let failed_session_uuid = actions::session_create(&mut client, sequence_name).await;
+    let uuid = actions::topic_create_with_writer_options(
+        &mut client,
+        &failed_session_uuid,
+        topic_name,
+        "default",
+        None,
+        Some(r#"{ "bloom_filter": ["missing"] }"#),
+    )
+    .await
+    .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let status = match actions::do_put(&mut client, &uuid, topic_name, batches, false).await {
+        Ok(response) => response.into_inner().message().await.unwrap_err(),
+        Err(status) => status,
+    };
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let topic_name = "test_sequence/my_topic";
+    let uuid = actions::topic_create_with_writer_options(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "default",
+        None,
+        Some(
+            r#"{
+                "codec": "gzip",
+                "level": 9,
+                "row_group_size": 2,
+                "bloom_filter": ["value"],
+                "columns": { "value": { "codec": "uncompressed", "statistics": "none" } }
+            }"#,
+        ),
+    )
+    .await
+    .unwrap();
+
+    let batch = ext::arrow::testing::dummy_batch();
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch.clone()], false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let received = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(received.columns(), batch.columns());
+
+    // Writer options are reported along the topic properties
+    let properties: serde_json::Value =
+        serde_json::from_str(&received.schema().metadata()["mosaico:properties"]).unwrap();
+    assert_eq!(properties["writer_options"]["codec"], "gzip");
+    assert_eq!(properties["writer_options"]["row_group_size"], 2);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn arrow_ipc_roundtrip(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/joint_states";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let session_uuid = actions::session_create(&mut client, sequence_name).await;
+
+    // Arrow IPC files have no row groups, bloom filters or per-column settings
+    let status = actions::topic_create_with_writer_options(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "arrow_ipc",
+        None,
+        Some(r#"{ "row_group_size": 1024 }"#),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let uuid = actions::topic_create_with_writer_options(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        "arrow_ipc",
+        None,
+        Some(r#"{ "codec": "zstd" }"#),
+    )
+    .await
+    .unwrap();
+
+    let batch = ext::arrow::testing::dummy_batch();
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch.clone()], false)
+        .await
+        .unwrap();
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let received = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(received.columns(), batch.columns());
+
+    let properties: serde_json::Value =
+        serde_json::from_str(&received.schema().metadata()["mosaico:properties"]).unwrap();
+    assert_eq!(properties["serialization_format"], "arrow_ipc");
+
+    server.shutdown().await;
+}